@@ -1,11 +1,31 @@
 use chrono::Utc;
 use clap::{Parser, Subcommand};
+use grotto_core::approvals::{self, ApprovalStatus};
+use grotto_core::budget::{self, BudgetScope};
+use grotto_core::changes;
 use grotto_core::daemon::{self, SessionEntry, SessionRegistry};
-use grotto_core::{Grotto, Result};
-use std::collections::HashMap;
+use grotto_core::detect::{PhaseRules, RulesSource};
+use grotto_core::headless;
+use grotto_core::logsink::{self, LogSink};
+use grotto_core::merge::{self, MergeOrder, StepStatus};
+use grotto_core::monitor;
+use grotto_core::mux::{Multiplexer, SessionMux, Tmux};
+use grotto_core::policy::{self, Policy};
+use grotto_core::routing::ModelRule;
+use grotto_core::runtime::RuntimeConfig;
+use grotto_core::scale;
+use grotto_core::scorecard;
+use grotto_core::stall::{self, StallAction};
+use grotto_core::steer::{self, Delivery, SteerOptions};
+use grotto_core::supervise::{self, RestartPolicy};
+use grotto_core::usage;
+use grotto_core::verify;
+use grotto_core::{AgentLaunch, Backend, Grotto, Isolation, Result};
+use std::collections::{BTreeMap, HashMap};
 use std::env;
 use std::fs;
-use std::path::PathBuf;
+use std::io::Read;
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 
 #[derive(Parser)]
@@ -15,10 +35,13 @@ struct Cli {
     #[command(subcommand)]
     command: Commands,
 
-    #[arg(long, short, global = true)]
+    /// Project directory containing .grotto/ (defaults to the current directory)
+    #[arg(long, short, global = true, env = "GROTTO_DIR")]
     dir: Option<PathBuf>,
 }
 
+// Parsed once per run, so the size of `Spawn` doesn't matter
+#[allow(clippy::large_enum_variant)]
 #[derive(Subcommand)]
 enum Commands {
     /// Spawn N agents in tmux session
@@ -30,6 +53,63 @@ enum Commands {
         /// Optional mission template to structure the task prompt
         #[arg(long)]
         template: Option<String>,
+        /// Isolate agents from each other: "worktree" gives each agent its own branch + git worktree
+        #[arg(long, value_name = "MODE")]
+        isolate: Option<String>,
+        /// Success criteria command; `grotto wait` re-runs it until it passes (repeatable)
+        #[arg(long = "until", value_name = "CMD")]
+        until: Vec<String>,
+        /// Verification rounds before the session is marked failed
+        #[arg(long, default_value_t = grotto_core::verify::DEFAULT_MAX_ROUNDS)]
+        max_rounds: u32,
+        /// Wall-clock budget for the session (e.g. 45m, 2h); agents are stopped when it runs out
+        #[arg(long, value_name = "DURATION", value_parser = parse_duration_arg)]
+        max_duration: Option<u64>,
+        /// Budget for each agent (e.g. 20m); the agent is stopped and its task requeued
+        #[arg(long, value_name = "DURATION", value_parser = parse_duration_arg)]
+        agent_timeout: Option<u64>,
+        /// Spending limit for the team in USD (e.g. 5, $2.50); every agent is stopped when it is reached
+        #[arg(long, value_name = "USD", value_parser = parse_cost_arg)]
+        max_cost: Option<f64>,
+        /// Relaunch agents whose process exits: never, on-failure or always
+        #[arg(long, value_name = "POLICY", default_value = "never")]
+        restart: String,
+        /// Restarts allowed per agent before it is left stopped
+        #[arg(long, default_value_t = supervise::DEFAULT_MAX_RESTARTS)]
+        max_restarts: u32,
+        /// Flag a working agent as stalled once its screen is unchanged this long (e.g. 20m)
+        #[arg(long, value_name = "DURATION", value_parser = parse_duration_arg)]
+        stall_after: Option<u64>,
+        /// Flag an agent as looping once a command or error line repeats this many times
+        #[arg(long, default_value_t = stall::DEFAULT_LOOP_REPEATS)]
+        loop_repeats: u32,
+        /// What the daemon does about stalled or looping agents: notify, nudge or escalate
+        #[arg(long, value_name = "ACTION", default_value = "notify")]
+        on_stall: String,
+        /// Agent program: claude, fake, or a shell command with a {prompt} placeholder
+        #[arg(long, value_name = "RUNTIME", default_value = "claude")]
+        runtime: String,
+        /// Runtime for one agent, e.g. agent-2="aider --message {prompt}" (repeatable)
+        #[arg(long = "agent-runtime", value_name = "AGENT=RUNTIME")]
+        agent_runtimes: Vec<String>,
+        /// Model for agents no --route rule matches (passed to the runtime, e.g. claude --model)
+        #[arg(long)]
+        model: Option<String>,
+        /// Model routing rule: role:<role>=<model> or label:<label>=<model> (repeatable)
+        #[arg(long = "route", value_name = "RULE")]
+        routes: Vec<String>,
+        /// Specialty for one agent, e.g. agent-3=tester (repeatable)
+        #[arg(long = "role", value_name = "AGENT=ROLE")]
+        roles: Vec<String>,
+        /// What runs the agents: tmux, or headless (PTYs owned by a background `grotto run`; no tmux needed)
+        #[arg(long, value_name = "BACKEND", default_value = "tmux")]
+        backend: String,
+    },
+    /// Run the session's agents on PTYs in the foreground, without tmux (started by `spawn --backend headless`)
+    Run {
+        /// Relaunch the agents after this reason instead of starting them fresh (used by `wait`)
+        #[arg(long, value_name = "REASON", hide = true)]
+        resume: Option<String>,
     },
     /// Attach to the grotto tmux session
     View,
@@ -51,6 +131,26 @@ enum Commands {
     Kill {
         /// Agent ID or "all"
         target: String,
+        /// Also remove the agents' git worktrees (branches are kept)
+        #[arg(long)]
+        cleanup: bool,
+    },
+    /// Grow or shrink the running team; idle agents are retired first
+    Scale {
+        /// Number of agents the team should have
+        count: usize,
+    },
+    /// Add one agent to the running team
+    AddAgent {
+        /// What the new agent should focus on (e.g. reviewer, "write tests")
+        #[arg(long)]
+        role: Option<String>,
+        /// Agent program, if not the session's: claude, fake, or a shell command with {prompt}
+        #[arg(long, value_name = "RUNTIME")]
+        runtime: Option<String>,
+        /// Task to claim for the new agent before it starts; its labels pick the model
+        #[arg(long, value_name = "TASK_ID")]
+        task: Option<String>,
     },
     /// Show agent's log output
     Log {
@@ -63,6 +163,16 @@ enum Commands {
         #[arg(long, short)]
         follow: bool,
     },
+    /// Add a task to the board
+    AddTask {
+        /// Task ID (e.g. docs, api-tests)
+        task_id: String,
+        /// What needs doing
+        description: String,
+        /// Kind of work, used for model routing (repeatable)
+        #[arg(long = "label", value_name = "LABEL")]
+        labels: Vec<String>,
+    },
     /// Claim a task
     Claim {
         /// Task ID to claim
@@ -71,6 +181,12 @@ enum Commands {
         #[arg(long)]
         agent: String,
     },
+    /// Restart agents whose process exited, following the session's restart policy
+    Supervise {
+        /// Poll interval in seconds
+        #[arg(long, default_value = "5")]
+        interval: u64,
+    },
     /// Wait for all agents to finish, then print summary
     Wait {
         /// Poll interval in seconds
@@ -82,6 +198,79 @@ enum Commands {
         /// Task ID to complete
         task_id: String,
     },
+    /// Ask the lead to approve a risky step; blocks until answered or timed out
+    RequestApproval {
+        /// What the agent is about to do
+        description: String,
+        /// Agent ID asking for approval
+        #[arg(long)]
+        agent: String,
+        /// Seconds to wait for an answer before the request expires
+        #[arg(long, default_value_t = approvals::DEFAULT_TIMEOUT_SECS)]
+        timeout: u64,
+    },
+    /// Approve a pending approval request
+    Approve {
+        /// Approval ID (approval-1, approval-2, etc.)
+        id: String,
+    },
+    /// Deny a pending approval request
+    Deny {
+        /// Approval ID (approval-1, approval-2, etc.)
+        id: String,
+        /// Why the step was denied (shown to the agent)
+        #[arg(long)]
+        reason: Option<String>,
+    },
+    /// Merge agent branches one at a time, running checks after each merge
+    Merge {
+        /// Branch to merge into (defaults to the current branch)
+        #[arg(long)]
+        into: Option<String>,
+        /// "auto" (agent order) or a comma-separated list like agent-1,agent-3
+        #[arg(long, default_value = "auto")]
+        order: String,
+        /// Check command to run after each merge (repeatable; overrides `checks` in config.toml)
+        #[arg(long = "check")]
+        checks: Vec<String>,
+    },
+    /// Show commits and diffstat since the session was spawned, or with --progress record an agent's progress
+    Report {
+        /// Agent reporting its progress
+        #[arg(long, requires = "progress")]
+        agent: Option<String>,
+        /// What the agent just finished or is doing now
+        #[arg(long, requires = "agent")]
+        progress: Option<String>,
+        /// How far through its task the agent is (0-100)
+        #[arg(long, requires = "progress", value_parser = clap::value_parser!(u8).range(0..=100))]
+        percent: Option<u8>,
+        /// What the agent is waiting for, if it can't go on
+        #[arg(long, requires = "progress")]
+        blocked_on: Option<String>,
+    },
+    /// Show each agent's recorded phase changes and how long each phase lasted
+    Timeline {
+        /// Only show this agent
+        #[arg(long)]
+        agent: Option<String>,
+    },
+    /// Show the session scorecard (tasks, phase time, errors, steering, verification, changes)
+    Scorecard {
+        /// Print scorecard.json instead of the rendered table
+        #[arg(long)]
+        json: bool,
+    },
+    /// Check the guardrail policy or resume an agent it paused
+    Policy {
+        #[command(subcommand)]
+        action: PolicyAction,
+    },
+    /// Check the rules that read agent phases from their screens
+    Monitor {
+        #[command(subcommand)]
+        action: MonitorAction,
+    },
     /// Start one-session WebSocket server + web UI (foreground; debug use)
     Serve {
         /// Port to listen on
@@ -96,6 +285,19 @@ enum Commands {
         #[command(subcommand)]
         action: DaemonAction,
     },
+    /// Internal: write a pane's output to its raw and cleaned logs (used by tmux pipe-pane)
+    #[command(hide = true)]
+    LogSink {
+        /// Raw log to append to; the cleaned log and its index go beside it
+        path: PathBuf,
+    },
+    /// Internal: run Claude Code and show its stream-json as readable lines, keeping its usage
+    #[command(hide = true)]
+    StreamView {
+        /// The command to run, with its arguments
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true, required = true)]
+        command: Vec<String>,
+    },
     /// Internal: run the daemon server process (used by `daemon start`)
     #[command(hide = true)]
     DaemonServe {
@@ -108,6 +310,42 @@ enum Commands {
     },
 }
 
+#[derive(Subcommand)]
+enum PolicyAction {
+    /// Validate .grotto/policy.toml and list its rules
+    Check {
+        /// Test a line of agent output against the rules
+        #[arg(long)]
+        line: Option<String>,
+    },
+    /// Resume an agent paused by a policy violation
+    Resume {
+        /// Agent ID
+        agent: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum MonitorAction {
+    /// Explain which phase rule matches a saved screen capture ("-" reads stdin)
+    Test {
+        /// File holding captured agent output (e.g. from `tmux capture-pane -p`)
+        capture: PathBuf,
+        /// Use this runtime's rules: claude, shell or fake (defaults to the session's)
+        #[arg(long, conflicts_with = "agent")]
+        runtime: Option<String>,
+        /// Use the rules of this agent's runtime
+        #[arg(long)]
+        agent: Option<String>,
+    },
+    /// List phase rules in the order they are tried
+    Rules {
+        /// Runtime whose rules to list: claude, shell or fake (defaults to the session's)
+        #[arg(long)]
+        runtime: Option<String>,
+    },
+}
+
 #[derive(Subcommand)]
 enum DaemonAction {
     /// Start the persistent daemon server (recommended default)
@@ -144,37 +382,124 @@ fn main() {
         .dir
         .unwrap_or_else(|| env::current_dir().expect("Failed to get current directory"));
 
-    if let Err(e) = run_command(cli.command, project_dir) {
+    if let Err(e) = run_command(cli.command, project_dir, &Tmux) {
         eprintln!("Error: {}", e);
         std::process::exit(1);
     }
 }
 
-fn run_command(command: Commands, project_dir: PathBuf) -> Result<()> {
+fn run_command(command: Commands, project_dir: PathBuf, mux: &dyn Multiplexer) -> Result<()> {
     match command {
         Commands::Spawn {
             count,
             task,
             template,
-        } => spawn_agents(project_dir, count, task, template),
-        Commands::View => view_session(),
-        Commands::Status => show_status(project_dir),
-        Commands::Steer { agent, message } => steer_agent(project_dir, agent, message),
-        Commands::Broadcast { message } => broadcast_message(project_dir, message),
-        Commands::Wait { interval } => wait_for_completion(project_dir, interval),
-        Commands::Kill { target } => kill_target(project_dir, target),
-        Commands::Log { agent } => show_log(project_dir, agent),
+            isolate,
+            until,
+            max_rounds,
+            max_duration,
+            agent_timeout,
+            max_cost,
+            restart,
+            max_restarts,
+            stall_after,
+            loop_repeats,
+            on_stall,
+            runtime,
+            agent_runtimes,
+            model,
+            routes,
+            roles,
+            backend,
+        } => spawn_agents(
+            project_dir,
+            mux,
+            count,
+            task,
+            SpawnOptions {
+                template,
+                isolate,
+                until,
+                max_rounds,
+                max_duration,
+                agent_timeout,
+                max_cost,
+                restart,
+                max_restarts,
+                stall_after,
+                loop_repeats,
+                on_stall,
+                runtime,
+                agent_runtimes,
+                model,
+                routes,
+                roles,
+                backend,
+            },
+        ),
+        Commands::Run { resume } => run_headless(project_dir, resume),
+        Commands::View => view_session(project_dir, mux),
+        Commands::Status => show_status(project_dir, mux),
+        Commands::Steer { agent, message } => steer_agent(project_dir, mux, agent, message),
+        Commands::Broadcast { message } => broadcast_message(project_dir, mux, message),
+        Commands::Supervise { interval } => supervise_agents(project_dir, mux, interval),
+        Commands::Wait { interval } => wait_for_completion(project_dir, mux, interval),
+        Commands::Kill { target, cleanup } => kill_target(project_dir, mux, target, cleanup),
+        Commands::Scale { count } => scale_team(project_dir, mux, count),
+        Commands::AddAgent {
+            role,
+            runtime,
+            task,
+        } => add_team_agent(project_dir, mux, role, runtime, task),
+        Commands::Log { agent } => show_log(project_dir, mux, agent),
         Commands::Events { follow } => show_events(project_dir, follow),
+        Commands::AddTask {
+            task_id,
+            description,
+            labels,
+        } => add_task(project_dir, task_id, description, labels),
         Commands::Claim { task_id, agent } => claim_task(project_dir, task_id, agent),
         Commands::Complete { task_id } => complete_task(project_dir, task_id),
+        Commands::RequestApproval {
+            description,
+            agent,
+            timeout,
+        } => request_approval(project_dir, description, agent, timeout),
+        Commands::Approve { id } => {
+            decide_approval(project_dir, id, ApprovalStatus::Approved, None)
+        }
+        Commands::Deny { id, reason } => {
+            decide_approval(project_dir, id, ApprovalStatus::Denied, reason)
+        }
+        Commands::Merge {
+            into,
+            order,
+            checks,
+        } => merge_branches(project_dir, into, order, checks),
+        Commands::Report {
+            agent: Some(agent),
+            progress: Some(progress),
+            percent,
+            blocked_on,
+        } => report_progress(project_dir, agent, progress, percent, blocked_on),
+        Commands::Report { .. } => show_report(project_dir),
+        Commands::Timeline { agent } => show_timeline(project_dir, agent),
+        Commands::Scorecard { json } => show_scorecard(project_dir, json),
+        Commands::Policy { action } => run_policy(project_dir, mux, action),
+        Commands::Monitor { action } => run_monitor(project_dir, action),
         Commands::Serve { port, no_open } => serve(project_dir, port, no_open),
         Commands::Daemon { action } => run_daemon(project_dir, action),
         Commands::DaemonServe { port, web_dir } => daemon_serve(port, web_dir),
+        Commands::LogSink { path } => run_log_sink(&path),
+        Commands::StreamView { command } => run_stream_view(&command),
     }
 }
 
 const SUPPORTED_SPAWN_TEMPLATES: [&str; 3] = ["bugfix-swarm", "test-hardening", "migration-slice"];
 
+/// Runtimes that can have their own phase rules file (see `grotto monitor`).
+const RUNTIME_KINDS: [&str; 3] = ["claude", "shell", "fake"];
+
 fn render_spawn_template(template: &str, task: &str) -> Result<String> {
     let rendered = match template {
         "bugfix-swarm" => format!(
@@ -228,14 +553,136 @@ fn build_spawn_task(task: &str, template: Option<&str>) -> Result<String> {
     }
 }
 
+/// How to launch one agent of a freshly spawned team.
+fn spawn_launch(grotto: &Grotto, agent_id: &str) -> AgentLaunch {
+    grotto.agent_launch(agent_id, grotto.generate_claude_prompt(agent_id))
+}
+
+/// Optional `grotto spawn` flags.
+struct SpawnOptions {
+    template: Option<String>,
+    isolate: Option<String>,
+    until: Vec<String>,
+    max_rounds: u32,
+    max_duration: Option<u64>,
+    agent_timeout: Option<u64>,
+    max_cost: Option<f64>,
+    restart: String,
+    max_restarts: u32,
+    stall_after: Option<u64>,
+    loop_repeats: u32,
+    on_stall: String,
+    runtime: String,
+    agent_runtimes: Vec<String>,
+    model: Option<String>,
+    routes: Vec<String>,
+    roles: Vec<String>,
+    backend: String,
+}
+
+/// Parse `--<flag> agent-N=<value>` flags for a team of `count`; `usage`
+/// is shown when a flag is malformed.
+fn parse_agent_values(
+    flag: &str,
+    usage: &str,
+    specs: &[String],
+    count: usize,
+) -> Result<BTreeMap<String, String>> {
+    let mut values = BTreeMap::new();
+    for spec in specs {
+        let invalid = |message: String| {
+            grotto_core::GrottoError::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                message,
+            ))
+        };
+        let (agent_id, value) = spec
+            .split_once('=')
+            .ok_or_else(|| invalid(format!("Invalid --{} '{}'. Use {}", flag, spec, usage)))?;
+        let known = agent_id
+            .strip_prefix("agent-")
+            .and_then(|n| n.parse::<usize>().ok())
+            .is_some_and(|n| (1..=count).contains(&n));
+        if !known {
+            return Err(invalid(format!(
+                "Unknown agent '{}' in --{}; the team is agent-1 to agent-{}",
+                agent_id, flag, count
+            )));
+        }
+        values.insert(agent_id.to_string(), value.to_string());
+    }
+    Ok(values)
+}
+
+/// Parse `--agent-runtime agent-N=<runtime>` flags for a team of `count`.
+fn parse_agent_runtimes(specs: &[String], count: usize) -> Result<BTreeMap<String, RuntimeConfig>> {
+    parse_agent_values(
+        "agent-runtime",
+        "AGENT=RUNTIME, e.g. agent-2=fake",
+        specs,
+        count,
+    )?
+    .into_iter()
+    .map(|(agent_id, runtime)| Ok((agent_id, runtime.parse()?)))
+    .collect()
+}
+
+fn parse_duration_arg(input: &str) -> std::result::Result<u64, String> {
+    budget::parse_duration(input).map_err(|e| e.to_string())
+}
+
+fn parse_cost_arg(input: &str) -> std::result::Result<f64, String> {
+    match input.trim().trim_start_matches('$').parse::<f64>() {
+        Ok(usd) if usd > 0.0 && usd.is_finite() => Ok(usd),
+        _ => Err(format!(
+            "Invalid cost '{}'. Use a dollar amount such as 5 or 2.50",
+            input
+        )),
+    }
+}
+
 fn spawn_agents(
     project_dir: PathBuf,
+    mux: &dyn Multiplexer,
     count: usize,
     task: String,
-    template: Option<String>,
+    options: SpawnOptions,
 ) -> Result<()> {
+    let SpawnOptions {
+        template,
+        isolate,
+        until,
+        max_rounds,
+        max_duration,
+        agent_timeout,
+        max_cost,
+        restart,
+        max_restarts,
+        stall_after,
+        loop_repeats,
+        on_stall,
+        runtime,
+        agent_runtimes,
+        model,
+        routes,
+        roles,
+        backend,
+    } = options;
+
+    let backend: Backend = backend.parse()?;
+    let runtime: RuntimeConfig = runtime.parse()?;
+    let agent_runtimes = parse_agent_runtimes(&agent_runtimes, count)?;
+    let routes = routes
+        .iter()
+        .map(|rule| rule.parse())
+        .collect::<Result<Vec<ModelRule>>>()?;
+    let roles = parse_agent_values("role", "AGENT=ROLE, e.g. agent-3=tester", &roles, count)?;
+
     // Check dependencies before doing anything
-    if let Err(missing) = Grotto::check_dependencies() {
+    if let Err(missing) = Grotto::check_dependencies(
+        backend,
+        std::iter::once(&runtime).chain(agent_runtimes.values()),
+    ) {
         eprintln!("❌ Missing required dependencies: {}", missing.join(", "));
         for bin in &missing {
             match bin.as_str() {
@@ -254,10 +701,23 @@ fn spawn_agents(
         )));
     }
 
-    // Kill any existing grotto session
-    let _ = Command::new("tmux")
-        .args(["kill-session", "-t", "grotto"])
-        .output();
+    let isolation: Isolation = isolate.as_deref().unwrap_or("shared").parse()?;
+    let restart: RestartPolicy = restart.parse()?;
+    let on_stall: StallAction = on_stall.parse()?;
+    if isolation == Isolation::Worktree && !grotto_core::git::is_repo(&project_dir) {
+        return Err(grotto_core::GrottoError::Git(format!(
+            "--isolate worktree requires a git repository: {}",
+            project_dir.display()
+        )));
+    }
+
+    // Stop this project's previous team; other projects' sessions are left alone
+    if let Ok(previous) = Grotto::load(&project_dir)
+        && previous.config.backend.is_tmux()
+    {
+        let _ = mux.kill_session(&previous.config.tmux_session);
+    }
+    let _ = headless::request(&project_dir.join(".grotto"), &headless::Request::Stop);
 
     let final_task = build_spawn_task(&task, template.as_deref())?;
 
@@ -267,106 +727,172 @@ fn spawn_agents(
     }
 
     // Initialize grotto project (generates session ID)
-    let grotto = Grotto::new(&project_dir, count, final_task)?;
-    let session_id = grotto.config.session_id.as_deref().unwrap_or("unknown");
+    let mut grotto = Grotto::new(&project_dir, count, final_task)?;
+    if isolation == Isolation::Worktree {
+        grotto.create_worktrees()?;
+        println!("   Isolation: one git worktree per agent under .grotto/worktrees/");
+    }
+    if !until.is_empty() {
+        grotto.config.until = until;
+        grotto.config.max_rounds = max_rounds.max(1);
+        grotto.write_config()?;
+        println!(
+            "   Success criteria: {} (up to {} rounds)",
+            grotto.config.until.join(" && "),
+            grotto.config.max_rounds
+        );
+    }
+    if max_duration.is_some() || agent_timeout.is_some() {
+        grotto.config.max_duration_secs = max_duration;
+        grotto.config.agent_timeout_secs = agent_timeout;
+        grotto.write_config()?;
+        let describe = |secs: Option<u64>| {
+            secs.map(budget::format_duration)
+                .unwrap_or_else(|| "none".to_string())
+        };
+        println!(
+            "   Time budget: {} per session, {} per agent (enforced by `grotto wait`)",
+            describe(max_duration),
+            describe(agent_timeout)
+        );
+    }
+    if let Some(usd) = max_cost {
+        grotto.config.max_cost_usd = Some(usd);
+        grotto.write_config()?;
+        println!(
+            "   Cost budget: {} for the team (enforced by `grotto wait`)",
+            usage::format_cost(usd)
+        );
+    }
+    if !restart.is_never() {
+        grotto.config.restart = restart;
+        grotto.config.max_restarts = max_restarts;
+        grotto.write_config()?;
+        println!(
+            "   Restart policy: {} (up to {} restarts per agent; run `grotto supervise`, `grotto wait` or the daemon)",
+            restart, max_restarts
+        );
+    }
+    let stall_after = stall_after.unwrap_or(stall::DEFAULT_STALL_AFTER_SECS);
+    if stall_after != stall::DEFAULT_STALL_AFTER_SECS
+        || loop_repeats != stall::DEFAULT_LOOP_REPEATS
+        || !on_stall.is_notify()
+    {
+        grotto.config.stall_after_secs = stall_after.max(1);
+        grotto.config.loop_repeats = loop_repeats.max(2);
+        grotto.config.on_stall = on_stall;
+        grotto.write_config()?;
+        println!(
+            "   Stall detection: after {} unchanged or {} repeats, then {} (run by the daemon)",
+            budget::format_duration(grotto.config.stall_after_secs),
+            grotto.config.loop_repeats,
+            on_stall
+        );
+    }
+    if !runtime.is_claude() || !agent_runtimes.is_empty() {
+        println!("   Runtime: {}", runtime);
+        for (agent_id, agent_runtime) in &agent_runtimes {
+            println!("   Runtime for {}: {}", agent_id, agent_runtime);
+        }
+        grotto.config.runtime = runtime;
+        grotto.config.agent_runtimes = agent_runtimes;
+        grotto.write_config()?;
+    }
+    for (agent_id, role) in roles {
+        if let Some(agent) = grotto.agents.get_mut(&agent_id) {
+            agent.role = Some(role);
+        }
+        grotto.write_agent_status(&agent_id)?;
+    }
+    if model.is_some() || !routes.is_empty() {
+        grotto.config.model = model;
+        grotto.config.models = routes;
+        grotto.write_config()?;
+        let mut assigned = Vec::new();
+        for agent_id in grotto.sorted_agent_ids() {
+            if let Some(model) = grotto.assign_model(&agent_id)? {
+                assigned.push(format!("{} {}", agent_id, model));
+            }
+        }
+        println!("   Models: {}", assigned.join(", "));
+    }
+    if !backend.is_tmux() {
+        grotto.config.backend = backend;
+        grotto.write_config()?;
+    }
+    let session_id = grotto
+        .config
+        .session_id
+        .clone()
+        .unwrap_or_else(|| "unknown".to_string());
+    let tmux_session = grotto.config.tmux_session.clone();
+
+    if !backend.is_tmux() {
+        start_headless_runner(&project_dir, None)?;
+        println!("✅ Spawned {} agents without tmux (grotto run)", count);
+        println!("   Session: {}", session_id);
+        println!("   Use 'grotto status' to see task board and 'grotto log <agent>' for output");
+        register_spawned_session(&project_dir, &grotto, count);
+        return Ok(());
+    }
 
     // Create new tmux session with first agent
-    let first_agent_prompt = grotto.generate_claude_prompt("agent-1");
     let mut startup_output_chunks: Vec<String> = Vec::new();
 
-    let output = Command::new("tmux")
-        .args([
-            "new-session",
-            "-d",
-            "-s",
-            "grotto",
-            "-c",
-            &project_dir.to_string_lossy(),
-            "claude",
-            "--dangerously-skip-permissions",
-            "-p",
-            &first_agent_prompt,
-        ])
-        .output()
-        .expect("Failed to create tmux session");
-
-    if !output.status.success() {
-        eprintln!(
-            "Failed to create tmux session: {}",
-            String::from_utf8_lossy(&output.stderr)
-        );
-        return Err(grotto_core::GrottoError::Io(std::io::Error::other(
-            "Failed to create tmux session",
-        )));
+    let mut last_pane = match mux.new_session(&tmux_session, &spawn_launch(&grotto, "agent-1")) {
+        Ok(pane) => {
+            push_startup_output("tmux new-session", &pane.output, &mut startup_output_chunks);
+            grotto.set_pane_id("agent-1", pane.pane_id)?;
+            grotto.pane_target("agent-1")?
+        }
+        Err(e) => {
+            eprintln!("Failed to create tmux session: {}", e);
+            return Err(e);
+        }
+    };
+
+    // Keep exited panes around so the supervisor can read their exit status
+    // and relaunch the agent in the same slot
+    if !grotto.config.restart.is_never() {
+        let _ = mux.keep_exited_panes(&tmux_session);
     }
-    push_command_output("tmux new-session", &output, &mut startup_output_chunks);
 
-    // Add additional agents as new panes
+    // Add additional agents as new panes, each after the last, recording
+    // pane IDs so later commands reach the right agent after panes close
     for i in 2..=count {
         let agent_id = format!("agent-{}", i);
-        let agent_prompt = grotto.generate_claude_prompt(&agent_id);
-
-        let output = Command::new("tmux")
-            .args([
-                "split-window",
-                "-t",
-                "grotto",
-                "-c",
-                &project_dir.to_string_lossy(),
-                "claude",
-                "--dangerously-skip-permissions",
-                "-p",
-                &agent_prompt,
-            ])
-            .output()
-            .expect("Failed to split window");
-
-        if !output.status.success() {
-            eprintln!(
-                "Failed to create pane for {}: {}",
-                agent_id,
-                String::from_utf8_lossy(&output.stderr)
-            );
+        let label = format!("tmux split-window {}", agent_id);
+        match mux.split(&last_pane, &spawn_launch(&grotto, &agent_id)) {
+            Ok(pane) => {
+                push_startup_output(&label, &pane.output, &mut startup_output_chunks);
+                grotto.set_pane_id(&agent_id, pane.pane_id)?;
+                last_pane = grotto.pane_target(&agent_id)?;
+            }
+            Err(e) => {
+                eprintln!("Failed to create pane for {}: {}", agent_id, e);
+                push_startup_output(&label, &e.to_string(), &mut startup_output_chunks);
+            }
         }
-        push_command_output(
-            &format!("tmux split-window {}", agent_id),
-            &output,
-            &mut startup_output_chunks,
-        );
     }
 
-    // Tile the panes evenly
-    let _ = Command::new("tmux")
-        .args(["select-layout", "-t", "grotto", "tiled"])
-        .output();
-
     // Set up pipe-pane for persistent stream logging per agent
-    for i in 0..count {
-        let agent_id = format!("agent-{}", i + 1);
+    let panes = grotto.agent_panes();
+    for pane in &panes {
         let stream_path = grotto
             .grotto_dir
             .join("agents")
-            .join(&agent_id)
-            .join("stream.log");
-        let pane_target = format!("grotto:0.{}", i);
-        let _ = Command::new("tmux")
-            .args([
-                "pipe-pane",
-                "-o",
-                "-t",
-                &pane_target,
-                &format!("cat >> {}", stream_path.display()),
-            ])
-            .output();
+            .join(&pane.agent_id)
+            .join(logsink::RAW_LOG);
+        let _ = mux.pipe(&pane.target, &stream_path);
     }
 
-    for pane_index in 0..count {
-        if let Some(captured) = capture_tmux_pane(&format!("grotto:0.{}", pane_index)) {
-            startup_output_chunks.push(format!("pane {}:\n{}", pane_index, captured));
+    for pane in &panes {
+        if let Some(captured) = mux.capture(&pane.target, 0) {
+            startup_output_chunks.push(format!("pane {}:\n{}", pane.pane_index, captured.trim()));
         }
     }
 
-    if !tmux_session_survived_startup_window("grotto") {
+    if !session_survived_startup_window(mux, &tmux_session) {
         let startup_output = startup_output_chunks.join("\n");
         handle_startup_failure(&project_dir, &grotto, &startup_output)?;
         return Err(grotto_core::GrottoError::Io(std::io::Error::other(
@@ -374,25 +900,91 @@ fn spawn_agents(
         )));
     }
 
-    println!("✅ Spawned {} agents in tmux session 'grotto'", count);
+    println!(
+        "✅ Spawned {} agents in tmux session '{}'",
+        count, tmux_session
+    );
     println!("   Session: {}", session_id);
     println!("   Use 'grotto view' to attach and see all agents");
     println!("   Use 'grotto status' to see task board");
+    register_spawned_session(&project_dir, &grotto, count);
 
-    // Register with daemon if running
-    if daemon::is_daemon_running() {
-        let mut registry = SessionRegistry::load();
-        registry.register(SessionEntry {
-            id: session_id.to_string(),
-            dir: project_dir.display().to_string(),
-            agent_count: count,
-            task: grotto.config.task.clone(),
-        });
-        let _ = registry.save();
-        let url = daemon::daemon_url(9091);
-        println!("   🪸 Portal: {}/{}", url, session_id);
+    Ok(())
+}
+
+/// Register a freshly spawned session with the daemon, if it is running.
+fn register_spawned_session(project_dir: &Path, grotto: &Grotto, count: usize) {
+    if !daemon::is_daemon_running() {
+        return;
+    }
+    let session_id = grotto.config.session_id.as_deref().unwrap_or("unknown");
+    let mut registry = SessionRegistry::load();
+    registry.register(SessionEntry {
+        id: session_id.to_string(),
+        dir: project_dir.display().to_string(),
+        agent_count: count,
+        task: grotto.config.task.clone(),
+    });
+    let _ = registry.save();
+    let url = daemon::daemon_url(9091);
+    println!("   🪸 Portal: {}/{}", url, session_id);
+}
+
+/// Start `grotto run` in the background, logging to `.grotto/run.log`, and
+/// wait for its control socket. With `resume`, it relaunches the agents
+/// after that reason.
+fn start_headless_runner(project_dir: &Path, resume: Option<&str>) -> Result<()> {
+    let exe =
+        env::current_exe().map_err(|e| grotto_core::GrottoError::Io(std::io::Error::other(e)))?;
+    let grotto_dir = project_dir.join(".grotto");
+    let log_path = grotto_dir.join("run.log");
+    let log = fs::File::create(&log_path)?;
+    let mut command = Command::new(exe);
+    command.arg("--dir").arg(project_dir).arg("run");
+    if let Some(reason) = resume {
+        command.arg("--resume").arg(reason);
+    }
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(log.try_clone()?)
+        .stderr(log)
+        .spawn()?;
+
+    let deadline = std::time::Instant::now()
+        + std::time::Duration::from_millis(startup_check_window_ms().max(5000));
+    while !headless::is_running(&grotto_dir) {
+        if child.try_wait()?.is_some() || std::time::Instant::now() > deadline {
+            let output = fs::read_to_string(&log_path).unwrap_or_default();
+            eprintln!("❌ grotto run failed to start:\n{}", output.trim_end());
+            return Err(grotto_core::GrottoError::Io(std::io::Error::other(
+                "Agent startup failed: grotto run exited during startup",
+            )));
+        }
+        std::thread::sleep(std::time::Duration::from_millis(50));
     }
+    Ok(())
+}
 
+fn run_headless(project_dir: PathBuf, resume: Option<String>) -> Result<()> {
+    let mut grotto = Grotto::load(&project_dir)?;
+    if grotto.config.backend.is_tmux() {
+        grotto.config.backend = Backend::Headless;
+        grotto.write_config()?;
+    }
+    println!(
+        "🪸 Running {} agents without tmux (control socket: {})",
+        grotto.active_agent_ids().len(),
+        headless::socket_path(&grotto.grotto_dir).display()
+    );
+    let statuses = headless::run(&project_dir, resume.as_deref())?;
+    for (agent_id, exit_status) in &statuses {
+        println!(
+            "  {} exited with status {}",
+            agent_id,
+            supervise::describe_status(*exit_status)
+        );
+    }
+    println!("🪸 All agents exited");
     Ok(())
 }
 
@@ -410,13 +1002,34 @@ fn startup_check_poll_interval_ms() -> u64 {
         .unwrap_or(250)
 }
 
-fn tmux_session_survived_startup_window(session_name: &str) -> bool {
+/// How long an agent gets to `/exit` before its pane is killed.
+fn exit_grace_ms() -> u64 {
+    env::var("GROTTO_EXIT_GRACE_MS")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .unwrap_or(2000)
+}
+
+/// How often steering re-reads an agent's pane while waiting for it to
+/// finish thinking or for a pasted message to show up.
+fn steer_options() -> SteerOptions {
+    let mut options = SteerOptions::default();
+    if let Some(ms) = env::var("GROTTO_STEER_POLL_MS")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+    {
+        options.poll = std::time::Duration::from_millis(ms);
+    }
+    options
+}
+
+fn session_survived_startup_window(mux: &dyn Multiplexer, session_name: &str) -> bool {
     let window = startup_check_window_ms();
     let poll = startup_check_poll_interval_ms().max(50);
     let mut elapsed = 0;
 
     while elapsed <= window {
-        if !tmux_session_exists(session_name) {
+        if !mux.has_session(session_name) {
             return false;
         }
         std::thread::sleep(std::time::Duration::from_millis(poll));
@@ -426,42 +1039,41 @@ fn tmux_session_survived_startup_window(session_name: &str) -> bool {
     true
 }
 
-fn tmux_session_exists(session_name: &str) -> bool {
-    Command::new("tmux")
-        .args(["has-session", "-t", session_name])
-        .output()
-        .map(|o| o.status.success())
-        .unwrap_or(false)
+/// Whether the session's agents are still up: its tmux session, or the
+/// headless runner.
+fn session_alive(grotto: &Grotto, mux: &dyn Multiplexer) -> bool {
+    match grotto.config.backend {
+        Backend::Tmux => mux.has_session(&grotto.config.tmux_session),
+        Backend::Headless => headless::is_running(&grotto.grotto_dir),
+    }
 }
 
-fn capture_tmux_pane(target: &str) -> Option<String> {
-    let output = Command::new("tmux")
-        .args(["capture-pane", "-t", target, "-p"])
-        .output()
-        .ok()?;
-    if output.status.success() {
-        Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
-    } else {
-        None
+/// Type `keys` (text or tmux key names) into an agent's terminal. Fails
+/// rather than typing into whatever took its place if the pane is dead.
+fn send_agent_keys(
+    grotto: &Grotto,
+    mux: &dyn Multiplexer,
+    agent_id: &str,
+    keys: &[String],
+) -> Result<()> {
+    match grotto.config.backend {
+        Backend::Tmux => {
+            let pane = grotto.pane_target(agent_id)?;
+            mux.check_alive(&pane)?;
+            mux.send_keys(&pane, keys)
+        }
+        Backend::Headless => headless::send_keys(&grotto.grotto_dir, agent_id, keys),
     }
 }
 
-fn push_command_output(label: &str, output: &std::process::Output, chunks: &mut Vec<String>) {
-    let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
-    let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
-    if !stdout.is_empty() {
-        chunks.push(format!("{label} stdout:\n{stdout}"));
-    }
-    if !stderr.is_empty() {
-        chunks.push(format!("{label} stderr:\n{stderr}"));
+fn push_startup_output(label: &str, printed: &str, chunks: &mut Vec<String>) {
+    let printed = printed.trim();
+    if !printed.is_empty() {
+        chunks.push(format!("{label} output:\n{printed}"));
     }
 }
 
-fn handle_startup_failure(
-    project_dir: &PathBuf,
-    grotto: &Grotto,
-    startup_output: &str,
-) -> Result<()> {
+fn handle_startup_failure(project_dir: &Path, grotto: &Grotto, startup_output: &str) -> Result<()> {
     let output_lower = startup_output.to_lowercase();
     let rate_limit_detected = output_lower.contains("rate limit")
         || output_lower.contains("limit exceeded")
@@ -512,25 +1124,16 @@ fn handle_startup_failure(
     Ok(())
 }
 
+/// Lines at the end of an agent's cleaned log checked for completion or
+/// failure.
+const STREAM_TAIL_LINES: usize = 80;
+
 fn infer_terminal_state_from_stream(
     agent: &grotto_core::AgentState,
-    project_dir: &PathBuf,
+    project_dir: &Path,
 ) -> Option<(String, String)> {
-    let stream_path = project_dir
-        .join(".grotto")
-        .join("agents")
-        .join(&agent.id)
-        .join("stream.log");
-
-    let content = fs::read_to_string(stream_path).ok()?;
-    let tail: String = content
-        .lines()
-        .rev()
-        .take(80)
-        .collect::<Vec<_>>()
-        .into_iter()
-        .rev()
-        .collect::<Vec<_>>()
+    let tail = logsink::tail_lines(&project_dir.join(".grotto"), &agent.id, STREAM_TAIL_LINES)
+        .ok()?
         .join("\n");
 
     if tail.trim().is_empty() {
@@ -574,7 +1177,7 @@ fn infer_terminal_state_from_stream(
     None
 }
 
-fn reconcile_terminal_states(project_dir: &PathBuf, grotto: &mut Grotto) -> Result<usize> {
+fn reconcile_terminal_states(project_dir: &Path, grotto: &mut Grotto) -> Result<usize> {
     let mut updated = 0usize;
 
     for (agent_id, agent) in grotto.agents.clone() {
@@ -600,53 +1203,79 @@ fn reconcile_terminal_states(project_dir: &PathBuf, grotto: &mut Grotto) -> Resu
     Ok(updated)
 }
 
-fn view_session() -> Result<()> {
-    let output = Command::new("tmux")
-        .args(["has-session", "-t", "grotto"])
-        .output()
-        .expect("Failed to check tmux session");
-
-    if !output.status.success() {
+fn view_session(project_dir: PathBuf, mux: &dyn Multiplexer) -> Result<()> {
+    let Ok(grotto) = Grotto::load(&project_dir) else {
         eprintln!("No grotto session found. Run 'grotto spawn' first.");
         return Ok(());
+    };
+    if !grotto.config.backend.is_tmux() {
+        eprintln!("This session runs without tmux; use 'grotto status' and 'grotto log <agent>'.");
+        return Ok(());
     }
+    let tmux_session = grotto.config.tmux_session.as_str();
 
-    // Replace current process with tmux attach
-    #[cfg(unix)]
-    {
-        use std::os::unix::process::CommandExt;
-        let error = Command::new("tmux")
-            .args(["attach-session", "-t", "grotto"])
-            .exec();
-        // If we get here, exec failed
-        eprintln!("Failed to attach to tmux session: {}", error);
+    if !mux.has_session(tmux_session) {
+        eprintln!("No grotto session found. Run 'grotto spawn' first.");
+        return Ok(());
     }
 
-    #[cfg(not(unix))]
-    {
-        // On non-unix systems, just run the command normally
-        let status = Command::new("tmux")
-            .args(["attach-session", "-t", "grotto"])
-            .status()
-            .expect("Failed to run tmux");
-        if !status.success() {
-            eprintln!("Failed to attach to tmux session");
-        }
+    if let Err(e) = mux.attach(tmux_session) {
+        eprintln!("Failed to attach to tmux session: {}", e);
     }
 
     Ok(())
 }
 
-fn show_status(project_dir: PathBuf) -> Result<()> {
+fn show_status(project_dir: PathBuf, mux: &dyn Multiplexer) -> Result<()> {
     let mut grotto = Grotto::load(&project_dir)?;
 
     println!("🪸 Grotto Status");
     println!("================");
     println!("Project: {}", project_dir.display());
-    println!("Task: {}\n", grotto.config.task);
+    println!("Task: {}", grotto.config.task);
+    if !grotto.config.until.is_empty() {
+        let outcome = grotto
+            .config
+            .outcome
+            .map(|o| o.to_string())
+            .unwrap_or_else(|| "pending".to_string());
+        println!(
+            "Success criteria: {} ({})",
+            grotto.config.until.join(" && "),
+            outcome
+        );
+    }
+    if let Some(limit) = grotto.config.max_duration_secs {
+        let used = grotto
+            .read_session_events()?
+            .first()
+            .map(|e| (Utc::now() - e.timestamp).num_seconds().max(0) as u64)
+            .unwrap_or(0);
+        println!(
+            "Time budget: {} of {} used",
+            budget::format_duration(used),
+            budget::format_duration(limit)
+        );
+    }
+    if let Some(limit) = grotto.config.agent_timeout_secs {
+        println!("Agent timeout: {}", budget::format_duration(limit));
+    }
+    let usage = usage::report(&grotto)?;
+    if let Some(limit) = grotto.config.max_cost_usd {
+        println!(
+            "Cost budget: {} of {} used",
+            usage::format_cost(usage.total.cost_usd),
+            usage::format_cost(limit)
+        );
+    }
+    println!();
 
-    // Check if tmux session exists
-    let session_exists = tmux_session_exists("grotto");
+    // Check if tmux session (or headless runner) exists
+    let session_exists = session_alive(&grotto, mux);
+    let session_label = match grotto.config.backend {
+        Backend::Tmux => format!("📺 Tmux session: {}", grotto.config.tmux_session),
+        Backend::Headless => "📺 Headless runner".to_string(),
+    };
     if !session_exists {
         let _ = reconcile_terminal_states(&project_dir, &mut grotto);
     }
@@ -656,9 +1285,9 @@ fn show_status(project_dir: PathBuf) -> Result<()> {
     });
 
     if !session_exists && has_startup_failed_agents {
-        println!("📺 Tmux session: grotto (startup failed)");
+        println!("{} (startup failed)", session_label);
     } else if session_exists {
-        println!("📺 Tmux session: grotto (active)");
+        println!("{} (active)", session_label);
     } else {
         // Distinguish normal completion from abrupt disappearance.
         let mut inferred_done = 0usize;
@@ -670,15 +1299,15 @@ fn show_status(project_dir: PathBuf) -> Result<()> {
             }
         }
         if inferred_done > 0 {
-            println!("📺 Tmux session: grotto (completed)");
+            println!("{} (completed)", session_label);
         } else {
-            println!("📺 Tmux session: grotto (not found)");
+            println!("{} (not found)", session_label);
         }
     }
 
     // Capture live tmux state if session is active
     let live_snapshots = if session_exists {
-        grotto_core::monitor::capture_all_agents("grotto", grotto.config.agent_count)
+        grotto_core::monitor::capture_team(&grotto, mux)
     } else {
         Vec::new()
     };
@@ -687,7 +1316,14 @@ fn show_status(project_dir: PathBuf) -> Result<()> {
         .map(|s| (s.agent_id.clone(), s))
         .collect();
 
-    println!("\n🤖 Agents ({}):", grotto.agents.len());
+    // Retired agents stay listed but aren't on the team any more
+    let active = grotto.active_agent_ids().len();
+    let retired = grotto.agents.len() - active;
+    if retired > 0 {
+        println!("\n🤖 Agents ({}, {} retired):", active, retired);
+    } else {
+        println!("\n🤖 Agents ({}):", active);
+    }
     for (agent_id, agent) in &grotto.agents {
         // Prefer live tmux phase over stale file-based state
         let (display_state, display_detail) = if let Some(snap) = live_phases.get(agent_id) {
@@ -722,17 +1358,94 @@ fn show_status(project_dir: PathBuf) -> Result<()> {
             "failed" => "❌",
             "spawning" => "🚀",
             "done" => "✅",
+            "paused" => "⏸️",
+            "timed_out" => "⏱️",
+            "rate_limited" => "🚦",
+            "stuck" => "🆘",
             _ => "❓",
         };
 
+        let role = agent
+            .role
+            .as_deref()
+            .map(|r| format!(" [{}]", r))
+            .unwrap_or_default();
+        if agent.is_retired() {
+            println!("  👋 {}{} - retired", agent_id, role);
+            continue;
+        }
         println!(
-            "  {} {} (pane {}) - {} - {}",
-            status_emoji, agent_id, agent.pane_index, display_state, display_detail
+            "  {} {}{} (pane {}) - {} - {}",
+            status_emoji, agent_id, role, agent.pane_index, display_state, display_detail
         );
+        if let Some(report) = &agent.report {
+            let percent = report
+                .percent
+                .map(|p| format!(" ({}%)", p))
+                .unwrap_or_default();
+            println!(
+                "     📝 {}{} — reported {}",
+                report.message,
+                percent,
+                report.timestamp.format("%H:%M:%S")
+            );
+            if let Some(blocked_on) = &report.blocked_on {
+                println!("     🚧 Blocked on: {}", blocked_on);
+            }
+        }
 
         if let Some(task) = &agent.current_task {
             println!("      Current task: {}", task);
         }
+        if let Some(model) = &agent.model {
+            println!("      Model: {}", model);
+        }
+    }
+
+    if !usage.total.is_empty() {
+        println!("\n💰 Usage: {}", usage.total);
+        for (agent_id, agent_usage) in &usage.agents {
+            if !agent_usage.is_empty() {
+                println!("  {}: {}", agent_id, agent_usage);
+            }
+        }
+        for (task_id, task_usage) in &usage.tasks {
+            println!("  task {}: {}", task_id, task_usage);
+        }
+    }
+
+    let pending: Vec<_> = approvals::list(&grotto.grotto_dir)?
+        .into_iter()
+        .filter(|a| a.status == ApprovalStatus::Pending)
+        .collect();
+    if !pending.is_empty() {
+        println!("\n⏳ Pending approvals ({}):", pending.len());
+        for approval in &pending {
+            println!(
+                "  {} {}: {}",
+                approval.id, approval.agent_id, approval.description
+            );
+        }
+        println!("  Answer with: grotto approve <id> | grotto deny <id> --reason \"...\"");
+    }
+
+    let violations: Vec<_> = grotto
+        .read_session_events()?
+        .into_iter()
+        .filter(|e| e.event_type == "policy_violation")
+        .collect();
+    if !violations.is_empty() {
+        println!("\n🚨 Policy violations ({}):", violations.len());
+        for event in &violations {
+            println!(
+                "  {} {} [{}] {}",
+                event.timestamp.format("%H:%M:%S"),
+                event.agent_id.as_deref().unwrap_or("?"),
+                event.data["rule"].as_str().unwrap_or("?"),
+                event.data["line"].as_str().unwrap_or("")
+            );
+        }
+        println!("  Resume a paused agent with: grotto policy resume <agent>");
     }
 
     println!("\n📋 Task Board:");
@@ -747,7 +1460,12 @@ fn show_status(project_dir: PathBuf) -> Result<()> {
     Ok(())
 }
 
-fn steer_agent(project_dir: PathBuf, agent: String, message: String) -> Result<()> {
+fn steer_agent(
+    project_dir: PathBuf,
+    mux: &dyn Multiplexer,
+    agent: String,
+    message: String,
+) -> Result<()> {
     let grotto = Grotto::load(&project_dir)?;
 
     let agent_state = grotto
@@ -755,40 +1473,26 @@ fn steer_agent(project_dir: PathBuf, agent: String, message: String) -> Result<(
         .get(&agent)
         .ok_or_else(|| grotto_core::GrottoError::AgentNotFound(agent.clone()))?;
 
-    let pane_target = format!("grotto:0.{}", agent_state.pane_index);
-
     println!(
         "💬 Sending message to {} (pane {})...",
         agent, agent_state.pane_index
     );
 
-    let output = Command::new("tmux")
-        .args(["send-keys", "-t", &pane_target, &message, "Enter"])
-        .output()
-        .expect("Failed to send keys to tmux");
-
-    if !output.status.success() {
-        eprintln!(
-            "Failed to send message: {}",
-            String::from_utf8_lossy(&output.stderr)
-        );
-    } else {
-        println!("✅ Message sent to {}", agent);
-
-        // Log the steering event
-        grotto.log_event(
-            "agent_steered",
-            Some(&agent),
-            None,
-            Some(&message),
-            serde_json::json!({ "message": message }),
-        )?;
+    let outcome = steer::steer(&grotto, mux, &agent, &message, steer_options())?;
+    match outcome.delivery {
+        Delivery::Delivered => println!("✅ Message delivered to {}", agent),
+        Delivery::Queued => println!("⏳ {} is thinking; message queued until it is idle", agent),
+        Delivery::Failed => eprintln!(
+            "❌ Failed to deliver message to {}: {}",
+            agent,
+            outcome.reason.unwrap_or_default()
+        ),
     }
 
     Ok(())
 }
 
-fn broadcast_message(project_dir: PathBuf, message: String) -> Result<()> {
+fn broadcast_message(project_dir: PathBuf, mux: &dyn Multiplexer, message: String) -> Result<()> {
     let grotto = Grotto::load(&project_dir)?;
 
     println!(
@@ -796,34 +1500,27 @@ fn broadcast_message(project_dir: PathBuf, message: String) -> Result<()> {
         grotto.agents.len()
     );
 
-    for (agent_id, agent_state) in &grotto.agents {
-        let pane_target = format!("grotto:0.{}", agent_state.pane_index);
-
-        let output = Command::new("tmux")
-            .args(["send-keys", "-t", &pane_target, &message, "Enter"])
-            .output()
-            .expect("Failed to send keys to tmux");
-
-        if output.status.success() {
-            println!("  ✅ Sent to {}", agent_id);
-        } else {
-            println!("  ❌ Failed to send to {}", agent_id);
+    for (agent_id, outcome) in steer::broadcast(&grotto, mux, &message, steer_options())? {
+        match outcome.delivery {
+            Delivery::Delivered => println!("  ✅ Delivered to {}", agent_id),
+            Delivery::Queued => println!("  ⏳ Queued for {} (thinking)", agent_id),
+            Delivery::Failed => println!(
+                "  ❌ Failed to deliver to {}: {}",
+                agent_id,
+                outcome.reason.unwrap_or_default()
+            ),
         }
     }
 
-    // Log the broadcast event
-    grotto.log_event(
-        "broadcast",
-        None,
-        None,
-        Some(&message),
-        serde_json::json!({ "message": message, "agent_count": grotto.agents.len() }),
-    )?;
-
     Ok(())
 }
 
-fn kill_target(project_dir: PathBuf, target: String) -> Result<()> {
+fn kill_target(
+    project_dir: PathBuf,
+    mux: &dyn Multiplexer,
+    target: String,
+    cleanup: bool,
+) -> Result<()> {
     if target == "all" {
         println!("💀 Killing entire grotto session...");
 
@@ -839,48 +1536,70 @@ fn kill_target(project_dir: PathBuf, target: String) -> Result<()> {
             }
         }
 
-        let output = Command::new("tmux")
-            .args(["kill-session", "-t", "grotto"])
-            .output()
-            .expect("Failed to kill tmux session");
+        let killed = match Grotto::load(&project_dir) {
+            Ok(grotto) if !grotto.config.backend.is_tmux() => {
+                headless::request(&grotto.grotto_dir, &headless::Request::Stop).is_ok()
+            }
+            Ok(grotto) => mux.kill_session(&grotto.config.tmux_session).is_ok(),
+            Err(_) => false,
+        };
 
-        if output.status.success() {
+        if killed {
             println!("✅ Grotto session killed");
         } else {
             println!("❌ Failed to kill session (may not exist)");
         }
 
+        if cleanup {
+            let mut grotto = Grotto::load(&project_dir)?;
+            let mut agent_ids: Vec<String> = grotto.agents.keys().cloned().collect();
+            agent_ids.sort();
+            for agent_id in agent_ids {
+                cleanup_worktree(&mut grotto, &agent_id);
+            }
+        }
+
         return Ok(());
     }
 
     // Kill specific agent
-    let grotto = Grotto::load(&project_dir)?;
+    let mut grotto = Grotto::load(&project_dir)?;
     let agent_state = grotto
         .agents
         .get(&target)
+        .cloned()
         .ok_or_else(|| grotto_core::GrottoError::AgentNotFound(target.clone()))?;
 
-    let pane_target = format!("grotto:0.{}", agent_state.pane_index);
-
     println!(
         "💀 Killing agent {} (pane {})...",
         target, agent_state.pane_index
     );
 
     // Try graceful exit first
-    let _ = Command::new("tmux")
-        .args(["send-keys", "-t", &pane_target, "/exit", "Enter"])
-        .output();
+    let _ = send_agent_keys(
+        &grotto,
+        mux,
+        &target,
+        &grotto.agent_runtime(&target).exit_keys(),
+    );
 
     // Wait a moment then force kill the pane
-    std::thread::sleep(std::time::Duration::from_secs(2));
-
-    let output = Command::new("tmux")
-        .args(["kill-pane", "-t", &pane_target])
-        .output()
-        .expect("Failed to kill tmux pane");
+    std::thread::sleep(std::time::Duration::from_millis(exit_grace_ms()));
+
+    let killed = match grotto.config.backend {
+        Backend::Tmux => mux.kill_pane(&grotto.pane_target(&target)?),
+        Backend::Headless => headless::request(
+            &grotto.grotto_dir,
+            &headless::Request::Kill {
+                agent_id: target.clone(),
+            },
+        )
+        .map(drop),
+    };
 
-    if output.status.success() {
+    if let Err(e) = killed {
+        println!("❌ Failed to kill agent {}: {}", target, e);
+    } else {
         println!("✅ Agent {} killed", target);
 
         // Log the kill event
@@ -889,39 +1608,171 @@ fn kill_target(project_dir: PathBuf, target: String) -> Result<()> {
             Some(&target),
             None,
             None,
-            serde_json::json!({ "pane_index": agent_state.pane_index }),
+            serde_json::json!({
+                "pane_index": agent_state.pane_index,
+                "pane_id": agent_state.pane_id,
+            }),
         )?;
-    } else {
-        println!("❌ Failed to kill agent {}", target);
     }
 
+    if cleanup {
+        cleanup_worktree(&mut grotto, &target);
+    }
+
+    Ok(())
+}
+
+fn scale_team(project_dir: PathBuf, mux: &dyn Multiplexer, count: usize) -> Result<()> {
+    require_live_session(&project_dir, mux)?;
+    let mut grotto = Grotto::load(&project_dir)?;
+    let current = grotto.active_agent_ids().len();
+    if count == current {
+        println!("🪸 Team already has {} agents", count);
+        return Ok(());
+    }
+
+    println!("🪸 Scaling team from {} to {} agents...", current, count);
+    let snapshots = grotto_core::monitor::capture_team(&grotto, mux);
+    let change = scale::scale(
+        &mut grotto,
+        mux,
+        count,
+        &snapshots,
+        std::time::Duration::from_millis(exit_grace_ms()),
+    )?;
+    for agent_id in &change.added {
+        println!(
+            "  ➕ Added {} (pane {})",
+            agent_id, grotto.agents[agent_id].pane_index
+        );
+    }
+    for (agent_id, requeued_task) in &change.retired {
+        match requeued_task {
+            Some(task_id) => println!("  👋 Retired {}, requeued {}", agent_id, task_id),
+            None => println!("  👋 Retired {}", agent_id),
+        }
+    }
+    update_registered_agent_count(&grotto);
+    println!("✅ Team now has {} agents", grotto.config.agent_count);
+    Ok(())
+}
+
+fn add_team_agent(
+    project_dir: PathBuf,
+    mux: &dyn Multiplexer,
+    role: Option<String>,
+    runtime: Option<String>,
+    task: Option<String>,
+) -> Result<()> {
+    require_live_session(&project_dir, mux)?;
+    let runtime: Option<RuntimeConfig> = runtime.map(|r| r.parse()).transpose()?;
+    if let Some(runtime) = &runtime
+        && let Err(missing) = Grotto::check_dependencies(Backend::Tmux, [runtime])
+    {
+        return Err(grotto_core::GrottoError::Io(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("Missing dependencies: {}", missing.join(", ")),
+        )));
+    }
+    let mut grotto = Grotto::load(&project_dir)?;
+    let agent_id = scale::add_agent(&mut grotto, mux, role.as_deref(), runtime, task.as_deref())?;
+    let role = role.map(|r| format!(" as {}", r)).unwrap_or_default();
+    println!(
+        "➕ Added {}{} (pane {})",
+        agent_id, role, grotto.agents[&agent_id].pane_index
+    );
+    if let Some(task_id) = &task {
+        println!("   Claimed task: {}", task_id);
+    }
+    if let Some(model) = &grotto.agents[&agent_id].model {
+        println!("   Model: {}", model);
+    }
+    update_registered_agent_count(&grotto);
+    println!("✅ Team now has {} agents", grotto.config.agent_count);
     Ok(())
 }
 
-fn show_log(project_dir: PathBuf, agent: String) -> Result<()> {
+fn require_live_session(project_dir: &Path, mux: &dyn Multiplexer) -> Result<()> {
+    let grotto = Grotto::load(project_dir).ok();
+    if let Some(grotto) = &grotto
+        && !grotto.config.backend.is_tmux()
+    {
+        return Err(grotto_core::GrottoError::Io(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "Resizing the team needs the tmux backend; this session runs headless",
+        )));
+    }
+    if grotto.is_some_and(|g| mux.has_session(&g.config.tmux_session)) {
+        Ok(())
+    } else {
+        Err(grotto_core::GrottoError::Io(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "No grotto session found. Run 'grotto spawn' first.",
+        )))
+    }
+}
+
+/// Keep the daemon's session list in step with the team size.
+fn update_registered_agent_count(grotto: &Grotto) {
+    let Some(session_id) = &grotto.config.session_id else {
+        return;
+    };
+    let mut registry = SessionRegistry::load();
+    if let Some(entry) = registry.sessions.get_mut(session_id) {
+        entry.agent_count = grotto.config.agent_count;
+        let _ = registry.save();
+    }
+}
+
+fn cleanup_worktree(grotto: &mut Grotto, agent_id: &str) {
+    match grotto.remove_worktree(agent_id) {
+        Ok(Some(path)) => println!("🧹 Removed worktree {}", path.display()),
+        Ok(None) => {}
+        Err(e) => eprintln!("❌ Failed to remove worktree for {}: {}", agent_id, e),
+    }
+}
+
+/// Lines of the cleaned log shown by `grotto log` once an agent's pane is gone.
+const LOG_TAIL_LINES: usize = 50;
+
+fn show_log(project_dir: PathBuf, mux: &dyn Multiplexer, agent: String) -> Result<()> {
     let grotto = Grotto::load(&project_dir)?;
     let agent_state = grotto
         .agents
         .get(&agent)
         .ok_or_else(|| grotto_core::GrottoError::AgentNotFound(agent.clone()))?;
 
-    let pane_target = format!("grotto:0.{}", agent_state.pane_index);
-
     println!("📜 Log for {} (pane {}):", agent, agent_state.pane_index);
     println!("{}", "=".repeat(50));
 
-    let output = Command::new("tmux")
-        .args(["capture-pane", "-t", &pane_target, "-p"])
-        .output()
-        .expect("Failed to capture tmux pane");
+    if !grotto.config.backend.is_tmux() {
+        match headless::request(
+            &grotto.grotto_dir,
+            &headless::Request::Capture {
+                agent_id: agent.clone(),
+            },
+        ) {
+            Ok(response) => print!("{}", response.content.unwrap_or_default()),
+            Err(e) => println!("❌ Failed to capture output: {}", e),
+        }
+        return Ok(());
+    }
 
-    if output.status.success() {
-        print!("{}", String::from_utf8_lossy(&output.stdout));
-    } else {
-        println!(
-            "❌ Failed to capture pane: {}",
-            String::from_utf8_lossy(&output.stderr)
-        );
+    let pane = grotto.pane_target(&agent)?;
+    match mux.capture(&pane, 0) {
+        Some(content) => print!("{}", content),
+        None => {
+            // The pane is gone, but its output was logged
+            let tail = logsink::tail_lines(&grotto.grotto_dir, &agent, LOG_TAIL_LINES)?;
+            if tail.is_empty() {
+                println!("❌ Failed to capture pane {}: it was closed", pane);
+            } else {
+                println!("(pane {} was closed; last lines of its log)", pane);
+                for line in tail {
+                    println!("{}", line);
+                }
+            }
+        }
     }
 
     Ok(())
@@ -958,38 +1809,168 @@ fn show_events(project_dir: PathBuf, follow: bool) -> Result<()> {
     Ok(())
 }
 
-fn wait_for_completion(project_dir: PathBuf, interval: u64) -> Result<()> {
+fn wait_for_completion(project_dir: PathBuf, mux: &dyn Multiplexer, interval: u64) -> Result<()> {
     println!("⏳ Waiting for grotto agents to finish...");
 
     let start = std::time::Instant::now();
 
+    let criteria = Grotto::load(&project_dir)
+        .map(|g| g.config.until.clone())
+        .ok()
+        .filter(|until| !until.is_empty());
+    let budgeted = Grotto::load(&project_dir)
+        .map(|g| {
+            g.config.max_duration_secs.is_some()
+                || g.config.agent_timeout_secs.is_some()
+                || g.config.max_cost_usd.is_some()
+        })
+        .unwrap_or(false);
+    let supervised = Grotto::load(&project_dir)
+        .map(|g| !g.config.restart.is_never())
+        .unwrap_or(false);
+    let mut rounds: Vec<verify::RoundResult> = Vec::new();
+    let mut outcome = None;
+    // After feedback is broadcast, wait for agents to pick it up before re-verifying
+    let mut awaiting_activity = false;
+    let mut idle_polls = 0;
+
     loop {
-        // Check if tmux session still exists
-        let session_alive = Command::new("tmux")
-            .args(["has-session", "-t", "grotto"])
-            .output()
-            .map(|o| o.status.success())
+        if budgeted && let Err(e) = enforce_budgets(&project_dir, mux) {
+            eprintln!("⚠️  Budget check failed: {}", e);
+        }
+        if supervised && let Err(e) = supervise_once(&project_dir, mux) {
+            eprintln!("⚠️  Supervisor pass failed: {}", e);
+        }
+        if let Err(e) = flush_steer_queues(&project_dir, mux) {
+            eprintln!("⚠️  Could not deliver queued messages: {}", e);
+        }
+
+        // Check if tmux session (or headless runner) still exists
+        let session_alive = Grotto::load(&project_dir)
+            .map(|g| session_alive(&g, mux))
             .unwrap_or(false);
 
-        if !session_alive {
-            break;
+        if criteria.is_none() {
+            if !session_alive {
+                break;
+            }
+            wait_tick(start, interval);
+            continue;
         }
 
-        let elapsed = start.elapsed().as_secs();
-        let mins = elapsed / 60;
-        let secs = elapsed % 60;
-        eprint!("\r⏳ Agents working... ({mins}m {secs}s)   ");
+        let settled = !session_alive || {
+            // The team can be scaled while waiting
+            let grotto = Grotto::load(&project_dir)?;
+            let snapshots = grotto_core::monitor::capture_team(&grotto, mux);
+            if !verify::agents_settled(&snapshots) {
+                awaiting_activity = false;
+                idle_polls = 0;
+                false
+            } else if awaiting_activity {
+                idle_polls += 1;
+                idle_polls >= RECHECK_IDLE_POLLS
+            } else {
+                true
+            }
+        };
 
-        std::thread::sleep(std::time::Duration::from_secs(interval));
+        if settled {
+            let mut grotto = Grotto::load(&project_dir)?;
+            let round = rounds.len() as u32 + 1;
+            eprintln!();
+            println!(
+                "🧪 Verification round {}/{}...",
+                round, grotto.config.max_rounds
+            );
+            let result = verify::run_round(&grotto, round)?;
+            for check in &result.checks {
+                let emoji = if check.success { "✅" } else { "❌" };
+                println!("  {} {}", emoji, check.command);
+            }
+            let passed = result.success();
+            let exhausted = round >= grotto.config.max_rounds;
+            let feedback = result.feedback_message();
+            let reason = result.relaunch_reason();
+            rounds.push(result);
+
+            if passed || exhausted {
+                let finished = if passed {
+                    verify::SessionOutcome::Succeeded
+                } else {
+                    verify::SessionOutcome::Failed
+                };
+                verify::finish(&mut grotto, finished, round)?;
+                outcome = Some(finished);
+                break;
+            }
+
+            println!("📢 Sending failure output back to the team");
+            steer::broadcast(&grotto, mux, &feedback, steer_options())?;
+            for agent_id in relaunch_exited_agents(&project_dir, mux, &reason)? {
+                println!("🔁 Relaunched {} with the failure output", agent_id);
+            }
+            awaiting_activity = true;
+            idle_polls = 0;
+        }
+
+        wait_tick(start, interval);
     }
 
     let elapsed = start.elapsed();
     let mins = elapsed.as_secs() / 60;
     let secs = elapsed.as_secs() % 60;
 
+    let timeouts = Grotto::load(&project_dir)
+        .and_then(|g| budget::timeouts(&g))
+        .unwrap_or_default();
+    let session_timeout = timeouts.iter().find(|t| t.scope.stops_team());
+
     eprintln!();
     println!("\n🪸 Grotto Complete ({mins}m {secs}s)");
     println!("{}", "=".repeat(50));
+    if let Some(timeout) = session_timeout {
+        println!(
+            "⏱️  Session stopped: {} {} budget exceeded",
+            timeout.scope.format(timeout.limit),
+            if timeout.scope == BudgetScope::Cost {
+                "cost"
+            } else {
+                "time"
+            }
+        );
+    }
+    let agent_timeouts: Vec<String> = timeouts
+        .iter()
+        .filter(|t| t.scope == BudgetScope::Agent)
+        .map(|t| {
+            let requeued = t
+                .requeued_task
+                .as_deref()
+                .map(|task| format!(", requeued {}", task))
+                .unwrap_or_default();
+            format!(
+                "{} after {}{}",
+                t.agent_id.as_deref().unwrap_or("?"),
+                t.scope.format(t.limit),
+                requeued
+            )
+        })
+        .collect();
+    for timeout in &agent_timeouts {
+        println!("⏱️  Timed out: {}", timeout);
+    }
+    if let Some(outcome) = outcome {
+        let emoji = match outcome {
+            verify::SessionOutcome::Succeeded => "✅",
+            verify::SessionOutcome::Failed => "❌",
+        };
+        println!(
+            "{} Session {} after {} verification round(s)",
+            emoji,
+            outcome,
+            rounds.len()
+        );
+    }
 
     // Load final state
     if let Ok(grotto) = Grotto::load(&project_dir) {
@@ -1029,18 +2010,594 @@ fn wait_for_completion(project_dir: PathBuf, interval: u64) -> Result<()> {
     }
 
     // Write a summary file for the lead to consume
-    let summary_path = project_dir.join(".grotto").join("summary.md");
-    let summary = format!(
-        "# Grotto Run Summary\n\n\
-         - Duration: {mins}m {secs}s\n\
-         - Status: All agents exited\n\
+    let status = match (outcome, session_timeout) {
+        (Some(outcome), _) => format!("Session {}", outcome),
+        (None, Some(timeout)) if timeout.scope == BudgetScope::Cost => format!(
+            "Stopped ({} cost budget exceeded)",
+            timeout.scope.format(timeout.limit)
+        ),
+        (None, Some(timeout)) => format!(
+            "Timed out ({} session budget exceeded)",
+            timeout.scope.format(timeout.limit)
+        ),
+        (None, None) => "All agents exited".to_string(),
+    };
+    let timeout_lines: String = agent_timeouts
+        .iter()
+        .map(|t| format!("- Timed out: {}\n", t))
+        .collect();
+    let run_section = format!(
+        "- Duration: {mins}m {secs}s\n\
+         - Status: {status}\n\
+         {timeout_lines}\
          - See `events.jsonl` for full event log\n\
          - See `tasks.md` for final task board\n"
     );
-    let _ = fs::write(&summary_path, &summary);
+    if let Ok(grotto) = Grotto::load(&project_dir) {
+        let _ = grotto.write_summary_section("Run", &run_section);
+        if !rounds.is_empty() {
+            let body: String = rounds.iter().map(|r| r.to_markdown()).collect();
+            let _ = grotto.write_summary_section("Verification", &body);
+        }
+        if grotto.config.git.is_some() {
+            match changes::build(&grotto) {
+                Ok(report) => {
+                    println!(
+                        "🧾 Changes: {} commit(s) since {}",
+                        report.commits.len(),
+                        &report.baseline.head[..8.min(report.baseline.head.len())]
+                    );
+                    let _ = grotto.write_summary_section("Changes", &report.to_markdown());
+                }
+                Err(e) => eprintln!("⚠️  Could not build change report: {}", e),
+            }
+        }
+        if let Err(e) = scorecard::refresh(&grotto) {
+            eprintln!("⚠️  Could not build scorecard: {}", e);
+        }
+    }
 
     println!("\n✅ Summary written to .grotto/summary.md");
 
+    match (outcome, rounds.last().and_then(|r| r.failed_check())) {
+        (Some(verify::SessionOutcome::Failed), Some(failed)) => {
+            Err(grotto_core::GrottoError::Verification(format!(
+                "`{}` still failing after {} round(s)",
+                failed.command,
+                rounds.len()
+            )))
+        }
+        (Some(verify::SessionOutcome::Succeeded), _) => Ok(()),
+        _ => match session_timeout {
+            Some(timeout) if timeout.scope == BudgetScope::Cost => {
+                Err(grotto_core::GrottoError::BudgetExceeded(format!(
+                    "session stopped after spending {}",
+                    timeout.scope.format(timeout.limit)
+                )))
+            }
+            Some(timeout) => Err(grotto_core::GrottoError::BudgetExceeded(format!(
+                "session stopped after {}",
+                timeout.scope.format(timeout.limit)
+            ))),
+            None => Ok(()),
+        },
+    }
+}
+
+/// Run the supervisor in the foreground until the tmux session ends.
+fn supervise_agents(project_dir: PathBuf, mux: &dyn Multiplexer, interval: u64) -> Result<()> {
+    let grotto = Grotto::load(&project_dir)?;
+    if !grotto.config.backend.is_tmux() {
+        println!(
+            "ℹ️  This session runs without tmux; grotto run applies the restart policy itself"
+        );
+        return Ok(());
+    }
+    if grotto.config.restart.is_never() {
+        println!("ℹ️  Restart policy is never: exited agents are recorded but not restarted");
+    }
+    println!(
+        "👀 Supervising agents (restart: {}, up to {} restarts per agent)...",
+        grotto.config.restart, grotto.config.max_restarts
+    );
+    while mux.has_session(&grotto.config.tmux_session) {
+        supervise_once(&project_dir, mux)?;
+        std::thread::sleep(std::time::Duration::from_secs(interval));
+    }
+    println!("🪸 Session ended");
+    Ok(())
+}
+
+/// Restart or settle agents whose process exited.
+fn supervise_once(project_dir: &Path, mux: &dyn Multiplexer) -> Result<()> {
+    let mut grotto = Grotto::load(project_dir)?;
+    if !grotto.config.backend.is_tmux() {
+        return Ok(());
+    }
+    // The daemon's monitor supervises too; whoever holds the lock goes
+    let Some(_lock) = grotto.try_lock("supervise")? else {
+        return Ok(());
+    };
+    for decision in supervise::tick(&mut grotto, mux)? {
+        match decision {
+            supervise::Decision::Restart {
+                agent_id,
+                exit_status,
+                attempt,
+            } => println!(
+                "🔁 Restarted {} after exit {} ({} of {})",
+                agent_id,
+                supervise::describe_status(exit_status),
+                attempt,
+                grotto.config.max_restarts
+            ),
+            supervise::Decision::Stop {
+                agent_id,
+                exit_status,
+                gave_up,
+            } => println!(
+                "🛑 {} exited with status {}{}",
+                agent_id,
+                supervise::describe_status(exit_status),
+                if gave_up { ", out of restarts" } else { "" }
+            ),
+        }
+    }
+    Ok(())
+}
+
+/// Deliver steering messages queued while their agents were thinking.
+fn flush_steer_queues(project_dir: &Path, mux: &dyn Multiplexer) -> Result<()> {
+    let grotto = Grotto::load(project_dir)?;
+    // The daemon's monitor delivers queued messages too; whoever holds the lock goes
+    let Some(_lock) = grotto.try_lock("steer")? else {
+        return Ok(());
+    };
+    for (agent_id, outcome) in steer::flush_queued(&grotto, mux, steer_options())? {
+        match outcome.delivery {
+            Delivery::Failed => println!(
+                "❌ Queued message for {} failed: {}",
+                agent_id,
+                outcome.reason.unwrap_or_default()
+            ),
+            _ => println!("📨 Delivered queued message to {}", agent_id),
+        }
+    }
+    Ok(())
+}
+
+/// Warn agents nearing a time or cost budget and stop the ones past it.
+fn enforce_budgets(project_dir: &Path, mux: &dyn Multiplexer) -> Result<()> {
+    let mut grotto = Grotto::load(project_dir)?;
+    // The daemon's monitor enforces budgets too; whoever holds the lock goes
+    let Some(_lock) = grotto.try_lock("budget")? else {
+        return Ok(());
+    };
+    let alerts = budget::check(&grotto, Utc::now())?;
+    if alerts.is_empty() {
+        return Ok(());
+    }
+
+    eprintln!();
+    for alert in alerts
+        .iter()
+        .filter(|a| a.kind == budget::AlertKind::Warning)
+    {
+        println!(
+            "⏰ {} budget: {} of {} used, warning {}",
+            alert.scope,
+            alert.scope.format(alert.used),
+            alert.scope.format(alert.limit),
+            alert.agent_id.as_deref().unwrap_or("all agents")
+        );
+    }
+    let grace = std::time::Duration::from_millis(exit_grace_ms());
+    for timeout in budget::enforce(&mut grotto, mux, &alerts, grace)? {
+        println!(
+            "⏱️  Stopping {}: {} exceeded",
+            timeout.agent_id.as_deref().unwrap_or("?"),
+            timeout.scope.describe(timeout.limit)
+        );
+    }
+    Ok(())
+}
+
+/// Relaunch agents whose process exited, with `reason` in their resume
+/// prompt, so the next verification round has someone to fix the failure:
+/// print-mode runtimes like `claude -p` stop after one pass, taking their pane
+/// (or the whole session) with them. Agents stopped by a budget stay stopped.
+/// Returns the relaunched agents.
+fn relaunch_exited_agents(
+    project_dir: &Path,
+    mux: &dyn Multiplexer,
+    reason: &str,
+) -> Result<Vec<String>> {
+    let mut grotto = Grotto::load(project_dir)?;
+    let exited: Vec<String> = {
+        let terminals = SessionMux::new(&grotto, mux);
+        grotto
+            .active_agent_ids()
+            .into_iter()
+            .filter(|id| grotto.agents[id].state != "timed_out")
+            .filter(|id| {
+                grotto
+                    .pane_target(id)
+                    .is_ok_and(|pane| terminals.check_alive(&pane).is_err())
+            })
+            .collect()
+    };
+    if exited.is_empty() {
+        return Ok(exited);
+    }
+
+    if !grotto.config.backend.is_tmux() {
+        // A runner that is still up keeps its exited agents to its restart
+        // policy
+        if headless::is_running(&grotto.grotto_dir) {
+            return Ok(Vec::new());
+        }
+        start_headless_runner(project_dir, Some(reason))?;
+        return Ok(exited);
+    }
+
+    let session = grotto.config.tmux_session.clone();
+    for agent_id in &exited {
+        grotto.assign_model(agent_id)?;
+        let launch = grotto.agent_resume_launch(agent_id, reason);
+        let pane = grotto.pane_target(agent_id)?;
+        if mux.pane_status(&pane).is_some() {
+            // Kept by remain-on-exit
+            mux.respawn(&pane, &launch)?;
+        } else {
+            let opened = if mux.has_session(&session) {
+                mux.split(&session, &launch)?
+            } else {
+                let opened = mux.new_session(&session, &launch)?;
+                if !grotto.config.restart.is_never() {
+                    let _ = mux.keep_exited_panes(&session);
+                }
+                opened
+            };
+            grotto.set_pane_id(agent_id, opened.pane_id)?;
+        }
+        let stream_path = grotto
+            .grotto_dir
+            .join("agents")
+            .join(agent_id)
+            .join(logsink::RAW_LOG);
+        let _ = mux.pipe(&grotto.pane_target(agent_id)?, &stream_path);
+    }
+    Ok(exited)
+}
+
+/// Polls to wait for agents to react to feedback before verifying again anyway.
+const RECHECK_IDLE_POLLS: u32 = 12;
+
+fn wait_tick(start: std::time::Instant, interval: u64) {
+    let elapsed = start.elapsed().as_secs();
+    let mins = elapsed / 60;
+    let secs = elapsed % 60;
+    eprint!("\r⏳ Agents working... ({mins}m {secs}s)   ");
+
+    std::thread::sleep(std::time::Duration::from_secs(interval));
+}
+
+fn show_report(project_dir: PathBuf) -> Result<()> {
+    let grotto = Grotto::load(&project_dir)?;
+    let report = changes::build(&grotto)?;
+
+    println!("🧾 Changes since spawn");
+    println!("{}", "=".repeat(50));
+    print!("{}", report.to_markdown());
+
+    grotto.write_summary_section("Changes", &report.to_markdown())?;
+    println!("\n📝 Change report written to .grotto/summary.md");
+    Ok(())
+}
+
+fn show_scorecard(project_dir: PathBuf, json: bool) -> Result<()> {
+    let grotto = Grotto::load(&project_dir)?;
+    let card = scorecard::refresh(&grotto)?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&card)?);
+        return Ok(());
+    }
+
+    println!("📊 Grotto Scorecard");
+    println!("{}", "=".repeat(50));
+    println!("📋 Task: {}", card.task);
+    print!("{}", card.to_markdown());
+    println!("\n📝 Written to .grotto/scorecard.json and .grotto/summary.md");
+    Ok(())
+}
+
+/// Longest activity line shown per phase change in `grotto timeline`.
+const TIMELINE_LINE_CHARS: usize = 60;
+
+fn show_timeline(project_dir: PathBuf, agent: Option<String>) -> Result<()> {
+    let grotto = Grotto::load(&project_dir)?;
+    let mut timeline = monitor::session_timeline(&grotto)?;
+    if let Some(agent) = &agent {
+        if !grotto.agents.contains_key(agent) {
+            return Err(grotto_core::GrottoError::AgentNotFound(agent.clone()));
+        }
+        timeline.retain(|id, _| id == agent);
+    }
+    if timeline.values().all(Vec::is_empty) {
+        println!(
+            "No phase changes recorded yet (the daemon records them; see `grotto daemon start`)"
+        );
+        return Ok(());
+    }
+
+    let now = Utc::now();
+    for (agent_id, transitions) in &timeline {
+        println!("🕒 {}", agent_id);
+        if transitions.is_empty() {
+            println!("  (no phase changes recorded)");
+        }
+        for (i, transition) in transitions.iter().enumerate() {
+            let (until, suffix) = match transitions.get(i + 1) {
+                Some(next) => (next.timestamp, ""),
+                None => (now, " so far"),
+            };
+            let secs = (until - transition.timestamp).num_seconds().max(0) as u64;
+            let lasted = format!("{}{}", budget::format_duration(secs), suffix);
+            let line: String = transition
+                .last_activity
+                .trim()
+                .chars()
+                .take(TIMELINE_LINE_CHARS)
+                .collect();
+            println!(
+                "  {}  {:<12}  {:<14}  {}",
+                transition.timestamp.format("%H:%M:%S"),
+                transition.phase.to_string(),
+                lasted,
+                line
+            );
+        }
+    }
+    Ok(())
+}
+
+fn run_policy(project_dir: PathBuf, mux: &dyn Multiplexer, action: PolicyAction) -> Result<()> {
+    match action {
+        PolicyAction::Check { line } => check_policy(project_dir, line),
+        PolicyAction::Resume { agent } => resume_agent(project_dir, mux, agent),
+    }
+}
+
+fn check_policy(project_dir: PathBuf, line: Option<String>) -> Result<()> {
+    let grotto_dir = project_dir.join(".grotto");
+    let Some(policy) = Policy::load(&grotto_dir)? else {
+        println!(
+            "No policy found at {}",
+            grotto_dir.join(policy::POLICY_FILE).display()
+        );
+        return Ok(());
+    };
+    let matcher = policy.compile()?;
+
+    println!(
+        "🛡️  Policy ({} deny rules, pause: {:?})",
+        policy.deny.len(),
+        policy.pause
+    );
+    for rule in &policy.deny {
+        if let Some(command) = &rule.command {
+            println!("  {} command: {}", rule.name, command);
+        }
+        if let Some(path) = &rule.path {
+            println!("  {} path: {}", rule.name, path);
+        }
+    }
+
+    if let Some(line) = line {
+        match matcher.check_line(&line) {
+            Some(m) => {
+                println!("\n🚨 Denied by '{}' ({:?}): {}", m.rule, m.kind, m.matched);
+                std::process::exit(1);
+            }
+            None => println!("\n✅ Allowed"),
+        }
+    }
+    Ok(())
+}
+
+fn run_monitor(project_dir: PathBuf, action: MonitorAction) -> Result<()> {
+    match action {
+        MonitorAction::Test {
+            capture,
+            runtime,
+            agent,
+        } => test_phase_rules(project_dir, capture, runtime, agent),
+        MonitorAction::Rules { runtime } => {
+            let rules = load_phase_rules(&project_dir, runtime, None)?;
+            print_rules_source(&rules);
+            for rule in rules.rules() {
+                println!(
+                    "  {:>4}  {:<16} → {:<12} window {:<3} {}",
+                    rule.priority,
+                    rule.name,
+                    rule.phase.to_string(),
+                    rule.window,
+                    rule.pattern
+                );
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Phase rules for `--runtime`, `--agent`, or the session's runtime
+/// (Claude Code's rules outside a session).
+fn load_phase_rules(
+    project_dir: &Path,
+    runtime: Option<String>,
+    agent: Option<String>,
+) -> Result<PhaseRules> {
+    let grotto_dir = project_dir.join(".grotto");
+    if let Some(agent) = agent {
+        return Grotto::load(project_dir)?.phase_rules(&agent);
+    }
+    let kind = match runtime {
+        Some(kind) => {
+            if !RUNTIME_KINDS.contains(&kind.as_str()) {
+                return Err(grotto_core::GrottoError::Io(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!(
+                        "Unknown runtime '{kind}'. Valid runtimes: {}",
+                        RUNTIME_KINDS.join(", ")
+                    ),
+                )));
+            }
+            kind
+        }
+        None => Grotto::load(project_dir)
+            .map(|g| g.config.runtime.kind().to_string())
+            .unwrap_or_else(|_| "claude".to_string()),
+    };
+    PhaseRules::load(&grotto_dir, &kind)
+}
+
+fn print_rules_source(rules: &PhaseRules) {
+    match &rules.source {
+        RulesSource::BuiltIn => println!("🔎 Phase rules: built-in"),
+        RulesSource::File(path) => println!("🔎 Phase rules: {}", path.display()),
+    }
+}
+
+fn test_phase_rules(
+    project_dir: PathBuf,
+    capture: PathBuf,
+    runtime: Option<String>,
+    agent: Option<String>,
+) -> Result<()> {
+    let rules = load_phase_rules(&project_dir, runtime, agent)?;
+    let content = if capture.as_os_str() == "-" {
+        std::io::read_to_string(std::io::stdin())?
+    } else {
+        fs::read_to_string(&capture)?
+    };
+    let content = monitor::strip_ansi(&content);
+    let explanation = rules.explain(&content);
+
+    print_rules_source(&rules);
+    println!("Phase: {}", explanation.phase);
+    match &explanation.matched {
+        Some(m) => {
+            println!(
+                "  Matched '{}' (priority {}, window {}): {}",
+                m.rule, m.priority, m.window, m.pattern
+            );
+            println!(
+                "    line {} from bottom: {}",
+                m.line_from_end,
+                m.line.trim()
+            );
+        }
+        None => println!("  No rule matched; using the default phase"),
+    }
+    if !explanation.shadowed.is_empty() {
+        println!("  Also matched, but outranked:");
+        for m in &explanation.shadowed {
+            println!(
+                "    '{}' → {} (priority {}), line {}: {}",
+                m.rule,
+                m.phase,
+                m.priority,
+                m.line_from_end,
+                m.line.trim()
+            );
+        }
+    }
+    Ok(())
+}
+
+fn resume_agent(project_dir: PathBuf, mux: &dyn Multiplexer, agent: String) -> Result<()> {
+    let mut grotto = Grotto::load(&project_dir)?;
+    policy::resume(&mut grotto, mux, &agent)?;
+    println!("▶️  Resumed {}", agent);
+    Ok(())
+}
+
+fn request_approval(
+    project_dir: PathBuf,
+    description: String,
+    agent: String,
+    timeout: u64,
+) -> Result<()> {
+    let grotto = Grotto::load(&project_dir)?;
+    let approval = approvals::request(&grotto, &agent, &description)?;
+    println!(
+        "⏳ {} waiting for approval (timeout {}s): {}",
+        approval.id, timeout, description
+    );
+
+    let decided = approvals::wait_for_decision(
+        &grotto,
+        &approval.id,
+        std::time::Duration::from_secs(timeout),
+        std::time::Duration::from_secs(1),
+    )?;
+    let reason = decided
+        .reason
+        .as_deref()
+        .map(|r| format!(": {}", r))
+        .unwrap_or_default();
+    match decided.status {
+        ApprovalStatus::Approved => {
+            println!("✅ {} approved{} — go ahead", decided.id, reason);
+            Ok(())
+        }
+        ApprovalStatus::Denied => {
+            println!("❌ {} denied{} — do not proceed", decided.id, reason);
+            std::process::exit(1);
+        }
+        _ => {
+            println!("⌛ {} expired{} — do not proceed", decided.id, reason);
+            std::process::exit(2);
+        }
+    }
+}
+
+fn decide_approval(
+    project_dir: PathBuf,
+    id: String,
+    status: ApprovalStatus,
+    reason: Option<String>,
+) -> Result<()> {
+    let grotto = Grotto::load(&project_dir)?;
+    let approval = approvals::decide(&grotto, &id, status, reason.as_deref(), "lead")?;
+    println!(
+        "{} {} {} for {}: {}",
+        if status == ApprovalStatus::Approved {
+            "✅"
+        } else {
+            "❌"
+        },
+        approval.id,
+        approval.status,
+        approval.agent_id,
+        approval.description
+    );
+    Ok(())
+}
+
+fn add_task(
+    project_dir: PathBuf,
+    task_id: String,
+    description: String,
+    labels: Vec<String>,
+) -> Result<()> {
+    let mut grotto = Grotto::load(&project_dir)?;
+
+    grotto.add_task(&task_id, &description, labels)?;
+
+    println!("📝 Task '{}' added to the board", task_id);
+
     Ok(())
 }
 
@@ -1054,6 +2611,22 @@ fn claim_task(project_dir: PathBuf, task_id: String, agent: String) -> Result<()
     Ok(())
 }
 
+fn report_progress(
+    project_dir: PathBuf,
+    agent: String,
+    progress: String,
+    percent: Option<u8>,
+    blocked_on: Option<String>,
+) -> Result<()> {
+    let mut grotto = Grotto::load(&project_dir)?;
+
+    grotto.report_progress(&agent, &progress, percent, blocked_on.as_deref())?;
+
+    println!("📝 Progress recorded for {}", agent);
+
+    Ok(())
+}
+
 fn complete_task(project_dir: PathBuf, task_id: String) -> Result<()> {
     let mut grotto = Grotto::load(&project_dir)?;
 
@@ -1064,6 +2637,60 @@ fn complete_task(project_dir: PathBuf, task_id: String) -> Result<()> {
     Ok(())
 }
 
+fn merge_branches(
+    project_dir: PathBuf,
+    into: Option<String>,
+    order: String,
+    checks: Vec<String>,
+) -> Result<()> {
+    let grotto = Grotto::load(&project_dir)?;
+    let order: MergeOrder = order.parse()?;
+    let checks = if checks.is_empty() {
+        grotto.config.checks.clone()
+    } else {
+        checks
+    };
+
+    println!("🔀 Running merge pipeline...");
+    let report = merge::run(&grotto, into.as_deref(), &order, &checks)?;
+
+    for step in &report.steps {
+        let emoji = match step.status {
+            StepStatus::Merged => "✅",
+            StepStatus::UpToDate => "➖",
+            StepStatus::Conflict => "💥",
+            StepStatus::CheckFailed => "❌",
+        };
+        println!(
+            "  {} {} ({}) - {} commit(s)",
+            emoji, step.agent_id, step.branch, step.commits
+        );
+        for file in &step.conflicts {
+            println!("      conflict: {}", file);
+        }
+        if let Some(failed) = step.checks.iter().find(|c| !c.success) {
+            println!("      check failed: {}", failed.command);
+            for line in failed.output.lines() {
+                println!("      | {}", line);
+            }
+        }
+    }
+
+    grotto.write_summary_section("Merge Pipeline", &report.to_markdown())?;
+    println!("\n📝 Merge report written to .grotto/summary.md");
+
+    match report.failed_step() {
+        None => {
+            println!("✅ All agent branches merged into {}", report.into);
+            Ok(())
+        }
+        Some(step) => Err(grotto_core::GrottoError::Git(format!(
+            "merge pipeline stopped at {} ({:?}); {} left at its last good merge",
+            step.agent_id, step.status, report.into
+        ))),
+    }
+}
+
 fn serve(project_dir: PathBuf, port: u16, no_open: bool) -> Result<()> {
     let grotto_dir = project_dir.join(".grotto");
     if !grotto_dir.exists() {
@@ -1292,6 +2919,42 @@ fn daemon_register(
     Ok(())
 }
 
+fn run_log_sink(path: &Path) -> Result<()> {
+    let mut sink = LogSink::open(path)?;
+    let mut stdin = std::io::stdin().lock();
+    let mut buf = [0u8; 8192];
+    loop {
+        let n = stdin.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        sink.write(&buf[..n])?;
+    }
+    sink.finish()
+}
+
+/// Run `command` with its output rendered by [`logsink::render_stream`],
+/// keeping usage in the launching agent's directory, and exit with its code.
+fn run_stream_view(command: &[String]) -> Result<()> {
+    let agent_dir = match (env::var_os("GROTTO_DIR"), env::var_os("GROTTO_AGENT_ID")) {
+        (Some(dir), Some(id)) => Some(PathBuf::from(dir).join(".grotto/agents").join(id)),
+        _ => None,
+    };
+    let mut child = Command::new(&command[0])
+        .args(&command[1..])
+        .stdout(Stdio::piped())
+        .spawn()?;
+    let stdout = child.stdout.take().expect("stdout is piped");
+    let rendered = logsink::render_stream(
+        std::io::BufReader::new(stdout),
+        &mut std::io::stdout().lock(),
+        agent_dir.as_deref().filter(|dir| dir.is_dir()),
+    );
+    let status = child.wait()?;
+    rendered?;
+    std::process::exit(status.code().unwrap_or(1));
+}
+
 fn daemon_serve(port: u16, web_dir: Option<PathBuf>) -> Result<()> {
     let rt = tokio::runtime::Runtime::new()
         .map_err(|e| grotto_core::GrottoError::Io(std::io::Error::other(e)))?;
@@ -0,0 +1,164 @@
+mod common;
+
+use common::{grotto_command, setup_fake_binaries_with};
+use std::fs;
+use std::path::Path;
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+
+/// Fake tmux where the session stays alive until `GROTTO_TEST_SESSION_GONE` is set.
+fn setup_fake_binaries() -> (TempDir, String) {
+    setup_fake_binaries_with(|_| {
+        "#!/usr/bin/env bash\n\
+         if [ \"$1\" = \"has-session\" ] && [ -n \"$GROTTO_TEST_SESSION_GONE\" ]; then exit 1; fi\n\
+         exit 0\n"
+            .to_string()
+    })
+}
+
+fn spawn_session(project: &Path, home: &Path, path: &str) {
+    let spawn = grotto_command(project, home, path, &["spawn", "2", "t"])
+        .output()
+        .unwrap();
+    assert!(spawn.status.success());
+}
+
+/// Start a blocking `request-approval` and wait until its request file exists.
+fn request_in_background(project: &Path, home: &Path, path: &str) -> std::process::Child {
+    let child = grotto_command(
+        project,
+        home,
+        path,
+        &[
+            "request-approval",
+            "about to run the DB migration",
+            "--agent",
+            "agent-2",
+        ],
+    )
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped())
+    .spawn()
+    .unwrap();
+
+    let request_file = project.join(".grotto/approvals/approval-1.json");
+    let start = Instant::now();
+    while !request_file.exists() {
+        assert!(
+            start.elapsed() < Duration::from_secs(10),
+            "request never filed"
+        );
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    child
+}
+
+#[test]
+fn request_approval_blocks_until_approved() {
+    let project = TempDir::new().unwrap();
+    let home = TempDir::new().unwrap();
+    let (_bin_dir, path) = setup_fake_binaries();
+    spawn_session(project.path(), home.path(), &path);
+
+    let child = request_in_background(project.path(), home.path(), &path);
+
+    let status = grotto_command(project.path(), home.path(), &path, &["status"])
+        .env("GROTTO_TEST_SESSION_GONE", "1")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&status.stdout);
+    assert!(stdout.contains("Pending approvals (1)"), "stdout: {stdout}");
+    assert!(
+        stdout.contains("approval-1 agent-2: about to run the DB migration"),
+        "stdout: {stdout}"
+    );
+
+    let approve = grotto_command(
+        project.path(),
+        home.path(),
+        &path,
+        &["approve", "approval-1"],
+    )
+    .output()
+    .unwrap();
+    assert!(approve.status.success());
+
+    let output = child.wait_with_output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "stdout: {stdout}");
+    assert!(stdout.contains("approval-1 approved"), "stdout: {stdout}");
+
+    let events = fs::read_to_string(project.path().join(".grotto/events.jsonl")).unwrap();
+    assert!(events.contains("\"event_type\":\"approval_requested\""));
+    assert!(events.contains("\"event_type\":\"approval_approved\""));
+}
+
+#[test]
+fn request_approval_fails_when_denied() {
+    let project = TempDir::new().unwrap();
+    let home = TempDir::new().unwrap();
+    let (_bin_dir, path) = setup_fake_binaries();
+    spawn_session(project.path(), home.path(), &path);
+
+    let child = request_in_background(project.path(), home.path(), &path);
+    let deny = grotto_command(
+        project.path(),
+        home.path(),
+        &path,
+        &["deny", "approval-1", "--reason", "take a backup first"],
+    )
+    .output()
+    .unwrap();
+    assert!(deny.status.success());
+
+    let output = child.wait_with_output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(1), "stdout: {stdout}");
+    assert!(
+        stdout.contains("approval-1 denied: take a backup first"),
+        "stdout: {stdout}"
+    );
+
+    let again = grotto_command(
+        project.path(),
+        home.path(),
+        &path,
+        &["approve", "approval-1"],
+    )
+    .output()
+    .unwrap();
+    assert!(!again.status.success());
+    assert!(String::from_utf8_lossy(&again.stderr).contains("already denied"));
+}
+
+#[test]
+fn request_approval_expires_after_timeout() {
+    let project = TempDir::new().unwrap();
+    let home = TempDir::new().unwrap();
+    let (_bin_dir, path) = setup_fake_binaries();
+    spawn_session(project.path(), home.path(), &path);
+
+    let output = grotto_command(
+        project.path(),
+        home.path(),
+        &path,
+        &[
+            "request-approval",
+            "delete the staging bucket",
+            "--agent",
+            "agent-1",
+            "--timeout",
+            "0",
+        ],
+    )
+    .output()
+    .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(2), "stdout: {stdout}");
+    assert!(stdout.contains("approval-1 expired"), "stdout: {stdout}");
+
+    let approval =
+        fs::read_to_string(project.path().join(".grotto/approvals/approval-1.json")).unwrap();
+    assert!(approval.contains("\"status\": \"expired\""), "{approval}");
+}
@@ -0,0 +1,150 @@
+mod common;
+
+use common::{logging_tmux, run_grotto, setup_fake_binaries_with, tmux_session};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+/// Fake tmux that logs its arguments; once a pane is killed the session is
+/// reported gone, as it would be after the last pane exits.
+fn setup_fake_binaries(log: &Path) -> (TempDir, String) {
+    setup_fake_binaries_with(|bin_dir| {
+        logging_tmux(
+            log,
+            &format!(
+                "if [ \"$1\" = \"kill-pane\" ]; then touch '{killed}'; fi\n\
+                 if [ \"$1\" = \"has-session\" ] && [ -e '{killed}' ]; then exit 1; fi\n",
+                killed = bin_dir.join("killed").display()
+            ),
+        )
+    })
+}
+
+#[test]
+fn spawn_rejects_invalid_duration() {
+    let project = TempDir::new().unwrap();
+    let home = TempDir::new().unwrap();
+    let (_bin_dir, path) = setup_fake_binaries(&home.path().join("tmux.log"));
+
+    let spawn = run_grotto(
+        project.path(),
+        home.path(),
+        &path,
+        &["spawn", "1", "t", "--max-duration", "45x"],
+    );
+    assert!(!spawn.status.success());
+    assert!(String::from_utf8_lossy(&spawn.stderr).contains("Invalid duration '45x'"));
+}
+
+#[test]
+fn wait_stops_timed_out_agents_and_requeues_their_tasks() {
+    let project = TempDir::new().unwrap();
+    let home = TempDir::new().unwrap();
+    let log = home.path().join("tmux.log");
+    let (_bin_dir, path) = setup_fake_binaries(&log);
+
+    let spawn = run_grotto(
+        project.path(),
+        home.path(),
+        &path,
+        &["spawn", "2", "t", "--agent-timeout", "1s"],
+    );
+    assert!(spawn.status.success());
+    let config = fs::read_to_string(project.path().join(".grotto/config.toml")).unwrap();
+    assert!(
+        config.contains("agent_timeout_secs = 1"),
+        "config: {config}"
+    );
+
+    let claim = run_grotto(
+        project.path(),
+        home.path(),
+        &path,
+        &["claim", "main", "--agent", "agent-1"],
+    );
+    assert!(claim.status.success());
+    std::thread::sleep(std::time::Duration::from_millis(1100));
+
+    let wait = run_grotto(
+        project.path(),
+        home.path(),
+        &path,
+        &["wait", "--interval", "0"],
+    );
+    let stdout = String::from_utf8_lossy(&wait.stdout);
+    assert!(wait.status.success(), "stdout: {stdout}");
+    assert!(
+        stdout.contains("Timed out: agent-1 after 1s, requeued main"),
+        "stdout: {stdout}"
+    );
+    assert!(
+        stdout.contains("Timed out: agent-2 after 1s"),
+        "stdout: {stdout}"
+    );
+
+    // Graceful /exit first, then panes are killed highest index first
+    let tmux_log = fs::read_to_string(&log).unwrap();
+    let lines: Vec<&str> = tmux_log.lines().collect();
+    let session = tmux_session(project.path());
+    let position = |needle: String| lines.iter().position(|l| *l == needle).unwrap();
+    assert!(
+        position(format!("send-keys -t {session}:0.0 /exit Enter"))
+            < position(format!("kill-pane -t {session}:0.1"))
+    );
+    assert!(
+        position(format!("kill-pane -t {session}:0.1"))
+            < position(format!("kill-pane -t {session}:0.0"))
+    );
+
+    let board = fs::read_to_string(project.path().join(".grotto/tasks.md")).unwrap();
+    assert!(!board.contains("Claimed by"), "board: {board}");
+    let events = fs::read_to_string(project.path().join(".grotto/events.jsonl")).unwrap();
+    assert_eq!(
+        events.matches("\"event_type\":\"budget_exceeded\"").count(),
+        2
+    );
+    assert!(events.contains("\"event_type\":\"task_requeued\""));
+    let status =
+        fs::read_to_string(project.path().join(".grotto/agents/agent-1/status.json")).unwrap();
+    assert!(status.contains("\"state\": \"timed_out\""), "{status}");
+}
+
+#[test]
+fn wait_reports_session_timeout_distinctly() {
+    let project = TempDir::new().unwrap();
+    let home = TempDir::new().unwrap();
+    let (_bin_dir, path) = setup_fake_binaries(&home.path().join("tmux.log"));
+
+    let spawn = run_grotto(
+        project.path(),
+        home.path(),
+        &path,
+        &["spawn", "1", "t", "--max-duration", "1s"],
+    );
+    assert!(spawn.status.success());
+    std::thread::sleep(std::time::Duration::from_millis(1100));
+
+    let wait = run_grotto(
+        project.path(),
+        home.path(),
+        &path,
+        &["wait", "--interval", "0"],
+    );
+    let stdout = String::from_utf8_lossy(&wait.stdout);
+    let stderr = String::from_utf8_lossy(&wait.stderr);
+    assert!(!wait.status.success(), "stdout: {stdout}");
+    assert!(
+        stdout.contains("Session stopped: 1s time budget exceeded"),
+        "stdout: {stdout}"
+    );
+    assert!(
+        stderr.contains("Budget exceeded: session stopped after 1s"),
+        "stderr: {stderr}"
+    );
+
+    let summary = fs::read_to_string(project.path().join(".grotto/summary.md")).unwrap();
+    assert!(
+        summary.contains("Status: Timed out (1s session budget exceeded)"),
+        "summary: {summary}"
+    );
+}
@@ -0,0 +1,194 @@
+mod common;
+
+use common::{logging_tmux, run_grotto_with, setup_fake_binaries_with};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use tempfile::TempDir;
+
+/// Fake tmux that logs its arguments, reports every pane as running and
+/// the session as gone once spawn is done, so `grotto wait` returns
+/// immediately.
+fn setup_fake_binaries(log: &Path) -> (TempDir, String) {
+    setup_fake_binaries_with(|_| {
+        logging_tmux(
+            log,
+            "if [ \"$1\" = \"has-session\" ] && [ -n \"$GROTTO_TEST_SESSION_GONE\" ]; then exit 1; fi\n\
+             if [ \"$1\" = \"display-message\" ]; then echo '%0 0 0'; fi\n",
+        )
+    })
+}
+
+fn git(dir: &Path, args: &[&str], envs: &[(&str, &str)]) {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .envs(envs.iter().copied())
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "git {:?} failed", args);
+}
+
+fn init_repo(dir: &Path) {
+    git(dir, &["init", "-q", "-b", "main"], &[]);
+    git(dir, &["config", "user.name", "Test"], &[]);
+    git(dir, &["config", "user.email", "test@example.com"], &[]);
+    fs::write(dir.join("README.md"), "hello\n").unwrap();
+    git(dir, &["add", "-A"], &[]);
+    git(dir, &["commit", "-q", "-m", "initial"], &[]);
+}
+
+#[test]
+fn report_and_wait_summarize_session_commits() {
+    let project = TempDir::new().unwrap();
+    let home = TempDir::new().unwrap();
+    let log = home.path().join("tmux.log");
+    let (_bin_dir, path) = setup_fake_binaries(&log);
+    init_repo(project.path());
+
+    let spawn = run_grotto_with(
+        project.path(),
+        home.path(),
+        &path,
+        &["spawn", "2", "ship it"],
+        &[],
+    );
+    assert!(
+        spawn.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&spawn.stderr)
+    );
+
+    let config = fs::read_to_string(project.path().join(".grotto/config.toml")).unwrap();
+    assert!(config.contains("[git]"), "config: {config}");
+    assert!(config.contains("branch = \"main\""), "config: {config}");
+
+    let tmux_log = fs::read_to_string(&log).unwrap();
+    assert!(
+        tmux_log.contains("GIT_AUTHOR_NAME=agent-2 (grotto)"),
+        "tmux log: {tmux_log}"
+    );
+
+    // One commit via the pane's author name, one via the trailer
+    fs::write(project.path().join("a.txt"), "a\n").unwrap();
+    git(project.path(), &["add", "-A"], &[]);
+    git(
+        project.path(),
+        &["commit", "-q", "-m", "Add a"],
+        &[("GIT_AUTHOR_NAME", "agent-2 (grotto)")],
+    );
+    fs::write(project.path().join("b.txt"), "b\n").unwrap();
+    git(project.path(), &["add", "-A"], &[]);
+    git(
+        project.path(),
+        &["commit", "-q", "-m", "Add b\n\nGrotto-Agent: agent-1"],
+        &[],
+    );
+
+    let report = run_grotto_with(project.path(), home.path(), &path, &["report"], &[]);
+    assert!(
+        report.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&report.stderr)
+    );
+    let stdout = String::from_utf8_lossy(&report.stdout);
+    assert!(stdout.contains("- Commits: 2"), "stdout: {stdout}");
+    assert!(stdout.contains("Add a (agent-2)"), "stdout: {stdout}");
+    assert!(stdout.contains("Add b (agent-1)"), "stdout: {stdout}");
+    assert!(stdout.contains("2 files changed"), "stdout: {stdout}");
+
+    let wait = run_grotto_with(
+        project.path(),
+        home.path(),
+        &path,
+        &["wait", "--interval", "0"],
+        &[("GROTTO_TEST_SESSION_GONE", "1")],
+    );
+    assert!(wait.status.success());
+
+    let summary = fs::read_to_string(project.path().join(".grotto/summary.md")).unwrap();
+    assert!(summary.starts_with("# Grotto Run Summary"));
+    assert!(summary.contains("## Run"), "summary: {summary}");
+    assert!(summary.contains("## Changes"), "summary: {summary}");
+    assert!(summary.contains("Add a (agent-2)"), "summary: {summary}");
+    assert_eq!(summary.matches("## Changes").count(), 1);
+}
+
+#[test]
+fn report_outside_git_repo_fails() {
+    let project = TempDir::new().unwrap();
+    let home = TempDir::new().unwrap();
+    let log = home.path().join("tmux.log");
+    let (_bin_dir, path) = setup_fake_binaries(&log);
+
+    let spawn = run_grotto_with(
+        project.path(),
+        home.path(),
+        &path,
+        &["spawn", "1", "t"],
+        &[],
+    );
+    assert!(spawn.status.success());
+
+    let tmux_log = fs::read_to_string(&log).unwrap();
+    assert!(!tmux_log.contains("GIT_AUTHOR_NAME"));
+
+    let report = run_grotto_with(project.path(), home.path(), &path, &["report"], &[]);
+    assert!(!report.status.success());
+    let stderr = String::from_utf8_lossy(&report.stderr);
+    assert!(stderr.contains("no git baseline"), "stderr: {stderr}");
+}
+
+#[test]
+fn scorecard_prints_json_and_wait_writes_it() {
+    let project = TempDir::new().unwrap();
+    let home = TempDir::new().unwrap();
+    let log = home.path().join("tmux.log");
+    let (_bin_dir, path) = setup_fake_binaries(&log);
+    init_repo(project.path());
+
+    let spawn = run_grotto_with(
+        project.path(),
+        home.path(),
+        &path,
+        &["spawn", "2", "score me"],
+        &[],
+    );
+    assert!(spawn.status.success());
+    let steer = run_grotto_with(
+        project.path(),
+        home.path(),
+        &path,
+        &["steer", "agent-1", "add tests"],
+        &[],
+    );
+    assert!(steer.status.success());
+
+    let card = run_grotto_with(
+        project.path(),
+        home.path(),
+        &path,
+        &["scorecard", "--json"],
+        &[],
+    );
+    assert!(card.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&card.stdout).unwrap();
+    assert_eq!(json["steering"], 1);
+    assert_eq!(json["tasks"]["opened"], 1);
+    assert_eq!(json["files_changed"], 0);
+    assert_eq!(json["agents"].as_array().unwrap().len(), 2);
+
+    let wait = run_grotto_with(
+        project.path(),
+        home.path(),
+        &path,
+        &["wait", "--interval", "0"],
+        &[("GROTTO_TEST_SESSION_GONE", "1")],
+    );
+    assert!(wait.status.success());
+    assert!(project.path().join(".grotto/scorecard.json").exists());
+    let summary = fs::read_to_string(project.path().join(".grotto/summary.md")).unwrap();
+    assert!(summary.contains("## Scorecard"), "summary: {summary}");
+    assert!(summary.contains("| agent-1 |"), "summary: {summary}");
+}
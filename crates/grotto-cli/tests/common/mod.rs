@@ -0,0 +1,96 @@
+//! Helpers shared by the CLI integration tests: fake `tmux` and `claude`
+//! binaries put first on `PATH`, and `grotto` run against a project.
+#![allow(dead_code)]
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::process::{Command, Output};
+use tempfile::TempDir;
+
+/// A binary that does nothing and succeeds.
+pub const NOOP_SCRIPT: &str = "#!/usr/bin/env bash\nexit 0\n";
+
+pub fn write_executable(path: &Path, contents: &str) {
+    fs::write(path, contents).unwrap();
+    let mut perms = fs::metadata(path).unwrap().permissions();
+    perms.set_mode(0o755);
+    fs::set_permissions(path, perms).unwrap();
+}
+
+/// `PATH` with `bin_dir` searched first.
+pub fn path_with(bin_dir: &Path) -> String {
+    let base_path = std::env::var("PATH").unwrap_or_default();
+    format!("{}:{}", bin_dir.display(), base_path)
+}
+
+/// Fake tmux and claude where every command succeeds and the session stays
+/// alive. Returns the directory holding them (keep it alive) and the `PATH`.
+pub fn setup_fake_binaries() -> (TempDir, String) {
+    setup_fake_binaries_with(|_| NOOP_SCRIPT.to_string())
+}
+
+/// A no-op claude and the tmux script `tmux` builds; it is given the bin
+/// directory for any state files the script keeps.
+pub fn setup_fake_binaries_with(tmux: impl FnOnce(&Path) -> String) -> (TempDir, String) {
+    let bin_dir = TempDir::new().unwrap();
+    write_executable(&bin_dir.path().join("tmux"), &tmux(bin_dir.path()));
+    write_executable(&bin_dir.path().join("claude"), NOOP_SCRIPT);
+    let path = path_with(bin_dir.path());
+    (bin_dir, path)
+}
+
+/// A tmux script that appends its arguments to `log`, runs `extra` (shell
+/// lines, each ending in a newline) and succeeds.
+pub fn logging_tmux(log: &Path, extra: &str) -> String {
+    format!(
+        "#!/usr/bin/env bash\nprintf '%s\\n' \"$*\" >> '{}'\n{extra}exit 0\n",
+        log.display()
+    )
+}
+
+/// `grotto --dir <project> <args>` with the fake binaries on `path`, a
+/// scratch `HOME` and the startup, exit and steering waits turned off.
+pub fn grotto_command(project: &Path, home: &Path, path: &str, args: &[&str]) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_grotto"));
+    command
+        .arg("--dir")
+        .arg(project)
+        .args(args)
+        .env("PATH", path)
+        .env("HOME", home)
+        .env("GROTTO_STARTUP_CHECK_MS", "0")
+        .env("GROTTO_EXIT_GRACE_MS", "0")
+        .env("GROTTO_STEER_POLL_MS", "0")
+        .env_remove("GROTTO_DIR");
+    command
+}
+
+pub fn run_grotto(project: &Path, home: &Path, path: &str, args: &[&str]) -> Output {
+    grotto_command(project, home, path, args).output().unwrap()
+}
+
+/// [`run_grotto`] with extra environment variables.
+pub fn run_grotto_with(
+    project: &Path,
+    home: &Path,
+    path: &str,
+    args: &[&str],
+    envs: &[(&str, &str)],
+) -> Output {
+    grotto_command(project, home, path, args)
+        .envs(envs.iter().copied())
+        .output()
+        .unwrap()
+}
+
+/// The tmux session spawn named after the session ID.
+pub fn tmux_session(project: &Path) -> String {
+    let config = fs::read_to_string(project.join(".grotto/config.toml")).unwrap();
+    config
+        .lines()
+        .find_map(|l| l.strip_prefix("tmux_session = \""))
+        .and_then(|l| l.strip_suffix('"'))
+        .unwrap()
+        .to_string()
+}
@@ -0,0 +1,237 @@
+mod common;
+
+use common::{grotto_command, path_with, run_grotto, write_executable};
+use std::fs;
+use std::path::Path;
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+
+/// A tmux that logs and fails, to show the headless backend never calls it.
+fn setup_broken_tmux(log: &Path) -> (TempDir, String) {
+    let bin_dir = TempDir::new().unwrap();
+    write_executable(
+        &bin_dir.path().join("tmux"),
+        &format!(
+            "#!/usr/bin/env bash\nprintf '%s\\n' \"$*\" >> '{}'\nexit 1\n",
+            log.display()
+        ),
+    );
+    let path = path_with(bin_dir.path());
+    (bin_dir, path)
+}
+
+fn wait_until(what: &str, mut check: impl FnMut() -> bool) {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while !check() {
+        assert!(Instant::now() < deadline, "timed out waiting for {what}");
+        std::thread::sleep(Duration::from_millis(50));
+    }
+}
+
+#[test]
+fn headless_session_supports_status_log_steer_and_exit() {
+    let project = TempDir::new().unwrap();
+    let home = TempDir::new().unwrap();
+    let tmux_log = home.path().join("tmux.log");
+    let (_bin_dir, path) = setup_broken_tmux(&tmux_log);
+
+    let spawn = run_grotto(
+        project.path(),
+        home.path(),
+        &path,
+        &[
+            "spawn",
+            "2",
+            "t",
+            "--backend",
+            "headless",
+            "--runtime",
+            "echo started; : {prompt}; while read l; do echo \"heard $l\"; [ \"$l\" = bye ] && exit 0; done",
+        ],
+    );
+    let stdout = String::from_utf8_lossy(&spawn.stdout);
+    assert!(
+        spawn.status.success(),
+        "stdout: {stdout}\nstderr: {}",
+        String::from_utf8_lossy(&spawn.stderr)
+    );
+    assert!(stdout.contains("without tmux"), "stdout: {stdout}");
+
+    let status = run_grotto(project.path(), home.path(), &path, &["status"]);
+    let stdout = String::from_utf8_lossy(&status.stdout);
+    assert!(stdout.contains("Headless runner (active)"), "{stdout}");
+
+    let steer = run_grotto(
+        project.path(),
+        home.path(),
+        &path,
+        &["steer", "agent-1", "hello"],
+    );
+    assert!(steer.status.success());
+    let stdout = String::from_utf8_lossy(&steer.stdout);
+    assert!(
+        stdout.contains("Message delivered to agent-1"),
+        "stdout: {stdout}\nstderr: {}",
+        String::from_utf8_lossy(&steer.stderr)
+    );
+    wait_until("agent-1 to answer", || {
+        let log = run_grotto(project.path(), home.path(), &path, &["log", "agent-1"]);
+        String::from_utf8_lossy(&log.stdout).contains("heard hello")
+    });
+    let stream =
+        fs::read_to_string(project.path().join(".grotto/agents/agent-1/stream.log")).unwrap();
+    assert!(stream.contains("started"), "{stream}");
+    let output =
+        fs::read_to_string(project.path().join(".grotto/agents/agent-1/output.log")).unwrap();
+    assert!(
+        output.lines().any(|l| l.ends_with(" heard hello")),
+        "{output}"
+    );
+
+    let broadcast = run_grotto(project.path(), home.path(), &path, &["broadcast", "bye"]);
+    assert!(broadcast.status.success());
+    wait_until("the runner to exit", || {
+        !project.path().join(".grotto/run.sock").exists()
+    });
+    let agent =
+        fs::read_to_string(project.path().join(".grotto/agents/agent-2/status.json")).unwrap();
+    assert!(agent.contains("\"state\": \"done\""), "{agent}");
+    let status = run_grotto(project.path(), home.path(), &path, &["status"]);
+    assert!(
+        String::from_utf8_lossy(&status.stdout).contains("Headless runner (completed)")
+            || String::from_utf8_lossy(&status.stdout).contains("Headless runner (not found)")
+    );
+
+    assert!(
+        !tmux_log.exists(),
+        "{}",
+        fs::read_to_string(&tmux_log).unwrap()
+    );
+}
+
+#[test]
+fn budget_stop_kills_the_headless_agent_process() {
+    let project = TempDir::new().unwrap();
+    let home = TempDir::new().unwrap();
+    let tmux_log = home.path().join("tmux.log");
+    let (_bin_dir, path) = setup_broken_tmux(&tmux_log);
+    let pid_file = home.path().join("agent.pid");
+
+    // Ignores its exit keys (C-c), as an agent busy with a tool call would
+    let runtime = format!(
+        "echo $$ > '{}'; trap '' INT; : {{prompt}}; while :; do sleep 0.1; done",
+        pid_file.display()
+    );
+    let spawn = run_grotto(
+        project.path(),
+        home.path(),
+        &path,
+        &[
+            "spawn",
+            "1",
+            "t",
+            "--backend",
+            "headless",
+            "--agent-timeout",
+            "1s",
+            "--runtime",
+            &runtime,
+        ],
+    );
+    assert!(
+        spawn.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&spawn.stderr)
+    );
+    wait_until("the agent to start", || pid_file.exists());
+    let pid = fs::read_to_string(&pid_file).unwrap().trim().to_string();
+    std::thread::sleep(Duration::from_millis(1100));
+
+    // Before budgets reached the runner, this waited forever on an agent
+    // that kept running
+    let mut wait = grotto_command(
+        project.path(),
+        home.path(),
+        &path,
+        &["wait", "--interval", "0"],
+    )
+    .stdout(Stdio::piped())
+    .spawn()
+    .unwrap();
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while wait.try_wait().unwrap().is_none() {
+        if Instant::now() > deadline {
+            let _ = wait.kill();
+            panic!("grotto wait didn't stop the agent");
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    let output = wait.wait_with_output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("Timed out: agent-1 after 1s"),
+        "stdout: {stdout}"
+    );
+
+    wait_until("the agent process to exit", || {
+        !std::process::Command::new("kill")
+            .args(["-0", &pid])
+            .status()
+            .unwrap()
+            .success()
+    });
+    wait_until("the runner to exit", || {
+        !project.path().join(".grotto/run.sock").exists()
+    });
+    assert!(!tmux_log.exists());
+}
+
+#[test]
+fn claude_agents_show_readable_output_and_record_usage() {
+    let project = TempDir::new().unwrap();
+    let home = TempDir::new().unwrap();
+    let tmux_log = home.path().join("tmux.log");
+    let (bin_dir, path) = setup_broken_tmux(&tmux_log);
+    write_executable(
+        &bin_dir.path().join("claude"),
+        r#"#!/bin/sh
+echo '{"type":"assistant","message":{"id":"m1","content":[{"type":"tool_use","name":"Bash","input":{"command":"cargo test"}}],"usage":{"input_tokens":100,"output_tokens":20}}}'
+echo '{"type":"result","subtype":"success","num_turns":1,"result":"Done.","total_cost_usd":0.25,"usage":{"input_tokens":1200,"output_tokens":300}}'
+"#,
+    );
+
+    let spawn = run_grotto(
+        project.path(),
+        home.path(),
+        &path,
+        &["spawn", "1", "t", "--backend", "headless"],
+    );
+    assert!(
+        spawn.status.success(),
+        "stdout: {}\nstderr: {}",
+        String::from_utf8_lossy(&spawn.stdout),
+        String::from_utf8_lossy(&spawn.stderr)
+    );
+    wait_until("the runner to exit", || {
+        !project.path().join(".grotto/run.sock").exists()
+    });
+
+    // The terminal and both logs read like Claude's terminal, not like JSON
+    let agent_dir = project.path().join(".grotto/agents/agent-1");
+    let stream = fs::read_to_string(agent_dir.join("stream.log")).unwrap();
+    assert!(stream.contains("Bash(cargo test)"), "{stream}");
+    assert!(!stream.contains(r#"{"type""#), "{stream}");
+    let output = fs::read_to_string(agent_dir.join("output.log")).unwrap();
+    assert!(
+        output.contains(" Finished: success after 1 turn(s)"),
+        "{output}"
+    );
+
+    let status = run_grotto(project.path(), home.path(), &path, &["status"]);
+    let stdout = String::from_utf8_lossy(&status.stdout);
+    assert!(
+        stdout.contains("agent-1: $0.25 (1.2k in / 300 out)"),
+        "stdout: {stdout}"
+    );
+}
@@ -0,0 +1,45 @@
+use std::fs;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use tempfile::TempDir;
+
+#[test]
+fn log_sink_writes_raw_and_cleaned_rotated_logs() {
+    let dir = TempDir::new().unwrap();
+    let raw = dir.path().join("stream.log");
+
+    let mut sink = Command::new(env!("CARGO_BIN_EXE_grotto"))
+        .arg("log-sink")
+        .arg(&raw)
+        .env("GROTTO_LOG_MAX_BYTES", "300")
+        .stdin(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = sink.stdin.take().unwrap();
+    stdin
+        .write_all("\x1b[?25l✻ Thinking… (1s)\r✶ Thinking… (2s)\r\n".as_bytes())
+        .unwrap();
+    for i in 0..10 {
+        write!(stdin, "\x1b[1;32mstep {i}\x1b[0m done\r\n").unwrap();
+    }
+    stdin.write_all(b"$ ").unwrap();
+    drop(stdin);
+    assert!(sink.wait().unwrap().success());
+
+    let raw_log = fs::read_to_string(&raw).unwrap();
+    assert!(raw_log.contains("\x1b[1;32m"), "{raw_log:?}");
+    assert!(dir.path().join("output.log.1").exists());
+
+    let rotated = fs::read_to_string(dir.path().join("output.log.1")).unwrap();
+    let current = fs::read_to_string(dir.path().join("output.log")).unwrap();
+    let text: Vec<&str> = rotated
+        .lines()
+        .chain(current.lines())
+        .map(|line| line.split_once(' ').unwrap().1)
+        .collect();
+    assert_eq!(text[0], "✻ Thinking… (1s)");
+    assert_eq!(text[1], "step 0 done");
+    assert_eq!(text.last(), Some(&"$"));
+    assert_eq!(text.len(), 12);
+    assert!(!current.contains('\x1b'), "{current:?}");
+}
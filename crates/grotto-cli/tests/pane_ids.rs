@@ -0,0 +1,128 @@
+mod common;
+
+use common::{run_grotto, write_executable};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+/// Fake tmux that logs its arguments and hands out pane IDs like the real
+/// one: `%0`, `%1`, ... in creation order, never reused. Closed panes are
+/// unknown to `display-message`. Pasted text shows in every capture.
+fn setup_fake_binaries(log: &Path) -> (TempDir, String) {
+    let bin_dir = TempDir::new().unwrap();
+    let panes = bin_dir.path().join("panes");
+    let next = bin_dir.path().join("next");
+    let tmux_script = format!(
+        r#"#!/usr/bin/env bash
+printf '%s\n' "$*" >> '{log}'
+open_pane() {{
+  id="%$(cat '{next}' 2>/dev/null || echo 0)"
+  echo $(( ${{id#%}} + 1 )) > '{next}'
+  echo "$id" >> '{panes}'
+  echo "$id"
+}}
+target() {{
+  while [ $# -gt 0 ]; do
+    if [ "$1" = "-t" ]; then echo "$2"; return; fi
+    shift
+  done
+}}
+case "$1" in
+  new-session|split-window) open_pane ;;
+  kill-pane)
+    t=$(target "$@")
+    grep -vx -- "$t" '{panes}' > '{panes}.tmp'; mv '{panes}.tmp' '{panes}'
+    ;;
+  set-buffer) printf '%s\n' "${{@: -1}}" >> '{pasted}' ;;
+  capture-pane) cat '{pasted}' 2>/dev/null ;;
+  display-message)
+    t=$(target "$@")
+    index=$(grep -nx -- "$t" '{panes}' | cut -d: -f1)
+    [ -n "$index" ] || exit 1
+    echo "$t $(( index - 1 )) 0"
+    ;;
+esac
+exit 0
+"#,
+        log = log.display(),
+        panes = panes.display(),
+        next = next.display(),
+        pasted = bin_dir.path().join("pasted").display(),
+    );
+    write_executable(&bin_dir.path().join("tmux"), &tmux_script);
+    write_executable(
+        &bin_dir.path().join("claude"),
+        "#!/usr/bin/env bash\nexit 0\n",
+    );
+
+    let base_path = std::env::var("PATH").unwrap_or_default();
+    let full_path = format!("{}:{}", bin_dir.path().display(), base_path);
+    (bin_dir, full_path)
+}
+
+#[test]
+fn commands_follow_pane_ids_after_a_pane_closes() {
+    let project = TempDir::new().unwrap();
+    let home = TempDir::new().unwrap();
+    let log = home.path().join("tmux.log");
+    let (_bin_dir, path) = setup_fake_binaries(&log);
+
+    let spawn = run_grotto(project.path(), home.path(), &path, &["spawn", "3", "t"]);
+    assert!(spawn.status.success());
+    let status =
+        fs::read_to_string(project.path().join(".grotto/agents/agent-3/status.json")).unwrap();
+    assert!(status.contains("\"pane_id\": \"%2\""), "status: {status}");
+    let tmux_log = fs::read_to_string(&log).unwrap();
+    assert!(
+        tmux_log.contains("split-window -P -F #{pane_id} -t %1"),
+        "tmux log: {tmux_log}"
+    );
+
+    let kill = run_grotto(project.path(), home.path(), &path, &["kill", "agent-2"]);
+    assert!(kill.status.success());
+    assert!(
+        fs::read_to_string(&log)
+            .unwrap()
+            .contains("kill-pane -t %1")
+    );
+
+    // agent-3's pane is now index 1, but its ID still reaches it
+    fs::write(&log, "").unwrap();
+    let steer = run_grotto(
+        project.path(),
+        home.path(),
+        &path,
+        &["steer", "agent-3", "hi"],
+    );
+    assert!(steer.status.success());
+    let log_agent = run_grotto(project.path(), home.path(), &path, &["log", "agent-3"]);
+    assert!(log_agent.status.success());
+    let tmux_log = fs::read_to_string(&log).unwrap();
+    assert!(
+        tmux_log
+            .lines()
+            .any(|l| l.starts_with("paste-buffer") && l.ends_with("-t %2"))
+            && tmux_log.contains("send-keys -t %2 Enter"),
+        "tmux log: {tmux_log}"
+    );
+    assert!(
+        tmux_log.contains("capture-pane -t %2 -p"),
+        "tmux log: {tmux_log}"
+    );
+
+    // The killed agent's pane is reported dead instead of typing elsewhere
+    fs::write(&log, "").unwrap();
+    let steer = run_grotto(
+        project.path(),
+        home.path(),
+        &path,
+        &["steer", "agent-2", "hi"],
+    );
+    let stderr = String::from_utf8_lossy(&steer.stderr);
+    assert!(
+        stderr.contains("Pane is dead: %1 was closed"),
+        "stderr: {stderr}"
+    );
+    let tmux_log = fs::read_to_string(&log).unwrap();
+    assert!(!tmux_log.contains("paste-buffer"), "tmux log: {tmux_log}");
+}
@@ -0,0 +1,79 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use tempfile::TempDir;
+
+fn run_grotto(project: &Path, args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_grotto"))
+        .arg("--dir")
+        .arg(project)
+        .args(args)
+        .env_remove("GROTTO_DIR")
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn monitor_test_explains_the_matching_rule() {
+    let project = TempDir::new().unwrap();
+    let capture = project.path().join("capture.txt");
+    fs::write(
+        &capture,
+        "$ cargo test\n\x1b[31merror[E0308]\x1b[0m: mismatched types\n\n",
+    )
+    .unwrap();
+
+    let output = run_grotto(
+        project.path(),
+        &["monitor", "test", capture.to_str().unwrap()],
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "stdout: {stdout}");
+    assert!(stdout.contains("Phase rules: built-in"), "stdout: {stdout}");
+    assert!(stdout.contains("Phase: error"), "stdout: {stdout}");
+    assert!(stdout.contains("Matched 'error-line'"), "stdout: {stdout}");
+    assert!(
+        stdout.contains("line 1 from bottom: error[E0308]: mismatched types"),
+        "stdout: {stdout}"
+    );
+    assert!(stdout.contains("'command' → running"), "stdout: {stdout}");
+
+    // A project rules file replaces the built-in rules for its runtime
+    fs::create_dir_all(project.path().join(".grotto/phases")).unwrap();
+    fs::write(
+        project.path().join(".grotto/phases/shell.toml"),
+        "[[rule]]\nname = \"compile-failed\"\nphase = \"idle\"\npattern = 'mismatched'\n",
+    )
+    .unwrap();
+    let output = run_grotto(
+        project.path(),
+        &[
+            "monitor",
+            "test",
+            capture.to_str().unwrap(),
+            "--runtime",
+            "shell",
+        ],
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("phases/shell.toml"), "stdout: {stdout}");
+    assert!(stdout.contains("Phase: idle"), "stdout: {stdout}");
+
+    let rules = run_grotto(project.path(), &["monitor", "rules", "--runtime", "shell"]);
+    let stdout = String::from_utf8_lossy(&rules.stdout);
+    assert!(stdout.contains("compile-failed"), "stdout: {stdout}");
+    assert!(!stdout.contains("error-line"), "stdout: {stdout}");
+
+    let unknown = run_grotto(
+        project.path(),
+        &[
+            "monitor",
+            "test",
+            capture.to_str().unwrap(),
+            "--runtime",
+            "aider",
+        ],
+    );
+    assert!(!unknown.status.success());
+    assert!(String::from_utf8_lossy(&unknown.stderr).contains("Unknown runtime 'aider'"));
+}
@@ -0,0 +1,154 @@
+mod common;
+
+use common::{logging_tmux, run_grotto_with, setup_fake_binaries_with, tmux_session};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+/// Fake tmux that logs its arguments; `has-session` fails so status reads
+/// files only, and panes run `$GROTTO_TEST_PANE_PID`.
+fn setup_fake_binaries(log: &Path) -> (TempDir, String) {
+    setup_fake_binaries_with(|_| {
+        logging_tmux(
+            log,
+            "if [ \"$1\" = \"has-session\" ] && [ -n \"$GROTTO_TEST_SESSION_GONE\" ]; then exit 1; fi\n\
+             if [ \"$1\" = \"display-message\" ]; then echo \"$GROTTO_TEST_PANE_PID\"; fi\n",
+        )
+    })
+}
+
+const POLICY: &str = r#"
+[[deny]]
+name = "force-push"
+command = 'git push .*--force'
+
+[[deny]]
+name = "env-files"
+path = "**/.env*"
+"#;
+
+#[test]
+fn policy_check_evaluates_lines() {
+    let project = TempDir::new().unwrap();
+    let home = TempDir::new().unwrap();
+    let (_bin_dir, path) = setup_fake_binaries(&home.path().join("tmux.log"));
+    fs::create_dir_all(project.path().join(".grotto")).unwrap();
+    fs::write(project.path().join(".grotto/policy.toml"), POLICY).unwrap();
+
+    let allowed = run_grotto_with(
+        project.path(),
+        home.path(),
+        &path,
+        &["policy", "check", "--line", "git push origin main"],
+        &[],
+    );
+    let stdout = String::from_utf8_lossy(&allowed.stdout);
+    assert!(allowed.status.success(), "stdout: {stdout}");
+    assert!(stdout.contains("force-push command"), "stdout: {stdout}");
+    assert!(stdout.contains("Allowed"), "stdout: {stdout}");
+
+    let denied = run_grotto_with(
+        project.path(),
+        home.path(),
+        &path,
+        &["policy", "check", "--line", "$ cat config/.env.local"],
+        &[],
+    );
+    let stdout = String::from_utf8_lossy(&denied.stdout);
+    assert!(!denied.status.success());
+    assert!(stdout.contains("Denied by 'env-files'"), "stdout: {stdout}");
+}
+
+#[test]
+fn policy_check_reports_invalid_rules() {
+    let project = TempDir::new().unwrap();
+    let home = TempDir::new().unwrap();
+    let (_bin_dir, path) = setup_fake_binaries(&home.path().join("tmux.log"));
+    fs::create_dir_all(project.path().join(".grotto")).unwrap();
+    fs::write(
+        project.path().join(".grotto/policy.toml"),
+        "[[deny]]\nname = \"bad\"\ncommand = \"(unclosed\"\n",
+    )
+    .unwrap();
+
+    let output = run_grotto_with(
+        project.path(),
+        home.path(),
+        &path,
+        &["policy", "check"],
+        &[],
+    );
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Policy error"), "stderr: {stderr}");
+}
+
+#[test]
+fn status_alerts_on_violations_and_resume_clears_pause() {
+    let project = TempDir::new().unwrap();
+    let home = TempDir::new().unwrap();
+    let log = home.path().join("tmux.log");
+    let (_bin_dir, path) = setup_fake_binaries(&log);
+
+    let spawn = run_grotto_with(
+        project.path(),
+        home.path(),
+        &path,
+        &["spawn", "1", "t"],
+        &[],
+    );
+    assert!(spawn.status.success());
+
+    let event = serde_json::json!({
+        "timestamp": "2026-01-01T12:00:00Z",
+        "event_type": "policy_violation",
+        "agent_id": "agent-1",
+        "task_id": null,
+        "message": "Policy rule 'force-push' violated",
+        "data": { "rule": "force-push", "line": "git push --force origin main" },
+    });
+    let events_path = project.path().join(".grotto/events.jsonl");
+    let mut events = fs::read_to_string(&events_path).unwrap();
+    events.push_str(&format!("{}\n", event));
+    fs::write(&events_path, events).unwrap();
+
+    let status = run_grotto_with(
+        project.path(),
+        home.path(),
+        &path,
+        &["status"],
+        &[("GROTTO_TEST_SESSION_GONE", "1")],
+    );
+    let stdout = String::from_utf8_lossy(&status.stdout);
+    assert!(stdout.contains("Policy violations (1)"), "stdout: {stdout}");
+    assert!(
+        stdout.contains("agent-1 [force-push] git push --force origin main"),
+        "stdout: {stdout}"
+    );
+
+    let mut pane_process = std::process::Command::new("sleep")
+        .arg("30")
+        .spawn()
+        .unwrap();
+    let resume = run_grotto_with(
+        project.path(),
+        home.path(),
+        &path,
+        &["policy", "resume", "agent-1"],
+        &[("GROTTO_TEST_PANE_PID", &pane_process.id().to_string())],
+    );
+    let _ = pane_process.kill();
+    let _ = pane_process.wait();
+    assert!(
+        resume.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&resume.stderr)
+    );
+    let tmux_log = fs::read_to_string(&log).unwrap();
+    let session = tmux_session(project.path());
+    assert!(tmux_log.contains(&format!(
+        "display-message -p -t {session}:0.0 #{{pane_pid}}"
+    )));
+    let events = fs::read_to_string(&events_path).unwrap();
+    assert!(events.contains("\"event_type\":\"policy_resumed\""));
+}
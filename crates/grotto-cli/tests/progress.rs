@@ -0,0 +1,78 @@
+mod common;
+
+use common::{run_grotto, setup_fake_binaries};
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn agents_report_progress_shown_by_status() {
+    let project = TempDir::new().unwrap();
+    let home = TempDir::new().unwrap();
+    let (_bin_dir, path) = setup_fake_binaries();
+
+    let spawn = run_grotto(project.path(), home.path(), &path, &["spawn", "2", "t"]);
+    assert!(spawn.status.success());
+
+    let report = run_grotto(
+        project.path(),
+        home.path(),
+        &path,
+        &[
+            "report",
+            "--agent",
+            "agent-2",
+            "--progress",
+            "wired up JWT middleware",
+            "--percent",
+            "60",
+            "--blocked-on",
+            "staging credentials",
+        ],
+    );
+    assert!(
+        report.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&report.stderr)
+    );
+
+    let status =
+        fs::read_to_string(project.path().join(".grotto/agents/agent-2/status.json")).unwrap();
+    assert!(status.contains("\"percent\": 60"), "status: {status}");
+    let events = fs::read_to_string(project.path().join(".grotto/events.jsonl")).unwrap();
+    assert!(events.contains("\"event_type\":\"progress_reported\""));
+
+    let status = run_grotto(project.path(), home.path(), &path, &["status"]);
+    let stdout = String::from_utf8_lossy(&status.stdout);
+    assert!(
+        stdout.contains("📝 wired up JWT middleware (60%)"),
+        "stdout: {stdout}"
+    );
+    assert!(
+        stdout.contains("🚧 Blocked on: staging credentials"),
+        "stdout: {stdout}"
+    );
+
+    // Out of range, or a report without its agent, is rejected
+    let bad = run_grotto(
+        project.path(),
+        home.path(),
+        &path,
+        &[
+            "report",
+            "--agent",
+            "agent-1",
+            "--progress",
+            "x",
+            "--percent",
+            "150",
+        ],
+    );
+    assert!(!bad.status.success());
+    let bad = run_grotto(
+        project.path(),
+        home.path(),
+        &path,
+        &["report", "--progress", "x"],
+    );
+    assert!(!bad.status.success());
+}
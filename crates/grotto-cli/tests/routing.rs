@@ -0,0 +1,114 @@
+mod common;
+
+use common::{logging_tmux, path_with, run_grotto, write_executable};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn setup_fake_tmux(log: &Path) -> (TempDir, String) {
+    let bin_dir = TempDir::new().unwrap();
+    write_executable(&bin_dir.path().join("tmux"), &logging_tmux(log, ""));
+    let path = path_with(bin_dir.path());
+    (bin_dir, path)
+}
+
+#[test]
+fn spawn_and_add_agent_route_models_by_role_and_label() {
+    let project = TempDir::new().unwrap();
+    let home = TempDir::new().unwrap();
+    let log = home.path().join("tmux.log");
+    let (_bin_dir, path) = setup_fake_tmux(&log);
+
+    let spawn = run_grotto(
+        project.path(),
+        home.path(),
+        &path,
+        &[
+            "spawn",
+            "2",
+            "t",
+            "--runtime",
+            "echo model={model} {prompt}",
+            "--model",
+            "sonnet",
+            "--route",
+            "role:tester=haiku",
+            "--route",
+            "label:docs=haiku-docs",
+            "--role",
+            "agent-2=tester",
+        ],
+    );
+    let stdout = String::from_utf8_lossy(&spawn.stdout);
+    assert!(spawn.status.success(), "stdout: {stdout}");
+    assert!(
+        stdout.contains("Models: agent-1 sonnet, agent-2 haiku"),
+        "stdout: {stdout}"
+    );
+    let tmux_log = fs::read_to_string(&log).unwrap();
+    assert!(
+        tmux_log.contains("GROTTO_AGENT_ID=agent-1 sh -c echo model='sonnet' 'You are agent-1"),
+        "tmux log: {tmux_log}"
+    );
+    assert!(
+        tmux_log.contains("GROTTO_AGENT_ID=agent-2 sh -c echo model='haiku' 'You are agent-2"),
+        "tmux log: {tmux_log}"
+    );
+
+    let add_task = run_grotto(
+        project.path(),
+        home.path(),
+        &path,
+        &["add-task", "docs", "Document the API", "--label", "docs"],
+    );
+    assert!(add_task.status.success());
+    let add_agent = run_grotto(
+        project.path(),
+        home.path(),
+        &path,
+        &["add-agent", "--task", "docs"],
+    );
+    let stdout = String::from_utf8_lossy(&add_agent.stdout);
+    assert!(add_agent.status.success(), "stdout: {stdout}");
+    assert!(stdout.contains("Claimed task: docs"), "stdout: {stdout}");
+    assert!(stdout.contains("Model: haiku-docs"), "stdout: {stdout}");
+    let tmux_log = fs::read_to_string(&log).unwrap();
+    assert!(
+        tmux_log.contains("GROTTO_AGENT_ID=agent-3 sh -c echo model='haiku-docs'"),
+        "tmux log: {tmux_log}"
+    );
+
+    let status =
+        fs::read_to_string(project.path().join(".grotto/agents/agent-3/status.json")).unwrap();
+    assert!(status.contains("\"model\": \"haiku-docs\""), "{status}");
+    let events = fs::read_to_string(project.path().join(".grotto/events.jsonl")).unwrap();
+    assert_eq!(events.matches("\"model_assigned\"").count(), 3, "{events}");
+    assert!(
+        events.contains("\"rule\":\"label:docs=haiku-docs\""),
+        "{events}"
+    );
+}
+
+#[test]
+fn spawn_rejects_malformed_routes() {
+    let project = TempDir::new().unwrap();
+    let home = TempDir::new().unwrap();
+    let (_bin_dir, path) = setup_fake_tmux(&home.path().join("tmux.log"));
+
+    let spawn = run_grotto(
+        project.path(),
+        home.path(),
+        &path,
+        &[
+            "spawn",
+            "1",
+            "t",
+            "--runtime",
+            "fake",
+            "--route",
+            "tester=haiku",
+        ],
+    );
+    assert!(!spawn.status.success());
+    assert!(String::from_utf8_lossy(&spawn.stderr).contains("Invalid model route 'tester=haiku'"));
+}
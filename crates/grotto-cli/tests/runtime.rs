@@ -0,0 +1,94 @@
+mod common;
+
+use common::{logging_tmux, path_with, run_grotto, write_executable};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+/// Fake tmux only: runtimes other than Claude Code must not need `claude`.
+fn setup_fake_tmux(log: &Path) -> (TempDir, String) {
+    let bin_dir = TempDir::new().unwrap();
+    write_executable(&bin_dir.path().join("tmux"), &logging_tmux(log, ""));
+    let path = path_with(bin_dir.path());
+    (bin_dir, path)
+}
+
+#[test]
+fn spawn_launches_session_and_agent_runtimes() {
+    let project = TempDir::new().unwrap();
+    let home = TempDir::new().unwrap();
+    let log = home.path().join("tmux.log");
+    let (_bin_dir, path) = setup_fake_tmux(&log);
+
+    let spawn = run_grotto(
+        project.path(),
+        home.path(),
+        &path,
+        &[
+            "spawn",
+            "2",
+            "t",
+            "--runtime",
+            "fake",
+            "--agent-runtime",
+            "agent-2=echo {prompt}",
+        ],
+    );
+    let stdout = String::from_utf8_lossy(&spawn.stdout);
+    assert!(spawn.status.success(), "stdout: {stdout}");
+    assert!(stdout.contains("Runtime: fake"), "stdout: {stdout}");
+    assert!(
+        stdout.contains("Runtime for agent-2: shell: echo {prompt}"),
+        "stdout: {stdout}"
+    );
+
+    let tmux_log = fs::read_to_string(&log).unwrap();
+    let new_session = tmux_log
+        .lines()
+        .find(|l| l.starts_with("new-session"))
+        .unwrap();
+    assert!(
+        new_session.contains("GROTTO_AGENT_ID=agent-1 sh -c printf '%s\\n' 'Working on it...'"),
+        "{new_session}"
+    );
+    assert!(!tmux_log.contains("claude --dangerously-skip-permissions"));
+    assert!(
+        tmux_log.contains("GROTTO_AGENT_ID=agent-2 sh -c echo 'You are agent-2"),
+        "tmux log: {tmux_log}"
+    );
+
+    let config = fs::read_to_string(project.path().join(".grotto/config.toml")).unwrap();
+    assert!(config.contains("kind = \"fake\""), "config: {config}");
+    assert!(
+        config.contains("[agent_runtimes.agent-2]"),
+        "config: {config}"
+    );
+}
+
+#[test]
+fn spawn_rejects_unknown_runtimes_and_agents() {
+    let project = TempDir::new().unwrap();
+    let home = TempDir::new().unwrap();
+    let (_bin_dir, path) = setup_fake_tmux(&home.path().join("tmux.log"));
+
+    let spawn = run_grotto(
+        project.path(),
+        home.path(),
+        &path,
+        &["spawn", "1", "t", "--runtime", "gpt"],
+    );
+    assert!(!spawn.status.success());
+    assert!(String::from_utf8_lossy(&spawn.stderr).contains("Unknown runtime 'gpt'"));
+
+    let spawn = run_grotto(
+        project.path(),
+        home.path(),
+        &path,
+        &["spawn", "1", "t", "--agent-runtime", "agent-3=fake"],
+    );
+    assert!(!spawn.status.success());
+    assert!(
+        String::from_utf8_lossy(&spawn.stderr)
+            .contains("Unknown agent 'agent-3' in --agent-runtime")
+    );
+}
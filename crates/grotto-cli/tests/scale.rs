@@ -0,0 +1,152 @@
+mod common;
+
+use common::{logging_tmux, run_grotto, setup_fake_binaries_with, tmux_session};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn setup_fake_binaries(log: &Path) -> (TempDir, String) {
+    setup_fake_binaries_with(|_| logging_tmux(log, ""))
+}
+
+#[test]
+fn scale_adds_agents_then_retires_idle_ones_first() {
+    let project = TempDir::new().unwrap();
+    let home = TempDir::new().unwrap();
+    let log = home.path().join("tmux.log");
+    let (_bin_dir, path) = setup_fake_binaries(&log);
+
+    let spawn = run_grotto(project.path(), home.path(), &path, &["spawn", "2", "t"]);
+    assert!(spawn.status.success());
+    let claim = run_grotto(
+        project.path(),
+        home.path(),
+        &path,
+        &["claim", "main", "--agent", "agent-2"],
+    );
+    assert!(claim.status.success());
+
+    // A daemon-registered session gets its agent count updated
+    let config = fs::read_to_string(project.path().join(".grotto/config.toml")).unwrap();
+    let session_id = config
+        .lines()
+        .find_map(|l| l.strip_prefix("session_id = \""))
+        .and_then(|l| l.strip_suffix('"'))
+        .unwrap()
+        .to_string();
+    fs::create_dir_all(home.path().join(".grotto")).unwrap();
+    let registry = home.path().join(".grotto/sessions.json");
+    fs::write(
+        &registry,
+        serde_json::json!({
+            "sessions": {
+                &session_id: {
+                    "id": &session_id,
+                    "dir": project.path().display().to_string(),
+                    "agent_count": 2,
+                    "task": "t",
+                }
+            }
+        })
+        .to_string(),
+    )
+    .unwrap();
+
+    fs::write(&log, "").unwrap();
+    let up = run_grotto(project.path(), home.path(), &path, &["scale", "3"]);
+    let stdout = String::from_utf8_lossy(&up.stdout);
+    assert!(up.status.success(), "stdout: {stdout}");
+    assert!(
+        stdout.contains("Added agent-3 (pane 2)"),
+        "stdout: {stdout}"
+    );
+    let session = tmux_session(project.path());
+    let tmux_log = fs::read_to_string(&log).unwrap();
+    assert!(
+        tmux_log.contains(&format!(
+            "split-window -P -F #{{pane_id}} -t {session}:0.1 -c"
+        )),
+        "tmux log: {tmux_log}"
+    );
+    assert!(tmux_log.contains("You are agent-3"), "tmux log: {tmux_log}");
+    assert!(
+        tmux_log
+            .lines()
+            .any(|l| l.starts_with(&format!("pipe-pane -t {session}:0.2 "))
+                && l.contains(" log-sink ")
+                && l.ends_with("agents/agent-3/stream.log'")),
+        "tmux log: {tmux_log}"
+    );
+
+    let add = run_grotto(
+        project.path(),
+        home.path(),
+        &path,
+        &["add-agent", "--role", "reviewer"],
+    );
+    let stdout = String::from_utf8_lossy(&add.stdout);
+    assert!(add.status.success(), "stdout: {stdout}");
+    assert!(
+        stdout.contains("Added agent-4 as reviewer (pane 3)"),
+        "stdout: {stdout}"
+    );
+    assert!(
+        fs::read_to_string(&log)
+            .unwrap()
+            .contains("Your specialty on this team: reviewer")
+    );
+    let registered = fs::read_to_string(&registry).unwrap();
+    assert!(
+        registered.contains("\"agent_count\": 4"),
+        "registry: {registered}"
+    );
+
+    // agent-2 holds the main task, so it is kept over the idle agents
+    fs::write(&log, "").unwrap();
+    let down = run_grotto(project.path(), home.path(), &path, &["scale", "1"]);
+    let stdout = String::from_utf8_lossy(&down.stdout);
+    assert!(down.status.success(), "stdout: {stdout}");
+    for retired in ["agent-1", "agent-3", "agent-4"] {
+        assert!(
+            stdout.contains(&format!("Retired {}\n", retired)),
+            "stdout: {stdout}"
+        );
+    }
+    assert!(stdout.contains("Team now has 1 agents"), "stdout: {stdout}");
+    let tmux_log = fs::read_to_string(&log).unwrap();
+    let kills: Vec<&str> = tmux_log
+        .lines()
+        .filter(|l| l.starts_with("kill-pane"))
+        .collect();
+    assert_eq!(
+        kills,
+        vec![
+            format!("kill-pane -t {session}:0.3"),
+            format!("kill-pane -t {session}:0.2"),
+            format!("kill-pane -t {session}:0.0")
+        ]
+    );
+
+    let status = run_grotto(project.path(), home.path(), &path, &["status"]);
+    let stdout = String::from_utf8_lossy(&status.stdout);
+    assert!(
+        stdout.contains("Agents (1, 3 retired):"),
+        "stdout: {stdout}"
+    );
+    assert!(stdout.contains("agent-2 (pane 0)"), "stdout: {stdout}");
+    assert!(
+        stdout.contains("agent-4 [reviewer] - retired"),
+        "stdout: {stdout}"
+    );
+    let config = fs::read_to_string(project.path().join(".grotto/config.toml")).unwrap();
+    assert!(config.contains("agent_count = 1"), "config: {config}");
+    let events = fs::read_to_string(project.path().join(".grotto/events.jsonl")).unwrap();
+    assert_eq!(
+        events.matches("\"event_type\":\"agent_retired\"").count(),
+        3
+    );
+
+    let empty = run_grotto(project.path(), home.path(), &path, &["scale", "0"]);
+    assert!(!empty.status.success());
+    assert!(String::from_utf8_lossy(&empty.stderr).contains("at least one agent"));
+}
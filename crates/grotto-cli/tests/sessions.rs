@@ -0,0 +1,93 @@
+mod common;
+
+use common::{logging_tmux, run_grotto, setup_fake_binaries_with};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn setup_fake_binaries(log: &Path) -> (TempDir, String) {
+    setup_fake_binaries_with(|bin_dir| {
+        logging_tmux(
+            log,
+            &format!(
+                "if [ \"$1\" = \"display-message\" ]; then echo '%0 0 0'; fi\n\
+                 if [ \"$1\" = \"set-buffer\" ]; then printf '%s\\n' \"${{@: -1}}\" >> '{pasted}'; fi\n\
+                 if [ \"$1\" = \"capture-pane\" ]; then cat '{pasted}' 2>/dev/null; fi\n",
+                pasted = bin_dir.join("pasted").display(),
+            ),
+        )
+    })
+}
+
+fn config_value(project: &Path, key: &str) -> String {
+    let config = fs::read_to_string(project.join(".grotto/config.toml")).unwrap();
+    let prefix = format!("{} = \"", key);
+    config
+        .lines()
+        .find_map(|l| l.strip_prefix(&prefix))
+        .and_then(|l| l.strip_suffix('"'))
+        .unwrap()
+        .to_string()
+}
+
+#[test]
+fn teams_in_different_projects_get_their_own_tmux_sessions() {
+    let first = TempDir::new().unwrap();
+    let second = TempDir::new().unwrap();
+    let home = TempDir::new().unwrap();
+    let log = home.path().join("tmux.log");
+    let (_bin_dir, path) = setup_fake_binaries(&log);
+
+    let spawn = run_grotto(first.path(), home.path(), &path, &["spawn", "1", "a"]);
+    assert!(spawn.status.success());
+    let first_session = config_value(first.path(), "tmux_session");
+    let session_id = config_value(first.path(), "session_id");
+    assert_eq!(first_session, format!("grotto-{session_id}"));
+    assert!(
+        String::from_utf8_lossy(&spawn.stdout)
+            .contains(&format!("in tmux session '{first_session}'"))
+    );
+
+    fs::write(&log, "").unwrap();
+    let spawn = run_grotto(second.path(), home.path(), &path, &["spawn", "1", "b"]);
+    assert!(spawn.status.success());
+    let second_session = config_value(second.path(), "tmux_session");
+    assert_ne!(first_session, second_session);
+    let tmux_log = fs::read_to_string(&log).unwrap();
+    assert!(!tmux_log.contains("kill-session"), "tmux log: {tmux_log}");
+    assert!(
+        tmux_log.contains(&format!(
+            "new-session -d -P -F #{{pane_id}} -s {second_session}"
+        )),
+        "tmux log: {tmux_log}"
+    );
+
+    // Commands resolve the session from the project's config
+    fs::write(&log, "").unwrap();
+    let steer = run_grotto(
+        first.path(),
+        home.path(),
+        &path,
+        &["steer", "agent-1", "hi"],
+    );
+    assert!(steer.status.success());
+    let tmux_log = fs::read_to_string(&log).unwrap();
+    assert!(
+        tmux_log
+            .lines()
+            .any(|l| l.starts_with("paste-buffer")
+                && l.ends_with(&format!("-t {first_session}:0.0"))),
+        "tmux log: {tmux_log}"
+    );
+
+    // Respawning a project replaces only its own team
+    fs::write(&log, "").unwrap();
+    let respawn = run_grotto(first.path(), home.path(), &path, &["spawn", "1", "a"]);
+    assert!(respawn.status.success());
+    let tmux_log = fs::read_to_string(&log).unwrap();
+    assert!(
+        tmux_log.contains(&format!("kill-session -t {first_session}")),
+        "tmux log: {tmux_log}"
+    );
+    assert!(!tmux_log.contains(&second_session), "tmux log: {tmux_log}");
+}
@@ -1,18 +1,11 @@
+mod common;
+
+use common::{tmux_session, write_executable};
 use std::fs;
-use std::io::Write;
-use std::os::unix::fs::PermissionsExt;
 use std::path::Path;
 use std::process::Command;
 use tempfile::TempDir;
 
-fn write_executable(path: &Path, contents: &str) {
-    let mut file = fs::File::create(path).unwrap();
-    file.write_all(contents.as_bytes()).unwrap();
-    let mut perms = fs::metadata(path).unwrap().permissions();
-    perms.set_mode(0o755);
-    fs::set_permissions(path, perms).unwrap();
-}
-
 fn setup_fake_binaries() -> (TempDir, String) {
     let bin_dir = TempDir::new().unwrap();
 
@@ -197,7 +190,7 @@ fn status_infers_completed_from_stream_when_tmux_missing() {
 
     // Simulate completed terminal output after tmux session disappears.
     fs::write(
-        project.path().join(".grotto/agents/agent-1/stream.log"),
+        project.path().join(".grotto/agents/agent-1/output.log"),
         "All done. Summary of what I accomplished... committed and pushed",
     )
     .unwrap();
@@ -215,7 +208,10 @@ fn status_infers_completed_from_stream_when_tmux_missing() {
     let stdout = String::from_utf8_lossy(&status_output.stdout);
     let lower = stdout.to_lowercase();
     assert!(
-        lower.contains("tmux session: grotto (completed)"),
+        lower.contains(&format!(
+            "tmux session: {} (completed)",
+            tmux_session(project.path())
+        )),
         "stdout: {}",
         stdout
     );
@@ -246,7 +242,7 @@ fn status_keeps_not_found_when_no_completion_signal() {
     assert!(spawn_output.status.success());
 
     fs::write(
-        project.path().join(".grotto/agents/agent-1/stream.log"),
+        project.path().join(".grotto/agents/agent-1/output.log"),
         "still starting up",
     )
     .unwrap();
@@ -264,7 +260,10 @@ fn status_keeps_not_found_when_no_completion_signal() {
     let stdout = String::from_utf8_lossy(&status_output.stdout);
     let lower = stdout.to_lowercase();
     assert!(
-        lower.contains("tmux session: grotto (not found)"),
+        lower.contains(&format!(
+            "tmux session: {} (not found)",
+            tmux_session(project.path())
+        )),
         "stdout: {}",
         stdout
     );
@@ -294,7 +293,7 @@ fn status_rewrites_spawning_to_done_when_completion_signal_exists() {
     assert!(spawn_output.status.success());
 
     fs::write(
-        project.path().join(".grotto/agents/agent-1/stream.log"),
+        project.path().join(".grotto/agents/agent-1/output.log"),
         "All done. committed and pushed",
     )
     .unwrap();
@@ -0,0 +1,54 @@
+mod common;
+
+use common::{run_grotto, setup_fake_binaries};
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn spawn_records_stall_thresholds_and_action() {
+    let project = TempDir::new().unwrap();
+    let home = TempDir::new().unwrap();
+    let (_bin_dir, path) = setup_fake_binaries();
+
+    let spawn = run_grotto(
+        project.path(),
+        home.path(),
+        &path,
+        &[
+            "spawn",
+            "1",
+            "t",
+            "--stall-after",
+            "20m",
+            "--loop-repeats",
+            "4",
+            "--on-stall",
+            "escalate",
+        ],
+    );
+    let stdout = String::from_utf8_lossy(&spawn.stdout);
+    assert!(spawn.status.success(), "stdout: {stdout}");
+    assert!(
+        stdout.contains("Stall detection: after 20m unchanged or 4 repeats, then escalate"),
+        "stdout: {stdout}"
+    );
+    let config = fs::read_to_string(project.path().join(".grotto/config.toml")).unwrap();
+    assert!(
+        config.contains("stall_after_secs = 1200"),
+        "config: {config}"
+    );
+    assert!(config.contains("loop_repeats = 4"), "config: {config}");
+    assert!(
+        config.contains("on_stall = \"escalate\""),
+        "config: {config}"
+    );
+
+    let spawn = run_grotto(
+        project.path(),
+        home.path(),
+        &path,
+        &["spawn", "1", "t", "--on-stall", "panic"],
+    );
+    assert!(!spawn.status.success());
+    assert!(String::from_utf8_lossy(&spawn.stderr).contains("Unknown stall action 'panic'"));
+}
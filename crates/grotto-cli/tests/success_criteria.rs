@@ -0,0 +1,206 @@
+mod common;
+
+use common::{logging_tmux, run_grotto_with, setup_fake_binaries_with};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+/// Fake tmux that logs its arguments. `GROTTO_TEST_SESSION_GONE` makes the
+/// session and its panes look gone, as after `claude -p` agents finish their
+/// pass and exit (new ones still open); otherwise every pane is running and
+/// shows whatever was pasted above an idle prompt.
+fn setup_fake_binaries(log: &Path) -> (TempDir, String) {
+    setup_fake_binaries_with(|bin_dir| {
+        logging_tmux(
+            log,
+            &format!(
+                "if [ -n \"$GROTTO_TEST_SESSION_GONE\" ]; then\n\
+                   case \"$1\" in has-session|display-message|capture-pane) exit 1;; esac\n\
+                 fi\n\
+                 if [ \"$1\" = \"display-message\" ]; then echo '%0 0 0'; fi\n\
+                 if [ \"$1\" = \"set-buffer\" ]; then printf '%s\\n' \"${{@: -1}}\" >> '{pasted}'; fi\n\
+                 if [ \"$1\" = \"capture-pane\" ]; then cat '{pasted}' 2>/dev/null; echo 'claude>'; fi\n",
+                pasted = bin_dir.join("pasted").display(),
+            ),
+        )
+    })
+}
+
+#[test]
+fn wait_marks_session_succeeded_when_criteria_pass() {
+    let project = TempDir::new().unwrap();
+    let home = TempDir::new().unwrap();
+    let log = home.path().join("tmux.log");
+    let (_bin_dir, path) = setup_fake_binaries(&log);
+
+    let spawn = run_grotto_with(
+        project.path(),
+        home.path(),
+        &path,
+        &["spawn", "1", "make it pass", "--until", "test -f done.txt"],
+        &[],
+    );
+    assert!(
+        spawn.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&spawn.stderr)
+    );
+    let config = fs::read_to_string(project.path().join(".grotto/config.toml")).unwrap();
+    assert!(
+        config.contains("until = [\"test -f done.txt\"]"),
+        "config: {config}"
+    );
+
+    fs::write(project.path().join("done.txt"), "").unwrap();
+    let wait = run_grotto_with(
+        project.path(),
+        home.path(),
+        &path,
+        &["wait", "--interval", "0"],
+        &[("GROTTO_TEST_SESSION_GONE", "1")],
+    );
+    let stdout = String::from_utf8_lossy(&wait.stdout);
+    assert!(wait.status.success(), "stdout: {stdout}");
+    assert!(stdout.contains("Session succeeded"), "stdout: {stdout}");
+
+    let config = fs::read_to_string(project.path().join(".grotto/config.toml")).unwrap();
+    assert!(
+        config.contains("outcome = \"succeeded\""),
+        "config: {config}"
+    );
+
+    let summary = fs::read_to_string(project.path().join(".grotto/summary.md")).unwrap();
+    assert!(summary.contains("## Verification"), "summary: {summary}");
+    assert!(summary.contains("Round 1/3: passed"), "summary: {summary}");
+
+    let events = fs::read_to_string(project.path().join(".grotto/events.jsonl")).unwrap();
+    assert!(events.contains("\"event_type\":\"session_succeeded\""));
+}
+
+#[test]
+fn wait_relaunches_exited_agents_with_the_failure_until_rounds_run_out() {
+    let project = TempDir::new().unwrap();
+    let home = TempDir::new().unwrap();
+    let log = home.path().join("tmux.log");
+    let (_bin_dir, path) = setup_fake_binaries(&log);
+
+    let spawn = run_grotto_with(
+        project.path(),
+        home.path(),
+        &path,
+        &[
+            "spawn",
+            "2",
+            "make it pass",
+            "--until",
+            "echo assertion failed; exit 1",
+            "--max-rounds",
+            "2",
+        ],
+        &[],
+    );
+    assert!(spawn.status.success());
+    let spawned = fs::read_to_string(&log).unwrap();
+
+    // The agents ran their one pass and exited, taking the session with them
+    let wait = run_grotto_with(
+        project.path(),
+        home.path(),
+        &path,
+        &["wait", "--interval", "0"],
+        &[("GROTTO_TEST_SESSION_GONE", "1")],
+    );
+    assert!(!wait.status.success());
+    let stdout = String::from_utf8_lossy(&wait.stdout);
+    assert!(
+        stdout.contains("Verification round 2/2"),
+        "stdout: {stdout}"
+    );
+    assert!(
+        stdout.contains("Relaunched agent-2 with the failure output"),
+        "stdout: {stdout}"
+    );
+    assert!(stdout.contains("Session failed"), "stdout: {stdout}");
+
+    let tmux_log = fs::read_to_string(&log).unwrap();
+    let after_spawn = &tmux_log[spawned.len()..];
+    let relaunches = after_spawn
+        .lines()
+        .filter(|l| l.starts_with("new-session") || l.starts_with("split-window"))
+        .count();
+    // Both agents once, after round 1; the last round has no retry
+    assert_eq!(relaunches, 2, "tmux log: {after_spawn}");
+    assert_eq!(
+        after_spawn
+            .matches("verification round 1/2 failed: `echo assertion failed; exit 1` exited with 1")
+            .count(),
+        2,
+        "tmux log: {after_spawn}"
+    );
+    assert!(after_spawn.contains("\nassertion failed\n"));
+
+    let config = fs::read_to_string(project.path().join(".grotto/config.toml")).unwrap();
+    assert!(config.contains("outcome = \"failed\""), "config: {config}");
+}
+
+#[test]
+fn wait_broadcasts_failures_until_rounds_run_out() {
+    let project = TempDir::new().unwrap();
+    let home = TempDir::new().unwrap();
+    let log = home.path().join("tmux.log");
+    let (_bin_dir, path) = setup_fake_binaries(&log);
+
+    let spawn = run_grotto_with(
+        project.path(),
+        home.path(),
+        &path,
+        &[
+            "spawn",
+            "2",
+            "make it pass",
+            "--until",
+            "echo assertion failed; exit 1",
+            "--max-rounds",
+            "2",
+        ],
+        &[],
+    );
+    assert!(spawn.status.success());
+
+    let wait = run_grotto_with(
+        project.path(),
+        home.path(),
+        &path,
+        &["wait", "--interval", "0"],
+        &[],
+    );
+    assert!(!wait.status.success());
+    let stderr = String::from_utf8_lossy(&wait.stderr);
+    assert!(
+        stderr.contains("Success criteria failed"),
+        "stderr: {stderr}"
+    );
+
+    let tmux_log = fs::read_to_string(&log).unwrap();
+    let feedback: Vec<&str> = tmux_log
+        .lines()
+        .filter(|l| l.starts_with("set-buffer") && l.contains("Success criteria failed"))
+        .collect();
+    // One round of feedback to each of the two agents; the last round has no retry
+    assert_eq!(feedback.len(), 2, "tmux log: {tmux_log}");
+    assert!(feedback[0].contains("round 1/2"));
+    // The output follows on its own lines
+    assert!(tmux_log.contains("Its output:\n\nassertion failed\n"));
+
+    let config = fs::read_to_string(project.path().join(".grotto/config.toml")).unwrap();
+    assert!(config.contains("outcome = \"failed\""), "config: {config}");
+
+    let events = fs::read_to_string(project.path().join(".grotto/events.jsonl")).unwrap();
+    assert_eq!(
+        events
+            .matches("\"event_type\":\"verification_round\"")
+            .count(),
+        2
+    );
+    assert!(events.contains("\"event_type\":\"session_failed\""));
+}
@@ -0,0 +1,123 @@
+mod common;
+
+use common::{logging_tmux, run_grotto, setup_fake_binaries_with, tmux_session};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+/// Fake tmux that logs its arguments and reports pane 0 as exited with
+/// status 1; the session is gone once the supervisor kills it.
+fn setup_fake_binaries(log: &Path) -> (TempDir, String) {
+    setup_fake_binaries_with(|bin_dir| {
+        logging_tmux(
+            log,
+            &format!(
+                "if [ \"$1\" = \"kill-session\" ] && [ -e '{listed}' ]; then touch '{killed}'; fi\n\
+                 if [ \"$1\" = \"has-session\" ] && [ -e '{killed}' ]; then exit 1; fi\n\
+                 if [ \"$1\" = \"list-panes\" ]; then touch '{listed}'; echo '%0 0 1 1'; fi\n",
+                killed = bin_dir.join("killed").display(),
+                listed = bin_dir.join("listed").display()
+            ),
+        )
+    })
+}
+
+#[test]
+fn spawn_rejects_unknown_restart_policy() {
+    let project = TempDir::new().unwrap();
+    let home = TempDir::new().unwrap();
+    let (_bin_dir, path) = setup_fake_binaries(&home.path().join("tmux.log"));
+
+    let spawn = run_grotto(
+        project.path(),
+        home.path(),
+        &path,
+        &["spawn", "1", "t", "--restart", "sometimes"],
+    );
+    assert!(!spawn.status.success());
+    assert!(String::from_utf8_lossy(&spawn.stderr).contains("Unknown restart policy 'sometimes'"));
+}
+
+#[test]
+fn supervise_restarts_failed_agent_with_its_task_then_gives_up() {
+    let project = TempDir::new().unwrap();
+    let home = TempDir::new().unwrap();
+    let log = home.path().join("tmux.log");
+    let (_bin_dir, path) = setup_fake_binaries(&log);
+
+    let spawn = run_grotto(
+        project.path(),
+        home.path(),
+        &path,
+        &[
+            "spawn",
+            "1",
+            "t",
+            "--restart",
+            "on-failure",
+            "--max-restarts",
+            "1",
+        ],
+    );
+    assert!(spawn.status.success());
+    let config = fs::read_to_string(project.path().join(".grotto/config.toml")).unwrap();
+    assert!(
+        config.contains("restart = \"on-failure\""),
+        "config: {config}"
+    );
+    assert!(config.contains("max_restarts = 1"), "config: {config}");
+    let session = tmux_session(project.path());
+    let tmux_log = fs::read_to_string(&log).unwrap();
+    assert!(
+        tmux_log.contains(&format!(
+            "set-window-option -t {session}:0 remain-on-exit on"
+        )),
+        "tmux log: {tmux_log}"
+    );
+
+    let claim = run_grotto(
+        project.path(),
+        home.path(),
+        &path,
+        &["claim", "main", "--agent", "agent-1"],
+    );
+    assert!(claim.status.success());
+
+    let supervise = run_grotto(
+        project.path(),
+        home.path(),
+        &path,
+        &["supervise", "--interval", "0"],
+    );
+    let stdout = String::from_utf8_lossy(&supervise.stdout);
+    assert!(supervise.status.success(), "stdout: {stdout}");
+    assert!(
+        stdout.contains("Restarted agent-1 after exit 1 (1 of 1)"),
+        "stdout: {stdout}"
+    );
+    assert!(
+        stdout.contains("agent-1 exited with status 1, out of restarts"),
+        "stdout: {stdout}"
+    );
+    assert!(stdout.contains("Session ended"), "stdout: {stdout}");
+
+    let tmux_log = fs::read_to_string(&log).unwrap();
+    // The prompt spans several lines of the log
+    let respawn = tmux_log
+        .find(&format!("respawn-pane -k -t {session}:0.0"))
+        .unwrap_or_else(|| panic!("tmux log: {tmux_log}"));
+    assert!(
+        tmux_log[respawn..].contains("You had claimed task main"),
+        "tmux log: {tmux_log}"
+    );
+    assert!(
+        tmux_log.contains(&format!("kill-session -t {session}")),
+        "tmux log: {tmux_log}"
+    );
+
+    let events = fs::read_to_string(project.path().join(".grotto/events.jsonl")).unwrap();
+    assert!(events.contains("\"event_type\":\"agent_restarted\""));
+    assert!(events.contains("\"event_type\":\"agent_exited\""));
+    let tasks = fs::read_to_string(project.path().join(".grotto/tasks.json")).unwrap();
+    assert!(tasks.contains("\"Open\""), "tasks: {tasks}");
+}
@@ -0,0 +1,69 @@
+mod common;
+
+use common::{run_grotto, setup_fake_binaries};
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn timeline_lists_recorded_phase_changes() {
+    let project = TempDir::new().unwrap();
+    let home = TempDir::new().unwrap();
+    let (_bin_dir, path) = setup_fake_binaries();
+
+    let spawn = run_grotto(project.path(), home.path(), &path, &["spawn", "2", "t"]);
+    assert!(spawn.status.success());
+
+    let empty = run_grotto(project.path(), home.path(), &path, &["timeline"]);
+    assert!(String::from_utf8_lossy(&empty.stdout).contains("No phase changes recorded yet"));
+
+    // What the daemon's monitor writes as phases change
+    let start = chrono::Utc::now();
+    let lines: Vec<String> = [
+        (0, "thinking", "◐ Thinking"),
+        (90, "editing", "Edit(src/a.rs)"),
+    ]
+    .iter()
+    .map(|(offset, phase, line)| {
+        serde_json::json!({
+            "timestamp": start + chrono::Duration::seconds(*offset),
+            "phase": phase,
+            "last_activity": line,
+        })
+        .to_string()
+            + "\n"
+    })
+    .collect();
+    fs::write(
+        project.path().join(".grotto/agents/agent-1/phases.jsonl"),
+        lines.concat(),
+    )
+    .unwrap();
+
+    let timeline = run_grotto(
+        project.path(),
+        home.path(),
+        &path,
+        &["timeline", "--agent", "agent-1"],
+    );
+    let stdout = String::from_utf8_lossy(&timeline.stdout);
+    assert!(timeline.status.success(), "stdout: {stdout}");
+    assert!(stdout.contains("🕒 agent-1"), "stdout: {stdout}");
+    assert!(!stdout.contains("agent-2"), "stdout: {stdout}");
+    let thinking = stdout.lines().find(|l| l.contains("thinking")).unwrap();
+    assert!(thinking.contains("1m 30s"), "stdout: {stdout}");
+    assert!(thinking.contains("◐ Thinking"), "stdout: {stdout}");
+    assert!(
+        stdout
+            .lines()
+            .any(|l| l.contains("editing") && l.contains("so far")),
+        "stdout: {stdout}"
+    );
+
+    let unknown = run_grotto(
+        project.path(),
+        home.path(),
+        &path,
+        &["timeline", "--agent", "agent-9"],
+    );
+    assert!(!unknown.status.success());
+}
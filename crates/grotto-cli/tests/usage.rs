@@ -0,0 +1,164 @@
+mod common;
+
+use common::{logging_tmux, run_grotto, setup_fake_binaries_with, tmux_session};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+/// Fake tmux that logs its arguments; once a pane is killed the session is
+/// reported gone, as it would be after the last pane exits.
+fn setup_fake_binaries(log: &Path) -> (TempDir, String) {
+    setup_fake_binaries_with(|bin_dir| {
+        logging_tmux(
+            log,
+            &format!(
+                "if [ \"$1\" = \"kill-pane\" ]; then touch '{killed}'; fi\n\
+                 if [ \"$1\" = \"has-session\" ] && [ -e '{killed}' ]; then exit 1; fi\n",
+                killed = bin_dir.join("killed").display()
+            ),
+        )
+    })
+}
+
+fn write_output_log(project: &Path, agent: &str, contents: &str) {
+    fs::write(
+        project
+            .join(".grotto/agents")
+            .join(agent)
+            .join("output.log"),
+        contents,
+    )
+    .unwrap();
+}
+
+#[test]
+fn spawn_rejects_invalid_cost() {
+    let project = TempDir::new().unwrap();
+    let home = TempDir::new().unwrap();
+    let (_bin_dir, path) = setup_fake_binaries(&home.path().join("tmux.log"));
+
+    let spawn = run_grotto(
+        project.path(),
+        home.path(),
+        &path,
+        &["spawn", "1", "t", "--max-cost", "lots"],
+    );
+    assert!(!spawn.status.success());
+    assert!(String::from_utf8_lossy(&spawn.stderr).contains("Invalid cost 'lots'"));
+}
+
+#[test]
+fn status_shows_usage_per_agent_and_task() {
+    let project = TempDir::new().unwrap();
+    let home = TempDir::new().unwrap();
+    let (_bin_dir, path) = setup_fake_binaries(&home.path().join("tmux.log"));
+
+    let spawn = run_grotto(
+        project.path(),
+        home.path(),
+        &path,
+        &["spawn", "2", "t", "--max-cost", "$5"],
+    );
+    assert!(spawn.status.success());
+    let config = fs::read_to_string(project.path().join(".grotto/config.toml")).unwrap();
+    assert!(config.contains("max_cost_usd = 5.0"), "config: {config}");
+
+    write_output_log(
+        project.path(),
+        "agent-1",
+        "Total cost: $0.10\nUsage: 4k input, 500 output\n",
+    );
+    let claim = run_grotto(
+        project.path(),
+        home.path(),
+        &path,
+        &["claim", "main", "--agent", "agent-1"],
+    );
+    assert!(claim.status.success());
+    write_output_log(
+        project.path(),
+        "agent-1",
+        "Total cost: $0.42\nUsage: 12k input, 3k output\n",
+    );
+    write_output_log(
+        project.path(),
+        "agent-2",
+        r#"{"type":"result","total_cost_usd":0.18,"usage":{"input_tokens":6000,"output_tokens":900}}"#,
+    );
+
+    let status = run_grotto(project.path(), home.path(), &path, &["status"]);
+    let stdout = String::from_utf8_lossy(&status.stdout);
+    assert!(status.status.success(), "stdout: {stdout}");
+    assert!(
+        stdout.contains("Cost budget: $0.60 of $5.00 used"),
+        "stdout: {stdout}"
+    );
+    assert!(
+        stdout.contains("💰 Usage: $0.60 (18.0k in / 3.9k out)"),
+        "stdout: {stdout}"
+    );
+    assert!(
+        stdout.contains("agent-2: $0.18 (6.0k in / 900 out)"),
+        "stdout: {stdout}"
+    );
+    assert!(
+        stdout.contains("task main: $0.32 (8.0k in / 2.5k out)"),
+        "stdout: {stdout}"
+    );
+}
+
+#[test]
+fn wait_stops_team_at_cost_budget() {
+    let project = TempDir::new().unwrap();
+    let home = TempDir::new().unwrap();
+    let log = home.path().join("tmux.log");
+    let (_bin_dir, path) = setup_fake_binaries(&log);
+
+    let spawn = run_grotto(
+        project.path(),
+        home.path(),
+        &path,
+        &["spawn", "2", "t", "--max-cost", "1"],
+    );
+    assert!(spawn.status.success());
+    write_output_log(
+        project.path(),
+        "agent-2",
+        "Total cost: $1.25\nUsage: 80k input, 9k output\n",
+    );
+
+    let wait = run_grotto(
+        project.path(),
+        home.path(),
+        &path,
+        &["wait", "--interval", "0"],
+    );
+    let stdout = String::from_utf8_lossy(&wait.stdout);
+    let stderr = String::from_utf8_lossy(&wait.stderr);
+    assert!(!wait.status.success(), "stdout: {stdout}");
+    assert!(
+        stdout.contains("Session stopped: $1.00 cost budget exceeded"),
+        "stdout: {stdout}"
+    );
+    assert!(
+        stderr.contains("Budget exceeded: session stopped after spending $1.00"),
+        "stderr: {stderr}"
+    );
+    let session = tmux_session(project.path());
+    let calls = fs::read_to_string(&log).unwrap();
+    assert!(
+        calls.contains(&format!("kill-pane -t {session}:0.0")),
+        "{calls}"
+    );
+    assert!(
+        calls.contains(&format!("kill-pane -t {session}:0.1")),
+        "{calls}"
+    );
+
+    let summary = fs::read_to_string(project.path().join(".grotto/summary.md")).unwrap();
+    assert!(
+        summary.contains("Status: Stopped ($1.00 cost budget exceeded)"),
+        "summary: {summary}"
+    );
+    assert!(summary.contains("- Cost: $1.25"), "summary: {summary}");
+}
@@ -0,0 +1,209 @@
+mod common;
+
+use common::{run_grotto, setup_fake_binaries};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use tempfile::TempDir;
+
+fn git(dir: &Path, args: &[&str]) -> std::process::Output {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "git {:?} failed", args);
+    output
+}
+
+fn init_repo(dir: &Path) {
+    git(dir, &["init", "-q", "-b", "main"]);
+    git(dir, &["config", "user.name", "Test"]);
+    git(dir, &["config", "user.email", "test@example.com"]);
+    fs::write(dir.join("README.md"), "hello\n").unwrap();
+    git(dir, &["add", "-A"]);
+    git(dir, &["commit", "-q", "-m", "initial"]);
+}
+
+#[test]
+fn spawn_with_worktree_isolation_creates_branch_per_agent() {
+    let (_bin_dir, path) = setup_fake_binaries();
+    let project = TempDir::new().unwrap();
+    let home = TempDir::new().unwrap();
+    init_repo(project.path());
+
+    let output = run_grotto(
+        project.path(),
+        home.path(),
+        &path,
+        &["spawn", "2", "isolated task", "--isolate", "worktree"],
+    );
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    for agent in ["agent-1", "agent-2"] {
+        let wt = project.path().join(".grotto/worktrees").join(agent);
+        assert!(
+            wt.join("README.md").exists(),
+            "missing worktree for {agent}"
+        );
+
+        let status = fs::read_to_string(
+            project
+                .path()
+                .join(".grotto/agents")
+                .join(agent)
+                .join("status.json"),
+        )
+        .unwrap();
+        assert!(status.contains("\"worktree\""), "status: {status}");
+        assert!(status.contains(&format!("/{agent}\"")), "status: {status}");
+    }
+
+    let branches = git(project.path(), &["branch", "--list", "grotto/*"]);
+    let branches = String::from_utf8_lossy(&branches.stdout);
+    assert!(branches.contains("/agent-1"), "branches: {branches}");
+    assert!(branches.contains("/agent-2"), "branches: {branches}");
+
+    let config = fs::read_to_string(project.path().join(".grotto/config.toml")).unwrap();
+    assert!(
+        config.contains("isolation = \"worktree\""),
+        "config: {config}"
+    );
+
+    // .grotto/ shouldn't show up as untracked in the main checkout
+    let status = git(project.path(), &["status", "--porcelain"]);
+    assert!(String::from_utf8_lossy(&status.stdout).trim().is_empty());
+}
+
+#[test]
+fn kill_all_cleanup_removes_worktrees_but_keeps_branches() {
+    let (_bin_dir, path) = setup_fake_binaries();
+    let project = TempDir::new().unwrap();
+    let home = TempDir::new().unwrap();
+    init_repo(project.path());
+
+    let spawn = run_grotto(
+        project.path(),
+        home.path(),
+        &path,
+        &["spawn", "2", "isolated task", "--isolate", "worktree"],
+    );
+    assert!(spawn.status.success());
+
+    let kill = run_grotto(
+        project.path(),
+        home.path(),
+        &path,
+        &["kill", "all", "--cleanup"],
+    );
+    assert!(kill.status.success());
+
+    assert!(!project.path().join(".grotto/worktrees/agent-1").exists());
+    assert!(!project.path().join(".grotto/worktrees/agent-2").exists());
+
+    let branches = git(project.path(), &["branch", "--list", "grotto/*"]);
+    assert!(String::from_utf8_lossy(&branches.stdout).contains("/agent-1"));
+
+    let events = fs::read_to_string(project.path().join(".grotto/events.jsonl")).unwrap();
+    assert!(events.contains("\"event_type\":\"worktree_removed\""));
+}
+
+#[test]
+fn spawn_with_worktree_isolation_outside_git_repo_fails() {
+    let (_bin_dir, path) = setup_fake_binaries();
+    let project = TempDir::new().unwrap();
+    let home = TempDir::new().unwrap();
+
+    let output = run_grotto(
+        project.path(),
+        home.path(),
+        &path,
+        &["spawn", "1", "task", "--isolate", "worktree"],
+    );
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("requires a git repository"),
+        "stderr: {stderr}"
+    );
+    assert!(!project.path().join(".grotto").exists());
+}
+
+#[test]
+fn merge_integrates_agent_branches_and_writes_summary() {
+    let (_bin_dir, path) = setup_fake_binaries();
+    let project = TempDir::new().unwrap();
+    let home = TempDir::new().unwrap();
+    init_repo(project.path());
+
+    let spawn = run_grotto(
+        project.path(),
+        home.path(),
+        &path,
+        &["spawn", "2", "isolated task", "--isolate", "worktree"],
+    );
+    assert!(spawn.status.success());
+
+    let wt = project.path().join(".grotto/worktrees/agent-2");
+    fs::write(wt.join("feature.txt"), "agent 2 work\n").unwrap();
+    git(&wt, &["add", "-A"]);
+    git(&wt, &["commit", "-q", "-m", "agent-2 feature"]);
+
+    let merge = run_grotto(
+        project.path(),
+        home.path(),
+        &path,
+        &["merge", "--into", "main", "--check", "test -f feature.txt"],
+    );
+    assert!(
+        merge.status.success(),
+        "stdout: {}\nstderr: {}",
+        String::from_utf8_lossy(&merge.stdout),
+        String::from_utf8_lossy(&merge.stderr)
+    );
+    assert!(project.path().join("feature.txt").exists());
+
+    let summary = fs::read_to_string(project.path().join(".grotto/summary.md")).unwrap();
+    assert!(summary.contains("## Merge Pipeline"), "summary: {summary}");
+    assert!(
+        summary.contains("all branches merged"),
+        "summary: {summary}"
+    );
+}
+
+#[test]
+fn merge_reports_failure_when_check_fails() {
+    let (_bin_dir, path) = setup_fake_binaries();
+    let project = TempDir::new().unwrap();
+    let home = TempDir::new().unwrap();
+    init_repo(project.path());
+
+    let spawn = run_grotto(
+        project.path(),
+        home.path(),
+        &path,
+        &["spawn", "1", "isolated task", "--isolate", "worktree"],
+    );
+    assert!(spawn.status.success());
+
+    let wt = project.path().join(".grotto/worktrees/agent-1");
+    fs::write(wt.join("feature.txt"), "work\n").unwrap();
+    git(&wt, &["add", "-A"]);
+    git(&wt, &["commit", "-q", "-m", "agent-1 feature"]);
+
+    let merge = run_grotto(
+        project.path(),
+        home.path(),
+        &path,
+        &["merge", "--check", "echo tests broke; exit 1"],
+    );
+    assert!(!merge.status.success());
+    let stdout = String::from_utf8_lossy(&merge.stdout);
+    assert!(stdout.contains("tests broke"), "stdout: {stdout}");
+    assert!(!project.path().join("feature.txt").exists());
+}
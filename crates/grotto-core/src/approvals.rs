@@ -0,0 +1,327 @@
+use crate::{Grotto, GrottoError, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Directory inside `.grotto/` holding one JSON file per approval request.
+pub const APPROVALS_DIR: &str = "approvals";
+
+/// How long `grotto request-approval` waits for an answer by default.
+pub const DEFAULT_TIMEOUT_SECS: u64 = 1800;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalStatus {
+    Pending,
+    Approved,
+    Denied,
+    Expired,
+}
+
+impl fmt::Display for ApprovalStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApprovalStatus::Pending => write!(f, "pending"),
+            ApprovalStatus::Approved => write!(f, "approved"),
+            ApprovalStatus::Denied => write!(f, "denied"),
+            ApprovalStatus::Expired => write!(f, "expired"),
+        }
+    }
+}
+
+/// An agent asking the lead before a risky step.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Approval {
+    pub id: String,
+    pub agent_id: String,
+    pub description: String,
+    pub status: ApprovalStatus,
+    pub requested_at: DateTime<Utc>,
+    #[serde(default)]
+    pub decided_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub decided_by: Option<String>,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+fn approvals_dir(grotto_dir: &Path) -> PathBuf {
+    grotto_dir.join(APPROVALS_DIR)
+}
+
+fn approval_path(grotto_dir: &Path, id: &str) -> PathBuf {
+    approvals_dir(grotto_dir).join(format!("{}.json", id))
+}
+
+/// Replace a request file atomically; `request-approval` polls it.
+fn write(grotto_dir: &Path, approval: &Approval) -> Result<()> {
+    let path = approval_path(grotto_dir, &approval.id);
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, serde_json::to_string_pretty(approval)?)?;
+    fs::rename(tmp, path)?;
+    Ok(())
+}
+
+/// Load one approval by ID.
+pub fn load(grotto_dir: &Path, id: &str) -> Result<Approval> {
+    let path = approval_path(grotto_dir, id);
+    if !path.exists() {
+        return Err(GrottoError::ApprovalNotFound(id.to_string()));
+    }
+    Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+}
+
+/// All approvals, oldest first.
+pub fn list(grotto_dir: &Path) -> Result<Vec<Approval>> {
+    let dir = approvals_dir(grotto_dir);
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut approvals: Vec<Approval> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|entry| fs::read_to_string(entry.path()).ok())
+        .filter_map(|content| serde_json::from_str(&content).ok())
+        .collect();
+    approvals.sort_by_key(|a| (a.requested_at, a.id.clone()));
+    Ok(approvals)
+}
+
+/// File a new pending request and log `approval_requested`.
+pub fn request(grotto: &Grotto, agent_id: &str, description: &str) -> Result<Approval> {
+    grotto.get_agent_pane(agent_id)?;
+    let dir = approvals_dir(&grotto.grotto_dir);
+    fs::create_dir_all(&dir)?;
+
+    let mut approval = Approval {
+        id: String::new(),
+        agent_id: agent_id.to_string(),
+        description: description.to_string(),
+        status: ApprovalStatus::Pending,
+        requested_at: Utc::now(),
+        decided_at: None,
+        decided_by: None,
+        reason: None,
+    };
+
+    // Agents can ask at the same time, so claim the next free ID atomically:
+    // hard-linking a fully written temp file fails if the ID is taken, and
+    // readers never see a half-written request.
+    let tmp = dir.join(format!(".request-{}.tmp", std::process::id()));
+    let mut n = list(&grotto.grotto_dir)?.len() + 1;
+    loop {
+        approval.id = format!("approval-{}", n);
+        fs::write(&tmp, serde_json::to_string_pretty(&approval)?)?;
+        match fs::hard_link(&tmp, approval_path(&grotto.grotto_dir, &approval.id)) {
+            Ok(()) => break,
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => n += 1,
+            Err(e) => {
+                let _ = fs::remove_file(&tmp);
+                return Err(e.into());
+            }
+        }
+    }
+    fs::remove_file(&tmp)?;
+
+    grotto.log_event(
+        "approval_requested",
+        Some(agent_id),
+        None,
+        Some(&format!("{} asks: {}", agent_id, description)),
+        serde_json::json!({ "approval_id": approval.id, "description": description }),
+    )?;
+    Ok(approval)
+}
+
+/// Answer a pending request and log `approval_<status>`.
+///
+/// Fails if the request was already answered or has expired.
+pub fn decide(
+    grotto: &Grotto,
+    id: &str,
+    status: ApprovalStatus,
+    reason: Option<&str>,
+    decided_by: &str,
+) -> Result<Approval> {
+    let mut approval = load(&grotto.grotto_dir, id)?;
+    if approval.status != ApprovalStatus::Pending {
+        return Err(GrottoError::Io(std::io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("{} is already {}", id, approval.status),
+        )));
+    }
+
+    approval.status = status;
+    approval.decided_at = Some(Utc::now());
+    approval.decided_by = Some(decided_by.to_string());
+    approval.reason = reason.map(str::to_string);
+    write(&grotto.grotto_dir, &approval)?;
+
+    let message = match reason {
+        Some(reason) => format!("{} {}: {}", id, status, reason),
+        None => format!("{} {}", id, status),
+    };
+    grotto.log_event(
+        &format!("approval_{}", status),
+        Some(&approval.agent_id),
+        None,
+        Some(&message),
+        serde_json::json!({
+            "approval_id": id,
+            "description": approval.description,
+            "reason": reason,
+            "decided_by": decided_by,
+        }),
+    )?;
+    Ok(approval)
+}
+
+/// Block until the request is answered, expiring it after `timeout`.
+pub fn wait_for_decision(
+    grotto: &Grotto,
+    id: &str,
+    timeout: Duration,
+    poll: Duration,
+) -> Result<Approval> {
+    let start = Instant::now();
+    loop {
+        let approval = load(&grotto.grotto_dir, id)?;
+        if approval.status != ApprovalStatus::Pending {
+            return Ok(approval);
+        }
+        if start.elapsed() >= timeout {
+            let reason = format!("no answer within {}s", timeout.as_secs());
+            return match decide(grotto, id, ApprovalStatus::Expired, Some(&reason), "grotto") {
+                Ok(approval) => Ok(approval),
+                // Answered between the load and the expiry
+                Err(GrottoError::Io(e)) if e.kind() == ErrorKind::InvalidInput => {
+                    load(&grotto.grotto_dir, id)
+                }
+                Err(e) => Err(e),
+            };
+        }
+        std::thread::sleep(poll.min(timeout.saturating_sub(start.elapsed())));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn request_persists_pending_approval_with_sequential_ids() {
+        let tmp = TempDir::new().unwrap();
+        let grotto = Grotto::new(tmp.path(), 2, "t".into()).unwrap();
+
+        let first = request(&grotto, "agent-2", "run the DB migration").unwrap();
+        let second = request(&grotto, "agent-1", "drop the cache table").unwrap();
+        assert_eq!(first.id, "approval-1");
+        assert_eq!(second.id, "approval-2");
+        assert_eq!(first.status, ApprovalStatus::Pending);
+        assert!(
+            tmp.path()
+                .join(".grotto/approvals/approval-1.json")
+                .exists()
+        );
+
+        let listed = list(&grotto.grotto_dir).unwrap();
+        assert_eq!(listed.len(), 2);
+        assert_eq!(listed[0].description, "run the DB migration");
+
+        let events = fs::read_to_string(tmp.path().join(".grotto/events.jsonl")).unwrap();
+        assert!(events.contains("\"event_type\":\"approval_requested\""));
+    }
+
+    #[test]
+    fn request_rejects_unknown_agent() {
+        let tmp = TempDir::new().unwrap();
+        let grotto = Grotto::new(tmp.path(), 1, "t".into()).unwrap();
+        assert!(matches!(
+            request(&grotto, "agent-9", "x"),
+            Err(GrottoError::AgentNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn decide_records_answer_once() {
+        let tmp = TempDir::new().unwrap();
+        let grotto = Grotto::new(tmp.path(), 1, "t".into()).unwrap();
+        let approval = request(&grotto, "agent-1", "force push").unwrap();
+
+        let denied = decide(
+            &grotto,
+            &approval.id,
+            ApprovalStatus::Denied,
+            Some("rebase instead"),
+            "lead",
+        )
+        .unwrap();
+        assert_eq!(denied.status, ApprovalStatus::Denied);
+        assert_eq!(denied.reason.as_deref(), Some("rebase instead"));
+        assert_eq!(load(&grotto.grotto_dir, &approval.id).unwrap(), denied);
+
+        let err = decide(
+            &grotto,
+            &approval.id,
+            ApprovalStatus::Approved,
+            None,
+            "lead",
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("already denied"), "{err}");
+        assert!(matches!(
+            decide(
+                &grotto,
+                "approval-9",
+                ApprovalStatus::Approved,
+                None,
+                "lead"
+            ),
+            Err(GrottoError::ApprovalNotFound(_))
+        ));
+
+        let events = fs::read_to_string(tmp.path().join(".grotto/events.jsonl")).unwrap();
+        assert!(events.contains("\"event_type\":\"approval_denied\""));
+    }
+
+    #[test]
+    fn wait_returns_decision_or_expires() {
+        let tmp = TempDir::new().unwrap();
+        let grotto = Grotto::new(tmp.path(), 1, "t".into()).unwrap();
+
+        let approved = request(&grotto, "agent-1", "deploy").unwrap();
+        decide(
+            &grotto,
+            &approved.id,
+            ApprovalStatus::Approved,
+            None,
+            "lead",
+        )
+        .unwrap();
+        let result = wait_for_decision(
+            &grotto,
+            &approved.id,
+            Duration::from_secs(5),
+            Duration::from_millis(10),
+        )
+        .unwrap();
+        assert_eq!(result.status, ApprovalStatus::Approved);
+
+        let ignored = request(&grotto, "agent-1", "delete prod").unwrap();
+        let result = wait_for_decision(
+            &grotto,
+            &ignored.id,
+            Duration::from_millis(30),
+            Duration::from_millis(10),
+        )
+        .unwrap();
+        assert_eq!(result.status, ApprovalStatus::Expired);
+        assert_eq!(result.decided_by.as_deref(), Some("grotto"));
+    }
+}
@@ -0,0 +1,422 @@
+use crate::monitor::{AgentPhase, PaneSnapshot};
+use crate::mux::{Multiplexer, SessionMux};
+use crate::{Grotto, Result, supervise};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Wait after the first rate limit; doubled on every retry.
+pub const BASE_DELAY_SECS: i64 = 30;
+
+/// Longest wait between retries.
+pub const MAX_DELAY_SECS: i64 = 600;
+
+/// Retries that nudge the agent before it is restarted instead.
+pub const NUDGES_BEFORE_RESTART: u32 = 2;
+
+/// Wait before retry `attempt` (from 1): 30s, 1m, 2m, 4m, 8m, then 10m.
+pub fn delay_secs(attempt: u32) -> i64 {
+    let doublings = attempt.saturating_sub(1).min(16);
+    (BASE_DELAY_SECS << doublings).min(MAX_DELAY_SECS)
+}
+
+/// How a rate-limited agent is brought back once its wait is over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Recovery {
+    /// Tell the running agent to continue its task
+    Nudge,
+    /// Relaunch the agent in its pane with its task in the prompt
+    Restart,
+}
+
+impl fmt::Display for Recovery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Recovery::Nudge => write!(f, "nudge"),
+            Recovery::Restart => write!(f, "restart"),
+        }
+    }
+}
+
+/// Something the supervisor should do about a rate-limited agent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Step {
+    /// The agent just hit a rate limit; leave it alone for `delay_secs`
+    Paused {
+        agent_id: String,
+        delay_secs: i64,
+        line: String,
+    },
+    /// The wait is over; if the agent is still limited afterwards the next
+    /// retry comes `next_delay_secs` later
+    Retry {
+        agent_id: String,
+        attempt: u32,
+        action: Recovery,
+        next_delay_secs: i64,
+    },
+    /// The agent is working again
+    Recovered { agent_id: String, attempts: u32 },
+}
+
+struct Limited {
+    attempts: u32,
+    retry_at: DateTime<Utc>,
+}
+
+/// Tracks rate-limited agents across monitor ticks and decides when to retry.
+#[derive(Default)]
+pub struct Backoff {
+    limited: HashMap<String, Limited>,
+}
+
+impl Backoff {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Agents currently backing off.
+    pub fn is_limited(&self, agent_id: &str) -> bool {
+        self.limited.contains_key(agent_id)
+    }
+
+    /// Compare the latest snapshots against the agents backing off.
+    ///
+    /// An agent stays tracked until it is seen thinking, editing or running
+    /// again, so a restart that runs straight into the limit keeps backing off
+    /// instead of starting over.
+    pub fn observe(&mut self, snapshots: &[PaneSnapshot], now: DateTime<Utc>) -> Vec<Step> {
+        let mut steps = Vec::new();
+        for snap in snapshots {
+            let agent_id = &snap.agent_id;
+            match (&snap.phase, self.limited.get_mut(agent_id)) {
+                (AgentPhase::RateLimited, None) => {
+                    let delay = delay_secs(1);
+                    self.limited.insert(
+                        agent_id.clone(),
+                        Limited {
+                            attempts: 0,
+                            retry_at: now + Duration::seconds(delay),
+                        },
+                    );
+                    steps.push(Step::Paused {
+                        agent_id: agent_id.clone(),
+                        delay_secs: delay,
+                        line: snap.last_activity_line.clone(),
+                    });
+                }
+                (AgentPhase::RateLimited, Some(limited)) if now >= limited.retry_at => {
+                    limited.attempts += 1;
+                    let next_delay = delay_secs(limited.attempts + 1);
+                    limited.retry_at = now + Duration::seconds(next_delay);
+                    steps.push(Step::Retry {
+                        agent_id: agent_id.clone(),
+                        attempt: limited.attempts,
+                        action: if limited.attempts > NUDGES_BEFORE_RESTART {
+                            Recovery::Restart
+                        } else {
+                            Recovery::Nudge
+                        },
+                        next_delay_secs: next_delay,
+                    });
+                }
+                (AgentPhase::Thinking | AgentPhase::Editing | AgentPhase::Running, Some(_)) => {
+                    let attempts = self.limited.remove(agent_id).map_or(0, |l| l.attempts);
+                    steps.push(Step::Recovered {
+                        agent_id: agent_id.clone(),
+                        attempts,
+                    });
+                }
+                // Pane gone: nothing left to retry
+                (AgentPhase::Finished, Some(_)) => {
+                    self.limited.remove(agent_id);
+                }
+                _ => {}
+            }
+        }
+        steps
+    }
+}
+
+/// Carry out a step: update the agent's status, nudge or relaunch its pane,
+/// and log a `rate_limited`, `rate_limit_retry` or
+/// `rate_limit_recovered` event so the lead can see why it paused.
+pub fn apply(grotto: &mut Grotto, mux: &dyn Multiplexer, step: &Step) -> Result<()> {
+    let mux = SessionMux::new(grotto, mux);
+    match step {
+        Step::Paused {
+            agent_id,
+            delay_secs,
+            line,
+        } => {
+            let wait = crate::budget::format_duration(*delay_secs as u64);
+            update_agent(
+                grotto,
+                agent_id,
+                "rate_limited",
+                format!("Rate limited; retrying in {}", wait),
+            )?;
+            grotto.log_event(
+                "rate_limited",
+                Some(agent_id),
+                current_task(grotto, agent_id).as_deref(),
+                Some(&format!(
+                    "{} hit a rate limit; backing off {}",
+                    agent_id, wait
+                )),
+                serde_json::json!({ "delay_secs": delay_secs, "line": line }),
+            )?;
+        }
+        Step::Retry {
+            agent_id,
+            attempt,
+            action,
+            next_delay_secs,
+        } => {
+            match action {
+                Recovery::Nudge => {
+                    let pane = grotto.pane_target(agent_id)?;
+                    let message = format!(
+                        "[grotto] The rate limit backoff is over (retry {}). Continue where you left off. {}",
+                        attempt,
+                        task_context(grotto, agent_id)
+                    );
+                    mux.send_text(&pane, &message)?;
+                }
+                Recovery::Restart => {
+                    supervise::respawn_agent(grotto, &*mux, agent_id, "repeated rate limits")?;
+                }
+            }
+            update_agent(
+                grotto,
+                agent_id,
+                "rate_limited",
+                format!("Rate limited; {} sent (retry {})", action, attempt),
+            )?;
+            grotto.log_event(
+                "rate_limit_retry",
+                Some(agent_id),
+                current_task(grotto, agent_id).as_deref(),
+                Some(&format!(
+                    "{} retry {} after rate limit: {}",
+                    agent_id, attempt, action
+                )),
+                serde_json::json!({
+                    "attempt": attempt,
+                    "action": action,
+                    "next_delay_secs": next_delay_secs,
+                }),
+            )?;
+        }
+        Step::Recovered { agent_id, attempts } => {
+            let state = if current_task(grotto, agent_id).is_some() {
+                "working"
+            } else {
+                "idle"
+            };
+            update_agent(
+                grotto,
+                agent_id,
+                state,
+                "Recovered from rate limit".to_string(),
+            )?;
+            grotto.log_event(
+                "rate_limit_recovered",
+                Some(agent_id),
+                current_task(grotto, agent_id).as_deref(),
+                Some(&format!(
+                    "{} recovered from rate limit after {} retries",
+                    agent_id, attempts
+                )),
+                serde_json::json!({ "attempts": attempts }),
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn current_task(grotto: &Grotto, agent_id: &str) -> Option<String> {
+    grotto
+        .agents
+        .get(agent_id)
+        .and_then(|a| a.current_task.clone())
+}
+
+/// What the agent was doing, for nudges and restart prompts.
+fn task_context(grotto: &Grotto, agent_id: &str) -> String {
+    current_task(grotto, agent_id)
+        .and_then(|id| grotto.tasks.iter().find(|t| t.id == id))
+        .map(|task| format!("Your task is {}: {}", task.id, task.description))
+        .unwrap_or_else(|| "Pick up an open task from the board (`grotto status`).".to_string())
+}
+
+fn update_agent(grotto: &mut Grotto, agent_id: &str, state: &str, progress: String) -> Result<()> {
+    if let Some(agent) = grotto.agents.get_mut(agent_id) {
+        agent.state = state.to_string();
+        agent.progress = progress;
+        agent.last_update = Utc::now();
+    }
+    grotto.write_agent_status(agent_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mux::FakeMultiplexer;
+    use tempfile::TempDir;
+
+    fn snapshot(agent_id: &str, phase: AgentPhase) -> PaneSnapshot {
+        PaneSnapshot {
+            agent_id: agent_id.to_string(),
+            pane_index: 0,
+            raw_content: String::new(),
+            phase,
+            last_activity_line: "API Error: Rate limit reached".to_string(),
+            timestamp: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn delay_doubles_up_to_the_cap() {
+        assert_eq!(delay_secs(1), 30);
+        assert_eq!(delay_secs(2), 60);
+        assert_eq!(delay_secs(3), 120);
+        assert_eq!(delay_secs(6), MAX_DELAY_SECS);
+        assert_eq!(delay_secs(40), MAX_DELAY_SECS);
+    }
+
+    #[test]
+    fn observe_backs_off_then_nudges_then_restarts() {
+        let mut backoff = Backoff::new();
+        let limited = [snapshot("agent-1", AgentPhase::RateLimited)];
+        let t0 = Utc::now();
+
+        let steps = backoff.observe(&limited, t0);
+        assert!(matches!(&steps[..], [Step::Paused { delay_secs: 30, .. }]));
+        assert!(
+            backoff
+                .observe(&limited, t0 + Duration::seconds(29))
+                .is_empty()
+        );
+
+        let mut at = t0 + Duration::seconds(30);
+        let mut actions = Vec::new();
+        for _ in 0..3 {
+            let steps = backoff.observe(&limited, at);
+            let [
+                Step::Retry {
+                    action,
+                    next_delay_secs,
+                    ..
+                },
+            ] = &steps[..]
+            else {
+                panic!("expected a retry, got {steps:?}");
+            };
+            actions.push(*action);
+            at += Duration::seconds(*next_delay_secs);
+        }
+        assert_eq!(
+            actions,
+            [Recovery::Nudge, Recovery::Nudge, Recovery::Restart]
+        );
+    }
+
+    #[test]
+    fn observe_recovers_only_when_working_again() {
+        let mut backoff = Backoff::new();
+        let t0 = Utc::now();
+        backoff.observe(&[snapshot("agent-1", AgentPhase::RateLimited)], t0);
+
+        // Restarting or waiting at a prompt is not recovery yet
+        assert!(
+            backoff
+                .observe(&[snapshot("agent-1", AgentPhase::Starting)], t0)
+                .is_empty()
+        );
+        assert!(backoff.is_limited("agent-1"));
+
+        let steps = backoff.observe(&[snapshot("agent-1", AgentPhase::Thinking)], t0);
+        assert_eq!(
+            steps,
+            vec![Step::Recovered {
+                agent_id: "agent-1".to_string(),
+                attempts: 0
+            }]
+        );
+        assert!(!backoff.is_limited("agent-1"));
+
+        backoff.observe(&[snapshot("agent-2", AgentPhase::RateLimited)], t0);
+        assert!(
+            backoff
+                .observe(&[snapshot("agent-2", AgentPhase::Finished)], t0)
+                .is_empty()
+        );
+        assert!(!backoff.is_limited("agent-2"));
+    }
+
+    #[test]
+    fn apply_logs_every_step_and_tracks_agent_state() {
+        let tmp = TempDir::new().unwrap();
+        let mut grotto = Grotto::new(tmp.path(), 1, "t".into()).unwrap();
+        grotto.claim_task("main", "agent-1").unwrap();
+        let agent_id = "agent-1".to_string();
+        let mux = FakeMultiplexer::with_team(&mut grotto);
+
+        for step in [
+            Step::Paused {
+                agent_id: agent_id.clone(),
+                delay_secs: 30,
+                line: "rate limit".to_string(),
+            },
+            Step::Retry {
+                agent_id: agent_id.clone(),
+                attempt: 1,
+                action: Recovery::Nudge,
+                next_delay_secs: 60,
+            },
+        ] {
+            apply(&mut grotto, &mux, &step).unwrap();
+        }
+        let nudge = &mux
+            .pane(&grotto.pane_target("agent-1").unwrap())
+            .unwrap()
+            .sent[0];
+        assert!(
+            nudge[0].contains("Continue where you left off"),
+            "{nudge:?}"
+        );
+        assert_eq!(nudge[1], "Enter");
+        let loaded = Grotto::load(tmp.path()).unwrap();
+        assert_eq!(loaded.agents["agent-1"].state, "rate_limited");
+        assert_eq!(
+            loaded.agents["agent-1"].progress,
+            "Rate limited; nudge sent (retry 1)"
+        );
+
+        apply(
+            &mut grotto,
+            &mux,
+            &Step::Recovered {
+                agent_id,
+                attempts: 1,
+            },
+        )
+        .unwrap();
+        assert_eq!(grotto.agents["agent-1"].state, "working");
+
+        let events: Vec<String> = grotto
+            .read_session_events()
+            .unwrap()
+            .into_iter()
+            .map(|e| e.event_type)
+            .filter(|t| t.starts_with("rate_limit"))
+            .collect();
+        assert_eq!(
+            events,
+            ["rate_limited", "rate_limit_retry", "rate_limit_recovered"]
+        );
+    }
+}
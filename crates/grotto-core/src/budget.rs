@@ -0,0 +1,589 @@
+use crate::mux::{Multiplexer, SessionMux};
+use crate::{Event, Grotto, GrottoError, Result, usage};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+use std::time::Duration;
+
+/// Share of a budget used before agents are warned to wrap up.
+pub const WARN_FRACTION: f64 = 0.8;
+
+/// Agent states that no longer count against a budget.
+const STOPPED_STATES: &[&str] = &["done", "failed", "timed_out", "retired"];
+
+/// Parse a budget like `45m`, `2h`, `1h30m` or `90s` into seconds.
+///
+/// A bare number is taken as minutes.
+pub fn parse_duration(input: &str) -> Result<u64> {
+    let invalid = || {
+        GrottoError::Io(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!(
+                "Invalid duration '{}'. Use e.g. 45m, 2h, 1h30m or 90s",
+                input
+            ),
+        ))
+    };
+
+    let input = input.trim();
+    if let Ok(minutes) = input.parse::<u64>() {
+        return Ok(minutes * 60);
+    }
+
+    let mut total = 0u64;
+    let mut digits = String::new();
+    for c in input.chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+        let value: u64 = digits.parse().map_err(|_| invalid())?;
+        digits.clear();
+        total += value
+            * match c {
+                'h' => 3600,
+                'm' => 60,
+                's' => 1,
+                _ => return Err(invalid()),
+            };
+    }
+    if !digits.is_empty() || total == 0 {
+        return Err(invalid());
+    }
+    Ok(total)
+}
+
+/// Render seconds the way budgets are written: `1h 30m`, `45m`, `90s` → `1m 30s`.
+pub fn format_duration(secs: u64) -> String {
+    let (h, m, s) = (secs / 3600, secs % 3600 / 60, secs % 60);
+    let parts: Vec<String> = [(h, "h"), (m, "m"), (s, "s")]
+        .iter()
+        .filter(|(n, _)| *n > 0)
+        .map(|(n, unit)| format!("{}{}", n, unit))
+        .collect();
+    if parts.is_empty() {
+        "0s".to_string()
+    } else {
+        parts.join(" ")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetScope {
+    /// `spawn --max-duration`: the whole session
+    Session,
+    /// `spawn --agent-timeout`: one agent
+    Agent,
+    /// `spawn --max-cost`: dollars spent by the whole team
+    Cost,
+}
+
+impl BudgetScope {
+    /// Render an amount of this budget: a duration, or dollars for `Cost`.
+    pub fn format(&self, amount: f64) -> String {
+        match self {
+            BudgetScope::Cost => usage::format_cost(amount),
+            _ => format_duration(amount as u64),
+        }
+    }
+
+    /// `session time budget of 45m`, `cost budget of $5.00`
+    pub fn describe(&self, limit: f64) -> String {
+        match self {
+            BudgetScope::Cost => format!("cost budget of {}", self.format(limit)),
+            _ => format!("{} time budget of {}", self, self.format(limit)),
+        }
+    }
+
+    /// Session and cost budgets stop every agent at once.
+    pub fn stops_team(&self) -> bool {
+        *self != BudgetScope::Agent
+    }
+}
+
+impl fmt::Display for BudgetScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BudgetScope::Session => write!(f, "session"),
+            BudgetScope::Agent => write!(f, "agent"),
+            BudgetScope::Cost => write!(f, "cost"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertKind {
+    Warning,
+    Exceeded,
+}
+
+/// A budget that crossed its warning threshold or its limit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BudgetAlert {
+    pub kind: AlertKind,
+    pub scope: BudgetScope,
+    /// The agent for agent budgets; `None` for session and cost budgets
+    pub agent_id: Option<String>,
+    /// Seconds for time budgets, dollars for the cost budget
+    pub limit: f64,
+    pub used: f64,
+}
+
+/// An agent stopped by a budget, read back from `budget_exceeded`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Timeout {
+    pub scope: BudgetScope,
+    pub agent_id: Option<String>,
+    pub limit: f64,
+    pub requeued_task: Option<String>,
+}
+
+fn scope_of(event: &Event) -> Option<BudgetScope> {
+    serde_json::from_value(event.data["scope"].clone()).ok()
+}
+
+/// Budgets that need action now. Each warning and each limit fires once per
+/// session; warnings and shutdowns already logged are skipped.
+pub fn check(grotto: &Grotto, now: DateTime<Utc>) -> Result<Vec<BudgetAlert>> {
+    let session_limit = grotto.config.max_duration_secs;
+    let agent_limit = grotto.config.agent_timeout_secs;
+    let cost_limit = grotto.config.max_cost_usd;
+    if session_limit.is_none() && agent_limit.is_none() && cost_limit.is_none() {
+        return Ok(Vec::new());
+    }
+
+    let events = grotto.read_session_events()?;
+    let Some(session_start) = events.first().map(|e| e.timestamp) else {
+        return Ok(Vec::new());
+    };
+    let fired = |event_type: &str| -> HashSet<(BudgetScope, Option<String>)> {
+        events
+            .iter()
+            .filter(|e| e.event_type == event_type)
+            .filter_map(|e| Some((scope_of(e)?, e.agent_id.clone())))
+            .collect()
+    };
+    let warned = fired("budget_warning");
+    let exceeded = fired("budget_exceeded");
+    let elapsed_since = |start: DateTime<Utc>| (now - start).num_seconds().max(0) as f64;
+
+    // Stopping the team covers every other budget
+    if exceeded.iter().any(|(scope, _)| scope.stops_team()) {
+        return Ok(Vec::new());
+    }
+
+    let mut alerts = Vec::new();
+    let mut alert = |scope, agent_id: Option<String>, limit: f64, used: f64| {
+        let key = (scope, agent_id.clone());
+        let kind = if used >= limit {
+            AlertKind::Exceeded
+        } else if used >= limit * WARN_FRACTION && !warned.contains(&key) {
+            AlertKind::Warning
+        } else {
+            return false;
+        };
+        alerts.push(BudgetAlert {
+            kind,
+            scope,
+            agent_id,
+            limit,
+            used,
+        });
+        kind == AlertKind::Exceeded
+    };
+
+    if let Some(limit) = session_limit
+        && alert(
+            BudgetScope::Session,
+            None,
+            limit as f64,
+            elapsed_since(session_start),
+        )
+    {
+        return Ok(alerts);
+    }
+    if let Some(limit) = cost_limit
+        && alert(
+            BudgetScope::Cost,
+            None,
+            limit,
+            usage::total(grotto).cost_usd,
+        )
+    {
+        return Ok(alerts);
+    }
+
+    if let Some(limit) = agent_limit {
+        for agent_id in grotto.sorted_agent_ids() {
+            let agent = &grotto.agents[&agent_id];
+            if STOPPED_STATES.contains(&agent.state.as_str())
+                || exceeded.contains(&(BudgetScope::Agent, Some(agent_id.clone())))
+            {
+                continue;
+            }
+            // Agents added or restarted mid-session get a fresh budget
+            let start = events
+                .iter()
+                .rev()
+                .find(|e| {
+                    matches!(e.event_type.as_str(), "agent_spawned" | "agent_restarted")
+                        && e.agent_id.as_deref() == Some(&agent_id)
+                })
+                .map(|e| e.timestamp)
+                .unwrap_or(session_start);
+            alert(
+                BudgetScope::Agent,
+                Some(agent_id),
+                limit as f64,
+                elapsed_since(start),
+            );
+        }
+    }
+
+    Ok(alerts)
+}
+
+/// Warn, or stop agents that ran out of budget: their runtime's exit keys
+/// (`/exit` for Claude Code), then `kill-pane` (or the headless runner's
+/// kill) after `grace`. Their tasks are requeued and `budget_exceeded` is
+/// logged.
+pub fn enforce(
+    grotto: &mut Grotto,
+    mux: &dyn Multiplexer,
+    alerts: &[BudgetAlert],
+    grace: Duration,
+) -> Result<Vec<Timeout>> {
+    let mux = SessionMux::new(grotto, mux);
+    let mut stopping: Vec<(&BudgetAlert, String)> = Vec::new();
+    for alert in alerts {
+        let targets: Vec<String> = match &alert.agent_id {
+            Some(agent_id) => vec![agent_id.clone()],
+            None => grotto
+                .sorted_agent_ids()
+                .into_iter()
+                .filter(|id| !STOPPED_STATES.contains(&grotto.agents[id].state.as_str()))
+                .collect(),
+        };
+
+        match alert.kind {
+            AlertKind::Warning => {
+                let message = format!(
+                    "[grotto] {} budget warning: {} of the {} {} budget used. Wrap up: commit your work and report progress before you are stopped.",
+                    if alert.scope == BudgetScope::Cost {
+                        "Cost"
+                    } else {
+                        "Time"
+                    },
+                    alert.scope.format(alert.used),
+                    alert.scope.format(alert.limit),
+                    alert.scope
+                );
+                // One unreachable agent shouldn't keep the rest from being
+                // warned; it is named in the event instead
+                let mut undelivered = Vec::new();
+                for agent_id in &targets {
+                    let pane = grotto.pane_target(agent_id)?;
+                    if let Err(e) = mux.send_text(&pane, &message) {
+                        undelivered.push(serde_json::json!({
+                            "agent_id": agent_id,
+                            "error": e.to_string(),
+                        }));
+                    }
+                }
+                grotto.log_event(
+                    "budget_warning",
+                    alert.agent_id.as_deref(),
+                    None,
+                    Some(&message),
+                    serde_json::json!({
+                        "scope": alert.scope,
+                        "limit": alert.limit,
+                        "used": alert.used,
+                        "undelivered": undelivered,
+                    }),
+                )?;
+            }
+            AlertKind::Exceeded => {
+                for agent_id in targets {
+                    let pane = grotto.pane_target(&agent_id)?;
+                    let exit_keys = grotto.agent_runtime(&agent_id).exit_keys();
+                    // Best effort: the agent is killed after the grace period
+                    // whether or not it got them
+                    let _ = mux.send_keys(&pane, &exit_keys);
+                    stopping.push((alert, agent_id));
+                }
+            }
+        }
+    }
+
+    if stopping.is_empty() {
+        return Ok(Vec::new());
+    }
+    std::thread::sleep(grace);
+
+    // Highest pane first so killing one doesn't renumber the rest, for
+    // agents addressed by index
+    stopping.sort_by_key(|(_, id)| std::cmp::Reverse(grotto.agents[id].pane_index));
+    for (_, agent_id) in &stopping {
+        let pane = grotto.pane_target(agent_id)?;
+        // Failing is fine if the agent already exited during the grace period
+        if let Err(e) = mux.kill_pane(&pane)
+            && mux.check_alive(&pane).is_ok()
+        {
+            return Err(e);
+        }
+    }
+    stopping.sort_by_key(|(_, id)| grotto.agents[id].pane_index);
+
+    let mut timeouts = Vec::new();
+    for (alert, agent_id) in stopping {
+        let budget = alert.scope.describe(alert.limit);
+        let requeued_task = grotto.agents[&agent_id].current_task.clone();
+        if let Some(task_id) = &requeued_task {
+            let reason = format!("{} stopped: {} exceeded", agent_id, budget);
+            match grotto.requeue_task(task_id, &reason) {
+                Ok(()) | Err(GrottoError::TaskNotFound(_)) => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        if let Some(agent) = grotto.agents.get_mut(&agent_id) {
+            agent.state = "timed_out".to_string();
+            agent.current_task = None;
+            agent.progress = format!("Stopped: {} exceeded", budget);
+            agent.last_update = Utc::now();
+        }
+        grotto.write_agent_status(&agent_id)?;
+
+        grotto.log_event(
+            "budget_exceeded",
+            Some(&agent_id),
+            requeued_task.as_deref(),
+            Some(&format!("{} stopped: {} exceeded", agent_id, budget)),
+            serde_json::json!({
+                "scope": alert.scope,
+                "limit": alert.limit,
+                "used": alert.used,
+                "requeued_task": requeued_task,
+            }),
+        )?;
+        timeouts.push(Timeout {
+            scope: alert.scope,
+            agent_id: Some(agent_id),
+            limit: alert.limit,
+            requeued_task,
+        });
+    }
+    Ok(timeouts)
+}
+
+/// Agents stopped by a budget during the current session.
+pub fn timeouts(grotto: &Grotto) -> Result<Vec<Timeout>> {
+    Ok(grotto
+        .read_session_events()?
+        .iter()
+        .filter(|e| e.event_type == "budget_exceeded")
+        .filter_map(|e| {
+            Some(Timeout {
+                scope: scope_of(e)?,
+                agent_id: e.agent_id.clone(),
+                limit: e.data["limit"].as_f64()?,
+                requeued_task: e.data["requeued_task"].as_str().map(str::to_string),
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mux::FakeMultiplexer;
+    use chrono::Duration as ChronoDuration;
+    use tempfile::TempDir;
+
+    fn session(agents: usize, max: Option<u64>, per_agent: Option<u64>) -> (TempDir, Grotto) {
+        let tmp = TempDir::new().unwrap();
+        let mut grotto = Grotto::new(tmp.path(), agents, "t".into()).unwrap();
+        grotto.config.max_duration_secs = max;
+        grotto.config.agent_timeout_secs = per_agent;
+        (tmp, grotto)
+    }
+
+    fn started(grotto: &Grotto) -> DateTime<Utc> {
+        grotto.read_session_events().unwrap()[0].timestamp
+    }
+
+    #[test]
+    fn parse_duration_accepts_units() {
+        assert_eq!(parse_duration("45m").unwrap(), 45 * 60);
+        assert_eq!(parse_duration("2h").unwrap(), 7200);
+        assert_eq!(parse_duration("1h30m").unwrap(), 5400);
+        assert_eq!(parse_duration("90s").unwrap(), 90);
+        assert_eq!(parse_duration("20").unwrap(), 1200);
+        for bad in ["", "0m", "45x", "m", "1h30"] {
+            let err = parse_duration(bad).unwrap_err().to_string();
+            assert!(err.contains("Invalid duration"), "{bad}: {err}");
+        }
+    }
+
+    #[test]
+    fn format_duration_is_compact() {
+        assert_eq!(format_duration(45 * 60), "45m");
+        assert_eq!(format_duration(5400), "1h 30m");
+        assert_eq!(format_duration(90), "1m 30s");
+        assert_eq!(format_duration(0), "0s");
+    }
+
+    #[test]
+    fn check_without_budgets_is_empty() {
+        let (_tmp, grotto) = session(1, None, None);
+        let later = started(&grotto) + ChronoDuration::hours(10);
+        assert!(check(&grotto, later).unwrap().is_empty());
+    }
+
+    #[test]
+    fn check_warns_at_eighty_percent_once() {
+        let (_tmp, mut grotto) = session(1, Some(100), None);
+        let start = started(&grotto);
+
+        assert!(
+            check(&grotto, start + ChronoDuration::seconds(79))
+                .unwrap()
+                .is_empty()
+        );
+        let alerts = check(&grotto, start + ChronoDuration::seconds(80)).unwrap();
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].kind, AlertKind::Warning);
+        assert_eq!(alerts[0].scope, BudgetScope::Session);
+
+        let mux = FakeMultiplexer::with_team(&mut grotto);
+        enforce(&mut grotto, &mux, &alerts, Duration::ZERO).unwrap();
+        let warning = &mux
+            .pane(&grotto.pane_target("agent-1").unwrap())
+            .unwrap()
+            .sent[0];
+        assert!(warning[0].contains("Time budget warning"), "{warning:?}");
+        assert!(
+            check(&grotto, start + ChronoDuration::seconds(90))
+                .unwrap()
+                .is_empty()
+        );
+        let events = grotto.read_session_events().unwrap();
+        assert!(events.iter().any(|e| e.event_type == "budget_warning"));
+    }
+
+    #[test]
+    fn session_limit_stops_every_agent_and_requeues_tasks() {
+        let (_tmp, mut grotto) = session(2, Some(60), Some(30));
+        grotto.claim_task("main", "agent-2").unwrap();
+        let start = started(&grotto);
+
+        let alerts = check(&grotto, start + ChronoDuration::seconds(61)).unwrap();
+        // The session budget covers the agent budgets
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].kind, AlertKind::Exceeded);
+
+        let mux = FakeMultiplexer::with_team(&mut grotto);
+        let stopped = enforce(&mut grotto, &mux, &alerts, Duration::ZERO).unwrap();
+        assert_eq!(stopped.len(), 2);
+        // Each agent got /exit before its pane was closed
+        assert!(!mux.has_session(&grotto.config.tmux_session));
+        assert_eq!(stopped[1].agent_id.as_deref(), Some("agent-2"));
+        assert_eq!(stopped[1].requeued_task.as_deref(), Some("main"));
+        assert_eq!(grotto.agents["agent-1"].state, "timed_out");
+
+        let loaded = Grotto::load(grotto.config.project_dir.clone()).unwrap();
+        assert!(matches!(loaded.tasks[0].status, crate::TaskStatus::Open));
+        assert_eq!(timeouts(&loaded).unwrap(), stopped);
+
+        // Already stopped: nothing more to do
+        assert!(
+            check(&loaded, start + ChronoDuration::hours(1))
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn cost_limit_warns_then_stops_the_team() {
+        let (tmp, mut grotto) = session(2, None, Some(3600));
+        grotto.config.max_cost_usd = Some(1.0);
+        let spent = |usd: &str| {
+            std::fs::write(
+                tmp.path().join(".grotto/agents/agent-1/output.log"),
+                format!("Total cost: ${}\nUsage: 40k input, 3k output\n", usd),
+            )
+            .unwrap();
+        };
+        let now = started(&grotto);
+
+        spent("0.85");
+        let alerts = check(&grotto, now).unwrap();
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].kind, AlertKind::Warning);
+        assert_eq!(alerts[0].scope, BudgetScope::Cost);
+        let mux = FakeMultiplexer::with_team(&mut grotto);
+        enforce(&mut grotto, &mux, &alerts, Duration::ZERO).unwrap();
+
+        spent("1.10");
+        let alerts = check(&grotto, now).unwrap();
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].kind, AlertKind::Exceeded);
+        let stopped = enforce(&mut grotto, &mux, &alerts, Duration::ZERO).unwrap();
+        assert_eq!(stopped.len(), 2);
+        assert!(stopped.iter().all(|t| t.scope == BudgetScope::Cost));
+        assert_eq!(
+            grotto.agents["agent-2"].progress,
+            "Stopped: cost budget of $1.00 exceeded"
+        );
+        assert!(
+            check(&grotto, now + ChronoDuration::hours(2))
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn agent_timeout_skips_stopped_agents_and_counts_from_respawn() {
+        let (_tmp, mut grotto) = session(3, None, Some(100));
+        let start = started(&grotto);
+        grotto.agents.get_mut("agent-1").unwrap().state = "done".to_string();
+        grotto
+            .log_event(
+                "agent_spawned",
+                Some("agent-3"),
+                None,
+                None,
+                serde_json::json!({}),
+            )
+            .unwrap();
+        let respawned = grotto
+            .read_session_events()
+            .unwrap()
+            .last()
+            .unwrap()
+            .timestamp;
+
+        let now = respawned.max(start + ChronoDuration::seconds(100));
+        let alerts = check(&grotto, now).unwrap();
+        let agent_2 = alerts
+            .iter()
+            .find(|a| a.agent_id.as_deref() == Some("agent-2"))
+            .unwrap();
+        assert_eq!(agent_2.kind, AlertKind::Exceeded);
+        assert!(
+            alerts
+                .iter()
+                .all(|a| a.agent_id.as_deref() != Some("agent-1"))
+        );
+        // agent-3 restarted at most a moment ago
+        assert!(
+            alerts
+                .iter()
+                .filter(|a| a.agent_id.as_deref() == Some("agent-3"))
+                .all(|a| a.kind != AlertKind::Exceeded)
+        );
+    }
+}
@@ -0,0 +1,212 @@
+use crate::git::{self, CommitInfo, GitBaseline};
+use crate::{Grotto, GrottoError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Diffstat for one line of work: the main checkout or an agent branch.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffStat {
+    /// `None` for the main checkout (including uncommitted changes)
+    pub agent_id: Option<String>,
+    pub branch: Option<String>,
+    pub stat: String,
+}
+
+/// What changed in the repository since the session was spawned.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangeReport {
+    pub baseline: GitBaseline,
+    /// Commits made during the session, newest first
+    pub commits: Vec<CommitInfo>,
+    pub diffstats: Vec<DiffStat>,
+}
+
+impl ChangeReport {
+    /// Number of session commits attributed to each agent; unattributed
+    /// commits are counted under `"unattributed"`.
+    pub fn commits_by_agent(&self) -> BTreeMap<String, usize> {
+        let mut counts = BTreeMap::new();
+        for commit in &self.commits {
+            let key = commit
+                .agent_id
+                .clone()
+                .unwrap_or_else(|| "unattributed".to_string());
+            *counts.entry(key).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        let short = short_sha(&self.baseline.head);
+        out.push_str(&format!(
+            "- Baseline: `{}` on {}{}\n",
+            short,
+            self.baseline.branch.as_deref().unwrap_or("detached HEAD"),
+            if self.baseline.dirty {
+                " (had uncommitted changes at spawn)"
+            } else {
+                ""
+            }
+        ));
+        out.push_str(&format!("- Commits: {}\n", self.commits.len()));
+        for (agent, count) in self.commits_by_agent() {
+            out.push_str(&format!("  - {}: {}\n", agent, count));
+        }
+
+        if !self.commits.is_empty() {
+            out.push_str("\n### Commits\n\n");
+            for commit in &self.commits {
+                out.push_str(&format!(
+                    "- `{}` {} ({})\n",
+                    short_sha(&commit.sha),
+                    commit.subject,
+                    commit.agent_id.as_deref().unwrap_or(&commit.author)
+                ));
+            }
+        }
+
+        for diff in &self.diffstats {
+            let title = match (&diff.agent_id, &diff.branch) {
+                (Some(agent), Some(branch)) => format!("{} (`{}`)", agent, branch),
+                (Some(agent), None) => agent.clone(),
+                _ => "Working tree".to_string(),
+            };
+            out.push_str(&format!("\n### Diffstat: {}\n\n", title));
+            if diff.stat.trim().is_empty() {
+                out.push_str("No changes.\n");
+            } else {
+                out.push_str(&format!("```\n{}\n```\n", diff.stat.trim_end()));
+            }
+        }
+
+        out
+    }
+}
+
+/// Build the change report from the baseline recorded at spawn.
+pub fn build(grotto: &Grotto) -> Result<ChangeReport> {
+    let baseline =
+        grotto.config.git.clone().ok_or_else(|| {
+            GrottoError::Git("no git baseline recorded for this session".to_string())
+        })?;
+    let repo = &grotto.config.project_dir;
+
+    let mut refs = vec!["HEAD".to_string()];
+    let mut diffstats = vec![DiffStat {
+        agent_id: None,
+        branch: None,
+        stat: git::diffstat(repo, &baseline.head, None)?,
+    }];
+    for agent_id in grotto.sorted_agent_ids() {
+        let Some(branch) = grotto.agents[&agent_id].branch.clone() else {
+            continue;
+        };
+        if !git::branch_exists(repo, &branch) {
+            continue;
+        }
+        diffstats.push(DiffStat {
+            agent_id: Some(agent_id),
+            branch: Some(branch.clone()),
+            stat: git::diffstat(repo, &baseline.head, Some(&branch))?,
+        });
+        refs.push(branch);
+    }
+
+    let commits = git::commits_since(repo, &baseline.head, &refs)?;
+
+    Ok(ChangeReport {
+        baseline,
+        commits,
+        diffstats,
+    })
+}
+
+/// Every path touched since the baseline, across the working tree and agent branches.
+pub fn changed_files(grotto: &Grotto) -> Result<BTreeSet<String>> {
+    let baseline =
+        grotto.config.git.as_ref().ok_or_else(|| {
+            GrottoError::Git("no git baseline recorded for this session".to_string())
+        })?;
+    let repo = &grotto.config.project_dir;
+
+    let mut files: BTreeSet<String> = git::changed_files(repo, &baseline.head, None)?
+        .into_iter()
+        .collect();
+    for agent in grotto.agents.values() {
+        if let Some(branch) = &agent.branch
+            && git::branch_exists(repo, branch)
+        {
+            files.extend(git::changed_files(repo, &baseline.head, Some(branch))?);
+        }
+    }
+    Ok(files)
+}
+
+fn short_sha(sha: &str) -> &str {
+    &sha[..sha.len().min(8)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::test_support::{commit_all, init_repo};
+    use tempfile::TempDir;
+
+    #[test]
+    fn build_without_baseline_is_an_error() {
+        let tmp = TempDir::new().unwrap();
+        let grotto = Grotto::new(tmp.path(), 1, "task".to_string()).unwrap();
+        assert!(grotto.config.git.is_none());
+        assert!(matches!(build(&grotto), Err(GrottoError::Git(_))));
+    }
+
+    #[test]
+    fn build_reports_commits_and_diffstat_since_spawn() {
+        let tmp = TempDir::new().unwrap();
+        init_repo(tmp.path());
+        let grotto = Grotto::new(tmp.path(), 2, "task".to_string()).unwrap();
+
+        std::fs::write(tmp.path().join("lib.rs"), "fn main() {}\n").unwrap();
+        commit_all(tmp.path(), "Add lib\n\nGrotto-Agent: agent-1");
+        std::fs::write(tmp.path().join("README.md"), "edited\n").unwrap();
+
+        let report = build(&grotto).unwrap();
+        assert_eq!(report.commits.len(), 1);
+        assert_eq!(report.commits[0].agent_id.as_deref(), Some("agent-1"));
+        assert_eq!(report.commits_by_agent()["agent-1"], 1);
+        // Working-tree diffstat includes the uncommitted README edit
+        assert!(report.diffstats[0].stat.contains("README.md"));
+        assert!(report.diffstats[0].stat.contains("lib.rs"));
+
+        let md = report.to_markdown();
+        assert!(md.contains("- Commits: 1"));
+        assert!(md.contains("Add lib (agent-1)"));
+        assert!(md.contains("### Diffstat: Working tree"));
+    }
+
+    #[test]
+    fn build_includes_agent_branches() {
+        let tmp = TempDir::new().unwrap();
+        init_repo(tmp.path());
+        let mut grotto = Grotto::new(tmp.path(), 2, "task".to_string()).unwrap();
+        grotto.create_worktrees().unwrap();
+
+        let wt = grotto.agent_workdir("agent-2");
+        std::fs::write(wt.join("feature.txt"), "x\n").unwrap();
+        commit_all(&wt, "Add feature");
+
+        let report = build(&grotto).unwrap();
+        assert_eq!(report.commits.len(), 1);
+        assert_eq!(report.commits[0].subject, "Add feature");
+        let agent_2 = report
+            .diffstats
+            .iter()
+            .find(|d| d.agent_id.as_deref() == Some("agent-2"))
+            .unwrap();
+        assert!(agent_2.stat.contains("feature.txt"));
+
+        let files = changed_files(&grotto).unwrap();
+        assert_eq!(files.into_iter().collect::<Vec<_>>(), vec!["feature.txt"]);
+    }
+}
@@ -0,0 +1,125 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::Command;
+use std::time::Instant;
+
+/// How many trailing output lines to keep from a check command.
+const OUTPUT_TAIL_LINES: usize = 60;
+
+/// Result of running one check command (e.g. `cargo test --workspace`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckResult {
+    pub command: String,
+    pub success: bool,
+    pub exit_code: Option<i32>,
+    /// Last lines of combined stdout + stderr
+    pub output: String,
+    pub duration_ms: u64,
+}
+
+/// Run a shell command in `dir` and capture the tail of its output.
+pub fn run_check(dir: &Path, command: &str) -> CheckResult {
+    let start = Instant::now();
+    let output = Command::new("sh")
+        .args(["-c", command])
+        .current_dir(dir)
+        .output();
+    let duration_ms = start.elapsed().as_millis() as u64;
+
+    match output {
+        Ok(output) => {
+            let mut combined = String::from_utf8_lossy(&output.stdout).to_string();
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            if !stderr.trim().is_empty() {
+                if !combined.is_empty() && !combined.ends_with('\n') {
+                    combined.push('\n');
+                }
+                combined.push_str(&stderr);
+            }
+            CheckResult {
+                command: command.to_string(),
+                success: output.status.success(),
+                exit_code: output.status.code(),
+                output: tail_lines(&combined, OUTPUT_TAIL_LINES),
+                duration_ms,
+            }
+        }
+        Err(e) => CheckResult {
+            command: command.to_string(),
+            success: false,
+            exit_code: None,
+            output: format!("failed to run command: {}", e),
+            duration_ms,
+        },
+    }
+}
+
+/// Run checks in order, stopping at the first failure.
+pub fn run_checks(dir: &Path, commands: &[String]) -> Vec<CheckResult> {
+    let mut results = Vec::with_capacity(commands.len());
+    for command in commands {
+        let result = run_check(dir, command);
+        let failed = !result.success;
+        results.push(result);
+        if failed {
+            break;
+        }
+    }
+    results
+}
+
+fn tail_lines(text: &str, n: usize) -> String {
+    let lines: Vec<&str> = text.lines().collect();
+    let start = lines.len().saturating_sub(n);
+    lines[start..].join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn run_check_success() {
+        let tmp = TempDir::new().unwrap();
+        let result = run_check(tmp.path(), "echo hello");
+        assert!(result.success);
+        assert_eq!(result.exit_code, Some(0));
+        assert_eq!(result.output, "hello");
+    }
+
+    #[test]
+    fn run_check_failure_captures_stderr() {
+        let tmp = TempDir::new().unwrap();
+        let result = run_check(tmp.path(), "echo out; echo boom >&2; exit 3");
+        assert!(!result.success);
+        assert_eq!(result.exit_code, Some(3));
+        assert!(result.output.contains("out"));
+        assert!(result.output.contains("boom"));
+    }
+
+    #[test]
+    fn run_check_runs_in_dir() {
+        let tmp = TempDir::new().unwrap();
+        std::fs::write(tmp.path().join("marker.txt"), "x").unwrap();
+        assert!(run_check(tmp.path(), "test -f marker.txt").success);
+    }
+
+    #[test]
+    fn run_check_keeps_output_tail() {
+        let tmp = TempDir::new().unwrap();
+        let result = run_check(tmp.path(), "seq 1 200");
+        assert_eq!(result.output.lines().count(), OUTPUT_TAIL_LINES);
+        assert!(result.output.ends_with("200"));
+    }
+
+    #[test]
+    fn run_checks_stops_at_first_failure() {
+        let tmp = TempDir::new().unwrap();
+        let commands = vec!["true".to_string(), "false".to_string(), "true".to_string()];
+        let results = run_checks(tmp.path(), &commands);
+        assert_eq!(results.len(), 2);
+        assert!(results[0].success);
+        assert!(!results[1].success);
+    }
+}
@@ -0,0 +1,343 @@
+use crate::monitor::AgentPhase;
+use crate::{GrottoError, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Directory inside `.grotto/` holding per-runtime rule files, named after
+/// the runtime: `claude.toml`, `shell.toml`, `fake.toml`.
+pub const RULES_DIR: &str = "phases";
+
+/// Non-empty lines from the bottom of the screen a rule looks at unless it
+/// sets `window`.
+pub const DEFAULT_WINDOW: usize = 5;
+
+/// Rules used when a runtime has no file in `.grotto/phases/`, written for
+/// Claude Code's output.
+pub const DEFAULT_RULES: &str = r#"# Phase detection rules. Each rule's `pattern` is a regex matched against
+# the last `window` non-empty lines of the agent's screen (1 = the bottom
+# line only). Rules are tried by `priority`, highest first, then in file
+# order; the first match sets the phase. `default` is used when none match.
+default = "starting"
+
+[[rule]]
+name = "exit-command"
+phase = "finished"
+pattern = '/exit|exited'
+window = 1
+priority = 100
+
+[[rule]]
+name = "session-over"
+phase = "finished"
+pattern = 'Process exited|session ended|has been completed|Finished: \S+ after \d+ turn'
+window = 20
+priority = 100
+
+[[rule]]
+name = "rate-limit"
+phase = "rate_limited"
+pattern = '(?i)rate.limit|overloaded|too many requests|usage limit'
+priority = 90
+
+[[rule]]
+name = "error-line"
+phase = "error"
+pattern = '(^|\s)(\w*Error|error|fatal|FATAL)(\[\w+\])?:|API error|panicked at|^\s*PANIC'
+priority = 80
+
+[[rule]]
+name = "spinner"
+phase = "thinking"
+pattern = '(?i)thinking|⏳|[◐◓◑◒⠋⠙⠹⠸⠼⠴⠦⠧⠇⠏]'
+window = 1
+priority = 70
+
+[[rule]]
+name = "file-write"
+phase = "editing"
+pattern = 'Write\(|Edit\(|Created |Updated |[Ww]rote |editing|Creating |Modified '
+priority = 60
+
+[[rule]]
+name = "prompt"
+phase = "idle"
+pattern = '^\s*(\S+\s?)?[>$❯%]\s*$'
+window = 1
+priority = 55
+
+[[rule]]
+name = "command"
+phase = "running"
+pattern = '\$ \S|[Rr]unning|[Bb]ash\('
+priority = 50
+"#;
+
+/// One rule as written in TOML.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PhaseRule {
+    pub name: String,
+    pub phase: AgentPhase,
+    pub pattern: String,
+    #[serde(default = "default_window")]
+    pub window: usize,
+    #[serde(default)]
+    pub priority: i32,
+}
+
+fn default_window() -> usize {
+    DEFAULT_WINDOW
+}
+
+fn default_phase() -> AgentPhase {
+    AgentPhase::Starting
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RulesFile {
+    #[serde(default = "default_phase")]
+    default: AgentPhase,
+    #[serde(default, rename = "rule")]
+    rules: Vec<PhaseRule>,
+}
+
+/// Where a rule set came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RulesSource {
+    BuiltIn,
+    File(PathBuf),
+}
+
+/// Compiled phase rules, sorted by priority.
+#[derive(Debug, Clone)]
+pub struct PhaseRules {
+    rules: Vec<(PhaseRule, Regex)>,
+    default: AgentPhase,
+    pub source: RulesSource,
+}
+
+/// A rule that matched a screen, and the line it matched.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleMatch {
+    pub rule: String,
+    pub phase: AgentPhase,
+    pub pattern: String,
+    pub priority: i32,
+    pub window: usize,
+    /// 1 for the bottom non-empty line, 2 for the one above, ...
+    pub line_from_end: usize,
+    pub line: String,
+}
+
+/// Why a screen got its phase: the winning rule, if any, and lower-ranked
+/// rules that matched too.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Explanation {
+    pub phase: AgentPhase,
+    pub matched: Option<RuleMatch>,
+    pub shadowed: Vec<RuleMatch>,
+}
+
+impl PhaseRules {
+    /// Parse and compile a rules file.
+    pub fn parse(content: &str, source: RulesSource) -> Result<Self> {
+        let file: RulesFile = toml::from_str(content)?;
+        let mut rules = Vec::with_capacity(file.rules.len());
+        for rule in file.rules {
+            let regex = Regex::new(&rule.pattern).map_err(|e| {
+                GrottoError::PhaseRules(format!("rule '{}': invalid pattern: {}", rule.name, e))
+            })?;
+            if rule.window == 0 {
+                return Err(GrottoError::PhaseRules(format!(
+                    "rule '{}': window must be at least 1",
+                    rule.name
+                )));
+            }
+            rules.push((rule, regex));
+        }
+        // Stable: equal priorities keep file order
+        rules.sort_by_key(|(rule, _)| std::cmp::Reverse(rule.priority));
+        Ok(Self {
+            rules,
+            default: file.default,
+            source,
+        })
+    }
+
+    /// The built-in rules.
+    pub fn built_in() -> &'static PhaseRules {
+        static RULES: OnceLock<PhaseRules> = OnceLock::new();
+        RULES.get_or_init(|| {
+            PhaseRules::parse(DEFAULT_RULES, RulesSource::BuiltIn)
+                .expect("built-in phase rules are valid")
+        })
+    }
+
+    /// Path of the rules file for a runtime kind (see
+    /// [`crate::runtime::RuntimeConfig::kind`]).
+    pub fn path(grotto_dir: &Path, runtime: &str) -> PathBuf {
+        grotto_dir.join(RULES_DIR).join(format!("{}.toml", runtime))
+    }
+
+    /// Rules for a runtime: its file in `.grotto/phases/` if there is one,
+    /// otherwise the built-in rules.
+    pub fn load(grotto_dir: &Path, runtime: &str) -> Result<PhaseRules> {
+        let path = Self::path(grotto_dir, runtime);
+        if !path.exists() {
+            return Ok(Self::built_in().clone());
+        }
+        let content = fs::read_to_string(&path)?;
+        Self::parse(&content, RulesSource::File(path))
+    }
+
+    /// The rules in priority order.
+    pub fn rules(&self) -> impl Iterator<Item = &PhaseRule> {
+        self.rules.iter().map(|(rule, _)| rule)
+    }
+
+    /// Phase of a captured screen.
+    pub fn infer(&self, content: &str) -> AgentPhase {
+        let recent = recent_lines(content);
+        if recent.is_empty() {
+            return AgentPhase::Starting;
+        }
+        self.rules
+            .iter()
+            .find(|(rule, regex)| first_match(&recent, rule, regex).is_some())
+            .map_or(self.default.clone(), |(rule, _)| rule.phase.clone())
+    }
+
+    /// Phase of a captured screen, with every rule that matched it.
+    pub fn explain(&self, content: &str) -> Explanation {
+        let recent = recent_lines(content);
+        let mut matches: Vec<RuleMatch> = if recent.is_empty() {
+            Vec::new()
+        } else {
+            self.rules
+                .iter()
+                .filter_map(|(rule, regex)| {
+                    first_match(&recent, rule, regex).map(|(i, line)| RuleMatch {
+                        rule: rule.name.clone(),
+                        phase: rule.phase.clone(),
+                        pattern: rule.pattern.clone(),
+                        priority: rule.priority,
+                        window: rule.window,
+                        line_from_end: i + 1,
+                        line: line.to_string(),
+                    })
+                })
+                .collect()
+        };
+        let matched = (!matches.is_empty()).then(|| matches.remove(0));
+        let phase = match (&matched, recent.is_empty()) {
+            (_, true) => AgentPhase::Starting,
+            (Some(m), _) => m.phase.clone(),
+            (None, _) => self.default.clone(),
+        };
+        Explanation {
+            phase,
+            matched,
+            shadowed: matches,
+        }
+    }
+}
+
+/// Non-empty lines, bottom first.
+fn recent_lines(content: &str) -> Vec<&str> {
+    content
+        .lines()
+        .rev()
+        .filter(|l| !l.trim().is_empty())
+        .collect()
+}
+
+fn first_match<'a>(
+    recent: &[&'a str],
+    rule: &PhaseRule,
+    regex: &Regex,
+) -> Option<(usize, &'a str)> {
+    recent
+        .iter()
+        .take(rule.window)
+        .enumerate()
+        .find(|(_, line)| regex.is_match(line))
+        .map(|(i, line)| (i, *line))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn explain_names_the_winning_rule_and_shadowed_ones() {
+        let explanation =
+            PhaseRules::built_in().explain("$ cargo test\nerror[E0308]: mismatched types\n");
+        assert_eq!(explanation.phase, AgentPhase::Error);
+        let matched = explanation.matched.unwrap();
+        assert_eq!(matched.rule, "error-line");
+        assert_eq!(matched.line_from_end, 1);
+        assert_eq!(matched.line, "error[E0308]: mismatched types");
+        assert_eq!(explanation.shadowed.len(), 1);
+        assert_eq!(explanation.shadowed[0].rule, "command");
+        assert_eq!(explanation.shadowed[0].line_from_end, 2);
+
+        let unmatched = PhaseRules::built_in().explain("loading model");
+        assert_eq!(unmatched.phase, AgentPhase::Starting);
+        assert_eq!(unmatched.matched, None);
+    }
+
+    #[test]
+    fn project_file_replaces_built_in_rules_for_its_runtime() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = PhaseRules::path(tmp.path(), "shell");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(
+            &path,
+            r#"
+default = "idle"
+
+[[rule]]
+name = "aider-busy"
+phase = "thinking"
+pattern = 'Waiting for'
+window = 1
+
+[[rule]]
+name = "aider-edit"
+phase = "editing"
+pattern = '^Applied edit to'
+priority = 10
+"#,
+        )
+        .unwrap();
+
+        let rules = PhaseRules::load(tmp.path(), "shell").unwrap();
+        assert_eq!(rules.source, RulesSource::File(path));
+        assert_eq!(
+            rules.rules().map(|r| r.name.as_str()).collect::<Vec<_>>(),
+            ["aider-edit", "aider-busy"]
+        );
+        assert_eq!(
+            rules.infer("Applied edit to src/a.py\nWaiting for gpt-4o"),
+            AgentPhase::Editing
+        );
+        assert_eq!(rules.infer("Waiting for gpt-4o"), AgentPhase::Thinking);
+        assert_eq!(rules.infer("> "), AgentPhase::Idle);
+
+        // Other runtimes keep the built-in rules
+        let claude = PhaseRules::load(tmp.path(), "claude").unwrap();
+        assert_eq!(claude.source, RulesSource::BuiltIn);
+    }
+
+    #[test]
+    fn invalid_rules_are_reported() {
+        let bad_regex = "[[rule]]\nname = \"x\"\nphase = \"idle\"\npattern = '('\n";
+        let err = PhaseRules::parse(bad_regex, RulesSource::BuiltIn).unwrap_err();
+        assert!(err.to_string().contains("rule 'x': invalid pattern"));
+
+        let bad_phase = "[[rule]]\nname = \"x\"\nphase = \"sleepy\"\npattern = 'z'\n";
+        assert!(PhaseRules::parse(bad_phase, RulesSource::BuiltIn).is_err());
+    }
+}
@@ -0,0 +1,377 @@
+use crate::{GrottoError, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::Command;
+
+/// Trailer agents add to commit messages so work can be attributed to them.
+pub const AGENT_TRAILER: &str = "Grotto-Agent";
+
+/// Author-name suffix set via `GIT_AUTHOR_NAME` in each agent's pane.
+const AUTHOR_SUFFIX: &str = " (grotto)";
+
+/// Repository state recorded when a session is spawned.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GitBaseline {
+    pub head: String,
+    /// `None` when HEAD is detached
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+    /// Whether tracked files had uncommitted changes at spawn time
+    pub dirty: bool,
+}
+
+/// A commit made during the session.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommitInfo {
+    pub sha: String,
+    pub author: String,
+    pub subject: String,
+    /// Agent the commit is attributed to, from its trailer or author name
+    pub agent_id: Option<String>,
+}
+
+/// Author name an agent commits under, e.g. `agent-2 (grotto)`.
+pub fn agent_author_name(agent_id: &str) -> String {
+    format!("{}{}", agent_id, AUTHOR_SUFFIX)
+}
+
+/// Branch name used for an agent's isolated worktree: `grotto/<session>/<agent>`.
+pub fn agent_branch(session_id: &str, agent_id: &str) -> String {
+    format!("grotto/{}/{}", session_id, agent_id)
+}
+
+/// Run `git -C <dir> <args>` and return trimmed stdout, or a `Git` error with stderr.
+pub fn run(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git").arg("-C").arg(dir).args(args).output()?;
+
+    if !output.status.success() {
+        return Err(GrottoError::Git(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Whether `dir` is inside a git work tree.
+pub fn is_repo(dir: &Path) -> bool {
+    run(dir, &["rev-parse", "--is-inside-work-tree"])
+        .map(|out| out == "true")
+        .unwrap_or(false)
+}
+
+/// Record HEAD, branch and dirty state, or `None` outside a git repo.
+pub fn baseline(dir: &Path) -> Option<GitBaseline> {
+    if !is_repo(dir) {
+        return None;
+    }
+    let head = run(dir, &["rev-parse", "HEAD"]).ok()?;
+    let branch = run(dir, &["symbolic-ref", "--quiet", "--short", "HEAD"]).ok();
+    let dirty = run(dir, &["status", "--porcelain", "--untracked-files=no"])
+        .map(|out| !out.is_empty())
+        .unwrap_or(false);
+    Some(GitBaseline {
+        head,
+        branch,
+        dirty,
+    })
+}
+
+/// Commits reachable from any of `refs` but not from `base`, newest first.
+pub fn commits_since(dir: &Path, base: &str, refs: &[String]) -> Result<Vec<CommitInfo>> {
+    let format = format!(
+        "--format=%H%x1f%an%x1f%s%x1f%(trailers:key={},valueonly,separator=%x2C)%x1e",
+        AGENT_TRAILER
+    );
+    let mut args: Vec<&str> = vec!["log", &format];
+    args.extend(refs.iter().map(|r| r.as_str()));
+    args.push("--not");
+    args.push(base);
+
+    let out = run(dir, &args)?;
+    Ok(out
+        .split('\x1e')
+        .filter_map(|record| {
+            let mut fields = record.trim_matches('\n').split('\x1f');
+            let sha = fields.next()?.trim().to_string();
+            if sha.is_empty() {
+                return None;
+            }
+            let author = fields.next().unwrap_or("").to_string();
+            let subject = fields.next().unwrap_or("").to_string();
+            let trailer = fields.next().unwrap_or("").trim();
+            let agent_id = attribute_commit(trailer, &author);
+            Some(CommitInfo {
+                sha,
+                author,
+                subject,
+                agent_id,
+            })
+        })
+        .collect())
+}
+
+/// Attribute a commit to an agent: the `Grotto-Agent` trailer wins, then an
+/// author name of the form `agent-N (grotto)`.
+pub fn attribute_commit(trailer: &str, author: &str) -> Option<String> {
+    let trailer = trailer.split(',').next().unwrap_or("").trim();
+    if !trailer.is_empty() {
+        return Some(trailer.to_string());
+    }
+    author
+        .strip_suffix(AUTHOR_SUFFIX)
+        .map(|agent| agent.trim().to_string())
+}
+
+/// `git diff --stat` from `base` to `target` (a ref), or to the working tree when `None`.
+pub fn diffstat(dir: &Path, base: &str, target: Option<&str>) -> Result<String> {
+    match target {
+        Some(target) => run(dir, &["diff", "--stat", base, target]),
+        None => run(dir, &["diff", "--stat", base]),
+    }
+}
+
+/// Paths changed from `base` to `target` (a ref), or to the working tree when `None`.
+pub fn changed_files(dir: &Path, base: &str, target: Option<&str>) -> Result<Vec<String>> {
+    let out = match target {
+        Some(target) => run(dir, &["diff", "--name-only", base, target])?,
+        None => run(dir, &["diff", "--name-only", base])?,
+    };
+    Ok(out.lines().map(|l| l.to_string()).collect())
+}
+
+/// Whether a local branch exists.
+pub fn branch_exists(dir: &Path, branch: &str) -> bool {
+    run(
+        dir,
+        &[
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            &format!("refs/heads/{}", branch),
+        ],
+    )
+    .is_ok()
+}
+
+/// Create a worktree at `path` on `branch`.
+///
+/// The branch is created from the current HEAD if it doesn't exist yet, so
+/// re-spawning a session reuses an agent's earlier work instead of failing.
+pub fn add_worktree(repo_dir: &Path, branch: &str, path: &Path) -> Result<()> {
+    let path_str = path.to_string_lossy();
+    if branch_exists(repo_dir, branch) {
+        run(repo_dir, &["worktree", "add", &path_str, branch])?;
+    } else {
+        run(
+            repo_dir,
+            &["worktree", "add", "-b", branch, &path_str, "HEAD"],
+        )?;
+    }
+    Ok(())
+}
+
+/// Remove a worktree (discarding uncommitted changes) and prune stale metadata.
+/// The agent's branch is kept so its commits can still be merged.
+pub fn remove_worktree(repo_dir: &Path, path: &Path) -> Result<()> {
+    if path.exists() {
+        run(
+            repo_dir,
+            &["worktree", "remove", "--force", &path.to_string_lossy()],
+        )?;
+    }
+    run(repo_dir, &["worktree", "prune"])?;
+    Ok(())
+}
+
+/// Add a pattern to `.git/info/exclude` unless it's already listed.
+///
+/// Keeps `.grotto/` (and the worktrees inside it) out of `git status` in the
+/// main checkout without touching the project's tracked `.gitignore`.
+pub fn exclude(repo_dir: &Path, pattern: &str) -> Result<()> {
+    let info_dir = run(repo_dir, &["rev-parse", "--git-path", "info"])?;
+    let info_dir = repo_dir.join(info_dir);
+    fs::create_dir_all(&info_dir)?;
+
+    let exclude_path = info_dir.join("exclude");
+    let existing = fs::read_to_string(&exclude_path).unwrap_or_default();
+    if existing.lines().any(|l| l.trim() == pattern) {
+        return Ok(());
+    }
+
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(exclude_path)?;
+    if !existing.is_empty() && !existing.ends_with('\n') {
+        file.write_all(b"\n")?;
+    }
+    file.write_all(format!("{}\n", pattern).as_bytes())?;
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod test_support {
+    use std::path::Path;
+    use std::process::Command;
+
+    /// Initialize a git repo with one commit, for tests that need real git.
+    pub fn init_repo(dir: &Path) {
+        for args in [
+            vec!["init", "-q", "-b", "main"],
+            vec!["config", "user.name", "Test"],
+            vec!["config", "user.email", "test@example.com"],
+            vec!["config", "commit.gpgsign", "false"],
+        ] {
+            let status = Command::new("git")
+                .arg("-C")
+                .arg(dir)
+                .args(&args)
+                .status()
+                .unwrap();
+            assert!(status.success(), "git {:?} failed", args);
+        }
+        std::fs::write(dir.join("README.md"), "hello\n").unwrap();
+        commit_all(dir, "initial commit");
+    }
+
+    pub fn commit_all(dir: &Path, message: &str) {
+        for args in [vec!["add", "-A"], vec!["commit", "-q", "-m", message]] {
+            let status = Command::new("git")
+                .arg("-C")
+                .arg(dir)
+                .args(&args)
+                .status()
+                .unwrap();
+            assert!(status.success(), "git {:?} failed", args);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_support::init_repo;
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn agent_branch_format() {
+        assert_eq!(
+            agent_branch("crimson-coral-tide", "agent-2"),
+            "grotto/crimson-coral-tide/agent-2"
+        );
+    }
+
+    #[test]
+    fn baseline_records_head_branch_and_dirty() {
+        let tmp = TempDir::new().unwrap();
+        assert_eq!(baseline(tmp.path()), None);
+
+        init_repo(tmp.path());
+        let clean = baseline(tmp.path()).unwrap();
+        assert_eq!(clean.head.len(), 40);
+        assert_eq!(clean.branch.as_deref(), Some("main"));
+        assert!(!clean.dirty);
+
+        std::fs::write(tmp.path().join("README.md"), "changed\n").unwrap();
+        assert!(baseline(tmp.path()).unwrap().dirty);
+    }
+
+    #[test]
+    fn commits_since_attributes_agents() {
+        let tmp = TempDir::new().unwrap();
+        init_repo(tmp.path());
+        let base = baseline(tmp.path()).unwrap().head;
+
+        std::fs::write(tmp.path().join("a.txt"), "a\n").unwrap();
+        super::test_support::commit_all(tmp.path(), "Add a\n\nGrotto-Agent: agent-2");
+        std::fs::write(tmp.path().join("b.txt"), "b\n").unwrap();
+        super::test_support::commit_all(tmp.path(), "Add b");
+
+        let commits = commits_since(tmp.path(), &base, &["HEAD".to_string()]).unwrap();
+        assert_eq!(commits.len(), 2);
+        assert_eq!(commits[0].subject, "Add b");
+        assert_eq!(commits[0].agent_id, None);
+        assert_eq!(commits[1].subject, "Add a");
+        assert_eq!(commits[1].agent_id.as_deref(), Some("agent-2"));
+
+        let files = changed_files(tmp.path(), &base, Some("HEAD")).unwrap();
+        assert_eq!(files, vec!["a.txt".to_string(), "b.txt".to_string()]);
+
+        let stat = diffstat(tmp.path(), &base, Some("HEAD")).unwrap();
+        assert!(stat.contains("a.txt"));
+        assert!(stat.contains("2 files changed"));
+    }
+
+    #[test]
+    fn attribute_commit_prefers_trailer_then_author() {
+        assert_eq!(
+            attribute_commit("agent-1", "agent-3 (grotto)").as_deref(),
+            Some("agent-1")
+        );
+        assert_eq!(
+            attribute_commit("", &agent_author_name("agent-3")).as_deref(),
+            Some("agent-3")
+        );
+        assert_eq!(attribute_commit("", "Jane Doe"), None);
+    }
+
+    #[test]
+    fn is_repo_detects_git_dirs() {
+        let tmp = TempDir::new().unwrap();
+        assert!(!is_repo(tmp.path()));
+        init_repo(tmp.path());
+        assert!(is_repo(tmp.path()));
+    }
+
+    #[test]
+    fn add_and_remove_worktree() {
+        let tmp = TempDir::new().unwrap();
+        init_repo(tmp.path());
+        let wt = tmp.path().join(".grotto/worktrees/agent-1");
+
+        add_worktree(tmp.path(), "grotto/s/agent-1", &wt).unwrap();
+        assert!(wt.join("README.md").exists());
+        assert!(branch_exists(tmp.path(), "grotto/s/agent-1"));
+
+        remove_worktree(tmp.path(), &wt).unwrap();
+        assert!(!wt.exists());
+        // Branch survives so the agent's commits can still be merged
+        assert!(branch_exists(tmp.path(), "grotto/s/agent-1"));
+    }
+
+    #[test]
+    fn add_worktree_reuses_existing_branch() {
+        let tmp = TempDir::new().unwrap();
+        init_repo(tmp.path());
+        let wt = tmp.path().join("wt");
+
+        add_worktree(tmp.path(), "grotto/s/agent-1", &wt).unwrap();
+        remove_worktree(tmp.path(), &wt).unwrap();
+        add_worktree(tmp.path(), "grotto/s/agent-1", &wt).unwrap();
+        assert!(wt.exists());
+    }
+
+    #[test]
+    fn add_worktree_outside_repo_fails() {
+        let tmp = TempDir::new().unwrap();
+        let err = add_worktree(tmp.path(), "b", &tmp.path().join("wt")).unwrap_err();
+        assert!(err.to_string().contains("Git error"), "got: {}", err);
+    }
+
+    #[test]
+    fn exclude_is_idempotent() {
+        let tmp = TempDir::new().unwrap();
+        init_repo(tmp.path());
+
+        exclude(tmp.path(), ".grotto/").unwrap();
+        exclude(tmp.path(), ".grotto/").unwrap();
+
+        let content = std::fs::read_to_string(tmp.path().join(".git/info/exclude")).unwrap();
+        assert_eq!(content.lines().filter(|l| *l == ".grotto/").count(), 1);
+    }
+}
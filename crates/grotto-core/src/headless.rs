@@ -0,0 +1,724 @@
+use crate::logsink::{self, LogSink};
+use crate::monitor;
+use crate::mux::{Multiplexer, NewPane, PaneStatus};
+use crate::supervise;
+use crate::{AgentLaunch, Grotto, GrottoError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::os::fd::{FromRawFd, OwnedFd};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Control socket of the `grotto run` process, in `.grotto/`.
+pub const SOCKET_FILE: &str = "run.sock";
+
+/// Lines of recent output kept per agent for capture, like a tmux pane's
+/// scrollback.
+pub const SCREEN_LINES: usize = 200;
+
+/// Terminal size the agents see.
+const ROWS: u16 = 50;
+const COLS: u16 = 200;
+
+/// How often the runner checks for exited agents.
+const POLL: Duration = Duration::from_millis(200);
+
+/// Path of the runner's control socket for a `.grotto` directory.
+pub fn socket_path(grotto_dir: &Path) -> PathBuf {
+    grotto_dir.join(SOCKET_FILE)
+}
+
+/// The last [`SCREEN_LINES`] lines an agent printed.
+#[derive(Debug, Clone, Default)]
+pub struct ScreenBuffer {
+    lines: VecDeque<String>,
+    current: String,
+    /// The program asked for bracketed paste (`ESC [?2004h`) and hasn't
+    /// turned it off since
+    bracketed_paste: bool,
+}
+
+impl ScreenBuffer {
+    pub fn push(&mut self, text: &str) {
+        let on = text.rfind("\x1b[?2004h");
+        let off = text.rfind("\x1b[?2004l");
+        if on.is_some() || off.is_some() {
+            self.bracketed_paste = on > off;
+        }
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\n' => {
+                    self.lines.push_back(std::mem::take(&mut self.current));
+                    if self.lines.len() > SCREEN_LINES {
+                        self.lines.pop_front();
+                    }
+                }
+                // A bare carriage return redraws the line (spinners, progress)
+                '\r' if chars.peek() != Some(&'\n') => self.current.clear(),
+                '\r' => {}
+                c => self.current.push(c),
+            }
+        }
+    }
+
+    /// The screen as plain text, like `tmux capture-pane -p`.
+    pub fn contents(&self) -> String {
+        let mut text = String::new();
+        for line in self.lines.iter().chain(std::iter::once(&self.current)) {
+            text.push_str(&monitor::strip_ansi(line));
+            text.push('\n');
+        }
+        text
+    }
+
+    /// Bytes a terminal sends when `text` is pasted: wrapped in bracketed
+    /// paste markers if the program asked for them, otherwise as typed.
+    pub fn paste_bytes(&self, text: &str) -> Vec<u8> {
+        if self.bracketed_paste {
+            format!("\x1b[200~{}\x1b[201~", text).into_bytes()
+        } else {
+            text.as_bytes().to_vec()
+        }
+    }
+}
+
+/// Bytes typed for a tmux-style key name (`Enter`, `C-c`, ...); anything
+/// else is typed as-is.
+pub fn key_bytes(key: &str) -> Vec<u8> {
+    match key {
+        "Enter" => b"\r".to_vec(),
+        "Tab" => b"\t".to_vec(),
+        "Escape" => b"\x1b".to_vec(),
+        "BSpace" => b"\x7f".to_vec(),
+        "Space" => b" ".to_vec(),
+        _ => match key.strip_prefix("C-").map(str::as_bytes) {
+            Some([c]) if c.is_ascii_alphabetic() => vec![c.to_ascii_lowercase() - b'a' + 1],
+            _ => key.as_bytes().to_vec(),
+        },
+    }
+}
+
+/// A request to the runner over its control socket, one JSON line.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Request {
+    /// Every agent process and whether it is still running
+    List,
+    /// An agent's recent screen
+    Capture { agent_id: String },
+    /// Type keys into an agent's terminal
+    Send { agent_id: String, keys: Vec<String> },
+    /// Paste text into an agent's terminal as-is, key names included
+    Paste { agent_id: String, text: String },
+    /// Terminate one agent's process; it is not restarted
+    Kill { agent_id: String },
+    /// Terminate every agent and exit the runner
+    Stop,
+}
+
+/// An agent process as reported by [`Request::List`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProcessStatus {
+    pub agent_id: String,
+    pub pid: u32,
+    pub running: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_status: Option<i32>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Response {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub agents: Vec<ProcessStatus>,
+}
+
+/// Send `request` to the runner for `grotto_dir`.
+pub fn request(grotto_dir: &Path, request: &Request) -> Result<Response> {
+    let mut stream = UnixStream::connect(socket_path(grotto_dir))?;
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    stream.write_all((serde_json::to_string(request)? + "\n").as_bytes())?;
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line)?;
+    let response: Response = serde_json::from_str(&line)?;
+    match response.error {
+        Some(error) => Err(GrottoError::Io(std::io::Error::other(error))),
+        None => Ok(response),
+    }
+}
+
+/// Whether a runner is serving `grotto_dir`.
+pub fn is_running(grotto_dir: &Path) -> bool {
+    request(grotto_dir, &Request::List).is_ok()
+}
+
+/// An agent's recent screen, or `None` when its process has exited or no
+/// runner is up.
+pub fn capture(grotto_dir: &Path, agent_id: &str) -> Option<String> {
+    let running = request(grotto_dir, &Request::List)
+        .ok()?
+        .agents
+        .into_iter()
+        .any(|p| p.agent_id == agent_id && p.running);
+    if !running {
+        return None;
+    }
+    request(
+        grotto_dir,
+        &Request::Capture {
+            agent_id: agent_id.to_string(),
+        },
+    )
+    .ok()?
+    .content
+}
+
+/// Type `keys` (tmux key names or text) into an agent's terminal.
+pub fn send_keys(grotto_dir: &Path, agent_id: &str, keys: &[String]) -> Result<()> {
+    request(
+        grotto_dir,
+        &Request::Send {
+            agent_id: agent_id.to_string(),
+            keys: keys.to_vec(),
+        },
+    )
+    .map(|_| ())
+}
+
+/// Paste `text` into an agent's terminal, as `tmux paste-buffer -p` would.
+pub fn paste(grotto_dir: &Path, agent_id: &str, text: &str) -> Result<()> {
+    request(
+        grotto_dir,
+        &Request::Paste {
+            agent_id: agent_id.to_string(),
+            text: text.to_string(),
+        },
+    )
+    .map(|_| ())
+}
+
+/// The `grotto run` process of a headless session, driven as a
+/// [`Multiplexer`] so budgets, policy pauses and rate-limit recovery reach
+/// its agents. Panes are addressed by agent ID (see
+/// [`Grotto::pane_target`]); opening or respawning panes is left to the
+/// runner itself.
+#[derive(Debug, Clone)]
+pub struct Runner {
+    grotto_dir: PathBuf,
+    /// Pane index of each agent, for [`PaneStatus`]
+    pane_indexes: BTreeMap<String, usize>,
+}
+
+impl Runner {
+    pub fn new(grotto: &Grotto) -> Self {
+        Self {
+            grotto_dir: grotto.grotto_dir.clone(),
+            pane_indexes: grotto
+                .agents
+                .iter()
+                .map(|(id, agent)| (id.clone(), agent.pane_index))
+                .collect(),
+        }
+    }
+
+    fn processes(&self) -> Option<Vec<ProcessStatus>> {
+        request(&self.grotto_dir, &Request::List)
+            .ok()
+            .map(|response| response.agents)
+    }
+
+    fn pane(&self, process: ProcessStatus) -> Option<PaneStatus> {
+        Some(PaneStatus {
+            pane_id: None,
+            pane_index: *self.pane_indexes.get(&process.agent_id)?,
+            dead: !process.running,
+            exit_status: process.exit_status,
+        })
+    }
+
+    fn unsupported(what: &str) -> GrottoError {
+        GrottoError::Multiplexer(format!("{} is not supported by the headless backend", what))
+    }
+}
+
+impl Multiplexer for Runner {
+    fn new_session(&self, _session: &str, _launch: &AgentLaunch) -> Result<NewPane> {
+        Err(Self::unsupported("Opening a session"))
+    }
+
+    fn split(&self, _after: &str, _launch: &AgentLaunch) -> Result<NewPane> {
+        Err(Self::unsupported("Opening a pane"))
+    }
+
+    fn respawn(&self, _pane: &str, _launch: &AgentLaunch) -> Result<()> {
+        Err(Self::unsupported("Relaunching an agent"))
+    }
+
+    fn send_keys(&self, pane: &str, keys: &[String]) -> Result<()> {
+        send_keys(&self.grotto_dir, pane, keys)
+    }
+
+    fn paste(&self, pane: &str, text: &str) -> Result<()> {
+        paste(&self.grotto_dir, pane, text)
+    }
+
+    fn capture(&self, pane: &str, _scrollback: usize) -> Option<String> {
+        capture(&self.grotto_dir, pane)
+    }
+
+    fn pipe(&self, _pane: &str, _path: &Path) -> Result<()> {
+        // The runner writes every agent's log itself
+        Ok(())
+    }
+
+    fn kill_pane(&self, pane: &str) -> Result<()> {
+        request(
+            &self.grotto_dir,
+            &Request::Kill {
+                agent_id: pane.to_string(),
+            },
+        )
+        .map(|_| ())
+    }
+
+    fn kill_session(&self, _session: &str) -> Result<()> {
+        request(&self.grotto_dir, &Request::Stop).map(|_| ())
+    }
+
+    fn has_session(&self, _session: &str) -> bool {
+        is_running(&self.grotto_dir)
+    }
+
+    fn list_panes(&self, _session: &str) -> Option<Vec<PaneStatus>> {
+        let processes = self.processes()?;
+        Some(processes.into_iter().filter_map(|p| self.pane(p)).collect())
+    }
+
+    fn pane_status(&self, pane: &str) -> Option<PaneStatus> {
+        let process = self.processes()?.into_iter().find(|p| p.agent_id == pane)?;
+        self.pane(process)
+    }
+
+    fn pane_pid(&self, pane: &str) -> Option<u32> {
+        self.processes()?
+            .into_iter()
+            .find(|p| p.agent_id == pane && p.running)
+            .map(|p| p.pid)
+    }
+
+    fn keep_exited_panes(&self, _session: &str) -> Result<()> {
+        Ok(())
+    }
+
+    fn attach(&self, _session: &str) -> Result<()> {
+        Err(Self::unsupported("Attaching"))
+    }
+}
+
+/// A process started on a new PTY.
+pub struct PtyProcess {
+    pub child: std::process::Child,
+    /// The PTY's controlling side: read the agent's output, write its input
+    pub master: File,
+}
+
+/// Start `launch` as a session leader on a new PTY.
+pub fn spawn_on_pty(launch: &AgentLaunch) -> Result<PtyProcess> {
+    let (program, args) = launch
+        .command
+        .split_first()
+        .ok_or_else(|| GrottoError::Io(std::io::Error::other("empty agent command")))?;
+
+    let mut master_fd = 0;
+    let mut slave_fd = 0;
+    let size = libc::winsize {
+        ws_row: ROWS,
+        ws_col: COLS,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    };
+    // SAFETY: openpty writes two fds we take ownership of right after
+    let rc = unsafe {
+        libc::openpty(
+            &mut master_fd,
+            &mut slave_fd,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            // `*mut` on some platforms; openpty only reads it
+            std::ptr::from_ref(&size).cast_mut(),
+        )
+    };
+    if rc != 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    // SAFETY: both fds were just opened and are owned by nothing else
+    let (master, slave) = unsafe {
+        (
+            OwnedFd::from_raw_fd(master_fd),
+            OwnedFd::from_raw_fd(slave_fd),
+        )
+    };
+    // Keep the controlling side out of agent processes
+    // SAFETY: fcntl on an fd we own
+    unsafe { libc::fcntl(master_fd, libc::F_SETFD, libc::FD_CLOEXEC) };
+
+    let mut command = Command::new(program);
+    command
+        .args(args)
+        .current_dir(&launch.workdir)
+        .envs(launch.env.iter().map(|(k, v)| (k, v)))
+        .env("TERM", "xterm-256color")
+        .stdin(Stdio::from(slave.try_clone()?))
+        .stdout(Stdio::from(slave.try_clone()?))
+        .stderr(Stdio::from(slave));
+    // SAFETY: only async-signal-safe calls between fork and exec
+    unsafe {
+        command.pre_exec(|| {
+            if libc::setsid() == -1 || libc::ioctl(0, libc::TIOCSCTTY as _, 0) == -1 {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+    let child = command.spawn()?;
+    Ok(PtyProcess {
+        child,
+        master: File::from(master),
+    })
+}
+
+/// One agent process owned by the runner.
+struct Agent {
+    pid: u32,
+    input: File,
+    screen: Arc<Mutex<ScreenBuffer>>,
+    /// Set once the process has exited: its exit code, if it had one
+    exit: Arc<Mutex<Option<Option<i32>>>>,
+}
+
+impl Agent {
+    fn status(&self, agent_id: &str) -> ProcessStatus {
+        let exit = *self.exit.lock().unwrap();
+        ProcessStatus {
+            agent_id: agent_id.to_string(),
+            pid: self.pid,
+            running: exit.is_none(),
+            exit_status: exit.flatten(),
+        }
+    }
+
+    fn terminate(&self) {
+        // The agent leads its own process group; take its children with it
+        // SAFETY: plain kill(2)
+        unsafe { libc::kill(-(self.pid as i32), libc::SIGTERM) };
+    }
+}
+
+/// Launch an agent on a PTY, copying its output to its log sink and its
+/// screen buffer until it exits.
+fn start_agent(grotto: &Grotto, agent_id: &str, launch: &AgentLaunch) -> Result<Agent> {
+    let PtyProcess { mut child, master } = spawn_on_pty(launch)?;
+    let mut output = master.try_clone()?;
+    let mut log = LogSink::open(
+        &grotto
+            .grotto_dir
+            .join("agents")
+            .join(agent_id)
+            .join(logsink::RAW_LOG),
+    )?;
+    let screen = Arc::new(Mutex::new(ScreenBuffer::default()));
+    let exit = Arc::new(Mutex::new(None));
+
+    let agent = Agent {
+        pid: child.id(),
+        input: master,
+        screen: Arc::clone(&screen),
+        exit: Arc::clone(&exit),
+    };
+    std::thread::spawn(move || {
+        let mut buf = [0u8; 8192];
+        // Reads fail with EIO once every process holding the PTY is gone
+        while let Ok(n) = output.read(&mut buf) {
+            if n == 0 {
+                break;
+            }
+            let _ = log.write(&buf[..n]);
+            screen
+                .lock()
+                .unwrap()
+                .push(&String::from_utf8_lossy(&buf[..n]));
+        }
+        let _ = log.finish();
+        let code = child.wait().ok().and_then(|status| status.code());
+        *exit.lock().unwrap() = Some(code);
+    });
+    Ok(agent)
+}
+
+type Agents = Arc<Mutex<BTreeMap<String, Agent>>>;
+
+/// Answer one control request.
+fn handle(agents: &Agents, killed: &Mutex<HashSet<String>>, request: Request) -> Response {
+    let mut agents = agents.lock().unwrap();
+    let not_found = |agent_id: &str| Response {
+        error: Some(GrottoError::AgentNotFound(agent_id.to_string()).to_string()),
+        ..Default::default()
+    };
+    match request {
+        Request::List => Response {
+            agents: agents.iter().map(|(id, agent)| agent.status(id)).collect(),
+            ..Default::default()
+        },
+        Request::Capture { agent_id } => match agents.get(&agent_id) {
+            Some(agent) => Response {
+                content: Some(agent.screen.lock().unwrap().contents()),
+                ..Default::default()
+            },
+            None => not_found(&agent_id),
+        },
+        Request::Send { agent_id, keys } => match agents.get_mut(&agent_id) {
+            Some(agent) => {
+                let bytes: Vec<u8> = keys.iter().flat_map(|k| key_bytes(k)).collect();
+                match agent.input.write_all(&bytes) {
+                    Ok(()) => Response::default(),
+                    Err(e) => Response {
+                        error: Some(e.to_string()),
+                        ..Default::default()
+                    },
+                }
+            }
+            None => not_found(&agent_id),
+        },
+        Request::Paste { agent_id, text } => match agents.get_mut(&agent_id) {
+            Some(agent) => {
+                let bytes = agent.screen.lock().unwrap().paste_bytes(&text);
+                match agent.input.write_all(&bytes) {
+                    Ok(()) => Response::default(),
+                    Err(e) => Response {
+                        error: Some(e.to_string()),
+                        ..Default::default()
+                    },
+                }
+            }
+            None => not_found(&agent_id),
+        },
+        Request::Kill { agent_id } => match agents.get(&agent_id) {
+            Some(agent) => {
+                killed.lock().unwrap().insert(agent_id);
+                agent.terminate();
+                Response::default()
+            }
+            None => not_found(&agent_id),
+        },
+        Request::Stop => {
+            let mut killed = killed.lock().unwrap();
+            for (agent_id, agent) in agents.iter() {
+                killed.insert(agent_id.clone());
+                agent.terminate();
+            }
+            Response::default()
+        }
+    }
+}
+
+fn serve(listener: UnixListener, agents: Agents, killed: Arc<Mutex<HashSet<String>>>) {
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else { continue };
+        let mut line = String::new();
+        let Ok(mut reader) = stream.try_clone().map(BufReader::new) else {
+            continue;
+        };
+        if reader.read_line(&mut line).is_err() {
+            continue;
+        }
+        let response = match serde_json::from_str(&line) {
+            Ok(request) => handle(&agents, &killed, request),
+            Err(e) => Response {
+                error: Some(format!("Invalid request: {}", e)),
+                ..Default::default()
+            },
+        };
+        let mut stream = stream;
+        let _ = stream
+            .write_all((serde_json::to_string(&response).unwrap_or_default() + "\n").as_bytes());
+    }
+}
+
+/// Run the session's agents on PTYs until they have all exited, serving
+/// [`Request`]s on `.grotto/run.sock` and applying the restart policy.
+/// With `resume`, the agents are relaunched after it (see
+/// [`Grotto::agent_resume_launch`]) and those stopped by a budget stay
+/// stopped. Returns each agent's last exit status.
+pub fn run(project_dir: &Path, resume: Option<&str>) -> Result<BTreeMap<String, Option<i32>>> {
+    let grotto = Grotto::load(project_dir)?;
+    let socket = socket_path(&grotto.grotto_dir);
+    if is_running(&grotto.grotto_dir) {
+        return Err(GrottoError::Io(std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            format!("A grotto run is already serving {}", socket.display()),
+        )));
+    }
+    let _ = fs::remove_file(&socket);
+    let listener = UnixListener::bind(&socket)?;
+
+    let agents: Agents = Arc::new(Mutex::new(BTreeMap::new()));
+    let killed = Arc::new(Mutex::new(HashSet::new()));
+    for agent_id in grotto.active_agent_ids() {
+        let launch = match resume {
+            Some(_) if grotto.agents[&agent_id].state == "timed_out" => continue,
+            Some(reason) => grotto.agent_resume_launch(&agent_id, reason),
+            None => grotto.agent_launch(&agent_id, grotto.generate_claude_prompt(&agent_id)),
+        };
+        let agent = start_agent(&grotto, &agent_id, &launch)?;
+        agents.lock().unwrap().insert(agent_id, agent);
+    }
+    grotto.log_event(
+        "runner_started",
+        None,
+        None,
+        Some("Agents started without tmux"),
+        serde_json::json!({ "pid": std::process::id() }),
+    )?;
+    std::thread::spawn({
+        let agents = Arc::clone(&agents);
+        let killed = Arc::clone(&killed);
+        move || serve(listener, agents, killed)
+    });
+
+    let result = supervise_agents(project_dir, &agents, &killed);
+    let _ = fs::remove_file(&socket);
+    result?;
+
+    let statuses = agents
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(id, agent)| (id.clone(), agent.status(id).exit_status))
+        .collect();
+    Ok(statuses)
+}
+
+/// Settle or relaunch exited agents until none is left running.
+fn supervise_agents(
+    project_dir: &Path,
+    agents: &Agents,
+    killed: &Mutex<HashSet<String>>,
+) -> Result<()> {
+    loop {
+        std::thread::sleep(POLL);
+        let mut grotto = Grotto::load(project_dir)?;
+        let statuses: Vec<ProcessStatus> = {
+            let agents = agents.lock().unwrap();
+            agents.iter().map(|(id, agent)| agent.status(id)).collect()
+        };
+        // Agents stopped through the socket are left alone, as a killed pane is
+        let killed = killed.lock().unwrap().clone();
+        let panes: Vec<PaneStatus> = statuses
+            .iter()
+            .filter(|p| !killed.contains(&p.agent_id))
+            .filter_map(|p| {
+                Some(PaneStatus {
+                    pane_id: None,
+                    pane_index: grotto.agents.get(&p.agent_id)?.pane_index,
+                    dead: !p.running,
+                    exit_status: p.exit_status,
+                })
+            })
+            .collect();
+        let decisions = supervise::plan(&grotto, &panes)?;
+        for decision in &decisions {
+            supervise::apply_with(&mut grotto, decision, |grotto, agent_id, reason| {
+                grotto.assign_model(agent_id)?;
+                let launch = grotto.agent_resume_launch(agent_id, reason);
+                let agent = start_agent(grotto, agent_id, &launch)?;
+                agents.lock().unwrap().insert(agent_id.to_string(), agent);
+                Ok(())
+            })?;
+        }
+
+        let restarted = decisions
+            .iter()
+            .any(|d| matches!(d, supervise::Decision::Restart { .. }));
+        if !restarted && statuses.iter().all(|p| !p.running) {
+            return Ok(());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn screen_buffer_keeps_recent_plain_lines() {
+        let mut screen = ScreenBuffer::default();
+        screen.push("\x1b[32mThinking\x1b[0m...\r\n50%\r100%\npartial");
+        assert_eq!(screen.contents(), "Thinking...\n100%\npartial\n");
+
+        for i in 0..SCREEN_LINES + 5 {
+            screen.push(&format!("line {}\n", i));
+        }
+        let contents = screen.contents();
+        assert!(!contents.contains("line 4\n"));
+        assert!(contents.contains(&format!("line {}\n", SCREEN_LINES + 4)));
+    }
+
+    #[test]
+    fn paste_is_bracketed_only_while_the_program_asks() {
+        let mut screen = ScreenBuffer::default();
+        assert_eq!(screen.paste_bytes("Escape\nhi"), b"Escape\nhi");
+        screen.push("\x1b[?2004h> ");
+        assert_eq!(screen.paste_bytes("hi"), b"\x1b[200~hi\x1b[201~");
+        screen.push("\x1b[?2004l");
+        assert_eq!(screen.paste_bytes("hi"), b"hi");
+    }
+
+    #[test]
+    fn key_names_become_control_bytes() {
+        assert_eq!(key_bytes("Enter"), b"\r");
+        assert_eq!(key_bytes("C-c"), [3]);
+        assert_eq!(key_bytes("C-D"), [4]);
+        assert_eq!(key_bytes("/exit"), b"/exit");
+    }
+
+    #[test]
+    fn pty_process_sees_a_terminal() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let launch = AgentLaunch {
+            workdir: tmp.path().to_path_buf(),
+            env: vec![("GREETING".into(), "hi".into())],
+            command: vec![
+                "sh".into(),
+                "-c".into(),
+                "test -t 0 && echo \"$GREETING tty\"; read line; echo \"got $line\"".into(),
+            ],
+        };
+        let PtyProcess { mut child, master } = spawn_on_pty(&launch).unwrap();
+        let mut input = master.try_clone().unwrap();
+        input.write_all(&key_bytes("answer")).unwrap();
+        input.write_all(&key_bytes("Enter")).unwrap();
+
+        let mut output = String::new();
+        let mut reader = master;
+        let mut buf = [0u8; 1024];
+        while let Ok(n) = reader.read(&mut buf) {
+            if n == 0 {
+                break;
+            }
+            output.push_str(&String::from_utf8_lossy(&buf[..n]));
+        }
+        assert!(child.wait().unwrap().success());
+        assert!(output.contains("hi tty"), "{output}");
+        assert!(output.contains("got answer"), "{output}");
+    }
+}
@@ -1,10 +1,33 @@
+pub mod approvals;
+pub mod backoff;
+pub mod budget;
+pub mod changes;
+pub mod checks;
 pub mod daemon;
+pub mod detect;
+pub mod git;
+pub mod headless;
+pub mod logsink;
+pub mod merge;
 pub mod monitor;
+pub mod mux;
+pub mod policy;
+pub mod routing;
+pub mod runtime;
+pub mod scale;
+pub mod scorecard;
+pub mod stall;
+pub mod steer;
+pub mod supervise;
+pub mod terminal;
+pub mod usage;
+pub mod verify;
 pub mod words;
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
 use std::fs;
 use std::io::Write;
 use std::path::{Path, PathBuf};
@@ -23,6 +46,22 @@ pub enum GrottoError {
     AgentNotFound(String),
     #[error("Task not found: {0}")]
     TaskNotFound(String),
+    #[error("Approval not found: {0}")]
+    ApprovalNotFound(String),
+    #[error("Git error: {0}")]
+    Git(String),
+    #[error("Success criteria failed: {0}")]
+    Verification(String),
+    #[error("Policy error: {0}")]
+    Policy(String),
+    #[error("Phase rules error: {0}")]
+    PhaseRules(String),
+    #[error("Budget exceeded: {0}")]
+    BudgetExceeded(String),
+    #[error("Multiplexer error: {0}")]
+    Multiplexer(String),
+    #[error("Pane is dead: {0}")]
+    DeadPane(String),
 }
 
 pub type Result<T> = std::result::Result<T, GrottoError>;
@@ -34,12 +73,217 @@ pub struct Config {
     pub project_dir: PathBuf,
     #[serde(default)]
     pub session_id: Option<String>,
+    /// tmux session the agents run in, named after the session ID
+    #[serde(default = "default_tmux_session")]
+    pub tmux_session: String,
+    #[serde(default)]
+    pub isolation: Isolation,
+    /// Commands run after each step of `grotto merge` (e.g. `cargo test --workspace`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub checks: Vec<String>,
+    /// Success criteria from `spawn --until`; `wait` runs them when agents settle
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub until: Vec<String>,
+    /// Verification rounds allowed before the session is marked failed
+    #[serde(default = "default_max_rounds")]
+    pub max_rounds: u32,
+    /// Set once the success criteria pass or the rounds run out
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outcome: Option<verify::SessionOutcome>,
+    /// Wall-clock budget for the whole session, from `spawn --max-duration`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_duration_secs: Option<u64>,
+    /// Budget for each agent, from `spawn --agent-timeout`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent_timeout_secs: Option<u64>,
+    /// Spending limit for the whole team, from `spawn --max-cost`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_cost_usd: Option<f64>,
+    /// What to do when an agent's process exits, from `spawn --restart`
+    #[serde(default, skip_serializing_if = "supervise::RestartPolicy::is_never")]
+    pub restart: supervise::RestartPolicy,
+    /// Restarts allowed per agent before it is left stopped
+    #[serde(default = "default_max_restarts")]
+    pub max_restarts: u32,
+    /// How long a working agent's screen may stay unchanged before it is
+    /// flagged as stalled, from `spawn --stall-after`
+    #[serde(default = "default_stall_after_secs")]
+    pub stall_after_secs: u64,
+    /// Repeats of a command or error line that flag an agent as looping
+    #[serde(default = "default_loop_repeats")]
+    pub loop_repeats: u32,
+    /// What the daemon does about stalled and looping agents, from `spawn --on-stall`
+    #[serde(default, skip_serializing_if = "stall::StallAction::is_notify")]
+    pub on_stall: stall::StallAction,
+    /// Program every agent runs, from `spawn --runtime`
+    #[serde(default, skip_serializing_if = "runtime::RuntimeConfig::is_claude")]
+    pub runtime: runtime::RuntimeConfig,
+    /// Per-agent overrides of `runtime`, keyed by agent ID
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub agent_runtimes: BTreeMap<String, runtime::RuntimeConfig>,
+    /// Model for agents no rule in `models` matches, from `spawn --model`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Model routing rules, from `spawn --route` or `[[models]]`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub models: Vec<routing::ModelRule>,
+    /// What the agents run in, from `spawn --backend`
+    #[serde(default, skip_serializing_if = "Backend::is_tmux")]
+    pub backend: Backend,
+    /// Repository state at spawn; `None` outside a git repo
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git: Option<git::GitBaseline>,
+}
+
+fn default_max_rounds() -> u32 {
+    verify::DEFAULT_MAX_ROUNDS
+}
+
+fn default_max_restarts() -> u32 {
+    supervise::DEFAULT_MAX_RESTARTS
+}
+
+fn default_stall_after_secs() -> u64 {
+    stall::DEFAULT_STALL_AFTER_SECS
+}
+
+fn default_loop_repeats() -> u32 {
+    stall::DEFAULT_LOOP_REPEATS
+}
+
+/// Sessions spawned before tmux sessions were named per team all used this.
+fn default_tmux_session() -> String {
+    "grotto".to_string()
+}
+
+/// The tmux session name for a grotto session: `grotto-<session-id>`, with
+/// characters tmux treats as separators (`.` and `:`) replaced.
+pub fn tmux_session_name(session_id: &str) -> String {
+    let id: String = session_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("grotto-{}", id)
+}
+
+/// How agents share the project checkout.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Isolation {
+    /// All agents run in `project_dir` (the original behavior).
+    #[default]
+    Shared,
+    /// Each agent gets its own branch and git worktree under `.grotto/worktrees/`.
+    Worktree,
+}
+
+impl std::str::FromStr for Isolation {
+    type Err = GrottoError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "shared" | "none" => Ok(Isolation::Shared),
+            "worktree" => Ok(Isolation::Worktree),
+            other => Err(GrottoError::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "Unknown isolation mode '{}'. Valid modes: shared, worktree",
+                    other
+                ),
+            ))),
+        }
+    }
+}
+
+/// What runs the agents' terminals.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Backend {
+    /// One pane per agent in the `grotto` tmux session (the original behavior).
+    #[default]
+    Tmux,
+    /// Each agent on its own PTY, owned by a `grotto run` process (see [`headless`]).
+    Headless,
+}
+
+impl Backend {
+    pub fn is_tmux(&self) -> bool {
+        *self == Backend::Tmux
+    }
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Backend::Tmux => write!(f, "tmux"),
+            Backend::Headless => write!(f, "headless"),
+        }
+    }
+}
+
+impl std::str::FromStr for Backend {
+    type Err = GrottoError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "tmux" => Ok(Backend::Tmux),
+            "headless" => Ok(Backend::Headless),
+            other => Err(GrottoError::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "Unknown backend '{}'. Valid backends: tmux, headless",
+                    other
+                ),
+            ))),
+        }
+    }
+}
+
+/// Where one agent's pane is, see [`Grotto::agent_panes`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AgentPane {
+    pub agent_id: String,
+    pub pane_index: usize,
+    /// Multiplexer target, see [`Grotto::pane_target`]
+    pub target: String,
+}
+
+/// How to start one agent's process: where, with what environment, and the
+/// runtime's command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AgentLaunch {
+    pub workdir: PathBuf,
+    pub env: Vec<(String, String)>,
+    pub command: Vec<String>,
+}
+
+impl AgentLaunch {
+    /// Arguments for `tmux new-session` / `split-window` / `respawn-pane`.
+    pub fn tmux_args(&self) -> Vec<String> {
+        let mut args = vec!["-c".to_string(), self.workdir.to_string_lossy().to_string()];
+        for (key, value) in &self.env {
+            args.push("-e".to_string());
+            args.push(format!("{}={}", key, value));
+        }
+        args.extend(self.command.iter().cloned());
+        args
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AgentState {
     pub id: String,
     pub pane_index: usize,
+    /// Stable tmux pane ID (`%3`) recorded at launch; unlike the index it
+    /// doesn't change when other panes close
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pane_id: Option<String>,
     pub state: String,
     pub current_task: Option<String>,
     pub progress: String,
@@ -47,6 +291,41 @@ pub struct AgentState {
     /// Real-time phase inferred from tmux pane capture (not persisted to status.json)
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub phase: Option<String>,
+    /// Git worktree the agent runs in, when spawned with worktree isolation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub worktree: Option<PathBuf>,
+    /// Branch checked out in the agent's worktree
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+    /// Specialty given with `grotto add-agent --role` or `spawn --role`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+    /// Model the agent was last launched on, picked by the session's routing rules
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// What the agent last said about its task with `grotto report`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub report: Option<ProgressReport>,
+}
+
+/// Progress an agent reported itself, as opposed to what was scraped from
+/// its pane.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProgressReport {
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub percent: Option<u8>,
+    /// What the agent is waiting for, if it can't go on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blocked_on: Option<String>,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl AgentState {
+    /// Whether the agent was retired by `grotto scale` and no longer has a pane.
+    pub fn is_retired(&self) -> bool {
+        self.state == "retired"
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -57,6 +336,9 @@ pub struct Task {
     pub claimed_by: Option<String>,
     pub created_at: DateTime<Utc>,
     pub completed_at: Option<DateTime<Utc>>,
+    /// Kinds of work (e.g. docs, planning), used for model routing
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -114,19 +396,46 @@ impl Grotto {
         fs::create_dir_all(grotto_dir.join("agents"))?;
         fs::create_dir_all(grotto_dir.join("messages"))?;
 
+        // A new session starts a new summary
+        let summary_path = grotto_dir.join("summary.md");
+        if summary_path.exists() {
+            fs::remove_file(summary_path)?;
+        }
+
+        // Record where the repo started so the run summary can report what changed
+        let git_baseline = git::baseline(&project_dir);
+        if git_baseline.is_some() {
+            git::exclude(&project_dir, ".grotto/")?;
+        }
+
         let session_id = session_id.unwrap_or_else(words::generate_session_id);
         let config = Config {
             agent_count,
             task: task.clone(),
             project_dir,
+            tmux_session: tmux_session_name(&session_id),
             session_id: Some(session_id),
+            isolation: Isolation::Shared,
+            checks: Vec::new(),
+            until: Vec::new(),
+            max_rounds: verify::DEFAULT_MAX_ROUNDS,
+            outcome: None,
+            max_duration_secs: None,
+            agent_timeout_secs: None,
+            max_cost_usd: None,
+            restart: supervise::RestartPolicy::Never,
+            max_restarts: supervise::DEFAULT_MAX_RESTARTS,
+            stall_after_secs: stall::DEFAULT_STALL_AFTER_SECS,
+            loop_repeats: stall::DEFAULT_LOOP_REPEATS,
+            on_stall: stall::StallAction::Notify,
+            runtime: runtime::RuntimeConfig::Claude,
+            agent_runtimes: BTreeMap::new(),
+            model: None,
+            models: Vec::new(),
+            backend: Backend::Tmux,
+            git: git_baseline,
         };
 
-        // Write config
-        let config_path = grotto_dir.join("config.toml");
-        let config_toml = toml::to_string(&config).unwrap();
-        fs::write(config_path, config_toml)?;
-
         // Initialize task board
         let main_task = Task {
             id: "main".to_string(),
@@ -135,10 +444,25 @@ impl Grotto {
             claimed_by: None,
             created_at: Utc::now(),
             completed_at: None,
+            labels: Vec::new(),
         };
 
         let tasks = vec![main_task];
 
+        // Drop agents left over from a larger previous team
+        for entry in fs::read_dir(grotto_dir.join("agents"))? {
+            let entry = entry?;
+            let keep = entry
+                .file_name()
+                .to_str()
+                .and_then(|name| name.strip_prefix("agent-"))
+                .and_then(|n| n.parse::<usize>().ok())
+                .is_some_and(|n| (1..=agent_count).contains(&n));
+            if !keep && entry.file_type()?.is_dir() {
+                fs::remove_dir_all(entry.path())?;
+            }
+        }
+
         // Create agents
         let mut agents = HashMap::new();
         for i in 0..agent_count {
@@ -146,15 +470,23 @@ impl Grotto {
             let agent = AgentState {
                 id: agent_id.clone(),
                 pane_index: i,
+                pane_id: None,
                 state: "spawning".to_string(),
                 current_task: None,
                 progress: "Starting up...".to_string(),
                 last_update: Utc::now(),
                 phase: None,
+                worktree: None,
+                branch: None,
+                role: None,
+                model: None,
+                report: None,
             };
             // Create agent directory and files
             let agent_dir = grotto_dir.join("agents").join(&agent_id);
             fs::create_dir_all(&agent_dir)?;
+            // Usage is parsed from the agent's logs, so each session starts new ones
+            logsink::remove_logs(&agent_dir)?;
 
             let status_path = agent_dir.join("status.json");
             let status_json = serde_json::to_string_pretty(&agent)?;
@@ -170,6 +502,8 @@ impl Grotto {
             tasks,
         };
 
+        grotto.write_config()?;
+
         // Write initial task board
         grotto.write_task_board()?;
 
@@ -222,8 +556,13 @@ impl Grotto {
             }
         }
 
-        // Load tasks from task board
-        let tasks = Vec::new(); // We'll parse this from tasks.md if needed
+        // Load tasks saved alongside the task board
+        let tasks_path = grotto_dir.join("tasks.json");
+        let tasks = if tasks_path.exists() {
+            serde_json::from_str(&fs::read_to_string(tasks_path)?)?
+        } else {
+            Vec::new()
+        };
 
         Ok(Grotto {
             grotto_dir,
@@ -233,6 +572,95 @@ impl Grotto {
         })
     }
 
+    pub fn write_config(&self) -> Result<()> {
+        let config_path = self.grotto_dir.join("config.toml");
+        let config_toml = toml::to_string(&self.config).unwrap();
+        fs::write(config_path, config_toml)?;
+        Ok(())
+    }
+
+    /// Replace (or append) a `## {heading}` section in `.grotto/summary.md`,
+    /// leaving the rest of the summary intact.
+    pub fn write_summary_section(&self, heading: &str, body: &str) -> Result<()> {
+        let summary_path = self.grotto_dir.join("summary.md");
+        let existing = fs::read_to_string(&summary_path)
+            .unwrap_or_else(|_| "# Grotto Run Summary\n".to_string());
+
+        let marker = format!("## {}", heading);
+        let mut out = String::new();
+        let mut skipping = false;
+        for line in existing.lines() {
+            if line.starts_with("## ") {
+                skipping = line.trim_end() == marker;
+            }
+            if !skipping {
+                out.push_str(line);
+                out.push('\n');
+            }
+        }
+
+        let out = out.trim_end();
+        let section = format!("{}\n\n{}\n\n{}\n", out, marker, body.trim_end());
+        fs::write(summary_path, section)?;
+        Ok(())
+    }
+
+    /// Take the `.grotto/locks/{name}.lock` advisory lock without waiting, so
+    /// `grotto wait` and the daemon's monitor don't run the same pass over a
+    /// session at once. `None` when another process holds it; the lock is
+    /// released when the returned file is dropped.
+    pub fn try_lock(&self, name: &str) -> Result<Option<fs::File>> {
+        let dir = self.grotto_dir.join("locks");
+        fs::create_dir_all(&dir)?;
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(dir.join(format!("{}.lock", name)))?;
+        match file.try_lock() {
+            Ok(()) => Ok(Some(file)),
+            Err(fs::TryLockError::WouldBlock) => Ok(None),
+            Err(fs::TryLockError::Error(e)) => Err(e.into()),
+        }
+    }
+
+    /// Agent IDs sorted by agent number (agent-2 before agent-10).
+    pub fn sorted_agent_ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self.agents.keys().cloned().collect();
+        ids.sort_by_key(|id| {
+            let number = id
+                .rsplit('-')
+                .next()
+                .and_then(|n| n.parse::<usize>().ok())
+                .unwrap_or(usize::MAX);
+            (number, id.clone())
+        });
+        ids
+    }
+
+    /// IDs of agents still on the team (not retired), sorted by agent number.
+    pub fn active_agent_ids(&self) -> Vec<String> {
+        self.sorted_agent_ids()
+            .into_iter()
+            .filter(|id| !self.agents[id].is_retired())
+            .collect()
+    }
+
+    /// The pane of every agent on the team, for pane capture.
+    pub fn agent_panes(&self) -> Vec<AgentPane> {
+        self.active_agent_ids()
+            .into_iter()
+            .map(|id| {
+                let agent = &self.agents[&id];
+                AgentPane {
+                    pane_index: agent.pane_index,
+                    target: self.target_of(agent),
+                    agent_id: id,
+                }
+            })
+            .collect()
+    }
+
     pub fn write_task_board(&self) -> Result<()> {
         let task_board_path = self.grotto_dir.join("tasks.md");
         let mut content = String::new();
@@ -255,10 +683,54 @@ impl Grotto {
             if let Some(agent) = &task.claimed_by {
                 content.push_str(&format!("   - Claimed by: {}\n", agent));
             }
+            if !task.labels.is_empty() {
+                content.push_str(&format!("   - Labels: {}\n", task.labels.join(", ")));
+            }
             content.push('\n');
         }
 
         fs::write(task_board_path, content)?;
+        fs::write(
+            self.grotto_dir.join("tasks.json"),
+            serde_json::to_string_pretty(&self.tasks)?,
+        )?;
+        Ok(())
+    }
+
+    /// Put a new open task on the board and log `task_added`.
+    pub fn add_task(
+        &mut self,
+        task_id: &str,
+        description: &str,
+        labels: Vec<String>,
+    ) -> Result<()> {
+        if task_id.trim().is_empty() || self.tasks.iter().any(|t| t.id == task_id) {
+            return Err(GrottoError::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("Task '{}' already exists or is not a valid ID", task_id),
+            )));
+        }
+        self.tasks.push(Task {
+            id: task_id.to_string(),
+            description: description.to_string(),
+            status: TaskStatus::Open,
+            claimed_by: None,
+            created_at: Utc::now(),
+            completed_at: None,
+            labels: labels.clone(),
+        });
+        self.write_task_board()?;
+
+        self.log_event(
+            "task_added",
+            None,
+            Some(task_id),
+            Some(&format!("Task {} added", task_id)),
+            serde_json::json!({
+                "task_description": description,
+                "labels": labels,
+            }),
+        )?;
         Ok(())
     }
 
@@ -286,6 +758,7 @@ impl Grotto {
             agent.current_task = Some(task_id.to_string());
             agent.state = "working".to_string();
             agent.progress = format!("Working on task: {}", task_description);
+            agent.report = None;
             agent.last_update = Utc::now();
         }
 
@@ -300,7 +773,8 @@ impl Grotto {
             Some(task_id),
             Some(&format!("Agent {} claimed task {}", agent_id, task_id)),
             serde_json::json!({
-                "task_description": &task_description
+                "task_description": &task_description,
+                "usage": usage::agent_usage(&self.grotto_dir, agent_id),
             }),
         )?;
 
@@ -327,6 +801,7 @@ impl Grotto {
                 agent.current_task = None;
                 agent.state = "idle".to_string();
                 agent.progress = "Task completed, ready for next task".to_string();
+                agent.report = None;
                 agent.last_update = Utc::now();
             }
 
@@ -341,7 +816,91 @@ impl Grotto {
             Some(task_id),
             Some(&format!("Task {} completed", task_id)),
             serde_json::json!({
-                "task_description": &task_description
+                "task_description": &task_description,
+                "usage": claimed_by_agent
+                    .as_deref()
+                    .map(|agent_id| usage::agent_usage(&self.grotto_dir, agent_id)),
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    /// Record progress an agent reported on its task: shown by `status` and
+    /// the web UI until it claims or completes another task.
+    pub fn report_progress(
+        &mut self,
+        agent_id: &str,
+        message: &str,
+        percent: Option<u8>,
+        blocked_on: Option<&str>,
+    ) -> Result<()> {
+        let agent = self
+            .agents
+            .get_mut(agent_id)
+            .filter(|a| !a.is_retired())
+            .ok_or_else(|| GrottoError::AgentNotFound(agent_id.to_string()))?;
+        let report = ProgressReport {
+            message: message.to_string(),
+            percent: percent.map(|p| p.min(100)),
+            blocked_on: blocked_on.map(str::to_string),
+            timestamp: Utc::now(),
+        };
+        agent.progress = message.to_string();
+        agent.last_update = report.timestamp;
+        agent.report = Some(report.clone());
+        let task_id = agent.current_task.clone();
+        self.write_agent_status(agent_id)?;
+
+        self.log_event(
+            "progress_reported",
+            Some(agent_id),
+            task_id.as_deref(),
+            Some(message),
+            serde_json::json!({
+                "progress": report.message,
+                "percent": report.percent,
+                "blocked_on": report.blocked_on,
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    /// Put a claimed task back on the board so another agent can pick it up.
+    pub fn requeue_task(&mut self, task_id: &str, reason: &str) -> Result<()> {
+        let previous_agent = {
+            let task = self
+                .tasks
+                .iter_mut()
+                .find(|t| t.id == task_id)
+                .ok_or_else(|| GrottoError::TaskNotFound(task_id.to_string()))?;
+
+            task.status = TaskStatus::Open;
+            task.claimed_by.take()
+        };
+
+        if let Some(agent_id) = &previous_agent
+            && let Some(agent) = self.agents.get_mut(agent_id)
+            && agent.current_task.as_deref() == Some(task_id)
+        {
+            agent.current_task = None;
+            agent.last_update = Utc::now();
+            self.write_agent_status(agent_id)?;
+        }
+
+        self.write_task_board()?;
+
+        self.log_event(
+            "task_requeued",
+            previous_agent.as_deref(),
+            Some(task_id),
+            Some(&format!("Task {} requeued: {}", task_id, reason)),
+            serde_json::json!({
+                "reason": reason,
+                "usage": previous_agent
+                    .as_deref()
+                    .map(|agent_id| usage::agent_usage(&self.grotto_dir, agent_id)),
             }),
         )?;
 
@@ -379,23 +938,390 @@ impl Grotto {
             data,
         };
 
-        let events_path = self.grotto_dir.join("events.jsonl");
-        let event_line = serde_json::to_string(&event)? + "\n";
+        let events_path = self.grotto_dir.join("events.jsonl");
+        let event_line = serde_json::to_string(&event)? + "\n";
+
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(events_path)?;
+        file.write_all(event_line.as_bytes())?;
+
+        Ok(())
+    }
+
+    /// Events from `events.jsonl`, oldest first; malformed lines are skipped.
+    pub fn read_events(&self) -> Result<Vec<Event>> {
+        let events_path = self.grotto_dir.join("events.jsonl");
+        if !events_path.exists() {
+            return Ok(Vec::new());
+        }
+        let content = fs::read_to_string(events_path)?;
+        Ok(content
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect())
+    }
+
+    /// Events since the most recent `team_spawned`; events.jsonl spans sessions.
+    pub fn read_session_events(&self) -> Result<Vec<Event>> {
+        let events = self.read_events()?;
+        let start = events
+            .iter()
+            .rposition(|e| e.event_type == "team_spawned")
+            .unwrap_or(0);
+        Ok(events.into_iter().skip(start).collect())
+    }
+
+    /// Directory an agent works in: its worktree if isolated, else the project dir.
+    pub fn agent_workdir(&self, agent_id: &str) -> PathBuf {
+        self.agents
+            .get(agent_id)
+            .and_then(|a| a.worktree.clone())
+            .unwrap_or_else(|| self.config.project_dir.clone())
+    }
+
+    /// The runtime an agent runs: its override in `agent_runtimes`, else the
+    /// session's.
+    pub fn agent_runtime(&self, agent_id: &str) -> Box<dyn runtime::AgentRuntime> {
+        self.config
+            .agent_runtimes
+            .get(agent_id)
+            .unwrap_or(&self.config.runtime)
+            .runtime()
+    }
+
+    /// Phase rules for the agent's runtime: `.grotto/phases/<runtime>.toml`
+    /// when it exists, otherwise the built-in rules.
+    pub fn phase_rules(&self, agent_id: &str) -> Result<detect::PhaseRules> {
+        let kind = self
+            .config
+            .agent_runtimes
+            .get(agent_id)
+            .unwrap_or(&self.config.runtime)
+            .kind();
+        detect::PhaseRules::load(&self.grotto_dir, kind)
+    }
+
+    /// Phase of an agent from its captured screen, read with its runtime's
+    /// phase rules. A rules file that fails to load falls back to the
+    /// built-in rules.
+    pub fn infer_agent_phase(&self, agent_id: &str, pane: &str) -> monitor::AgentPhase {
+        let runtime = self.agent_runtime(agent_id);
+        match self.phase_rules(agent_id) {
+            Ok(rules) => runtime.infer_phase(pane, &rules),
+            Err(_) => runtime.infer_phase(pane, detect::PhaseRules::built_in()),
+        }
+    }
+
+    /// Pick the agent's model from the routing rules (its role, and the
+    /// labels of the task it holds) and record it. Logs `model_assigned`
+    /// when the model changes. Call before launching or relaunching it.
+    pub fn assign_model(&mut self, agent_id: &str) -> Result<Option<String>> {
+        let agent = self
+            .agents
+            .get(agent_id)
+            .ok_or_else(|| GrottoError::AgentNotFound(agent_id.to_string()))?;
+        let labels = agent
+            .current_task
+            .as_deref()
+            .and_then(|id| self.tasks.iter().find(|t| t.id == id))
+            .map(|t| t.labels.clone())
+            .unwrap_or_default();
+        let route = routing::route(
+            &self.config.models,
+            self.config.model.as_deref(),
+            agent.role.as_deref(),
+            &labels,
+        );
+        let model = route.as_ref().map(|r| r.model.clone());
+        if agent.model == model {
+            return Ok(model);
+        }
+
+        if let Some(agent) = self.agents.get_mut(agent_id) {
+            agent.model = model.clone();
+            agent.last_update = Utc::now();
+        }
+        self.write_agent_status(agent_id)?;
+        if let Some(route) = route {
+            let task_id = self.agents[agent_id].current_task.clone();
+            self.log_event(
+                "model_assigned",
+                Some(agent_id),
+                task_id.as_deref(),
+                Some(&format!(
+                    "{} runs on {} ({})",
+                    agent_id, route.model, route.rule
+                )),
+                serde_json::json!({ "model": route.model, "rule": route.rule }),
+            )?;
+        }
+        Ok(model)
+    }
+
+    /// How to launch one agent: its working directory, environment, and its
+    /// runtime's command with `prompt` on its assigned model.
+    pub fn agent_launch(&self, agent_id: &str, prompt: String) -> AgentLaunch {
+        let command = self
+            .agent_runtime(agent_id)
+            .launch_command(&prompt, self.agent_model(agent_id));
+        self.launch(agent_id, command)
+    }
+
+    /// How to relaunch an agent after `reason`, with its claimed task in the
+    /// prompt.
+    pub fn agent_resume_launch(&self, agent_id: &str, reason: &str) -> AgentLaunch {
+        let prompt = self.generate_resume_prompt(agent_id, reason);
+        let command = self
+            .agent_runtime(agent_id)
+            .resume_command(&prompt, self.agent_model(agent_id));
+        self.launch(agent_id, command)
+    }
+
+    fn agent_model(&self, agent_id: &str) -> Option<&str> {
+        self.agents.get(agent_id).and_then(|a| a.model.as_deref())
+    }
+
+    fn launch(&self, agent_id: &str, command: Vec<String>) -> AgentLaunch {
+        let mut env = vec![
+            (
+                "GROTTO_DIR".to_string(),
+                self.config.project_dir.display().to_string(),
+            ),
+            ("GROTTO_AGENT_ID".to_string(), agent_id.to_string()),
+        ];
+        // Commits made by the agent are attributed to it in the change report
+        if self.config.git.is_some() {
+            env.push((
+                "GIT_AUTHOR_NAME".to_string(),
+                git::agent_author_name(agent_id),
+            ));
+        }
+        AgentLaunch {
+            workdir: self.agent_workdir(agent_id),
+            env,
+            command,
+        }
+    }
+
+    /// Give every agent its own branch and git worktree under `.grotto/worktrees/`.
+    pub fn create_worktrees(&mut self) -> Result<()> {
+        let project_dir = self.config.project_dir.clone();
+        if !git::is_repo(&project_dir) {
+            return Err(GrottoError::Git(format!(
+                "worktree isolation requires a git repository: {}",
+                project_dir.display()
+            )));
+        }
+        git::exclude(&project_dir, ".grotto/")?;
+
+        for agent_id in self.sorted_agent_ids() {
+            self.create_worktree(&agent_id)?;
+        }
+
+        self.config.isolation = Isolation::Worktree;
+        self.write_config()?;
+        Ok(())
+    }
+
+    /// Give one agent its `grotto/<session>/<agent>` branch and worktree.
+    fn create_worktree(&mut self, agent_id: &str) -> Result<()> {
+        let project_dir = self.config.project_dir.clone();
+        let session_id = self
+            .config
+            .session_id
+            .clone()
+            .unwrap_or_else(|| "session".to_string());
+        let worktrees_dir = self.grotto_dir.join("worktrees");
+        fs::create_dir_all(&worktrees_dir)?;
+
+        let branch = git::agent_branch(&session_id, agent_id);
+        let path = worktrees_dir.join(agent_id);
+        if path.exists() {
+            // Leftover from a previous session with the same agent slot
+            git::remove_worktree(&project_dir, &path)?;
+        }
+        git::add_worktree(&project_dir, &branch, &path)?;
+
+        if let Some(agent) = self.agents.get_mut(agent_id) {
+            agent.worktree = Some(path.clone());
+            agent.branch = Some(branch.clone());
+            agent.last_update = Utc::now();
+        }
+        self.write_agent_status(agent_id)?;
+
+        self.log_event(
+            "worktree_created",
+            Some(agent_id),
+            None,
+            Some(&format!("Created worktree for {} on {}", agent_id, branch)),
+            serde_json::json!({
+                "branch": branch,
+                "path": path.display().to_string(),
+            }),
+        )
+    }
+
+    /// Add an agent to the live team: the next agent number, in a new pane
+    /// after the last one. Creates its status dir (and worktree when isolated),
+    /// updates `agent_count` and logs `agent_spawned`. Starting its pane is up
+    /// to the caller.
+    pub fn add_agent(&mut self, role: Option<&str>) -> Result<String> {
+        let number = self
+            .agents
+            .keys()
+            .filter_map(|id| id.strip_prefix("agent-")?.parse::<usize>().ok())
+            .max()
+            .unwrap_or(0)
+            + 1;
+        let agent_id = format!("agent-{}", number);
+        let pane_index = self
+            .agent_panes()
+            .iter()
+            .map(|pane| pane.pane_index + 1)
+            .max()
+            .unwrap_or(0);
+
+        let agent_dir = self.grotto_dir.join("agents").join(&agent_id);
+        fs::create_dir_all(&agent_dir)?;
+        logsink::remove_logs(&agent_dir)?;
+        self.agents.insert(
+            agent_id.clone(),
+            AgentState {
+                id: agent_id.clone(),
+                pane_index,
+                pane_id: None,
+                state: "spawning".to_string(),
+                current_task: None,
+                progress: "Starting up...".to_string(),
+                last_update: Utc::now(),
+                phase: None,
+                worktree: None,
+                branch: None,
+                role: role.map(str::to_string),
+                model: None,
+                report: None,
+            },
+        );
+        self.write_agent_status(&agent_id)?;
+        if self.config.isolation == Isolation::Worktree {
+            self.create_worktree(&agent_id)?;
+        }
+
+        self.config.agent_count = self.active_agent_ids().len();
+        self.write_config()?;
+
+        let message = match role {
+            Some(role) => format!("Added {} as {}", agent_id, role),
+            None => format!("Added {}", agent_id),
+        };
+        self.log_event(
+            "agent_spawned",
+            Some(&agent_id),
+            None,
+            Some(&message),
+            serde_json::json!({ "pane_index": pane_index, "role": role }),
+        )?;
+        Ok(agent_id)
+    }
+
+    /// Take an agent off the team after its pane was closed: requeue its
+    /// claimed task, mark it retired, shift the panes after it down (tmux
+    /// renumbers them) and log `agent_retired`. Returns the requeued task.
+    pub fn retire_agent(&mut self, agent_id: &str) -> Result<Option<String>> {
+        let agent = self
+            .agents
+            .get(agent_id)
+            .filter(|a| !a.is_retired())
+            .cloned()
+            .ok_or_else(|| GrottoError::AgentNotFound(agent_id.to_string()))?;
+
+        let requeued_task = agent.current_task.clone();
+        if let Some(task_id) = &requeued_task {
+            match self.requeue_task(task_id, &format!("{} retired", agent_id)) {
+                Ok(()) | Err(GrottoError::TaskNotFound(_)) => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        for id in self.active_agent_ids() {
+            let other = self.agents.get_mut(&id).unwrap();
+            if other.pane_index > agent.pane_index {
+                other.pane_index -= 1;
+                self.write_agent_status(&id)?;
+            }
+        }
+        if let Some(agent) = self.agents.get_mut(agent_id) {
+            agent.state = "retired".to_string();
+            agent.current_task = None;
+            agent.progress = "Retired".to_string();
+            agent.last_update = Utc::now();
+        }
+        self.write_agent_status(agent_id)?;
+
+        self.config.agent_count = self.active_agent_ids().len();
+        self.write_config()?;
+
+        self.log_event(
+            "agent_retired",
+            Some(agent_id),
+            requeued_task.as_deref(),
+            Some(&format!("Retired {}", agent_id)),
+            serde_json::json!({
+                "pane_index": agent.pane_index,
+                "requeued_task": requeued_task,
+            }),
+        )?;
+        Ok(requeued_task)
+    }
+
+    /// Remove an agent's worktree, keeping its branch. Returns the removed path.
+    pub fn remove_worktree(&mut self, agent_id: &str) -> Result<Option<PathBuf>> {
+        let path = match self.agents.get(agent_id).and_then(|a| a.worktree.clone()) {
+            Some(path) => path,
+            None => return Ok(None),
+        };
+
+        git::remove_worktree(&self.config.project_dir, &path)?;
 
-        let mut file = fs::OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(events_path)?;
-        file.write_all(event_line.as_bytes())?;
+        if let Some(agent) = self.agents.get_mut(agent_id) {
+            agent.worktree = None;
+            agent.last_update = Utc::now();
+        }
+        self.write_agent_status(agent_id)?;
 
-        Ok(())
+        self.log_event(
+            "worktree_removed",
+            Some(agent_id),
+            None,
+            Some(&format!("Removed worktree for {}", agent_id)),
+            serde_json::json!({ "path": path.display().to_string() }),
+        )?;
+
+        Ok(Some(path))
     }
 
-    /// Check if required external dependencies are available
-    pub fn check_dependencies() -> std::result::Result<(), Vec<String>> {
+    /// Check that tmux (unless running headless) and the binaries each
+    /// runtime launches are available
+    pub fn check_dependencies<'a>(
+        backend: Backend,
+        runtimes: impl IntoIterator<Item = &'a runtime::RuntimeConfig>,
+    ) -> std::result::Result<(), Vec<String>> {
         let mut missing = Vec::new();
 
-        for bin in &["tmux", "claude"] {
+        let mut bins = Vec::new();
+        if backend.is_tmux() {
+            bins.push("tmux".to_string());
+        }
+        for config in runtimes {
+            for bin in config.runtime().required_binaries() {
+                if !bins.contains(&bin) {
+                    bins.push(bin);
+                }
+            }
+        }
+        for bin in &bins {
             if Command::new("which")
                 .arg(bin)
                 .stdout(Stdio::null())
@@ -415,46 +1341,165 @@ impl Grotto {
         }
     }
 
-    /// Get the pane index for an agent, validating it exists
+    /// Get the pane index for an agent, validating it exists and is on the team
     pub fn get_agent_pane(&self, agent_id: &str) -> Result<usize> {
         self.agents
             .get(agent_id)
+            .filter(|a| !a.is_retired())
             .map(|a| a.pane_index)
             .ok_or_else(|| GrottoError::AgentNotFound(agent_id.to_string()))
     }
 
+    /// Multiplexer target for an agent's pane: its pane ID, or
+    /// `session:0.index` for agents launched before IDs were recorded. A
+    /// headless session's agents are addressed by ID (see
+    /// [`headless::Runner`]).
+    pub fn pane_target(&self, agent_id: &str) -> Result<String> {
+        self.agents
+            .get(agent_id)
+            .filter(|a| !a.is_retired())
+            .map(|a| self.target_of(a))
+            .ok_or_else(|| GrottoError::AgentNotFound(agent_id.to_string()))
+    }
+
+    fn target_of(&self, agent: &AgentState) -> String {
+        match (&agent.pane_id, self.config.backend) {
+            (_, Backend::Headless) => agent.id.clone(),
+            (Some(id), Backend::Tmux) => id.clone(),
+            (None, Backend::Tmux) => format!("{}:0.{}", self.config.tmux_session, agent.pane_index),
+        }
+    }
+
+    /// Record the pane ID an agent was launched in.
+    pub fn set_pane_id(&mut self, agent_id: &str, pane_id: Option<String>) -> Result<()> {
+        let agent = self
+            .agents
+            .get_mut(agent_id)
+            .ok_or_else(|| GrottoError::AgentNotFound(agent_id.to_string()))?;
+        agent.pane_id = pane_id;
+        self.write_agent_status(agent_id)
+    }
+
     pub fn generate_claude_prompt(&self, agent_id: &str) -> String {
         let agent = self.agents.get(agent_id).unwrap();
+        let workdir = self.agent_workdir(agent_id);
+        let isolation_note = match &agent.branch {
+            Some(branch) => format!(
+                "\n## Isolation\n\
+                 You have your own git worktree on branch `{branch}`. Other agents cannot see\n\
+                 your uncommitted changes, so commit your work to this branch regularly.\n"
+            ),
+            None => String::new(),
+        };
+        let commit_note = match &self.config.git {
+            Some(_) => format!(
+                "\n## Commits\n\
+                 End every commit message with a `{trailer}: {agent_id}` trailer line so your\n\
+                 work shows up under your name in the run summary.\n",
+                trailer = git::AGENT_TRAILER,
+            ),
+            None => String::new(),
+        };
+
+        let criteria_note = if self.config.until.is_empty() {
+            String::new()
+        } else {
+            let commands: Vec<String> = self
+                .config
+                .until
+                .iter()
+                .map(|c| format!("- `{}`", c))
+                .collect();
+            format!(
+                "\n## Success Criteria\n\
+                 The session is done when these commands pass in {project_dir}:\n{commands}\n\
+                 When you go idle they are run for you; failures are sent back to the team.\n",
+                project_dir = self.config.project_dir.display(),
+                commands = commands.join("\n"),
+            )
+        };
+
+        let budget_note = match (
+            self.config.max_duration_secs,
+            self.config.agent_timeout_secs,
+        ) {
+            (None, None) => String::new(),
+            (session, agent) => {
+                let mut limits = Vec::new();
+                if let Some(secs) = session {
+                    limits.push(format!(
+                        "- The session stops after {}",
+                        budget::format_duration(secs)
+                    ));
+                }
+                if let Some(secs) = agent {
+                    limits.push(format!(
+                        "- You are stopped after {}",
+                        budget::format_duration(secs)
+                    ));
+                }
+                format!(
+                    "\n## Time Budget\n{}\n\
+                     You get a warning at 80%. Commit often; unfinished tasks are requeued.\n",
+                    limits.join("\n")
+                )
+            }
+        };
+        let mut specialty = match &agent.role {
+            Some(role) => format!("\n- Your specialty on this team: {}", role),
+            None => String::new(),
+        };
+        if let Some(task) = agent
+            .current_task
+            .as_deref()
+            .and_then(|id| self.tasks.iter().find(|t| t.id == id))
+        {
+            specialty.push_str(&format!(
+                "\n- You have already claimed task {}: {}",
+                task.id, task.description
+            ));
+        }
+        let cost_note = match self.config.max_cost_usd {
+            Some(usd) => format!(
+                "\n## Cost Budget\n\
+                 The team is stopped once it has spent {} in total. You get a warning at 80%.\n",
+                usage::format_cost(usd)
+            ),
+            None => String::new(),
+        };
 
         format!(
             r#"You are {agent_id}, an autonomous coding agent working as part of a team on this task:
 
 **MAIN TASK**: {task}
 
-You are working in: {project_dir}
+You are working in: {workdir}
 
 ## Your Role
-- You are agent {agent_id} (pane {pane_index}) in a tmux session called "grotto"
+- You are agent {agent_id} (pane {pane_index}) in a tmux session called "{tmux_session}"
 - Work collaboratively with other agents on the shared codebase
-- Use the `grotto` CLI to coordinate with your team
+- Use the `grotto` CLI to coordinate with your team{specialty}
 
 ## Available Commands
 - `grotto status` - See task board and agent states
 - `grotto claim <task-id> --agent {agent_id}` - Claim a task
+- `grotto add-task <task-id> "description" --label <label>` - Split off a subtask for the team
 - `grotto complete <task-id>` - Mark a task as done
+- `grotto report --agent {agent_id} --progress "what you just finished" [--percent 60] [--blocked-on "what you need"]` - Tell the lead how your task is going
 - `grotto steer <other-agent> "message"` - Send message to another agent
 - `grotto broadcast "message"` - Message all agents
 - `grotto log <agent>` - View another agent's output
+- `grotto request-approval "what you are about to do" --agent {agent_id}` - Ask the lead before a risky step (migrations, deletes, pushes); blocks until answered and exits non-zero if denied
 
 ## Coordination Protocol
 1. Check `grotto status` to see available tasks
 2. Claim tasks with `grotto claim <task-id> --agent {agent_id}`
-3. Work on your claimed task
+3. Work on your claimed task, running `grotto report` at each milestone and whenever you are blocked
 4. Mark it done with `grotto complete <task-id>`
 5. Communicate with teammates as needed
-
+{isolation_note}{commit_note}{criteria_note}{budget_note}{cost_note}
 ## Working Directory
-You are in: {project_dir}
+You are in: {workdir}
 Task board and coordination files are in: {project_dir}/.grotto/
 
 Start by checking `grotto status` to see the current state, then claim an available task and begin working.
@@ -462,7 +1507,40 @@ Start by checking `grotto status` to see the current state, then claim an availa
             agent_id = agent_id,
             task = self.config.task,
             project_dir = self.config.project_dir.display(),
+            workdir = workdir.display(),
             pane_index = agent.pane_index,
+            tmux_session = self.config.tmux_session,
+            specialty = specialty,
+            isolation_note = isolation_note,
+            commit_note = commit_note,
+            criteria_note = criteria_note,
+            budget_note = budget_note,
+            cost_note = cost_note,
+        )
+    }
+
+    /// The agent's prompt plus the task it held, for relaunching it mid-session
+    /// after `reason` (e.g. "a crash").
+    pub fn generate_resume_prompt(&self, agent_id: &str, reason: &str) -> String {
+        let task = self
+            .agents
+            .get(agent_id)
+            .and_then(|a| a.current_task.as_deref())
+            .and_then(|id| self.tasks.iter().find(|t| t.id == id));
+        let context = match task {
+            Some(task) => format!(
+                "You had claimed task {}: {}. It is still yours; finish it before claiming another.",
+                task.id, task.description
+            ),
+            None => "You had no task claimed; pick up an open one from the board.".to_string(),
+        };
+        format!(
+            "{}\n## Resuming\n\
+             You were restarted after {}. {}\n\
+             Check `git status` and the task board to see what is already done before continuing.\n",
+            self.generate_claude_prompt(agent_id),
+            reason,
+            context
         )
     }
 }
@@ -527,6 +1605,42 @@ mod tests {
         assert!(config_str.contains("session_id"));
     }
 
+    #[test]
+    fn new_records_git_baseline_in_repo() {
+        let (_plain_tmp, plain_dir) = setup();
+        assert!(
+            Grotto::new(&plain_dir, 1, "t".into())
+                .unwrap()
+                .config
+                .git
+                .is_none()
+        );
+
+        let (_tmp, dir) = setup();
+        git::test_support::init_repo(&dir);
+        let grotto = Grotto::new(&dir, 1, "t".into()).unwrap();
+        let baseline = grotto.config.git.as_ref().unwrap();
+        assert_eq!(baseline.branch.as_deref(), Some("main"));
+        assert!(!baseline.dirty);
+
+        let loaded = Grotto::load(&dir).unwrap();
+        assert_eq!(loaded.config.git.as_ref(), Some(baseline));
+
+        // Coordination files stay out of the agents' commits
+        let status = git::run(&dir, &["status", "--porcelain"]).unwrap();
+        assert!(status.is_empty(), "status: {status}");
+    }
+
+    #[test]
+    fn new_clears_previous_summary() {
+        let (_tmp, dir) = setup();
+        let grotto = Grotto::new(&dir, 1, "first".into()).unwrap();
+        grotto.write_summary_section("Changes", "- old").unwrap();
+
+        Grotto::new(&dir, 1, "second".into()).unwrap();
+        assert!(!dir.join(".grotto/summary.md").exists());
+    }
+
     #[test]
     fn new_generates_session_id() {
         let (_tmp, dir) = setup();
@@ -546,6 +1660,45 @@ mod tests {
         assert_eq!(loaded.config.session_id, grotto.config.session_id);
     }
 
+    #[test]
+    fn tmux_session_is_named_after_session_id() {
+        let (_tmp, dir) = setup();
+        let grotto =
+            Grotto::init_with_session(&dir, 1, "test".into(), Some("cron.job:7".into())).unwrap();
+        assert_eq!(grotto.config.tmux_session, "grotto-cron_job_7");
+
+        // Sessions spawned before per-team names keep the shared one
+        let config_path = dir.join(".grotto/config.toml");
+        let config = fs::read_to_string(&config_path).unwrap();
+        let legacy: String = config
+            .lines()
+            .filter(|l| !l.starts_with("tmux_session"))
+            .map(|l| format!("{l}\n"))
+            .collect();
+        fs::write(&config_path, legacy).unwrap();
+        let loaded = Grotto::load(&dir).unwrap();
+        assert_eq!(loaded.config.tmux_session, "grotto");
+    }
+
+    #[test]
+    fn pane_target_prefers_recorded_pane_id() {
+        let (_tmp, dir) = setup();
+        let mut grotto =
+            Grotto::init_with_session(&dir, 2, "test".into(), Some("s1".into())).unwrap();
+        assert_eq!(grotto.pane_target("agent-2").unwrap(), "grotto-s1:0.1");
+
+        grotto
+            .set_pane_id("agent-2", Some("%7".to_string()))
+            .unwrap();
+        let loaded = Grotto::load(&dir).unwrap();
+        assert_eq!(loaded.pane_target("agent-2").unwrap(), "%7");
+        assert_eq!(loaded.agent_panes()[1].target, "%7");
+        assert!(matches!(
+            loaded.pane_target("agent-9"),
+            Err(GrottoError::AgentNotFound(_))
+        ));
+    }
+
     #[test]
     fn new_writes_initial_event() {
         let (_tmp, dir) = setup();
@@ -568,6 +1721,21 @@ mod tests {
         assert_eq!(loaded.agents.len(), 3);
     }
 
+    #[test]
+    fn load_restores_claimed_tasks() {
+        let (_tmp, dir) = setup();
+        let mut grotto = Grotto::new(&dir, 2, "reload test".into()).unwrap();
+        grotto.claim_task("main", "agent-2").unwrap();
+
+        let mut loaded = Grotto::load(&dir).unwrap();
+        assert_eq!(loaded.tasks.len(), 1);
+        assert!(matches!(loaded.tasks[0].status, TaskStatus::Claimed));
+        assert_eq!(loaded.tasks[0].claimed_by.as_deref(), Some("agent-2"));
+
+        // Commands on a loaded session can find the task
+        loaded.complete_task("main").unwrap();
+    }
+
     #[test]
     fn load_fails_without_grotto_dir() {
         let (_tmp, dir) = setup();
@@ -687,6 +1855,73 @@ mod tests {
         assert!(matches!(grotto.tasks[0].status, TaskStatus::Completed));
     }
 
+    // === Task requeue ===
+
+    #[test]
+    fn requeue_task_reopens_and_frees_agent() {
+        let (_tmp, dir) = setup();
+        let mut grotto = Grotto::new(&dir, 1, "test".into()).unwrap();
+        grotto.claim_task("main", "agent-1").unwrap();
+
+        grotto.requeue_task("main", "agent timed out").unwrap();
+
+        let task = &grotto.tasks[0];
+        assert!(matches!(task.status, TaskStatus::Open));
+        assert_eq!(task.claimed_by, None);
+        assert_eq!(grotto.agents["agent-1"].current_task, None);
+
+        let board = fs::read_to_string(dir.join(".grotto/tasks.md")).unwrap();
+        assert!(!board.contains("Claimed by"));
+        let events = fs::read_to_string(dir.join(".grotto/events.jsonl")).unwrap();
+        assert!(events.contains("\"event_type\":\"task_requeued\""));
+        assert!(events.contains("agent timed out"));
+    }
+
+    #[test]
+    fn report_progress_updates_status_until_next_claim() {
+        let (_tmp, dir) = setup();
+        let mut grotto = Grotto::new(&dir, 1, "test".into()).unwrap();
+        grotto.claim_task("main", "agent-1").unwrap();
+
+        grotto
+            .report_progress(
+                "agent-1",
+                "wired up JWT middleware",
+                Some(140),
+                Some("API keys"),
+            )
+            .unwrap();
+
+        let status_str =
+            fs::read_to_string(dir.join(".grotto/agents/agent-1/status.json")).unwrap();
+        let agent: AgentState = serde_json::from_str(&status_str).unwrap();
+        assert_eq!(agent.progress, "wired up JWT middleware");
+        let report = agent.report.unwrap();
+        assert_eq!(report.percent, Some(100));
+        assert_eq!(report.blocked_on.as_deref(), Some("API keys"));
+        let event = grotto.read_events().unwrap().pop().unwrap();
+        assert_eq!(event.event_type, "progress_reported");
+        assert_eq!(event.task_id.as_deref(), Some("main"));
+        assert_eq!(event.data["blocked_on"], "API keys");
+
+        grotto.complete_task("main").unwrap();
+        assert_eq!(grotto.agents["agent-1"].report, None);
+        assert!(matches!(
+            grotto.report_progress("agent-9", "x", None, None),
+            Err(GrottoError::AgentNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn requeue_task_nonexistent() {
+        let (_tmp, dir) = setup();
+        let mut grotto = Grotto::new(&dir, 1, "test".into()).unwrap();
+        assert!(matches!(
+            grotto.requeue_task("nope", "x"),
+            Err(GrottoError::TaskNotFound(_))
+        ));
+    }
+
     // === Agent status ===
 
     #[test]
@@ -718,6 +1953,114 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn sorted_agent_ids_uses_agent_number() {
+        let (_tmp, dir) = setup();
+        let grotto = Grotto::new(&dir, 11, "many".into()).unwrap();
+        let ids = grotto.sorted_agent_ids();
+        assert_eq!(ids[0], "agent-1");
+        assert_eq!(ids[1], "agent-2");
+        assert_eq!(ids[10], "agent-11");
+    }
+
+    // === Scaling ===
+
+    #[test]
+    fn add_agent_takes_next_number_and_pane() {
+        let (_tmp, dir) = setup();
+        let mut grotto = Grotto::new(&dir, 2, "team".into()).unwrap();
+
+        let added = grotto.add_agent(Some("reviewer")).unwrap();
+        assert_eq!(added, "agent-3");
+        assert_eq!(grotto.get_agent_pane("agent-3").unwrap(), 2);
+        assert_eq!(grotto.config.agent_count, 3);
+        assert!(dir.join(".grotto/agents/agent-3/status.json").exists());
+        assert!(
+            grotto
+                .generate_claude_prompt("agent-3")
+                .contains("Your specialty on this team: reviewer")
+        );
+
+        let reloaded = Grotto::load(&dir).unwrap();
+        assert_eq!(reloaded.agents["agent-3"].role.as_deref(), Some("reviewer"));
+        let events = fs::read_to_string(dir.join(".grotto/events.jsonl")).unwrap();
+        assert!(events.contains("\"event_type\":\"agent_spawned\""));
+    }
+
+    #[test]
+    fn retire_agent_requeues_claim_and_shifts_later_panes() {
+        let (_tmp, dir) = setup();
+        let mut grotto = Grotto::new(&dir, 3, "team".into()).unwrap();
+        grotto.claim_task("main", "agent-2").unwrap();
+
+        let requeued = grotto.retire_agent("agent-2").unwrap();
+        assert_eq!(requeued.as_deref(), Some("main"));
+        assert!(matches!(grotto.tasks[0].status, TaskStatus::Open));
+        assert!(grotto.agents["agent-2"].is_retired());
+        assert_eq!(grotto.active_agent_ids(), vec!["agent-1", "agent-3"]);
+        assert_eq!(grotto.get_agent_pane("agent-3").unwrap(), 1);
+        assert!(matches!(
+            grotto.get_agent_pane("agent-2"),
+            Err(GrottoError::AgentNotFound(_))
+        ));
+        assert_eq!(grotto.config.agent_count, 2);
+
+        // Retired numbers are not reused; the new agent goes after the last pane
+        assert_eq!(grotto.add_agent(None).unwrap(), "agent-4");
+        assert_eq!(grotto.get_agent_pane("agent-4").unwrap(), 2);
+    }
+
+    #[test]
+    fn init_drops_agents_from_a_larger_previous_team() {
+        let (_tmp, dir) = setup();
+        let mut grotto = Grotto::new(&dir, 2, "first".into()).unwrap();
+        grotto.add_agent(None).unwrap();
+
+        let grotto = Grotto::new(&dir, 2, "second".into()).unwrap();
+        assert_eq!(grotto.agents.len(), 2);
+        assert!(!dir.join(".grotto/agents/agent-3").exists());
+        assert_eq!(Grotto::load(&dir).unwrap().agents.len(), 2);
+    }
+
+    // === Summary ===
+
+    #[test]
+    fn write_summary_section_appends_and_replaces() {
+        let (_tmp, dir) = setup();
+        let grotto = Grotto::new(&dir, 1, "test".into()).unwrap();
+
+        grotto
+            .write_summary_section("Merge Pipeline", "- first")
+            .unwrap();
+        grotto.write_summary_section("Changes", "- files").unwrap();
+        grotto
+            .write_summary_section("Merge Pipeline", "- second")
+            .unwrap();
+
+        let summary = fs::read_to_string(dir.join(".grotto/summary.md")).unwrap();
+        assert!(summary.starts_with("# Grotto Run Summary"));
+        assert!(!summary.contains("- first"));
+        assert!(summary.contains("## Merge Pipeline\n\n- second"));
+        assert!(summary.contains("## Changes\n\n- files"));
+        assert_eq!(summary.matches("## Merge Pipeline").count(), 1);
+    }
+
+    // === Locks ===
+
+    #[test]
+    fn try_lock_is_exclusive_until_dropped() {
+        let (_tmp, dir) = setup();
+        let grotto = Grotto::new(&dir, 1, "test".into()).unwrap();
+
+        let held = grotto.try_lock("budget").unwrap();
+        assert!(held.is_some());
+        assert!(grotto.try_lock("budget").unwrap().is_none());
+        assert!(grotto.try_lock("supervise").unwrap().is_some());
+
+        drop(held);
+        assert!(grotto.try_lock("budget").unwrap().is_some());
+    }
+
     // === Event logging ===
 
     #[test]
@@ -785,18 +2128,155 @@ mod tests {
         assert!(prompt.contains("pane 0"));
         assert!(prompt.contains("grotto status"));
         assert!(prompt.contains("grotto claim"));
+        assert!(prompt.contains("grotto request-approval"));
+        assert!(prompt.contains("grotto report --agent agent-1 --progress"));
 
         let prompt2 = grotto.generate_claude_prompt("agent-2");
         assert!(prompt2.contains("agent-2"));
         assert!(prompt2.contains("pane 1"));
     }
 
+    #[test]
+    fn generate_prompt_mentions_worktree_branch() {
+        let (_tmp, dir) = setup();
+        git::test_support::init_repo(&dir);
+        let mut grotto = Grotto::new(&dir, 1, "isolated".into()).unwrap();
+        grotto.create_worktrees().unwrap();
+
+        let prompt = grotto.generate_claude_prompt("agent-1");
+        assert!(prompt.contains(".grotto/worktrees/agent-1"));
+        assert!(prompt.contains("grotto/"));
+        assert!(prompt.contains("commit your work"));
+        assert!(prompt.contains("`Grotto-Agent: agent-1` trailer"));
+    }
+
+    #[test]
+    fn generate_prompt_lists_success_criteria() {
+        let (_tmp, dir) = setup();
+        let mut grotto = Grotto::new(&dir, 1, "fix tests".into()).unwrap();
+        assert!(
+            !grotto
+                .generate_claude_prompt("agent-1")
+                .contains("Success Criteria")
+        );
+
+        grotto.config.until = vec!["cargo test --workspace".to_string()];
+        let prompt = grotto.generate_claude_prompt("agent-1");
+        assert!(prompt.contains("## Success Criteria"));
+        assert!(prompt.contains("- `cargo test --workspace`"));
+    }
+
+    #[test]
+    fn generate_resume_prompt_carries_claimed_task() {
+        let (_tmp, dir) = setup();
+        let mut grotto = Grotto::new(&dir, 2, "fix the parser".into()).unwrap();
+        grotto.claim_task("main", "agent-2").unwrap();
+
+        let prompt = grotto.generate_resume_prompt("agent-2", "a crash");
+        assert!(prompt.starts_with("You are agent-2"));
+        assert!(prompt.contains("You were restarted after a crash."));
+        assert!(prompt.contains("You had claimed task main: fix the parser."));
+
+        let prompt = grotto.generate_resume_prompt("agent-1", "a crash");
+        assert!(prompt.contains("You had no task claimed"));
+    }
+
+    #[test]
+    fn generate_prompt_states_time_budget() {
+        let (_tmp, dir) = setup();
+        let mut grotto = Grotto::new(&dir, 1, "quick fix".into()).unwrap();
+        assert!(
+            !grotto
+                .generate_claude_prompt("agent-1")
+                .contains("Time Budget")
+        );
+
+        grotto.config.max_duration_secs = Some(45 * 60);
+        grotto.config.agent_timeout_secs = Some(20 * 60);
+        let prompt = grotto.generate_claude_prompt("agent-1");
+        assert!(prompt.contains("## Time Budget"));
+        assert!(prompt.contains("The session stops after 45m"));
+        assert!(prompt.contains("You are stopped after 20m"));
+        assert!(!prompt.contains("Cost Budget"));
+
+        grotto.config.max_cost_usd = Some(5.0);
+        let prompt = grotto.generate_claude_prompt("agent-1");
+        assert!(prompt.contains("## Cost Budget"));
+        assert!(prompt.contains("spent $5.00 in total"));
+    }
+
+    // === Worktree isolation ===
+
+    #[test]
+    fn create_worktrees_records_paths_and_branches() {
+        let (_tmp, dir) = setup();
+        git::test_support::init_repo(&dir);
+        let mut grotto = Grotto::new(&dir, 2, "isolated".into()).unwrap();
+        let session_id = grotto.config.session_id.clone().unwrap();
+
+        grotto.create_worktrees().unwrap();
+
+        assert_eq!(grotto.config.isolation, Isolation::Worktree);
+        for id in ["agent-1", "agent-2"] {
+            let wt = dir.join(".grotto/worktrees").join(id);
+            assert!(wt.join("README.md").exists());
+            assert_eq!(grotto.agent_workdir(id), wt);
+            assert_eq!(
+                grotto.agents[id].branch,
+                Some(format!("grotto/{}/{}", session_id, id))
+            );
+        }
+
+        let loaded = Grotto::load(&dir).unwrap();
+        assert_eq!(loaded.config.isolation, Isolation::Worktree);
+        assert!(loaded.agents["agent-1"].worktree.is_some());
+
+        let events = fs::read_to_string(dir.join(".grotto/events.jsonl")).unwrap();
+        assert!(events.contains("worktree_created"));
+    }
+
+    #[test]
+    fn create_worktrees_requires_git_repo() {
+        let (_tmp, dir) = setup();
+        let mut grotto = Grotto::new(&dir, 1, "not a repo".into()).unwrap();
+        let err = grotto.create_worktrees().unwrap_err();
+        assert!(err.to_string().contains("requires a git repository"));
+    }
+
+    #[test]
+    fn remove_worktree_clears_agent_path() {
+        let (_tmp, dir) = setup();
+        git::test_support::init_repo(&dir);
+        let mut grotto = Grotto::new(&dir, 1, "isolated".into()).unwrap();
+        grotto.create_worktrees().unwrap();
+
+        let removed = grotto.remove_worktree("agent-1").unwrap();
+        assert_eq!(removed, Some(dir.join(".grotto/worktrees/agent-1")));
+        assert!(!dir.join(".grotto/worktrees/agent-1").exists());
+        assert_eq!(grotto.agents["agent-1"].worktree, None);
+        assert_eq!(grotto.agent_workdir("agent-1"), dir);
+
+        // Removing again is a no-op
+        assert_eq!(grotto.remove_worktree("agent-1").unwrap(), None);
+    }
+
+    #[test]
+    fn isolation_parses_from_str() {
+        assert_eq!(
+            "worktree".parse::<Isolation>().unwrap(),
+            Isolation::Worktree
+        );
+        assert_eq!("shared".parse::<Isolation>().unwrap(), Isolation::Shared);
+        let err = "branches".parse::<Isolation>().unwrap_err().to_string();
+        assert!(err.contains("Unknown isolation mode 'branches'"));
+    }
+
     // === Dependency checking ===
 
     #[test]
     fn check_dependencies_runs() {
         // Just verify it doesn't panic — actual result depends on environment
-        let result = Grotto::check_dependencies();
+        let result = Grotto::check_dependencies(Backend::Tmux, [&runtime::RuntimeConfig::Claude]);
         match result {
             Ok(()) => {} // tmux + claude both found
             Err(missing) => {
@@ -808,6 +2288,103 @@ mod tests {
         }
     }
 
+    #[test]
+    fn models_are_routed_by_task_label_then_role() {
+        let (_tmp, dir) = setup();
+        let mut grotto = Grotto::new(&dir, 2, "test".into()).unwrap();
+        grotto.config.model = Some("sonnet".into());
+        grotto.config.models = vec![
+            "role:tester=haiku".parse().unwrap(),
+            "label:planning=opus".parse().unwrap(),
+        ];
+        grotto.write_config().unwrap();
+        grotto.agents.get_mut("agent-2").unwrap().role = Some("tester".into());
+        grotto
+            .add_task("plan", "Plan the rollout", vec!["planning".into()])
+            .unwrap();
+
+        assert_eq!(
+            grotto.assign_model("agent-1").unwrap().as_deref(),
+            Some("sonnet")
+        );
+        assert_eq!(
+            grotto.assign_model("agent-2").unwrap().as_deref(),
+            Some("haiku")
+        );
+        grotto.claim_task("plan", "agent-2").unwrap();
+        assert_eq!(
+            grotto.assign_model("agent-2").unwrap().as_deref(),
+            Some("opus")
+        );
+        // Unchanged models are not logged again
+        grotto.assign_model("agent-2").unwrap();
+
+        let launch = grotto.agent_launch("agent-2", "hi".into());
+        assert!(launch.command.ends_with(&[
+            "--model".to_string(),
+            "opus".to_string(),
+            "-p".to_string(),
+            "hi".to_string()
+        ]));
+        let loaded = Grotto::load(&dir).unwrap();
+        assert_eq!(loaded.agents["agent-2"].model.as_deref(), Some("opus"));
+        assert_eq!(loaded.config.models, grotto.config.models);
+        assert_eq!(loaded.tasks[1].labels, vec!["planning"]);
+
+        let assigned: Vec<_> = grotto
+            .read_session_events()
+            .unwrap()
+            .into_iter()
+            .filter(|e| e.event_type == "model_assigned")
+            .map(|e| e.data["rule"].as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(
+            assigned,
+            vec!["default", "role:tester=haiku", "label:planning=opus"]
+        );
+
+        let board = fs::read_to_string(dir.join(".grotto/tasks.md")).unwrap();
+        assert!(board.contains("   - Labels: planning"), "{board}");
+        assert!(grotto.add_task("plan", "again", Vec::new()).is_err());
+    }
+
+    #[test]
+    fn agents_use_their_runtime_override() {
+        let (_tmp, dir) = setup();
+        let mut grotto = Grotto::new(&dir, 2, "test".into()).unwrap();
+        grotto.config.agent_runtimes.insert(
+            "agent-2".into(),
+            "shell:aider --message {prompt}".parse().unwrap(),
+        );
+
+        let claude = grotto.agent_launch("agent-1", "hi".into());
+        assert!(
+            claude
+                .command
+                .ends_with(&["-p".to_string(), "hi".to_string()])
+        );
+        let shell = grotto.agent_launch("agent-2", "hi".into());
+        assert_eq!(shell.command.last().unwrap(), "aider --message 'hi'");
+        let resume = grotto.agent_resume_launch("agent-2", "a crash");
+        assert!(
+            resume
+                .command
+                .last()
+                .unwrap()
+                .contains("You were restarted after a crash")
+        );
+        assert_eq!(grotto.agent_runtime("agent-2").exit_keys(), vec!["C-c"]);
+
+        grotto.write_config().unwrap();
+        let config = fs::read_to_string(dir.join(".grotto/config.toml")).unwrap();
+        assert!(config.contains("[agent_runtimes.agent-2]"), "{config}");
+        let loaded = Grotto::load(&dir).unwrap();
+        assert_eq!(
+            loaded.config.agent_runtimes["agent-2"],
+            grotto.config.agent_runtimes["agent-2"]
+        );
+    }
+
     // === Edge cases ===
 
     #[test]
@@ -825,17 +2402,24 @@ mod tests {
         let agent = AgentState {
             id: "agent-1".into(),
             pane_index: 0,
+            pane_id: Some("%3".into()),
             state: "working".into(),
             current_task: Some("main".into()),
             progress: "doing stuff".into(),
             last_update: Utc::now(),
             phase: None,
+            worktree: None,
+            branch: None,
+            role: None,
+            model: None,
+            report: None,
         };
 
         let json = serde_json::to_string(&agent).unwrap();
         let back: AgentState = serde_json::from_str(&json).unwrap();
         assert_eq!(back.id, "agent-1");
         assert_eq!(back.state, "working");
+        assert_eq!(back.pane_id.as_deref(), Some("%3"));
         assert_eq!(back.current_task, Some("main".into()));
     }
 
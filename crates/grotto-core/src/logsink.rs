@@ -0,0 +1,841 @@
+use crate::Result;
+use crate::usage::{self, Usage, UsageParser};
+use chrono::{DateTime, SecondsFormat, Utc};
+use std::fs;
+use std::io::{BufRead, Read, Seek, SeekFrom, Write};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+/// Raw terminal output, ANSI escapes included.
+pub const RAW_LOG: &str = "stream.log";
+
+/// Cleaned output: one `<timestamp> <text>` line per line the agent printed.
+pub const CLEAN_LOG: &str = "output.log";
+
+/// Byte offset of every line in the cleaned log, as little-endian `u64`s.
+pub const INDEX: &str = "output.idx";
+
+/// Size at which a log is rotated to `<name>.1`, replacing the previous one.
+pub const DEFAULT_MAX_BYTES: u64 = 16 * 1024 * 1024;
+
+/// Overrides [`DEFAULT_MAX_BYTES`].
+pub const MAX_BYTES_ENV: &str = "GROTTO_LOG_MAX_BYTES";
+
+/// Braille, circle and star frames that terminal spinners cycle through.
+const SPINNER_FRAMES: &str = "⠋⠙⠹⠸⠼⠴⠦⠧⠇⠏◐◓◑◒✻✶✳✢✽·⏳";
+
+/// An agent's cleaned log.
+pub fn clean_path(grotto_dir: &Path, agent_id: &str) -> PathBuf {
+    grotto_dir.join("agents").join(agent_id).join(CLEAN_LOG)
+}
+
+/// Where `path` goes when it is rotated.
+pub fn rotated(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".1");
+    path.with_file_name(name)
+}
+
+/// Identifies the file at `path`, so readers can tell a rotation from an append.
+pub fn file_id(path: &Path) -> Option<u64> {
+    fs::metadata(path).ok().map(|m| m.ino())
+}
+
+/// Remove an agent's logs, current and rotated, and its usage totals so a
+/// new session starts fresh.
+pub fn remove_logs(agent_dir: &Path) -> Result<()> {
+    for name in [RAW_LOG, CLEAN_LOG, INDEX] {
+        let path = agent_dir.join(name);
+        for path in [rotated(&path), path] {
+            if path.exists() {
+                fs::remove_file(path)?;
+            }
+        }
+    }
+    for name in [usage::USAGE_FILE, usage::STREAM_USAGE_FILE] {
+        let totals = agent_dir.join(name);
+        if totals.exists() {
+            fs::remove_file(totals)?;
+        }
+    }
+    Ok(())
+}
+
+/// Shell command that feeds its input to `grotto log-sink`, writing the raw
+/// log at `raw_path` and the cleaned log beside it.
+pub fn sink_command(raw_path: &Path) -> String {
+    format!(
+        "{} log-sink {}",
+        shell_quote(&grotto_exe()),
+        shell_quote(&raw_path.to_string_lossy())
+    )
+}
+
+/// The running `grotto` binary, for commands it hands to panes.
+pub fn grotto_exe() -> String {
+    std::env::current_exe()
+        .unwrap_or_else(|_| PathBuf::from("grotto"))
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// Writes an agent's output to its raw log and, cleaned and timestamped, to
+/// its cleaned log and index, rotating each by size. Keeps the agent's
+/// usage totals in [`usage::USAGE_FILE`], which rotation doesn't touch.
+#[derive(Debug)]
+pub struct LogSink {
+    raw: Appender,
+    clean: Appender,
+    index: fs::File,
+    cleaner: LineCleaner,
+    usage: UsageParser,
+    /// Totals from before this sink was opened
+    usage_base: Usage,
+    usage_saved: Usage,
+}
+
+#[derive(Debug)]
+struct Appender {
+    path: PathBuf,
+    file: fs::File,
+    len: u64,
+    max_bytes: u64,
+}
+
+impl Appender {
+    fn open(path: PathBuf, max_bytes: u64) -> Result<Self> {
+        let file = append_to(&path)?;
+        let len = file.metadata()?.len();
+        Ok(Self {
+            path,
+            file,
+            len,
+            max_bytes,
+        })
+    }
+
+    /// Whether `bytes` more would overflow the log; an empty log takes
+    /// anything.
+    fn full(&self, bytes: usize) -> bool {
+        self.len > 0 && self.len + bytes as u64 > self.max_bytes
+    }
+
+    fn rotate(&mut self) -> Result<()> {
+        fs::rename(&self.path, rotated(&self.path))?;
+        self.file = append_to(&self.path)?;
+        self.len = 0;
+        Ok(())
+    }
+
+    fn append(&mut self, bytes: &[u8]) -> Result<()> {
+        self.file.write_all(bytes)?;
+        self.len += bytes.len() as u64;
+        Ok(())
+    }
+}
+
+fn append_to(path: &Path) -> Result<fs::File> {
+    Ok(fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?)
+}
+
+impl LogSink {
+    /// Open the logs beside `raw_path`, appending to any already there.
+    /// Rotates at [`MAX_BYTES_ENV`] bytes, or [`DEFAULT_MAX_BYTES`].
+    pub fn open(raw_path: &Path) -> Result<Self> {
+        let max_bytes = std::env::var(MAX_BYTES_ENV)
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_MAX_BYTES);
+        Self::with_max_bytes(raw_path, max_bytes)
+    }
+
+    pub fn with_max_bytes(raw_path: &Path, max_bytes: u64) -> Result<Self> {
+        let clean_path = raw_path.with_file_name(CLEAN_LOG);
+        let agent_dir = raw_path.parent().unwrap_or(Path::new("."));
+        let usage_base = usage::load(agent_dir, usage::USAGE_FILE)
+            .unwrap_or_else(|| usage::parse_usage(&read_clean(&clean_path)));
+        Ok(Self {
+            raw: Appender::open(raw_path.to_path_buf(), max_bytes)?,
+            index: append_to(&raw_path.with_file_name(INDEX))?,
+            clean: Appender::open(clean_path, max_bytes)?,
+            cleaner: LineCleaner::default(),
+            usage: UsageParser::default(),
+            usage_base,
+            usage_saved: usage_base,
+        })
+    }
+
+    /// Append a chunk of output, writing each line it completes.
+    pub fn write(&mut self, bytes: &[u8]) -> Result<()> {
+        if self.raw.full(bytes.len()) {
+            self.raw.rotate()?;
+        }
+        self.raw.append(bytes)?;
+        for line in self.cleaner.push(bytes) {
+            self.write_line(&line)?;
+        }
+        Ok(())
+    }
+
+    /// Write the last line, even if the output stopped partway through it.
+    pub fn finish(mut self) -> Result<()> {
+        if let Some(line) = self.cleaner.finish() {
+            self.write_line(&line)?;
+        }
+        Ok(())
+    }
+
+    /// Count the line's usage and log it, as readable text when it is a
+    /// `stream-json` event.
+    fn write_line(&mut self, text: &str) -> Result<()> {
+        self.usage.push(text);
+        let mut spent = self.usage_base;
+        spent += self.usage.usage();
+        if spent != self.usage_saved {
+            let agent_dir = self.clean.path.parent().unwrap_or(Path::new("."));
+            usage::save(agent_dir, usage::USAGE_FILE, &spent)?;
+            self.usage_saved = spent;
+        }
+
+        match render_stream_json(text) {
+            Some(lines) => lines.iter().try_for_each(|line| self.append_line(line)),
+            None => self.append_line(text),
+        }
+    }
+
+    fn append_line(&mut self, text: &str) -> Result<()> {
+        let line = format!(
+            "{} {}\n",
+            Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+            text
+        );
+        if self.clean.full(line.len()) {
+            self.clean.rotate()?;
+            let index = self.clean.path.with_file_name(INDEX);
+            fs::rename(&index, rotated(&index))?;
+            self.index = append_to(&index)?;
+        }
+        self.index.write_all(&self.clean.len.to_le_bytes())?;
+        self.clean.append(line.as_bytes())
+    }
+}
+
+/// Copy Claude Code's `stream-json` output from `input` to `out` as the
+/// readable lines [`render_stream_json`] makes of it, passing other lines
+/// through, and keep the usage it reports in `agent_dir`'s
+/// [`usage::STREAM_USAGE_FILE`], adding to what earlier runs saved there.
+pub fn render_stream(
+    mut input: impl BufRead,
+    out: &mut impl Write,
+    agent_dir: Option<&Path>,
+) -> Result<()> {
+    let base = agent_dir
+        .and_then(|dir| usage::load(dir, usage::STREAM_USAGE_FILE))
+        .unwrap_or_default();
+    let mut saved = base;
+    let mut parser = UsageParser::default();
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        if input.read_until(b'\n', &mut buf)? == 0 {
+            return Ok(());
+        }
+        let line = String::from_utf8_lossy(&buf);
+        let line = line.trim_end_matches(['\r', '\n']);
+
+        parser.push(line);
+        let mut spent = base;
+        spent += parser.usage();
+        if let Some(dir) = agent_dir
+            && spent != saved
+        {
+            usage::save(dir, usage::STREAM_USAGE_FILE, &spent)?;
+            saved = spent;
+        }
+
+        match render_stream_json(line) {
+            Some(lines) => lines.iter().try_for_each(|l| writeln!(out, "{}", l))?,
+            None => writeln!(out, "{}", line)?,
+        }
+        out.flush()?;
+    }
+}
+
+/// Longest tool result kept in the cleaned log, in lines.
+const TOOL_RESULT_LINES: usize = 3;
+
+/// What Claude's terminal would have shown for a `stream-json` event: the
+/// assistant's text, `Tool(input)` calls, the start of their results and
+/// the final result. `None` when `line` isn't such an event.
+pub fn render_stream_json(line: &str) -> Option<Vec<String>> {
+    let line = line.trim();
+    if !line.starts_with('{') {
+        return None;
+    }
+    let event: serde_json::Value = serde_json::from_str(line).ok()?;
+    let kind = event["type"].as_str()?;
+    let mut lines = Vec::new();
+    match kind {
+        "system" if event["subtype"] == "init" => lines.push(format!(
+            "Session started ({})",
+            event["model"].as_str().unwrap_or("default model")
+        )),
+        "assistant" => {
+            for block in content_blocks(&event) {
+                match block["type"].as_str() {
+                    Some("text") => push_text(&mut lines, block["text"].as_str(), "", usize::MAX),
+                    Some("tool_use") => lines.push(format!(
+                        "{}({})",
+                        block["name"].as_str().unwrap_or("Tool"),
+                        tool_input(&block["input"])
+                    )),
+                    _ => {}
+                }
+            }
+        }
+        "user" => {
+            for block in content_blocks(&event) {
+                if block["type"] != "tool_result" {
+                    continue;
+                }
+                let prefix = if block["is_error"] == true {
+                    "  ⎿ Error: "
+                } else {
+                    "  ⎿ "
+                };
+                let text = match &block["content"] {
+                    serde_json::Value::String(text) => text.clone(),
+                    serde_json::Value::Array(parts) => parts
+                        .iter()
+                        .filter_map(|p| p["text"].as_str())
+                        .collect::<Vec<_>>()
+                        .join("\n"),
+                    _ => String::new(),
+                };
+                push_text(&mut lines, Some(&text), prefix, TOOL_RESULT_LINES);
+            }
+        }
+        "result" => {
+            push_text(&mut lines, event["result"].as_str(), "", usize::MAX);
+            lines.push(format!(
+                "Finished: {} after {} turn(s)",
+                event["subtype"].as_str().unwrap_or("done"),
+                event["num_turns"].as_u64().unwrap_or(0)
+            ));
+        }
+        _ => {}
+    }
+    Some(lines)
+}
+
+fn content_blocks(event: &serde_json::Value) -> &[serde_json::Value] {
+    event["message"]["content"]
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+}
+
+/// Non-blank lines of `text`, the first with `prefix` and the rest indented
+/// to match, cut to `max` with a count of what was left out.
+fn push_text(lines: &mut Vec<String>, text: Option<&str>, prefix: &str, max: usize) {
+    let text: Vec<&str> = text
+        .unwrap_or_default()
+        .lines()
+        .map(str::trim_end)
+        .filter(|l| !l.trim().is_empty())
+        .collect();
+    let indent = " ".repeat(prefix.chars().count());
+    for (i, line) in text.iter().take(max).enumerate() {
+        let lead = if i == 0 { prefix } else { indent.as_str() };
+        lines.push(format!("{}{}", lead, line));
+    }
+    if text.len() > max {
+        lines.push(format!("{}… +{} lines", indent, text.len() - max));
+    }
+}
+
+/// A tool call's main argument (the command, file or pattern), or its
+/// input as compact JSON.
+fn tool_input(input: &serde_json::Value) -> String {
+    [
+        "command",
+        "file_path",
+        "path",
+        "pattern",
+        "url",
+        "description",
+    ]
+    .iter()
+    .find_map(|key| input[*key].as_str())
+    .map(|arg| arg.lines().collect::<Vec<_>>().join(" "))
+    .unwrap_or_else(|| input.to_string())
+}
+
+/// Turns raw terminal output into the lines a reader would have seen:
+/// escape sequences dropped, carriage returns and backspaces applied, blank
+/// lines skipped, and consecutive frames of the same spinner kept once.
+#[derive(Debug, Default)]
+pub struct LineCleaner {
+    line: String,
+    escape: Escape,
+    /// A carriage return: the next character overwrites the line
+    returned: bool,
+    /// The tail of a UTF-8 sequence split across chunks
+    partial: Vec<u8>,
+    last_spinner: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum Escape {
+    #[default]
+    None,
+    Esc,
+    Csi,
+    Osc,
+    OscEsc,
+    Charset,
+}
+
+impl LineCleaner {
+    /// Feed a chunk of output; returns the lines it completes.
+    pub fn push(&mut self, bytes: &[u8]) -> Vec<String> {
+        self.partial.extend_from_slice(bytes);
+        let mut pending = std::mem::take(&mut self.partial);
+        let text = match std::str::from_utf8(&pending) {
+            Ok(text) => text.to_string(),
+            Err(e) if e.error_len().is_none() => {
+                // Cut off mid-character: keep the rest for the next chunk
+                self.partial = pending.split_off(e.valid_up_to());
+                String::from_utf8_lossy(&pending).into_owned()
+            }
+            Err(_) => String::from_utf8_lossy(&pending).into_owned(),
+        };
+
+        let mut lines = Vec::new();
+        for c in text.chars() {
+            self.step(c, &mut lines);
+        }
+        lines
+    }
+
+    /// The line in progress, if it has any text.
+    pub fn finish(&mut self) -> Option<String> {
+        let mut lines = Vec::new();
+        self.end_line(&mut lines);
+        lines.pop()
+    }
+
+    fn step(&mut self, c: char, lines: &mut Vec<String>) {
+        self.escape = match (self.escape, c) {
+            (Escape::None, '\x1b') => Escape::Esc,
+            (Escape::None, _) => {
+                self.print(c, lines);
+                Escape::None
+            }
+            (Escape::Esc, '[') => Escape::Csi,
+            (Escape::Esc, ']') => Escape::Osc,
+            (Escape::Esc, '(' | ')') => Escape::Charset,
+            (Escape::Csi, '@'..='~') => Escape::None,
+            (Escape::Osc, '\x07') => Escape::None,
+            (Escape::Osc, '\x1b') => Escape::OscEsc,
+            (Escape::OscEsc, _) => Escape::None,
+            (Escape::Csi | Escape::Osc, _) => self.escape,
+            (Escape::Esc | Escape::Charset, _) => Escape::None,
+        };
+    }
+
+    fn print(&mut self, c: char, lines: &mut Vec<String>) {
+        match c {
+            '\n' => {
+                self.returned = false;
+                self.end_line(lines);
+            }
+            '\r' => self.returned = true,
+            '\x08' => {
+                self.line.pop();
+            }
+            c if c == '\t' || !c.is_control() => {
+                if self.returned {
+                    // Redrawn in place; keep what was shown before
+                    self.returned = false;
+                    self.end_line(lines);
+                }
+                self.line.push(c);
+            }
+            _ => {}
+        }
+    }
+
+    fn end_line(&mut self, lines: &mut Vec<String>) {
+        let line = std::mem::take(&mut self.line);
+        let line = line.trim_end();
+        if line.trim().is_empty() {
+            return;
+        }
+        let spinner = spinner_key(line);
+        if spinner.is_some() && spinner == self.last_spinner {
+            return;
+        }
+        self.last_spinner = spinner;
+        lines.push(line.to_string());
+    }
+}
+
+/// What stays the same between frames of a spinner line: the text after the
+/// frame with counters such as elapsed seconds dropped. `None` when the line
+/// doesn't start with a spinner frame.
+fn spinner_key(line: &str) -> Option<String> {
+    let rest = line.trim_start();
+    let first = rest.chars().next()?;
+    if !SPINNER_FRAMES.contains(first) && !('\u{2800}'..='\u{28FF}').contains(&first) {
+        return None;
+    }
+    Some(
+        rest.chars()
+            .filter(|c| !SPINNER_FRAMES.contains(*c) && !c.is_ascii_digit())
+            .collect(),
+    )
+}
+
+/// Split a cleaned log line into its timestamp and text. Lines without a
+/// timestamp are all text.
+pub fn split_timestamp(line: &str) -> (Option<DateTime<Utc>>, &str) {
+    if let Some((head, text)) = line.split_once(' ')
+        && let Ok(timestamp) = DateTime::parse_from_rfc3339(head)
+    {
+        return (Some(timestamp.with_timezone(&Utc)), text);
+    }
+    (None, line)
+}
+
+/// Text of an agent's cleaned log, the rotated part first, without
+/// timestamps.
+pub fn read_text(grotto_dir: &Path, agent_id: &str) -> String {
+    read_clean(&clean_path(grotto_dir, agent_id))
+}
+
+fn read_clean(path: &Path) -> String {
+    let mut text = String::new();
+    for path in [rotated(path), path.to_path_buf()] {
+        let Ok(bytes) = fs::read(path) else {
+            continue;
+        };
+        for line in String::from_utf8_lossy(&bytes).lines() {
+            text.push_str(split_timestamp(line).1);
+            text.push('\n');
+        }
+    }
+    text
+}
+
+/// The last `count` lines of an agent's cleaned log, without timestamps.
+/// Reads only those lines, found through the index.
+pub fn tail_lines(grotto_dir: &Path, agent_id: &str, count: usize) -> Result<Vec<String>> {
+    let path = clean_path(grotto_dir, agent_id);
+    let mut lines = tail_of(&path, count)?;
+    if lines.len() < count {
+        let mut earlier = tail_of(&rotated(&path), count - lines.len())?;
+        earlier.append(&mut lines);
+        lines = earlier;
+    }
+    Ok(lines)
+}
+
+fn tail_of(path: &Path, count: usize) -> Result<Vec<String>> {
+    let Ok(mut log) = fs::File::open(path) else {
+        return Ok(Vec::new());
+    };
+    let len = log.metadata()?.len();
+    let start = match line_offset_from_end(&path.with_file_name(index_name(path)), count) {
+        Some(offset) if offset <= len => offset,
+        // No usable index: read it all
+        _ => 0,
+    };
+
+    let mut bytes = Vec::new();
+    log.seek(SeekFrom::Start(start))?;
+    log.read_to_end(&mut bytes)?;
+    let text = String::from_utf8_lossy(&bytes);
+    let lines: Vec<&str> = text.lines().collect();
+    Ok(lines[lines.len().saturating_sub(count)..]
+        .iter()
+        .map(|line| split_timestamp(line).1.to_string())
+        .collect())
+}
+
+/// The index beside a cleaned log, rotated along with it.
+fn index_name(path: &Path) -> String {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    name.replacen(CLEAN_LOG, INDEX, 1)
+}
+
+/// Where the `count`th line from the end starts, per the index.
+fn line_offset_from_end(index_path: &Path, count: usize) -> Option<u64> {
+    let mut index = fs::File::open(index_path).ok()?;
+    let lines = index.metadata().ok()?.len() / 8;
+    if lines == 0 {
+        return None;
+    }
+    let entry = lines.saturating_sub(count as u64);
+    let mut offset = [0u8; 8];
+    index.seek(SeekFrom::Start(entry * 8)).ok()?;
+    index.read_exact(&mut offset).ok()?;
+    Some(u64::from_le_bytes(offset))
+}
+
+/// How far a reader has got through a log that may be rotated under it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Cursor {
+    file: Option<u64>,
+    offset: u64,
+}
+
+/// Complete lines appended to the cleaned log at `path` since `cursor`,
+/// without timestamps. Picks up the end of the rotated log first if the log
+/// was rotated, and starts over if it was truncated or replaced.
+pub fn read_new(path: &Path, cursor: &mut Cursor) -> Result<Vec<String>> {
+    let current = file_id(path);
+    let mut lines = Vec::new();
+    let mut offset = cursor.offset;
+    if cursor.file.is_some() && current != cursor.file {
+        if file_id(&rotated(path)) == cursor.file {
+            lines = read_lines_from(&rotated(path), offset)?.0;
+        }
+        offset = 0;
+    }
+    let Some(file) = current else {
+        *cursor = Cursor::default();
+        return Ok(lines);
+    };
+
+    let (mut new_lines, end) = read_lines_from(path, offset)?;
+    lines.append(&mut new_lines);
+    *cursor = Cursor {
+        file: Some(file),
+        offset: end,
+    };
+    Ok(lines)
+}
+
+fn read_lines_from(path: &Path, offset: u64) -> Result<(Vec<String>, u64)> {
+    let Ok(mut file) = fs::File::open(path) else {
+        return Ok((Vec::new(), 0));
+    };
+    let len = file.metadata()?.len();
+    // Truncated: start over
+    let offset = if len < offset { 0 } else { offset };
+    file.seek(SeekFrom::Start(offset))?;
+    let mut buf = Vec::new();
+    file.read_to_end(&mut buf)?;
+
+    let Some(last_newline) = buf.iter().rposition(|&b| b == b'\n') else {
+        return Ok((Vec::new(), offset));
+    };
+    let complete = String::from_utf8_lossy(&buf[..last_newline]);
+    let lines = complete
+        .lines()
+        .map(|line| split_timestamp(line).1.to_string())
+        .collect();
+    Ok((lines, offset + last_newline as u64 + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clean(chunks: &[&[u8]]) -> Vec<String> {
+        let mut cleaner = LineCleaner::default();
+        let mut lines: Vec<String> = chunks.iter().flat_map(|c| cleaner.push(c)).collect();
+        lines.extend(cleaner.finish());
+        lines
+    }
+
+    #[test]
+    fn cleaner_strips_escapes_and_applies_carriage_returns() {
+        assert_eq!(
+            clean(&[
+                b"\x1b]0;title\x07\x1b[1;31merror\x1b[0m: bad\r\n\r\n",
+                b"tail"
+            ]),
+            vec!["error: bad", "tail"]
+        );
+        // A line redrawn in place keeps what was on screen before
+        assert_eq!(
+            clean(&[b"Downloading 10%\r\x1b[2KDownloading 100%\n"]),
+            vec!["Downloading 10%", "Downloading 100%"]
+        );
+        assert_eq!(clean(&[b"typo\x08\x08po\n"]), vec!["typo"]);
+        // "◐" split across chunks
+        assert_eq!(
+            clean(&[b"\xe2\x97", b"\x90 Thinking\n"]),
+            vec!["◐ Thinking"]
+        );
+    }
+
+    #[test]
+    fn cleaner_collapses_spinner_frames() {
+        let frames = "✻ Thinking… (1s)\r✶ Thinking… (2s)\r✳ Thinking… (3s)\n\
+                      ⠋ Running tests\r⠙ Running tests\n\
+                      Edit(src/a.rs)\n\
+                      ✻ Thinking… (9s)\n";
+        assert_eq!(
+            clean(&[frames.as_bytes()]),
+            vec![
+                "✻ Thinking… (1s)",
+                "⠋ Running tests",
+                "Edit(src/a.rs)",
+                "✻ Thinking… (9s)"
+            ]
+        );
+    }
+
+    #[test]
+    fn stream_json_events_render_as_terminal_text() {
+        let render = |line: &str| render_stream_json(line).unwrap();
+        assert_eq!(
+            render(r#"{"type":"system","subtype":"init","model":"claude-sonnet-4"}"#),
+            vec!["Session started (claude-sonnet-4)"]
+        );
+        assert_eq!(
+            render(
+                r#"{"type":"assistant","message":{"content":[{"type":"text","text":"Running the tests.\n"},{"type":"tool_use","name":"Bash","input":{"command":"cargo test"}}]}}"#
+            ),
+            vec!["Running the tests.", "Bash(cargo test)"]
+        );
+        assert_eq!(
+            render(
+                r#"{"type":"user","message":{"content":[{"type":"tool_result","is_error":true,"content":"one\ntwo\nthree\nfour\nfive"}]}}"#
+            ),
+            vec![
+                "  ⎿ Error: one",
+                "           two",
+                "           three",
+                "           … +2 lines"
+            ]
+        );
+        assert_eq!(
+            render(r#"{"type":"result","subtype":"success","num_turns":3,"result":"All done."}"#),
+            vec!["All done.", "Finished: success after 3 turn(s)"]
+        );
+        assert!(render(r#"{"type":"stream_event"}"#).is_empty());
+        assert_eq!(render_stream_json("Edit(src/a.rs)"), None);
+        assert_eq!(render_stream_json("{not json"), None);
+    }
+
+    #[test]
+    fn render_stream_adds_to_earlier_runs_usage() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let result = r#"{"type":"result","subtype":"success","num_turns":2,"result":"Done.","total_cost_usd":0.5,"usage":{"input_tokens":100,"output_tokens":10}}"#;
+        let input = format!("warming up\n{}\n", result);
+
+        for _ in 0..2 {
+            let mut out = Vec::new();
+            render_stream(input.as_bytes(), &mut out, Some(tmp.path())).unwrap();
+            assert_eq!(
+                String::from_utf8(out).unwrap(),
+                "warming up\nDone.\nFinished: success after 2 turn(s)\n"
+            );
+        }
+        let spent = usage::load(tmp.path(), usage::STREAM_USAGE_FILE).unwrap();
+        assert_eq!(spent.input_tokens, 200);
+        assert!((spent.cost_usd - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn sink_rotates_and_tails_through_the_index() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let agent_dir = tmp.path().join("agents/agent-1");
+        fs::create_dir_all(&agent_dir).unwrap();
+        let raw = agent_dir.join(RAW_LOG);
+
+        let mut sink = LogSink::with_max_bytes(&raw, 400).unwrap();
+        for i in 0..20 {
+            sink.write(format!("\x1b[2K\x1b[32mline {i}\x1b[0m\r\n").as_bytes())
+                .unwrap();
+        }
+        sink.write(b"no newline").unwrap();
+        sink.finish().unwrap();
+
+        assert!(rotated(&raw).exists());
+        assert!(fs::metadata(&raw).unwrap().len() <= 400);
+        let clean = fs::read_to_string(agent_dir.join(CLEAN_LOG)).unwrap();
+        let (timestamp, text) = split_timestamp(clean.lines().last().unwrap());
+        assert!(timestamp.is_some());
+        assert_eq!(text, "no newline");
+
+        // Spans the rotated log and the current one
+        let tail = tail_lines(tmp.path(), "agent-1", 12).unwrap();
+        let expected: Vec<String> = (9..20)
+            .map(|i| format!("line {i}"))
+            .chain(["no newline".to_string()])
+            .collect();
+        assert_eq!(tail, expected);
+        let text = read_text(tmp.path(), "agent-1");
+        assert!(text.ends_with("line 19\nno newline\n"));
+    }
+
+    #[test]
+    fn usage_totals_survive_rotation_and_reopening() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let agent_dir = tmp.path().join("agents/agent-1");
+        fs::create_dir_all(&agent_dir).unwrap();
+        let raw = agent_dir.join(RAW_LOG);
+        let result = r#"{"type":"result","total_cost_usd":0.5,"usage":{"input_tokens":1000,"output_tokens":100}}"#;
+
+        let mut sink = LogSink::with_max_bytes(&raw, 200).unwrap();
+        sink.write(format!("{result}\n").as_bytes()).unwrap();
+        // Enough output to rotate the result out of both cleaned logs
+        for i in 0..40 {
+            sink.write(format!("working on step {i}\n").as_bytes())
+                .unwrap();
+        }
+        assert!(!read_text(tmp.path(), "agent-1").contains("result"));
+        let usage = usage::agent_usage(tmp.path(), "agent-1");
+        assert_eq!(usage.input_tokens, 1000);
+        assert!((usage.cost_usd - 0.5).abs() < 1e-9);
+
+        // A restarted agent gets a new sink; its runs add to the totals
+        let mut sink = LogSink::with_max_bytes(&raw, 200).unwrap();
+        sink.write(format!("{result}\n").as_bytes()).unwrap();
+        let usage = usage::agent_usage(tmp.path(), "agent-1");
+        assert_eq!(usage.input_tokens, 2000);
+        assert!((usage.cost_usd - 1.0).abs() < 1e-9);
+
+        remove_logs(&agent_dir).unwrap();
+        assert!(usage::agent_usage(tmp.path(), "agent-1").is_empty());
+    }
+
+    #[test]
+    fn reader_follows_the_log_across_rotation() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let raw = tmp.path().join(RAW_LOG);
+        let clean = tmp.path().join(CLEAN_LOG);
+
+        let mut sink = LogSink::with_max_bytes(&raw, 100).unwrap();
+        let mut cursor = Cursor::default();
+        sink.write(b"one\ntwo\n").unwrap();
+        assert_eq!(read_new(&clean, &mut cursor).unwrap(), vec!["one", "two"]);
+        assert!(read_new(&clean, &mut cursor).unwrap().is_empty());
+
+        // The fourth line doesn't fit, so it starts a new log
+        sink.write(b"three\nfour\n").unwrap();
+        assert!(rotated(&clean).exists());
+        assert_eq!(
+            read_new(&clean, &mut cursor).unwrap(),
+            vec!["three", "four"]
+        );
+
+        // A new session replaces the logs
+        remove_logs(tmp.path()).unwrap();
+        let mut sink = LogSink::with_max_bytes(&raw, 120).unwrap();
+        sink.write(b"fresh\n").unwrap();
+        assert_eq!(read_new(&clean, &mut cursor).unwrap(), vec!["fresh"]);
+    }
+}
@@ -0,0 +1,554 @@
+use crate::checks::{self, CheckResult};
+use crate::{Grotto, GrottoError, Result, git};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Which agent branches to merge, and in what order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MergeOrder {
+    /// Every agent with a branch, in agent-number order
+    Auto,
+    /// Explicit list, e.g. `agent-1,agent-3`
+    Agents(Vec<String>),
+}
+
+impl std::str::FromStr for MergeOrder {
+    type Err = GrottoError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if s.trim() == "auto" {
+            return Ok(MergeOrder::Auto);
+        }
+        let agents: Vec<String> = s
+            .split(',')
+            .map(|a| a.trim().to_string())
+            .filter(|a| !a.is_empty())
+            .collect();
+        if agents.is_empty() {
+            return Err(GrottoError::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "Merge order must be 'auto' or a comma-separated list of agent IDs",
+            )));
+        }
+        Ok(MergeOrder::Agents(agents))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepStatus {
+    /// Branch merged and all checks passed
+    Merged,
+    /// Branch had no commits that weren't already in the target
+    UpToDate,
+    /// `git merge` hit conflicts; the merge was aborted
+    Conflict,
+    /// A check failed after merging; the target was reset to before the merge
+    CheckFailed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergeStep {
+    pub agent_id: String,
+    pub branch: String,
+    pub status: StepStatus,
+    /// Commits on the branch that weren't in the target yet
+    pub commits: usize,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub conflicts: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub checks: Vec<CheckResult>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergeReport {
+    pub into: String,
+    pub checks: Vec<String>,
+    pub steps: Vec<MergeStep>,
+}
+
+impl MergeReport {
+    /// The step that stopped the pipeline, if any.
+    pub fn failed_step(&self) -> Option<&MergeStep> {
+        self.steps
+            .iter()
+            .find(|s| matches!(s.status, StepStatus::Conflict | StepStatus::CheckFailed))
+    }
+
+    pub fn success(&self) -> bool {
+        self.failed_step().is_none()
+    }
+
+    /// Render the report as the body of the `summary.md` merge section.
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        out.push_str(&format!("- Target: `{}`\n", self.into));
+        match self.failed_step() {
+            None => out.push_str("- Result: ✅ all branches merged\n"),
+            Some(step) => out.push_str(&format!(
+                "- Result: ❌ stopped at {} ({})\n",
+                step.agent_id,
+                status_label(&step.status)
+            )),
+        }
+        if self.checks.is_empty() {
+            out.push_str("- Checks: none configured\n");
+        } else {
+            let checks: Vec<String> = self.checks.iter().map(|c| format!("`{}`", c)).collect();
+            out.push_str(&format!("- Checks: {}\n", checks.join(", ")));
+        }
+        out.push('\n');
+
+        for step in &self.steps {
+            out.push_str(&format!(
+                "- {} `{}` — {} ({} commit{})\n",
+                step.agent_id,
+                step.branch,
+                status_label(&step.status),
+                step.commits,
+                if step.commits == 1 { "" } else { "s" }
+            ));
+            for file in &step.conflicts {
+                out.push_str(&format!("   - conflict: `{}`\n", file));
+            }
+            if let Some(failed) = step.checks.iter().find(|c| !c.success) {
+                out.push_str(&format!(
+                    "   - `{}` exited with {}\n\n```\n{}\n```\n",
+                    failed.command,
+                    failed
+                        .exit_code
+                        .map(|c| c.to_string())
+                        .unwrap_or_else(|| "no exit code".to_string()),
+                    failed.output
+                ));
+            }
+        }
+
+        out
+    }
+}
+
+fn status_label(status: &StepStatus) -> &'static str {
+    match status {
+        StepStatus::Merged => "merged",
+        StepStatus::UpToDate => "up to date",
+        StepStatus::Conflict => "conflict",
+        StepStatus::CheckFailed => "checks failed",
+    }
+}
+
+/// Resolve the `(agent_id, branch)` pairs to merge.
+pub fn resolve_branches(grotto: &Grotto, order: &MergeOrder) -> Result<Vec<(String, String)>> {
+    let repo_dir = &grotto.config.project_dir;
+    let branch_for = |agent_id: &str| -> Option<String> {
+        let agent = grotto.agents.get(agent_id)?;
+        if let Some(branch) = &agent.branch {
+            return Some(branch.clone());
+        }
+        let session_id = grotto.config.session_id.as_deref()?;
+        let branch = git::agent_branch(session_id, agent_id);
+        git::branch_exists(repo_dir, &branch).then_some(branch)
+    };
+
+    match order {
+        MergeOrder::Auto => Ok(grotto
+            .sorted_agent_ids()
+            .into_iter()
+            .filter_map(|id| branch_for(&id).map(|b| (id, b)))
+            .collect()),
+        MergeOrder::Agents(ids) => ids
+            .iter()
+            .map(|id| {
+                if !grotto.agents.contains_key(id) {
+                    return Err(GrottoError::AgentNotFound(id.clone()));
+                }
+                branch_for(id).map(|b| (id.clone(), b)).ok_or_else(|| {
+                    GrottoError::Git(format!(
+                        "{} has no branch (was the session spawned with --isolate worktree?)",
+                        id
+                    ))
+                })
+            })
+            .collect(),
+    }
+}
+
+/// Merge each agent branch into `into` (default: the current branch), running
+/// `checks` after every merge. Stops at the first conflict or failing check,
+/// leaving the target at the last good merge. Logs `merge_*` events. The
+/// branch that was checked out beforehand is checked out again afterwards,
+/// whether or not the merge succeeded.
+pub fn run(
+    grotto: &Grotto,
+    into: Option<&str>,
+    order: &MergeOrder,
+    checks: &[String],
+) -> Result<MergeReport> {
+    let repo_dir = grotto.config.project_dir.as_path();
+    if !git::is_repo(repo_dir) {
+        return Err(GrottoError::Git(format!(
+            "not a git repository: {}",
+            repo_dir.display()
+        )));
+    }
+
+    let dirty = git::run(repo_dir, &["status", "--porcelain", "--untracked-files=no"])?;
+    if !dirty.is_empty() {
+        return Err(GrottoError::Git(
+            "working tree has uncommitted changes; commit or stash them before merging".into(),
+        ));
+    }
+
+    let branches = resolve_branches(grotto, order)?;
+    if branches.is_empty() {
+        return Err(GrottoError::Git(
+            "no agent branches to merge (spawn with --isolate worktree)".into(),
+        ));
+    }
+
+    // Where to go back to afterwards: the branch, or the commit when detached
+    let original = match git::run(repo_dir, &["rev-parse", "--abbrev-ref", "HEAD"])? {
+        head if head == "HEAD" => git::run(repo_dir, &["rev-parse", "HEAD"])?,
+        head => head,
+    };
+    let into = match into {
+        Some(branch) if branch != original => branch.to_string(),
+        _ => return merge_branches(grotto, repo_dir, original, branches, checks),
+    };
+
+    git::run(repo_dir, &["checkout", "--quiet", &into])?;
+    let result = merge_branches(grotto, repo_dir, into, branches, checks);
+    let restored = git::run(repo_dir, &["checkout", "--quiet", &original]);
+    let report = result?;
+    restored?;
+    Ok(report)
+}
+
+/// Merge `branches` one by one into the checked-out `into` branch.
+fn merge_branches(
+    grotto: &Grotto,
+    repo_dir: &Path,
+    into: String,
+    branches: Vec<(String, String)>,
+    checks: &[String],
+) -> Result<MergeReport> {
+    grotto.log_event(
+        "merge_started",
+        None,
+        None,
+        Some(&format!(
+            "Merging {} branch(es) into {}",
+            branches.len(),
+            into
+        )),
+        serde_json::json!({
+            "into": into,
+            "branches": branches.iter().map(|(_, b)| b).collect::<Vec<_>>(),
+            "checks": checks,
+        }),
+    )?;
+
+    let mut report = MergeReport {
+        into: into.clone(),
+        checks: checks.to_vec(),
+        steps: Vec::new(),
+    };
+
+    for (agent_id, branch) in branches {
+        let step = merge_one(grotto, repo_dir, &agent_id, &branch, checks)?;
+        let stop = matches!(step.status, StepStatus::Conflict | StepStatus::CheckFailed);
+        report.steps.push(step);
+        if stop {
+            break;
+        }
+    }
+
+    let (event_type, message) = match report.failed_step() {
+        None => (
+            "merge_completed",
+            format!("Merged agent branches into {}", into),
+        ),
+        Some(step) => (
+            "merge_failed",
+            format!(
+                "Merge into {} stopped at {} ({})",
+                into,
+                step.agent_id,
+                status_label(&step.status)
+            ),
+        ),
+    };
+    grotto.log_event(
+        event_type,
+        None,
+        None,
+        Some(&message),
+        serde_json::json!({
+            "into": into,
+            "merged": report.steps.iter().filter(|s| s.status == StepStatus::Merged).count(),
+            "steps": report.steps.len(),
+        }),
+    )?;
+
+    Ok(report)
+}
+
+fn merge_one(
+    grotto: &Grotto,
+    repo_dir: &Path,
+    agent_id: &str,
+    branch: &str,
+    checks: &[String],
+) -> Result<MergeStep> {
+    let before = git::run(repo_dir, &["rev-parse", "HEAD"])?;
+    let commits: usize = git::run(
+        repo_dir,
+        &["rev-list", "--count", &format!("HEAD..{}", branch)],
+    )?
+    .parse()
+    .unwrap_or(0);
+
+    let mut step = MergeStep {
+        agent_id: agent_id.to_string(),
+        branch: branch.to_string(),
+        status: StepStatus::UpToDate,
+        commits,
+        conflicts: Vec::new(),
+        checks: Vec::new(),
+    };
+
+    if commits == 0 {
+        grotto.log_event(
+            "merge_skipped",
+            Some(agent_id),
+            None,
+            Some(&format!("{} has nothing new to merge", branch)),
+            serde_json::json!({ "branch": branch }),
+        )?;
+        return Ok(step);
+    }
+
+    let message = format!("Merge {} ({})", branch, agent_id);
+    if let Err(err) = git::run(repo_dir, &["merge", "--no-ff", "-m", &message, branch]) {
+        // Only unmerged paths make it a conflict; any other failure (e.g. an
+        // untracked file in the way) is an error, not a verdict on the branch
+        let conflicts =
+            git::run(repo_dir, &["diff", "--name-only", "--diff-filter=U"]).unwrap_or_default();
+        if conflicts.is_empty() {
+            return Err(err);
+        }
+        step.conflicts = conflicts.lines().map(|l| l.to_string()).collect();
+        let _ = git::run(repo_dir, &["merge", "--abort"]);
+        step.status = StepStatus::Conflict;
+
+        grotto.log_event(
+            "merge_conflict",
+            Some(agent_id),
+            None,
+            Some(&format!("Conflict merging {}", branch)),
+            serde_json::json!({ "branch": branch, "files": step.conflicts }),
+        )?;
+        return Ok(step);
+    }
+
+    step.checks = checks::run_checks(repo_dir, checks);
+    if let Some(failed) = step.checks.iter().find(|c| !c.success) {
+        git::run(repo_dir, &["reset", "--hard", "--quiet", &before])?;
+        step.status = StepStatus::CheckFailed;
+
+        grotto.log_event(
+            "merge_check_failed",
+            Some(agent_id),
+            None,
+            Some(&format!(
+                "`{}` failed after merging {}",
+                failed.command, branch
+            )),
+            serde_json::json!({
+                "branch": branch,
+                "command": failed.command,
+                "exit_code": failed.exit_code,
+                "output": failed.output,
+            }),
+        )?;
+        return Ok(step);
+    }
+
+    step.status = StepStatus::Merged;
+    grotto.log_event(
+        "merge_step",
+        Some(agent_id),
+        None,
+        Some(&format!("Merged {} ({} commits)", branch, commits)),
+        serde_json::json!({ "branch": branch, "commits": commits }),
+    )?;
+    Ok(step)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::test_support::{commit_all, init_repo};
+    use std::fs;
+    use tempfile::TempDir;
+
+    /// Repo with an isolated 2-agent session.
+    fn setup() -> (TempDir, Grotto) {
+        let tmp = TempDir::new().unwrap();
+        init_repo(tmp.path());
+        let mut grotto = Grotto::new(tmp.path(), 2, "merge test".into()).unwrap();
+        grotto.create_worktrees().unwrap();
+        (tmp, grotto)
+    }
+
+    fn agent_commit(grotto: &Grotto, agent_id: &str, file: &str, content: &str) {
+        let wt = grotto.agent_workdir(agent_id);
+        fs::write(wt.join(file), content).unwrap();
+        commit_all(&wt, &format!("{} edits {}", agent_id, file));
+    }
+
+    #[test]
+    fn parse_merge_order() {
+        assert_eq!("auto".parse::<MergeOrder>().unwrap(), MergeOrder::Auto);
+        assert_eq!(
+            "agent-1, agent-3".parse::<MergeOrder>().unwrap(),
+            MergeOrder::Agents(vec!["agent-1".into(), "agent-3".into()])
+        );
+        assert!(",".parse::<MergeOrder>().is_err());
+    }
+
+    #[test]
+    fn resolve_branches_explicit_order_and_unknown_agent() {
+        let (_tmp, grotto) = setup();
+        let order = MergeOrder::Agents(vec!["agent-2".into(), "agent-1".into()]);
+        let branches = resolve_branches(&grotto, &order).unwrap();
+        assert_eq!(branches[0].0, "agent-2");
+        assert_eq!(branches[1].0, "agent-1");
+
+        let order = MergeOrder::Agents(vec!["agent-9".into()]);
+        assert!(resolve_branches(&grotto, &order).is_err());
+    }
+
+    #[test]
+    fn merges_all_branches_and_runs_checks() {
+        let (tmp, grotto) = setup();
+        agent_commit(&grotto, "agent-1", "a.txt", "a\n");
+        agent_commit(&grotto, "agent-2", "b.txt", "b\n");
+
+        let checks = vec!["test -f README.md".to_string()];
+        let report = run(&grotto, None, &MergeOrder::Auto, &checks).unwrap();
+
+        assert!(report.success());
+        assert_eq!(report.into, "main");
+        assert_eq!(report.steps.len(), 2);
+        assert!(report.steps.iter().all(|s| s.status == StepStatus::Merged));
+        assert_eq!(report.steps[0].checks.len(), 1);
+        assert!(tmp.path().join("a.txt").exists());
+        assert!(tmp.path().join("b.txt").exists());
+
+        let events = fs::read_to_string(tmp.path().join(".grotto/events.jsonl")).unwrap();
+        assert!(events.contains("merge_started"));
+        assert!(events.contains("merge_step"));
+        assert!(events.contains("merge_completed"));
+    }
+
+    #[test]
+    fn skips_branches_without_new_commits() {
+        let (_tmp, grotto) = setup();
+        agent_commit(&grotto, "agent-2", "b.txt", "b\n");
+
+        let report = run(&grotto, None, &MergeOrder::Auto, &[]).unwrap();
+        assert!(report.success());
+        assert_eq!(report.steps[0].status, StepStatus::UpToDate);
+        assert_eq!(report.steps[1].status, StepStatus::Merged);
+    }
+
+    #[test]
+    fn stops_on_conflict_and_aborts_merge() {
+        let (tmp, grotto) = setup();
+        agent_commit(&grotto, "agent-1", "README.md", "from agent 1\n");
+        agent_commit(&grotto, "agent-2", "README.md", "from agent 2\n");
+
+        let report = run(&grotto, None, &MergeOrder::Auto, &[]).unwrap();
+        assert!(!report.success());
+        let failed = report.failed_step().unwrap();
+        assert_eq!(failed.agent_id, "agent-2");
+        assert_eq!(failed.status, StepStatus::Conflict);
+        assert_eq!(failed.conflicts, vec!["README.md".to_string()]);
+
+        // agent-1's merge stays, the conflicting merge is aborted
+        let readme = fs::read_to_string(tmp.path().join("README.md")).unwrap();
+        assert_eq!(readme, "from agent 1\n");
+        let status = git::run(
+            tmp.path(),
+            &["status", "--porcelain", "--untracked-files=no"],
+        );
+        assert_eq!(status.unwrap(), "");
+
+        let md = report.to_markdown();
+        assert!(md.contains("stopped at agent-2 (conflict)"), "{}", md);
+        assert!(md.contains("conflict: `README.md`"), "{}", md);
+    }
+
+    #[test]
+    fn stops_on_failed_check_and_resets() {
+        let (tmp, grotto) = setup();
+        agent_commit(&grotto, "agent-1", "broken.txt", "x\n");
+        agent_commit(&grotto, "agent-2", "b.txt", "b\n");
+
+        let checks = vec!["test ! -f broken.txt || (echo broken; exit 1)".to_string()];
+        let report = run(&grotto, None, &MergeOrder::Auto, &checks).unwrap();
+
+        assert_eq!(report.steps.len(), 1);
+        let failed = report.failed_step().unwrap();
+        assert_eq!(failed.status, StepStatus::CheckFailed);
+        assert!(failed.checks[0].output.contains("broken"));
+        assert!(!tmp.path().join("broken.txt").exists());
+
+        let events = fs::read_to_string(tmp.path().join(".grotto/events.jsonl")).unwrap();
+        assert!(events.contains("merge_check_failed"));
+        assert!(events.contains("merge_failed"));
+    }
+
+    #[test]
+    fn merges_into_another_branch_and_returns_to_the_original() {
+        let (tmp, grotto) = setup();
+        git::run(tmp.path(), &["branch", "release"]).unwrap();
+        agent_commit(&grotto, "agent-1", "a.txt", "a\n");
+
+        let report = run(&grotto, Some("release"), &MergeOrder::Auto, &[]).unwrap();
+        assert!(report.success());
+        assert_eq!(report.into, "release");
+        let head = git::run(tmp.path(), &["rev-parse", "--abbrev-ref", "HEAD"]).unwrap();
+        assert_eq!(head, "main");
+        assert!(!tmp.path().join("a.txt").exists());
+        let merged = git::run(tmp.path(), &["ls-tree", "--name-only", "release"]).unwrap();
+        assert!(merged.lines().any(|f| f == "a.txt"), "{}", merged);
+    }
+
+    #[test]
+    fn merge_failure_without_conflicts_is_an_error_and_restores_head() {
+        let (tmp, grotto) = setup();
+        git::run(tmp.path(), &["branch", "release"]).unwrap();
+        agent_commit(&grotto, "agent-1", "a.txt", "a\n");
+        // Untracked file the merge would overwrite: git refuses, no conflicts
+        fs::write(tmp.path().join("a.txt"), "local\n").unwrap();
+
+        let err = run(&grotto, Some("release"), &MergeOrder::Auto, &[]).unwrap_err();
+        assert!(matches!(err, GrottoError::Git(_)), "{}", err);
+        let head = git::run(tmp.path(), &["rev-parse", "--abbrev-ref", "HEAD"]).unwrap();
+        assert_eq!(head, "main");
+        assert_eq!(
+            fs::read_to_string(tmp.path().join("a.txt")).unwrap(),
+            "local\n"
+        );
+    }
+
+    #[test]
+    fn refuses_dirty_working_tree() {
+        let (tmp, grotto) = setup();
+        fs::write(tmp.path().join("README.md"), "dirty\n").unwrap();
+        let err = run(&grotto, None, &MergeOrder::Auto, &[]).unwrap_err();
+        assert!(err.to_string().contains("uncommitted changes"));
+    }
+}
@@ -1,8 +1,14 @@
+use crate::detect::PhaseRules;
+use crate::mux::Multiplexer;
+use crate::{AgentPane, Backend, Grotto, Result, headless};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::fmt;
-use std::process::Command;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
 
-/// Real-time phase of a Claude Code agent, inferred from tmux pane output.
+/// Real-time phase of an agent, inferred from tmux pane output.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum AgentPhase {
@@ -12,6 +18,8 @@ pub enum AgentPhase {
     Running,
     Idle,
     Finished,
+    /// Hit an API rate limit or overload; see [`crate::backoff`]
+    RateLimited,
     Error,
 }
 
@@ -24,6 +32,7 @@ impl fmt::Display for AgentPhase {
             AgentPhase::Running => write!(f, "running"),
             AgentPhase::Idle => write!(f, "idle"),
             AgentPhase::Finished => write!(f, "finished"),
+            AgentPhase::RateLimited => write!(f, "rate_limited"),
             AgentPhase::Error => write!(f, "error"),
         }
     }
@@ -43,160 +52,201 @@ pub struct PaneSnapshot {
     pub timestamp: chrono::DateTime<chrono::Utc>,
 }
 
-/// Capture raw text from a tmux pane.
-///
-/// Runs `tmux capture-pane -t {session}:0.{pane} -p -S -50` and returns the
-/// output. Returns `None` if the pane doesn't exist or tmux isn't available.
-pub fn capture_pane(session_name: &str, pane_index: usize) -> Option<String> {
-    let target = format!("{}:0.{}", session_name, pane_index);
-    let output = Command::new("tmux")
-        .args(["capture-pane", "-t", &target, "-p", "-S", "-50"])
-        .output()
-        .ok()?;
-
-    if !output.status.success() {
-        return None;
+/// A change in an agent's phase, as observed by the monitor.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PhaseTransition {
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub phase: AgentPhase,
+    /// Last non-empty pane line when the change was seen
+    #[serde(default)]
+    pub last_activity: String,
+}
+
+impl PhaseTransition {
+    pub fn from_snapshot(snapshot: &PaneSnapshot) -> Self {
+        Self {
+            timestamp: snapshot.timestamp,
+            phase: snapshot.phase.clone(),
+            last_activity: snapshot.last_activity_line.clone(),
+        }
     }
+}
 
-    Some(String::from_utf8_lossy(&output.stdout).to_string())
+fn phases_path(grotto_dir: &Path, agent_id: &str) -> std::path::PathBuf {
+    grotto_dir
+        .join("agents")
+        .join(agent_id)
+        .join("phases.jsonl")
 }
 
-/// Infer the current agent phase from captured pane content.
-///
-/// Pattern-matches on Claude Code output conventions to determine what the
-/// agent is currently doing.
-pub fn infer_phase(content: &str) -> AgentPhase {
-    if content.trim().is_empty() {
-        return AgentPhase::Starting;
+/// Append a phase transition to `.grotto/agents/<id>/phases.jsonl`.
+pub fn record_phase_transition(
+    grotto_dir: &Path,
+    agent_id: &str,
+    transition: &PhaseTransition,
+) -> std::io::Result<()> {
+    let path = phases_path(grotto_dir, agent_id);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
     }
+    let line = serde_json::to_string(transition)? + "\n";
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    file.write_all(line.as_bytes())
+}
 
-    // Work with the last ~20 meaningful lines for recency-sensitive checks
-    let lines: Vec<&str> = content.lines().collect();
-    let recent: Vec<&str> = lines
-        .iter()
-        .rev()
-        .filter(|l| !l.trim().is_empty())
-        .take(20)
-        .copied()
-        .collect();
-
-    if recent.is_empty() {
-        return AgentPhase::Starting;
-    }
+/// Read an agent's recorded phase transitions, oldest first.
+pub fn read_phase_transitions(grotto_dir: &Path, agent_id: &str) -> Vec<PhaseTransition> {
+    fs::read_to_string(phases_path(grotto_dir, agent_id))
+        .map(|content| {
+            content
+                .lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
 
-    let last_line = recent[0];
-    // Join recent lines for broad pattern scanning
-    let recent_text: String = recent.iter().copied().collect::<Vec<_>>().join("\n");
-
-    // --- Finished detection ---
-    // Pane capture returning only whitespace / very short content after agent ran
-    if last_line.contains("/exit")
-        || last_line.contains("exited")
-        || recent_text.contains("Process exited")
-        || recent_text.contains("session ended")
-        || recent_text.contains("has been completed")
-    {
-        return AgentPhase::Finished;
-    }
+/// Phase transitions of every agent on the team since the session was
+/// spawned, oldest first. Earlier sessions in the same project are left out.
+pub fn session_timeline(grotto: &Grotto) -> Result<BTreeMap<String, Vec<PhaseTransition>>> {
+    let started_at = grotto.read_session_events()?.first().map(|e| e.timestamp);
+    Ok(grotto
+        .sorted_agent_ids()
+        .into_iter()
+        .map(|id| {
+            let recorded = read_phase_transitions(&grotto.grotto_dir, &id)
+                .into_iter()
+                .filter(|t| started_at.is_none_or(|start| t.timestamp >= start))
+                .collect();
+            (id, recorded)
+        })
+        .collect())
+}
 
-    // --- Error detection ---
-    for pattern in &[
-        "Error:",
-        "error:",
-        "rate limit",
-        "Rate limit",
-        "APIError",
-        "API error",
-        "panic",
-        "PANIC",
-        "fatal:",
-        "FATAL",
-        "overloaded",
-    ] {
-        // Only check recent lines for errors (not the entire history)
-        if recent.iter().take(5).any(|l| l.contains(pattern)) {
-            return AgentPhase::Error;
-        }
-    }
+/// Writes each agent's phase changes to its `phases.jsonl`. An agent's last
+/// phase is read from the file the first time it is seen, so a restarted
+/// monitor doesn't record the same phase twice in a row.
+#[derive(Debug, Default)]
+pub struct PhaseRecorder {
+    last: HashMap<String, Option<AgentPhase>>,
+}
 
-    // --- Thinking detection ---
-    if last_line.contains("Thinking")
-        || last_line.contains("thinking")
-        || last_line.contains("⏳")
-        || last_line.contains("◐")
-        || last_line.contains("◓")
-        || last_line.contains("◑")
-        || last_line.contains("◒")
-        || last_line.contains("⠋")
-        || last_line.contains("⠙")
-        || last_line.contains("⠹")
-        || last_line.contains("⠸")
-        || last_line.contains("⠼")
-        || last_line.contains("⠴")
-        || last_line.contains("⠦")
-        || last_line.contains("⠧")
-        || last_line.contains("⠇")
-        || last_line.contains("⠏")
-    {
-        return AgentPhase::Thinking;
+impl PhaseRecorder {
+    pub fn new() -> Self {
+        Self::default()
     }
 
-    // --- Editing detection ---
-    // Look for file operation indicators in recent lines
-    let edit_patterns = [
-        "Write(",
-        "Edit(",
-        "Created ",
-        "Updated ",
-        "Wrote ",
-        "wrote ",
-        "editing",
-        "Creating ",
-        "Modified ",
-    ];
-    for line in recent.iter().take(5) {
-        for pat in &edit_patterns {
-            if line.contains(pat) {
-                return AgentPhase::Editing;
+    /// Record every snapshot whose phase differs from its agent's last
+    /// recorded phase, and return those snapshots.
+    pub fn record<'a>(
+        &mut self,
+        grotto_dir: &Path,
+        snapshots: &'a [PaneSnapshot],
+    ) -> Vec<&'a PaneSnapshot> {
+        let mut changed = Vec::new();
+        for snap in snapshots {
+            let last = self.last.entry(snap.agent_id.clone()).or_insert_with(|| {
+                read_phase_transitions(grotto_dir, &snap.agent_id)
+                    .pop()
+                    .map(|t| t.phase)
+            });
+            if last.as_ref() == Some(&snap.phase) {
+                continue;
             }
+            *last = Some(snap.phase.clone());
+            let _ = record_phase_transition(
+                grotto_dir,
+                &snap.agent_id,
+                &PhaseTransition::from_snapshot(snap),
+            );
+            changed.push(snap);
         }
+        changed
     }
+}
 
-    // --- Running detection ---
-    // Command execution indicators
-    let run_patterns = ["$ ", "Running", "running", "Bash(", "bash("];
-    for line in recent.iter().take(5) {
-        for pat in &run_patterns {
-            if line.contains(pat) {
-                return AgentPhase::Running;
+/// Remove ANSI escape sequences (colors, cursor movement, titles) from terminal output.
+pub fn strip_ansi(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            // CSI: parameters up to a final byte in @..~
+            Some('[') => {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
             }
+            // OSC: up to BEL or ESC \
+            Some(']') => {
+                while let Some(c) = chars.next() {
+                    if c == '\x07' {
+                        break;
+                    }
+                    if c == '\x1b' {
+                        if chars.peek() == Some(&'\\') {
+                            chars.next();
+                        }
+                        break;
+                    }
+                }
+            }
+            _ => {}
         }
     }
+    out
+}
 
-    // --- Idle detection ---
-    // Prompt-like endings suggest the agent is waiting for input
-    let trimmed = last_line.trim();
-    if trimmed.ends_with('>')
-        || trimmed.ends_with('$')
-        || trimmed.ends_with('❯')
-        || trimmed.ends_with('%')
-        || trimmed.ends_with("claude>")
-    {
-        return AgentPhase::Idle;
-    }
+/// Lines of history captured along with each pane's screen.
+pub const CAPTURE_SCROLLBACK: usize = 50;
+
+/// Infer the current agent phase from captured pane content.
+///
+/// Uses the built-in rules in [`crate::detect`], written for Claude Code's
+/// output. Agents whose runtime has its own rules file are read with
+/// `Grotto::infer_agent_phase` instead.
+pub fn infer_phase(content: &str) -> AgentPhase {
+    PhaseRules::built_in().infer(content)
+}
 
-    // Default: still starting up
-    AgentPhase::Starting
+/// Whether a line of agent output reports a rate limit or an overloaded API.
+pub fn is_rate_limit_line(line: &str) -> bool {
+    let line = line.to_lowercase();
+    [
+        "rate limit",
+        "rate_limit",
+        "overloaded",
+        "too many requests",
+        "usage limit",
+    ]
+    .iter()
+    .any(|pattern| line.contains(pattern))
 }
 
-/// Capture snapshots for all agents in a tmux session.
-pub fn capture_all_agents(session_name: &str, agent_count: usize) -> Vec<PaneSnapshot> {
-    let mut snapshots = Vec::with_capacity(agent_count);
+/// Capture snapshots of the given agent panes (see `Grotto::agent_panes`).
+pub fn capture_all_agents(mux: &dyn Multiplexer, agents: &[AgentPane]) -> Vec<PaneSnapshot> {
+    snapshot_agents(agents, |pane| mux.capture(&pane.target, CAPTURE_SCROLLBACK))
+}
+
+/// Snapshots of `agents`, reading each one's screen with `capture`.
+fn snapshot_agents(
+    agents: &[AgentPane],
+    capture: impl Fn(&AgentPane) -> Option<String>,
+) -> Vec<PaneSnapshot> {
+    let mut snapshots = Vec::with_capacity(agents.len());
     let now = chrono::Utc::now();
 
-    for i in 0..agent_count {
-        let agent_id = format!("agent-{}", i + 1);
-        let (raw_content, phase, last_activity_line) = match capture_pane(session_name, i) {
+    for pane in agents {
+        let (raw_content, phase, last_activity_line) = match capture(pane) {
             Some(content) => {
                 let phase = infer_phase(&content);
                 let last_line = content
@@ -214,8 +264,8 @@ pub fn capture_all_agents(session_name: &str, agent_count: usize) -> Vec<PaneSna
         };
 
         snapshots.push(PaneSnapshot {
-            agent_id,
-            pane_index: i,
+            agent_id: pane.agent_id.clone(),
+            pane_index: pane.pane_index,
             raw_content,
             phase,
             last_activity_line,
@@ -226,10 +276,89 @@ pub fn capture_all_agents(session_name: &str, agent_count: usize) -> Vec<PaneSna
     snapshots
 }
 
+/// Capture every agent on the team, from its tmux pane or the headless
+/// runner, reading each screen with the agent's runtime.
+pub fn capture_team(grotto: &Grotto, mux: &dyn Multiplexer) -> Vec<PaneSnapshot> {
+    let panes = grotto.agent_panes();
+    let mut snapshots = match grotto.config.backend {
+        Backend::Tmux => capture_all_agents(mux, &panes),
+        Backend::Headless => snapshot_agents(&panes, |pane| {
+            headless::capture(&grotto.grotto_dir, &pane.agent_id)
+        }),
+    };
+    for snap in &mut snapshots {
+        if !snap.raw_content.is_empty() {
+            snap.phase = grotto.infer_agent_phase(&snap.agent_id, &snap.raw_content);
+        }
+    }
+    snapshots
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn strip_ansi_removes_escape_sequences() {
+        assert_eq!(strip_ansi("\x1b[1;32m● Done\x1b[0m"), "● Done");
+        assert_eq!(strip_ansi("\x1b]0;title\x07$ ls"), "$ ls");
+        assert_eq!(strip_ansi("\x1b[?25lhidden cursor"), "hidden cursor");
+        assert_eq!(strip_ansi("plain"), "plain");
+    }
+
+    #[test]
+    fn phase_transitions_round_trip() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        assert!(read_phase_transitions(tmp.path(), "agent-1").is_empty());
+
+        let thinking = PhaseTransition {
+            timestamp: chrono::Utc::now(),
+            phase: AgentPhase::Thinking,
+            last_activity: "◐ Thinking".to_string(),
+        };
+        let idle = PhaseTransition {
+            phase: AgentPhase::Idle,
+            last_activity: "claude>".to_string(),
+            ..thinking.clone()
+        };
+        record_phase_transition(tmp.path(), "agent-1", &thinking).unwrap();
+        record_phase_transition(tmp.path(), "agent-1", &idle).unwrap();
+
+        let read = read_phase_transitions(tmp.path(), "agent-1");
+        assert_eq!(read, vec![thinking, idle]);
+        assert!(tmp.path().join("agents/agent-1/phases.jsonl").exists());
+    }
+
+    #[test]
+    fn phase_recorder_skips_phases_already_on_file() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let snap = |phase: AgentPhase| PaneSnapshot {
+            agent_id: "agent-1".into(),
+            pane_index: 0,
+            raw_content: "x".into(),
+            phase,
+            last_activity_line: "x".into(),
+            timestamp: chrono::Utc::now(),
+        };
+
+        let mut recorder = PhaseRecorder::new();
+        let thinking = [snap(AgentPhase::Thinking)];
+        assert_eq!(recorder.record(tmp.path(), &thinking).len(), 1);
+        assert!(recorder.record(tmp.path(), &thinking).is_empty());
+
+        // A restarted monitor picks up where the file left off
+        let mut restarted = PhaseRecorder::new();
+        assert!(restarted.record(tmp.path(), &thinking).is_empty());
+        let idle = [snap(AgentPhase::Idle)];
+        assert_eq!(restarted.record(tmp.path(), &idle).len(), 1);
+
+        let phases: Vec<AgentPhase> = read_phase_transitions(tmp.path(), "agent-1")
+            .into_iter()
+            .map(|t| t.phase)
+            .collect();
+        assert_eq!(phases, [AgentPhase::Thinking, AgentPhase::Idle]);
+    }
+
     #[test]
     fn phase_display() {
         assert_eq!(AgentPhase::Thinking.to_string(), "thinking");
@@ -237,6 +366,7 @@ mod tests {
         assert_eq!(AgentPhase::Running.to_string(), "running");
         assert_eq!(AgentPhase::Finished.to_string(), "finished");
         assert_eq!(AgentPhase::Error.to_string(), "error");
+        assert_eq!(AgentPhase::RateLimited.to_string(), "rate_limited");
         assert_eq!(AgentPhase::Idle.to_string(), "idle");
         assert_eq!(AgentPhase::Starting.to_string(), "starting");
     }
@@ -293,11 +423,29 @@ mod tests {
         let content = "working...\nError: connection refused";
         assert_eq!(infer_phase(content), AgentPhase::Error);
 
-        let content = "working...\nrate limit exceeded";
+        let content = "working...\nAPIError: 500";
         assert_eq!(infer_phase(content), AgentPhase::Error);
 
-        let content = "working...\nAPIError: 500";
+        let content = "thread 'main' panicked at src/main.rs:4:5";
         assert_eq!(infer_phase(content), AgentPhase::Error);
+
+        // Mentioning an error is not one
+        let content = "No need to panic: the error handling is fine\n❯";
+        assert_eq!(infer_phase(content), AgentPhase::Idle);
+    }
+
+    #[test]
+    fn infer_rate_limited() {
+        let content = "working...\nrate limit exceeded";
+        assert_eq!(infer_phase(content), AgentPhase::RateLimited);
+
+        let content = "working...\nAPI Error: 529 {\"type\":\"overloaded_error\"}";
+        assert_eq!(infer_phase(content), AgentPhase::RateLimited);
+
+        let content = "Claude usage limit reached. Your limit will reset at 5pm";
+        assert_eq!(infer_phase(content), AgentPhase::RateLimited);
+
+        assert!(!is_rate_limit_line("Refactored the limiter"));
     }
 
     #[test]
@@ -307,11 +455,17 @@ mod tests {
             infer_phase("Process exited with code 0"),
             AgentPhase::Finished
         );
+        // The last line `grotto stream-view` shows for a Claude Code run
+        assert_eq!(
+            infer_phase("All tests pass.\nFinished: success after 12 turn(s)"),
+            AgentPhase::Finished
+        );
     }
 
     #[test]
     fn infer_idle() {
-        assert_eq!(infer_phase("ready\n$ "), AgentPhase::Running);
+        assert_eq!(infer_phase("ready\n$ "), AgentPhase::Idle);
+        assert_eq!(infer_phase("ready\nuser@host:~/app$"), AgentPhase::Idle);
         assert_eq!(infer_phase("ready\nclaude>"), AgentPhase::Idle);
         assert_eq!(infer_phase("ready\n❯"), AgentPhase::Idle);
     }
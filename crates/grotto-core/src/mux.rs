@@ -0,0 +1,752 @@
+use crate::headless;
+use crate::{AgentLaunch, AgentPane, AgentState, Backend, Grotto, GrottoError, Result};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Mutex;
+
+/// A pane as reported by `tmux list-panes`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaneStatus {
+    /// Stable pane ID (`%3`); `None` for backends without one
+    pub pane_id: Option<String>,
+    pub pane_index: usize,
+    /// The process exited and `remain-on-exit` kept the pane
+    pub dead: bool,
+    pub exit_status: Option<i32>,
+}
+
+impl PaneStatus {
+    /// Whether this is `agent`'s pane: by ID when spawn recorded one, else by
+    /// index.
+    pub fn belongs_to(&self, agent: &AgentState) -> bool {
+        match &agent.pane_id {
+            Some(id) => self.pane_id.as_ref() == Some(id),
+            None => self.pane_index == agent.pane_index,
+        }
+    }
+}
+
+/// Format passed to `tmux list-panes -F`, read back by [`parse_panes`].
+pub const LIST_PANES_FORMAT: &str = "#{pane_id} #{pane_index} #{pane_dead} #{pane_dead_status}";
+
+/// Parse `tmux list-panes -F` output in [`LIST_PANES_FORMAT`].
+pub fn parse_panes(output: &str) -> Vec<PaneStatus> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let pane_id = fields.next()?.to_string();
+            let pane_index = fields.next()?.parse().ok()?;
+            let dead = fields.next()? == "1";
+            let exit_status = fields.next().and_then(|s| s.parse().ok());
+            Some(PaneStatus {
+                pane_id: Some(pane_id),
+                pane_index,
+                dead,
+                exit_status,
+            })
+        })
+        .collect()
+}
+
+/// A pane just opened by [`Multiplexer::new_session`] or [`Multiplexer::split`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NewPane {
+    /// Stable pane ID, when the multiplexer reported one
+    pub pane_id: Option<String>,
+    /// Anything else the multiplexer printed, which can explain an agent that
+    /// dies during startup
+    pub output: String,
+}
+
+/// The terminal multiplexer agents run in: a session with one window of
+/// panes.
+///
+/// Panes are addressed by target: a stable pane ID (`%3`) or, for teams
+/// spawned before IDs were recorded, `session:0.index`. See
+/// [`Grotto::pane_target`].
+///
+/// [`Tmux`] drives the real thing; [`FakeMultiplexer`] keeps panes in memory
+/// so orchestration can be tested without tmux.
+pub trait Multiplexer: Send + Sync {
+    /// Start `session` detached, running `launch` in its first pane.
+    fn new_session(&self, session: &str, launch: &AgentLaunch) -> Result<NewPane>;
+
+    /// Split pane `after` to run `launch` in a new pane that takes the next
+    /// index, retiling so every pane has room.
+    fn split(&self, after: &str, launch: &AgentLaunch) -> Result<NewPane>;
+
+    /// Replace whatever runs in a pane with `launch`.
+    fn respawn(&self, pane: &str, launch: &AgentLaunch) -> Result<()>;
+
+    /// Type into a pane. Each key is text or a tmux key name (`Enter`,
+    /// `Escape`, `C-c`).
+    fn send_keys(&self, pane: &str, keys: &[String]) -> Result<()>;
+
+    /// Type `text` into a pane and press Enter.
+    fn send_text(&self, pane: &str, text: &str) -> Result<()> {
+        self.send_keys(pane, &[text.to_string(), "Enter".to_string()])
+    }
+
+    /// Paste `text` into a pane without pressing Enter: literally, as one
+    /// bracketed paste if the program asked for that, so newlines, leading
+    /// dashes and key names like `Escape` arrive as text.
+    fn paste(&self, pane: &str, text: &str) -> Result<()>;
+
+    /// The pane's screen plus up to `scrollback` lines of history, or `None`
+    /// if the pane is gone.
+    fn capture(&self, pane: &str, scrollback: usize) -> Option<String>;
+
+    /// Send everything the pane prints through the log sink, which appends it
+    /// to the raw log at `path` and a cleaned log beside it, replacing any
+    /// earlier pipe.
+    fn pipe(&self, pane: &str, path: &Path) -> Result<()>;
+
+    /// Close a pane; later panes move down one index.
+    fn kill_pane(&self, pane: &str) -> Result<()>;
+
+    fn kill_session(&self, session: &str) -> Result<()>;
+
+    fn has_session(&self, session: &str) -> bool;
+
+    /// Panes of the agent window, or `None` if the session is gone.
+    fn list_panes(&self, session: &str) -> Option<Vec<PaneStatus>>;
+
+    /// One pane's status, or `None` if it was closed.
+    fn pane_status(&self, pane: &str) -> Option<PaneStatus>;
+
+    /// Fail with [`GrottoError::DeadPane`] unless the pane is open and its
+    /// process still running.
+    fn check_alive(&self, pane: &str) -> Result<()> {
+        match self.pane_status(pane) {
+            Some(status) if !status.dead => Ok(()),
+            Some(status) => Err(GrottoError::DeadPane(format!(
+                "{} exited with status {}",
+                pane,
+                crate::supervise::describe_status(status.exit_status)
+            ))),
+            None => Err(GrottoError::DeadPane(format!("{} was closed", pane))),
+        }
+    }
+
+    /// PID of the process running in a pane, if there is a real one.
+    fn pane_pid(&self, pane: &str) -> Option<u32>;
+
+    /// Keep panes open after their process exits, so their exit status can
+    /// be read and the agent relaunched in place.
+    fn keep_exited_panes(&self, session: &str) -> Result<()>;
+
+    /// Attach the current terminal to `session`.
+    fn attach(&self, session: &str) -> Result<()>;
+}
+
+/// The multiplexer that reaches a session's agents: `tmux` for a tmux
+/// session, its `grotto run` process ([`headless::Runner`]) for a headless
+/// one. Derefs to [`Multiplexer`].
+pub enum SessionMux<'a> {
+    Tmux(&'a dyn Multiplexer),
+    Headless(headless::Runner),
+}
+
+impl<'a> SessionMux<'a> {
+    pub fn new(grotto: &Grotto, tmux: &'a dyn Multiplexer) -> Self {
+        match grotto.config.backend {
+            Backend::Tmux => SessionMux::Tmux(tmux),
+            Backend::Headless => SessionMux::Headless(headless::Runner::new(grotto)),
+        }
+    }
+}
+
+impl<'a> std::ops::Deref for SessionMux<'a> {
+    type Target = dyn Multiplexer + 'a;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            SessionMux::Tmux(mux) => *mux,
+            SessionMux::Headless(runner) => runner,
+        }
+    }
+}
+
+/// The real tmux, run as a subprocess.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Tmux;
+
+impl Tmux {
+    /// Run `tmux <args>`, failing unless it exits successfully.
+    fn output(args: &[String]) -> Result<std::process::Output> {
+        let output = Command::new("tmux").args(args).output().map_err(|e| {
+            GrottoError::Multiplexer(format!("could not run tmux {}: {}", args[0], e))
+        })?;
+        if !output.status.success() {
+            return Err(GrottoError::Multiplexer(format!(
+                "tmux {} failed: {}",
+                args[0],
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(output)
+    }
+
+    /// Run `tmux <args>` and return its stdout.
+    fn run(args: &[String]) -> Result<String> {
+        let output = Self::output(args)?;
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+
+    /// Run a command given `-P -F '#{pane_id}'`: the new pane's ID is the
+    /// first line of stdout, and anything else printed is kept as output.
+    fn run_new_pane(args: &[String]) -> Result<NewPane> {
+        let output = Self::output(args)?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let mut lines = stdout.lines().peekable();
+        let pane_id = lines
+            .next_if(|line| line.starts_with('%'))
+            .map(|line| line.trim().to_string());
+        let printed: Vec<String> = [
+            lines.collect::<Vec<_>>().join("\n"),
+            String::from_utf8_lossy(&output.stderr).to_string(),
+        ]
+        .iter()
+        .map(|text| text.trim().to_string())
+        .filter(|text| !text.is_empty())
+        .collect();
+        Ok(NewPane {
+            pane_id,
+            output: printed.join("\n"),
+        })
+    }
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    fn select_tiled(target: &str) {
+        let _ = Self::run(&Self::args(&["select-layout", "-t", target, "tiled"]));
+    }
+}
+
+impl Multiplexer for Tmux {
+    fn new_session(&self, session: &str, launch: &AgentLaunch) -> Result<NewPane> {
+        let mut args = Self::args(&["new-session", "-d", "-P", "-F", "#{pane_id}", "-s", session]);
+        args.extend(launch.tmux_args());
+        Self::run_new_pane(&args)
+    }
+
+    fn split(&self, after: &str, launch: &AgentLaunch) -> Result<NewPane> {
+        // Tile first so the pane being split has room
+        Self::select_tiled(after);
+        let mut args = Self::args(&["split-window", "-P", "-F", "#{pane_id}", "-t", after]);
+        args.extend(launch.tmux_args());
+        let pane = Self::run_new_pane(&args)?;
+        Self::select_tiled(after);
+        Ok(pane)
+    }
+
+    fn respawn(&self, pane: &str, launch: &AgentLaunch) -> Result<()> {
+        let mut args = Self::args(&["respawn-pane", "-k", "-t", pane]);
+        args.extend(launch.tmux_args());
+        Self::run(&args).map(drop)
+    }
+
+    fn send_keys(&self, pane: &str, keys: &[String]) -> Result<()> {
+        let mut args = Self::args(&["send-keys", "-t", pane]);
+        args.extend(keys.iter().cloned());
+        Self::run(&args).map(drop)
+    }
+
+    fn paste(&self, pane: &str, text: &str) -> Result<()> {
+        // A buffer per process, so concurrent steers don't swap messages
+        let buffer = format!("grotto-{}", std::process::id());
+        Self::run(&Self::args(&["set-buffer", "-b", &buffer, "--", text]))?;
+        Self::run(&Self::args(&[
+            "paste-buffer",
+            "-p",
+            "-r",
+            "-d",
+            "-b",
+            &buffer,
+            "-t",
+            pane,
+        ]))
+        .map(drop)
+    }
+
+    fn capture(&self, pane: &str, scrollback: usize) -> Option<String> {
+        let mut args = Self::args(&["capture-pane", "-t", pane, "-p"]);
+        if scrollback > 0 {
+            args.extend(["-S".to_string(), format!("-{}", scrollback)]);
+        }
+        Self::run(&args).ok()
+    }
+
+    fn pipe(&self, pane: &str, path: &Path) -> Result<()> {
+        Self::run(&Self::args(&[
+            "pipe-pane",
+            "-t",
+            pane,
+            &crate::logsink::sink_command(path),
+        ]))
+        .map(drop)
+    }
+
+    fn kill_pane(&self, pane: &str) -> Result<()> {
+        Self::run(&Self::args(&["kill-pane", "-t", pane])).map(drop)
+    }
+
+    fn kill_session(&self, session: &str) -> Result<()> {
+        Self::run(&Self::args(&["kill-session", "-t", session])).map(drop)
+    }
+
+    fn has_session(&self, session: &str) -> bool {
+        Self::run(&Self::args(&["has-session", "-t", session])).is_ok()
+    }
+
+    fn list_panes(&self, session: &str) -> Option<Vec<PaneStatus>> {
+        let output = Self::run(&Self::args(&[
+            "list-panes",
+            "-t",
+            &format!("{}:0", session),
+            "-F",
+            LIST_PANES_FORMAT,
+        ]))
+        .ok()?;
+        Some(parse_panes(&output))
+    }
+
+    fn pane_status(&self, pane: &str) -> Option<PaneStatus> {
+        let output = Self::run(&Self::args(&[
+            "display-message",
+            "-p",
+            "-t",
+            pane,
+            LIST_PANES_FORMAT,
+        ]))
+        .ok()?;
+        parse_panes(&output).into_iter().next()
+    }
+
+    fn pane_pid(&self, pane: &str) -> Option<u32> {
+        Self::run(&Self::args(&[
+            "display-message",
+            "-p",
+            "-t",
+            pane,
+            "#{pane_pid}",
+        ]))
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+    }
+
+    fn keep_exited_panes(&self, session: &str) -> Result<()> {
+        Self::run(&Self::args(&[
+            "set-window-option",
+            "-t",
+            &format!("{}:0", session),
+            "remain-on-exit",
+            "on",
+        ]))
+        .map(drop)
+    }
+
+    fn attach(&self, session: &str) -> Result<()> {
+        let mut command = Command::new("tmux");
+        command.args(["attach-session", "-t", session]);
+
+        // Replace this process so the terminal belongs to tmux
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::CommandExt;
+            let error = command.exec();
+            Err(GrottoError::Multiplexer(format!(
+                "could not attach to tmux session {}: {}",
+                session, error
+            )))
+        }
+
+        #[cfg(not(unix))]
+        {
+            let status = command.status()?;
+            if status.success() {
+                Ok(())
+            } else {
+                Err(GrottoError::Multiplexer(format!(
+                    "could not attach to tmux session {}",
+                    session
+                )))
+            }
+        }
+    }
+}
+
+/// One pane of a [`FakeMultiplexer`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FakePane {
+    /// Stable ID, `%0` for the first pane the fake opened and so on
+    pub id: String,
+    /// Every launch in this pane, the current process last
+    pub launches: Vec<AgentLaunch>,
+    /// Each `send_keys` call, in order
+    pub sent: Vec<Vec<String>>,
+    /// Each `paste` call, in order; pasted text also shows on the screen
+    pub pasted: Vec<String>,
+    pub screen: String,
+    pub pipe: Option<PathBuf>,
+    pub dead: bool,
+    pub exit_status: Option<i32>,
+}
+
+#[derive(Debug, Default)]
+struct FakeSession {
+    panes: Vec<FakePane>,
+    keep_exited: bool,
+}
+
+#[derive(Debug, Default)]
+struct FakeState {
+    sessions: BTreeMap<String, FakeSession>,
+    next_id: usize,
+}
+
+impl FakeState {
+    fn open_pane(&mut self, launch: &AgentLaunch) -> FakePane {
+        let id = format!("%{}", self.next_id);
+        self.next_id += 1;
+        FakePane {
+            id,
+            launches: vec![launch.clone()],
+            sent: Vec::new(),
+            pasted: Vec::new(),
+            screen: String::new(),
+            pipe: None,
+            dead: false,
+            exit_status: None,
+        }
+    }
+
+    /// Session and index of a pane target, by ID or `session:0.index`.
+    fn locate(&self, target: &str) -> Option<(String, usize)> {
+        if target.starts_with('%') {
+            return self.sessions.iter().find_map(|(name, s)| {
+                let index = s.panes.iter().position(|p| p.id == target)?;
+                Some((name.clone(), index))
+            });
+        }
+        let (session, index) = target.rsplit_once(":0.")?;
+        let index = index.parse().ok()?;
+        self.sessions
+            .get(session)
+            .filter(|s| index < s.panes.len())
+            .map(|_| (session.to_string(), index))
+    }
+}
+
+fn no_pane(target: &str) -> GrottoError {
+    GrottoError::Multiplexer(format!("can't find pane: {}", target))
+}
+
+/// An in-memory multiplexer: records what each pane was told to run and
+/// type, and lets tests set screens and end processes.
+#[derive(Debug, Default)]
+pub struct FakeMultiplexer {
+    state: Mutex<FakeState>,
+}
+
+impl FakeMultiplexer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A fake already running `grotto`'s team in its tmux session, one pane
+    /// per active agent, with each agent's pane ID recorded as spawn does.
+    pub fn with_team(grotto: &mut Grotto) -> Self {
+        let mux = Self::new();
+        let session = grotto.config.tmux_session.clone();
+        let mut last: Option<String> = None;
+        for AgentPane { agent_id, .. } in grotto.agent_panes() {
+            let launch = grotto.agent_launch(&agent_id, grotto.generate_claude_prompt(&agent_id));
+            let opened = match &last {
+                None => mux.new_session(&session, &launch),
+                Some(after) => mux.split(after, &launch),
+            };
+            let pane_id = opened.ok().and_then(|p| p.pane_id);
+            let _ = grotto.set_pane_id(&agent_id, pane_id.clone());
+            last = pane_id;
+        }
+        mux
+    }
+
+    /// Panes of `session`, empty if it doesn't exist.
+    pub fn panes(&self, session: &str) -> Vec<FakePane> {
+        self.state
+            .lock()
+            .unwrap()
+            .sessions
+            .get(session)
+            .map(|s| s.panes.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn pane(&self, target: &str) -> Option<FakePane> {
+        self.with_pane(target, |p| p.clone()).ok()
+    }
+
+    /// What a pane shows from now on.
+    pub fn set_screen(&self, target: &str, screen: &str) {
+        let _ = self.with_pane(target, |p| p.screen = screen.to_string());
+    }
+
+    /// End a pane's process. The pane stays, dead, if exited panes are kept;
+    /// otherwise it closes like [`Multiplexer::kill_pane`].
+    pub fn exit(&self, target: &str, exit_status: i32) {
+        let keep = {
+            let state = self.state.lock().unwrap();
+            state
+                .locate(target)
+                .is_some_and(|(session, _)| state.sessions[&session].keep_exited)
+        };
+        if keep {
+            let _ = self.with_pane(target, |p| {
+                p.dead = true;
+                p.exit_status = Some(exit_status);
+            });
+        } else {
+            let _ = self.kill_pane(target);
+        }
+    }
+
+    fn with_pane<T>(&self, target: &str, f: impl FnOnce(&mut FakePane) -> T) -> Result<T> {
+        let mut state = self.state.lock().unwrap();
+        let (session, index) = state.locate(target).ok_or_else(|| no_pane(target))?;
+        let pane = &mut state.sessions.get_mut(&session).unwrap().panes[index];
+        Ok(f(pane))
+    }
+}
+
+fn fake_status(index: usize, pane: &FakePane) -> PaneStatus {
+    PaneStatus {
+        pane_id: Some(pane.id.clone()),
+        pane_index: index,
+        dead: pane.dead,
+        exit_status: pane.exit_status,
+    }
+}
+
+impl Multiplexer for FakeMultiplexer {
+    fn new_session(&self, session: &str, launch: &AgentLaunch) -> Result<NewPane> {
+        let mut state = self.state.lock().unwrap();
+        if state.sessions.contains_key(session) {
+            return Err(GrottoError::Multiplexer(format!(
+                "duplicate session: {}",
+                session
+            )));
+        }
+        let pane = state.open_pane(launch);
+        let pane_id = Some(pane.id.clone());
+        state.sessions.insert(
+            session.to_string(),
+            FakeSession {
+                panes: vec![pane],
+                keep_exited: false,
+            },
+        );
+        Ok(NewPane {
+            pane_id,
+            output: String::new(),
+        })
+    }
+
+    fn split(&self, after: &str, launch: &AgentLaunch) -> Result<NewPane> {
+        let mut state = self.state.lock().unwrap();
+        let (session, index) = state.locate(after).ok_or_else(|| no_pane(after))?;
+        let pane = state.open_pane(launch);
+        let pane_id = Some(pane.id.clone());
+        state
+            .sessions
+            .get_mut(&session)
+            .unwrap()
+            .panes
+            .insert(index + 1, pane);
+        Ok(NewPane {
+            pane_id,
+            output: String::new(),
+        })
+    }
+
+    fn respawn(&self, pane: &str, launch: &AgentLaunch) -> Result<()> {
+        self.with_pane(pane, |p| {
+            p.launches.push(launch.clone());
+            p.screen.clear();
+            p.dead = false;
+            p.exit_status = None;
+        })
+    }
+
+    fn send_keys(&self, pane: &str, keys: &[String]) -> Result<()> {
+        self.with_pane(pane, |p| p.sent.push(keys.to_vec()))
+    }
+
+    fn paste(&self, pane: &str, text: &str) -> Result<()> {
+        self.with_pane(pane, |p| {
+            p.pasted.push(text.to_string());
+            p.screen.push_str(text);
+        })
+    }
+
+    fn capture(&self, pane: &str, _scrollback: usize) -> Option<String> {
+        self.with_pane(pane, |p| p.screen.clone()).ok()
+    }
+
+    fn pipe(&self, pane: &str, path: &Path) -> Result<()> {
+        self.with_pane(pane, |p| p.pipe = Some(path.to_path_buf()))
+    }
+
+    fn kill_pane(&self, pane: &str) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        let (session, index) = state.locate(pane).ok_or_else(|| no_pane(pane))?;
+        let panes = &mut state.sessions.get_mut(&session).unwrap().panes;
+        panes.remove(index);
+        if panes.is_empty() {
+            state.sessions.remove(&session);
+        }
+        Ok(())
+    }
+
+    fn kill_session(&self, session: &str) -> Result<()> {
+        self.state
+            .lock()
+            .unwrap()
+            .sessions
+            .remove(session)
+            .map(drop)
+            .ok_or_else(|| GrottoError::Multiplexer(format!("can't find session: {}", session)))
+    }
+
+    fn has_session(&self, session: &str) -> bool {
+        self.state.lock().unwrap().sessions.contains_key(session)
+    }
+
+    fn list_panes(&self, session: &str) -> Option<Vec<PaneStatus>> {
+        let state = self.state.lock().unwrap();
+        let s = state.sessions.get(session)?;
+        Some(
+            s.panes
+                .iter()
+                .enumerate()
+                .map(|(index, p)| fake_status(index, p))
+                .collect(),
+        )
+    }
+
+    fn pane_status(&self, pane: &str) -> Option<PaneStatus> {
+        let state = self.state.lock().unwrap();
+        let (session, index) = state.locate(pane)?;
+        Some(fake_status(index, &state.sessions[&session].panes[index]))
+    }
+
+    fn pane_pid(&self, _pane: &str) -> Option<u32> {
+        None
+    }
+
+    fn keep_exited_panes(&self, session: &str) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        let s = state
+            .sessions
+            .get_mut(session)
+            .ok_or_else(|| GrottoError::Multiplexer(format!("can't find session: {}", session)))?;
+        s.keep_exited = true;
+        Ok(())
+    }
+
+    fn attach(&self, session: &str) -> Result<()> {
+        if self.has_session(session) {
+            Ok(())
+        } else {
+            Err(GrottoError::Multiplexer(format!(
+                "can't find session: {}",
+                session
+            )))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn launch(command: &str) -> AgentLaunch {
+        AgentLaunch {
+            workdir: PathBuf::from("/tmp"),
+            env: Vec::new(),
+            command: vec![command.to_string()],
+        }
+    }
+
+    #[test]
+    fn parse_panes_reads_ids_and_dead_status() {
+        let panes = parse_panes("%0 0 0 \n%4 1 1 137\n%2 2 1 0\n");
+        assert_eq!(
+            panes,
+            vec![
+                PaneStatus {
+                    pane_id: Some("%0".to_string()),
+                    pane_index: 0,
+                    dead: false,
+                    exit_status: None
+                },
+                PaneStatus {
+                    pane_id: Some("%4".to_string()),
+                    pane_index: 1,
+                    dead: true,
+                    exit_status: Some(137)
+                },
+                PaneStatus {
+                    pane_id: Some("%2".to_string()),
+                    pane_index: 2,
+                    dead: true,
+                    exit_status: Some(0)
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn fake_panes_keep_their_ids_when_others_close() {
+        let mux = FakeMultiplexer::new();
+        let a = mux.new_session("s", &launch("a")).unwrap().pane_id.unwrap();
+        let b = mux.split(&a, &launch("b")).unwrap().pane_id.unwrap();
+        let c = mux.split(&b, &launch("c")).unwrap().pane_id.unwrap();
+        assert!(mux.split("%9", &launch("d")).is_err());
+
+        mux.send_text(&b, "hi").unwrap();
+        assert_eq!(mux.pane(&b).unwrap().sent, vec![vec!["hi", "Enter"]]);
+        mux.set_screen(&c, "working");
+        assert_eq!(mux.capture(&c, 50).as_deref(), Some("working"));
+
+        // Without remain-on-exit an exited pane closes: later indexes move
+        // up but IDs still reach the same processes
+        mux.exit(&a, 0);
+        assert_eq!(mux.capture("s:0.1", 0).as_deref(), Some("working"));
+        assert_eq!(mux.capture(&c, 0).as_deref(), Some("working"));
+        assert!(matches!(mux.check_alive(&a), Err(GrottoError::DeadPane(_))));
+        assert!(mux.send_text(&a, "hi").is_err());
+
+        mux.keep_exited_panes("s").unwrap();
+        mux.exit(&c, 3);
+        let status = mux.pane_status(&c).unwrap();
+        assert_eq!((status.pane_index, status.dead), (1, true));
+        assert_eq!(status.exit_status, Some(3));
+        let err = mux.check_alive(&c).unwrap_err();
+        assert!(err.to_string().contains("exited with status 3"), "{err}");
+
+        mux.respawn(&c, &launch("c2")).unwrap();
+        assert!(mux.check_alive(&c).is_ok());
+
+        mux.kill_session("s").unwrap();
+        assert!(!mux.has_session("s"));
+        assert_eq!(mux.list_panes("s"), None);
+    }
+}
@@ -0,0 +1,575 @@
+use crate::logsink::{self, Cursor};
+use crate::monitor::{self, PaneSnapshot};
+use crate::mux::{Multiplexer, SessionMux};
+use crate::{Grotto, GrottoError, Result};
+use globset::{Glob, GlobMatcher};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Policy file inside `.grotto/`.
+pub const POLICY_FILE: &str = "policy.toml";
+
+/// How long a line reported from an agent's log suppresses the same line in pane captures.
+const CAPTURE_DEDUPE_WINDOW: Duration = Duration::from_secs(30);
+
+/// What to do to an agent that breaks a rule.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PauseAction {
+    /// Send Escape, interrupting the agent's current step
+    #[default]
+    Escape,
+    /// Freeze the pane's processes with SIGSTOP until `grotto policy resume`
+    Sigstop,
+    /// Only log and alert
+    None,
+}
+
+/// Deny rules loaded from `.grotto/policy.toml`.
+///
+/// ```toml
+/// pause = "escape"
+///
+/// [[deny]]
+/// name = "force-push"
+/// command = 'git push .*(--force|-f\b)'
+///
+/// [[deny]]
+/// name = "env-files"
+/// path = "**/.env*"
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Policy {
+    #[serde(default)]
+    pub pause: PauseAction,
+    #[serde(default)]
+    pub deny: Vec<DenyRule>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DenyRule {
+    pub name: String,
+    /// Regex matched against each line of agent output
+    #[serde(default)]
+    pub command: Option<String>,
+    /// Glob for protected paths mentioned in agent output
+    #[serde(default)]
+    pub path: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ViolationKind {
+    Command,
+    Path,
+}
+
+/// Where the offending line was seen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ViolationSource {
+    Stream,
+    Capture,
+}
+
+/// A rule match in one line of agent output.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RuleMatch {
+    pub rule: String,
+    pub kind: ViolationKind,
+    /// The part of the line that matched (command text or path)
+    pub matched: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Violation {
+    pub agent_id: String,
+    #[serde(flatten)]
+    pub rule_match: RuleMatch,
+    pub line: String,
+    pub source: ViolationSource,
+}
+
+enum Matcher {
+    Command(Regex),
+    Path(GlobMatcher),
+}
+
+/// A policy with its regexes and globs compiled.
+pub struct PolicyMatcher {
+    pub pause: PauseAction,
+    rules: Vec<(String, Matcher)>,
+}
+
+impl Policy {
+    /// Load `.grotto/policy.toml`, or `None` when there is no policy.
+    pub fn load(grotto_dir: &Path) -> Result<Option<Policy>> {
+        let path = grotto_dir.join(POLICY_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(path)?;
+        Ok(Some(toml::from_str(&content)?))
+    }
+
+    pub fn compile(&self) -> Result<PolicyMatcher> {
+        let mut rules = Vec::new();
+        for rule in &self.deny {
+            if rule.command.is_none() && rule.path.is_none() {
+                return Err(GrottoError::Policy(format!(
+                    "rule '{}' needs a `command` or `path`",
+                    rule.name
+                )));
+            }
+            if let Some(pattern) = &rule.command {
+                let regex = Regex::new(pattern).map_err(|e| {
+                    GrottoError::Policy(format!(
+                        "rule '{}': invalid command regex: {}",
+                        rule.name, e
+                    ))
+                })?;
+                rules.push((rule.name.clone(), Matcher::Command(regex)));
+            }
+            if let Some(pattern) = &rule.path {
+                let glob = Glob::new(pattern).map_err(|e| {
+                    GrottoError::Policy(format!("rule '{}': invalid path glob: {}", rule.name, e))
+                })?;
+                rules.push((rule.name.clone(), Matcher::Path(glob.compile_matcher())));
+            }
+        }
+        Ok(PolicyMatcher {
+            pause: self.pause,
+            rules,
+        })
+    }
+}
+
+impl PolicyMatcher {
+    /// First rule the line breaks, if any.
+    pub fn check_line(&self, line: &str) -> Option<RuleMatch> {
+        let line = monitor::strip_ansi(line);
+        let line = line.trim();
+        if line.is_empty() {
+            return None;
+        }
+        for (name, matcher) in &self.rules {
+            match matcher {
+                Matcher::Command(regex) => {
+                    if let Some(m) = regex.find(line) {
+                        return Some(RuleMatch {
+                            rule: name.clone(),
+                            kind: ViolationKind::Command,
+                            matched: m.as_str().to_string(),
+                        });
+                    }
+                }
+                Matcher::Path(glob) => {
+                    if let Some(path) = path_tokens(line).find(|t| path_matches(glob, t)) {
+                        return Some(RuleMatch {
+                            rule: name.clone(),
+                            kind: ViolationKind::Path,
+                            matched: path.to_string(),
+                        });
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Path-like tokens in a line, e.g. `Edit(config/.env)` yields `Edit` and `config/.env`.
+fn path_tokens(line: &str) -> impl Iterator<Item = &str> {
+    line.split(|c: char| !(c.is_alphanumeric() || "._-/~*".contains(c)))
+        .map(|t| t.trim_end_matches('.'))
+        .filter(|t| !t.is_empty())
+}
+
+/// Match a token and each of its `/`-separated suffixes, so relative globs
+/// also catch absolute paths into the project.
+fn path_matches(glob: &GlobMatcher, token: &str) -> bool {
+    let token = token.strip_prefix("./").unwrap_or(token);
+    if glob.is_match(token) {
+        return true;
+    }
+    token
+        .match_indices('/')
+        .any(|(i, _)| i + 1 < token.len() && glob.is_match(&token[i + 1..]))
+}
+
+/// Evaluates agents' cleaned logs and pane captures against `policy.toml`,
+/// reloading the policy when the file changes.
+pub struct PolicyWatcher {
+    grotto_dir: PathBuf,
+    matcher: Option<PolicyMatcher>,
+    policy_mtime: Option<SystemTime>,
+    stream_cursors: HashMap<String, Cursor>,
+    /// Lines already reported, so a line that stays on screen is reported once
+    reported: HashSet<(String, String)>,
+    recent_stream: HashMap<(String, String), Instant>,
+    /// On the first stream scan, skip lines already logged as violations
+    first_scan: bool,
+}
+
+impl PolicyWatcher {
+    pub fn new(grotto_dir: impl Into<PathBuf>) -> Self {
+        let grotto_dir = grotto_dir.into();
+        let reported = previous_violations(&grotto_dir);
+        Self {
+            grotto_dir,
+            matcher: None,
+            policy_mtime: None,
+            stream_cursors: HashMap::new(),
+            reported,
+            recent_stream: HashMap::new(),
+            first_scan: true,
+        }
+    }
+
+    /// Pause action from the current policy.
+    pub fn pause_action(&self) -> PauseAction {
+        self.matcher.as_ref().map(|m| m.pause).unwrap_or_default()
+    }
+
+    fn reload(&mut self) -> Result<()> {
+        let path = self.grotto_dir.join(POLICY_FILE);
+        let mtime = fs::metadata(&path).and_then(|m| m.modified()).ok();
+        if mtime == self.policy_mtime {
+            return Ok(());
+        }
+        self.policy_mtime = mtime;
+        self.matcher = match Policy::load(&self.grotto_dir)? {
+            Some(policy) => Some(policy.compile()?),
+            None => None,
+        };
+        Ok(())
+    }
+
+    /// Check new cleaned log output for each agent and the given pane captures.
+    pub fn scan(
+        &mut self,
+        agent_ids: &[String],
+        snapshots: &[PaneSnapshot],
+    ) -> Result<Vec<Violation>> {
+        self.reload()?;
+        let Some(matcher) = &self.matcher else {
+            return Ok(Vec::new());
+        };
+
+        let now = Instant::now();
+        self.recent_stream
+            .retain(|_, seen| now.duration_since(*seen) < CAPTURE_DEDUPE_WINDOW);
+
+        let mut violations = Vec::new();
+        for agent_id in agent_ids {
+            let cursor = self.stream_cursors.entry(agent_id.clone()).or_default();
+            let lines =
+                logsink::read_new(&logsink::clean_path(&self.grotto_dir, agent_id), cursor)?;
+
+            for line in lines {
+                let Some(rule_match) = matcher.check_line(&line) else {
+                    continue;
+                };
+                let line = line.trim().to_string();
+                let key = (agent_id.clone(), line.clone());
+                let already = self.reported.contains(&key);
+                self.reported.insert(key.clone());
+                self.recent_stream.insert(key, now);
+                if self.first_scan && already {
+                    continue;
+                }
+                violations.push(Violation {
+                    agent_id: agent_id.clone(),
+                    rule_match,
+                    line,
+                    source: ViolationSource::Stream,
+                });
+            }
+        }
+        self.first_scan = false;
+
+        for snapshot in snapshots {
+            for line in snapshot.raw_content.lines() {
+                let Some(rule_match) = matcher.check_line(line) else {
+                    continue;
+                };
+                let line = monitor::strip_ansi(line).trim().to_string();
+                let key = (snapshot.agent_id.clone(), line.clone());
+                if self.recent_stream.contains_key(&key) || !self.reported.insert(key) {
+                    continue;
+                }
+                violations.push(Violation {
+                    agent_id: snapshot.agent_id.clone(),
+                    rule_match,
+                    line,
+                    source: ViolationSource::Capture,
+                });
+            }
+        }
+
+        Ok(violations)
+    }
+}
+
+fn previous_violations(grotto_dir: &Path) -> HashSet<(String, String)> {
+    fs::read_to_string(grotto_dir.join("events.jsonl"))
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| serde_json::from_str::<crate::Event>(line).ok())
+        .filter(|e| e.event_type == "policy_violation")
+        .filter_map(|e| {
+            let line = e.data["line"].as_str()?.to_string();
+            Some((e.agent_id?, line))
+        })
+        .collect()
+}
+
+/// Pause the agent, mark it paused in its status, and log `policy_violation`.
+pub fn enforce(
+    grotto: &mut Grotto,
+    mux: &dyn Multiplexer,
+    violation: &Violation,
+    action: PauseAction,
+) -> Result<()> {
+    let mux = SessionMux::new(grotto, mux);
+    let pane = grotto.pane_target(&violation.agent_id)?;
+    match action {
+        PauseAction::Escape => mux.send_keys(&pane, &["Escape".to_string()])?,
+        PauseAction::Sigstop => signal_pane(&*mux, &pane, "STOP")?,
+        PauseAction::None => {}
+    }
+
+    if action != PauseAction::None
+        && let Some(agent) = grotto.agents.get_mut(&violation.agent_id)
+    {
+        agent.state = "paused".to_string();
+        agent.progress = format!("Paused by policy rule '{}'", violation.rule_match.rule);
+        agent.last_update = chrono::Utc::now();
+        grotto.write_agent_status(&violation.agent_id)?;
+    }
+
+    grotto.log_event(
+        "policy_violation",
+        Some(&violation.agent_id),
+        None,
+        Some(&format!(
+            "Policy rule '{}' violated: {}",
+            violation.rule_match.rule, violation.line
+        )),
+        serde_json::json!({
+            "rule": violation.rule_match.rule,
+            "kind": violation.rule_match.kind,
+            "matched": violation.rule_match.matched,
+            "line": violation.line,
+            "source": violation.source,
+            "action": action,
+        }),
+    )
+}
+
+/// Resume an agent paused by the policy watcher.
+pub fn resume(grotto: &mut Grotto, mux: &dyn Multiplexer, agent_id: &str) -> Result<()> {
+    let mux = SessionMux::new(grotto, mux);
+    let pane = grotto.pane_target(agent_id)?;
+    signal_pane(&*mux, &pane, "CONT")?;
+
+    if let Some(agent) = grotto.agents.get_mut(agent_id) {
+        agent.state = "working".to_string();
+        agent.progress = "Resumed after policy pause".to_string();
+        agent.last_update = chrono::Utc::now();
+    }
+    grotto.write_agent_status(agent_id)?;
+    grotto.log_event(
+        "policy_resumed",
+        Some(agent_id),
+        None,
+        Some("Agent resumed after policy pause"),
+        serde_json::json!({}),
+    )
+}
+
+/// Send a signal to a pane's process and its children.
+fn signal_pane(mux: &dyn Multiplexer, pane: &str, signal: &str) -> Result<()> {
+    let pid = mux
+        .pane_pid(pane)
+        .ok_or_else(|| GrottoError::DeadPane(format!("{} has no running process", pane)))?
+        .to_string();
+    // No children to signal is fine; pkill exits 1 for that
+    Command::new("pkill")
+        .args([&format!("-{}", signal), "-P", &pid])
+        .output()?;
+    let output = Command::new("kill")
+        .args([&format!("-{}", signal), &pid])
+        .output()?;
+    if !output.status.success() {
+        return Err(GrottoError::Io(std::io::Error::other(format!(
+            "kill -{} {} failed: {}",
+            signal,
+            pid,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitor::AgentPhase;
+    use crate::mux::FakeMultiplexer;
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    const POLICY: &str = r#"
+[[deny]]
+name = "force-push"
+command = 'git push .*(--force|-f\b)'
+
+[[deny]]
+name = "env-files"
+path = "**/.env*"
+
+[[deny]]
+name = "secrets"
+path = "secrets/**"
+"#;
+
+    fn matcher() -> PolicyMatcher {
+        toml::from_str::<Policy>(POLICY).unwrap().compile().unwrap()
+    }
+
+    fn snapshot(agent_id: &str, content: &str) -> PaneSnapshot {
+        PaneSnapshot {
+            agent_id: agent_id.to_string(),
+            pane_index: 0,
+            raw_content: content.to_string(),
+            phase: AgentPhase::Running,
+            last_activity_line: String::new(),
+            timestamp: chrono::Utc::now(),
+        }
+    }
+
+    fn append(path: &Path, text: &str) {
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .unwrap();
+        file.write_all(text.as_bytes()).unwrap();
+    }
+
+    #[test]
+    fn check_line_matches_commands() {
+        let m = matcher();
+        let hit = m.check_line("$ git push origin main --force").unwrap();
+        assert_eq!(hit.rule, "force-push");
+        assert_eq!(hit.kind, ViolationKind::Command);
+        assert!(
+            m.check_line("\x1b[32m● Bash(git push -f origin)\x1b[0m")
+                .is_some()
+        );
+        assert!(m.check_line("$ git push origin main").is_none());
+    }
+
+    #[test]
+    fn check_line_matches_protected_paths() {
+        let m = matcher();
+        let hit = m.check_line("● Edit(.env)").unwrap();
+        assert_eq!(hit.rule, "env-files");
+        assert_eq!(hit.matched, ".env");
+        assert_eq!(
+            m.check_line("Read /home/me/project/secrets/prod.key")
+                .unwrap()
+                .rule,
+            "secrets"
+        );
+        assert!(m.check_line("cat config/.env.local").is_some());
+        assert!(m.check_line("Edit(src/environment.rs)").is_none());
+    }
+
+    #[test]
+    fn compile_rejects_bad_rules() {
+        let bad_regex: Policy = toml::from_str("[[deny]]\nname = \"x\"\ncommand = \"(\"").unwrap();
+        assert!(matches!(bad_regex.compile(), Err(GrottoError::Policy(_))));
+        let empty: Policy = toml::from_str("[[deny]]\nname = \"x\"").unwrap();
+        assert!(matches!(empty.compile(), Err(GrottoError::Policy(_))));
+    }
+
+    #[test]
+    fn watcher_scans_stream_incrementally_and_dedupes_captures() {
+        let tmp = TempDir::new().unwrap();
+        let grotto = Grotto::new(tmp.path(), 1, "t".into()).unwrap();
+        let agents = vec!["agent-1".to_string()];
+        let stream = logsink::clean_path(&grotto.grotto_dir, "agent-1");
+        let mut watcher = PolicyWatcher::new(&grotto.grotto_dir);
+
+        // No policy yet: nothing is checked
+        append(&stream, "$ git push --force\n");
+        assert!(watcher.scan(&agents, &[]).unwrap().is_empty());
+
+        fs::write(grotto.grotto_dir.join(POLICY_FILE), POLICY).unwrap();
+        append(&stream, "$ git push --force\npartial line .env");
+        let found = watcher.scan(&agents, &[]).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].source, ViolationSource::Stream);
+
+        // The partial line is picked up once it's complete; the same line in a
+        // capture isn't reported again
+        append(&stream, "\n");
+        let capture = snapshot("agent-1", "$ git push --force\npartial line .env\n");
+        let found = watcher
+            .scan(&agents, std::slice::from_ref(&capture))
+            .unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].rule_match.rule, "env-files");
+        assert!(watcher.scan(&agents, &[capture]).unwrap().is_empty());
+
+        // Captures catch output that never reached the log
+        let found = watcher
+            .scan(&agents, &[snapshot("agent-1", "rm secrets/key.pem")])
+            .unwrap();
+        assert_eq!(found[0].source, ViolationSource::Capture);
+    }
+
+    #[test]
+    fn enforce_marks_agent_paused_and_logs_event() {
+        let tmp = TempDir::new().unwrap();
+        let mut grotto = Grotto::new(tmp.path(), 1, "t".into()).unwrap();
+        let violation = Violation {
+            agent_id: "agent-1".to_string(),
+            rule_match: matcher().check_line("git push --force").unwrap(),
+            line: "git push --force".to_string(),
+            source: ViolationSource::Stream,
+        };
+        let mux = FakeMultiplexer::with_team(&mut grotto);
+        enforce(&mut grotto, &mux, &violation, PauseAction::Escape).unwrap();
+        assert_eq!(
+            mux.pane(&grotto.pane_target("agent-1").unwrap())
+                .unwrap()
+                .sent,
+            vec![vec!["Escape"]]
+        );
+
+        assert_eq!(grotto.agents["agent-1"].state, "paused");
+        let events = fs::read_to_string(tmp.path().join(".grotto/events.jsonl")).unwrap();
+        assert!(events.contains("\"event_type\":\"policy_violation\""));
+        assert!(events.contains("\"rule\":\"force-push\""));
+
+        // A restarted watcher doesn't report the logged line again
+        let stream = logsink::clean_path(&grotto.grotto_dir, "agent-1");
+        fs::write(grotto.grotto_dir.join(POLICY_FILE), POLICY).unwrap();
+        append(&stream, "git push --force\n");
+        let mut watcher = PolicyWatcher::new(&grotto.grotto_dir);
+        assert!(
+            watcher
+                .scan(&["agent-1".to_string()], &[])
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
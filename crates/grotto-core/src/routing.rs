@@ -0,0 +1,150 @@
+use crate::{GrottoError, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Send agents with a role, or agents holding a task with a label, to a
+/// model. Written as `[[models]]` in `config.toml`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelRule {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    pub model: String,
+}
+
+impl fmt::Display for ModelRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.role, &self.label) {
+            (Some(role), _) => write!(f, "role:{}={}", role, self.model),
+            (None, Some(label)) => write!(f, "label:{}={}", label, self.model),
+            (None, None) => write!(f, "{}", self.model),
+        }
+    }
+}
+
+/// `role:<role>=<model>` or `label:<label>=<model>`, as given to
+/// `spawn --route`.
+impl std::str::FromStr for ModelRule {
+    type Err = GrottoError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let parsed = s.split_once('=').and_then(|(selector, model)| {
+            let (kind, value) = selector.trim().split_once(':')?;
+            let (value, model) = (value.trim(), model.trim());
+            if value.is_empty() || model.is_empty() {
+                return None;
+            }
+            let (role, label) = match kind {
+                "role" => (Some(value.to_string()), None),
+                "label" => (None, Some(value.to_string())),
+                _ => return None,
+            };
+            Some(ModelRule {
+                role,
+                label,
+                model: model.to_string(),
+            })
+        });
+        parsed.ok_or_else(|| {
+            GrottoError::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "Invalid model route '{}'. Use role:<role>=<model> or label:<label>=<model>",
+                    s
+                ),
+            ))
+        })
+    }
+}
+
+/// The model picked for an agent and why.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Route {
+    pub model: String,
+    /// The rule that matched, or "default"
+    pub rule: String,
+}
+
+/// Pick a model for an agent with `role` holding a task with `labels`.
+///
+/// Label rules win over role rules, since they describe the work at hand;
+/// within each kind the first matching rule wins. Falls back to `default`.
+pub fn route(
+    rules: &[ModelRule],
+    default: Option<&str>,
+    role: Option<&str>,
+    labels: &[String],
+) -> Option<Route> {
+    let by_label = rules.iter().find(|rule| {
+        rule.label
+            .as_ref()
+            .is_some_and(|label| labels.contains(label))
+    });
+    let by_role = || {
+        rules
+            .iter()
+            .find(|rule| rule.role.is_some() && rule.role.as_deref() == role)
+    };
+    match by_label.or_else(by_role) {
+        Some(rule) => Some(Route {
+            model: rule.model.clone(),
+            rule: rule.to_string(),
+        }),
+        None => default.map(|model| Route {
+            model: model.to_string(),
+            rule: "default".to_string(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules() -> Vec<ModelRule> {
+        vec![
+            "role:tester=haiku".parse().unwrap(),
+            "label:docs=haiku".parse().unwrap(),
+            "label:planning=opus".parse().unwrap(),
+        ]
+    }
+
+    #[test]
+    fn label_rules_win_over_role_rules_then_default() {
+        let rules = rules();
+        let labels = vec!["planning".to_string()];
+
+        let route_for = |role: Option<&str>, labels: &[String]| {
+            route(&rules, Some("sonnet"), role, labels).map(|r| r.model)
+        };
+        assert_eq!(route_for(Some("tester"), &labels).as_deref(), Some("opus"));
+        assert_eq!(route_for(Some("tester"), &[]).as_deref(), Some("haiku"));
+        assert_eq!(
+            route_for(None, &["docs".to_string()]).as_deref(),
+            Some("haiku")
+        );
+        assert_eq!(route_for(Some("reviewer"), &[]).as_deref(), Some("sonnet"));
+        assert_eq!(route(&rules, None, None, &[]), None);
+
+        let matched = route(&rules, None, Some("tester"), &[]).unwrap();
+        assert_eq!(matched.rule, "role:tester=haiku");
+    }
+
+    #[test]
+    fn rules_parse_from_flags_and_toml() {
+        let err = "tester=haiku".parse::<ModelRule>().unwrap_err().to_string();
+        assert!(err.contains("Invalid model route 'tester=haiku'"), "{err}");
+        assert!("role:=haiku".parse::<ModelRule>().is_err());
+
+        #[derive(Serialize, Deserialize)]
+        struct Wrapper {
+            models: Vec<ModelRule>,
+        }
+        let parsed: Wrapper = toml::from_str(
+            "[[models]]\nrole = \"tester\"\nmodel = \"haiku\"\n\n[[models]]\nlabel = \"docs\"\nmodel = \"haiku\"\n",
+        )
+        .unwrap();
+        assert_eq!(parsed.models, rules()[..2]);
+    }
+}
@@ -0,0 +1,453 @@
+use crate::detect::PhaseRules;
+use crate::logsink;
+use crate::monitor::AgentPhase;
+use crate::{GrottoError, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Placeholder replaced with the agent's prompt in a shell runtime command.
+pub const PROMPT_PLACEHOLDER: &str = "{prompt}";
+
+/// Placeholder replaced with the agent's routed model in a shell runtime
+/// command (empty when no model was routed).
+pub const MODEL_PLACEHOLDER: &str = "{model}";
+
+/// The program an agent runs in its pane: how to launch and relaunch it,
+/// how to read its output, and how to ask it to stop.
+pub trait AgentRuntime {
+    /// Binaries that must be on PATH to launch the agent.
+    fn required_binaries(&self) -> Vec<String>;
+
+    /// Command run in the agent's pane with its prompt, on `model` when
+    /// one was routed to the agent.
+    fn launch_command(&self, prompt: &str, model: Option<&str>) -> Vec<String>;
+
+    /// Command that relaunches an agent after a crash or rate limit; the
+    /// prompt already says it is resuming.
+    fn resume_command(&self, prompt: &str, model: Option<&str>) -> Vec<String> {
+        self.launch_command(prompt, model)
+    }
+
+    /// Whether the agent has started producing output.
+    fn is_ready(&self, pane: &str) -> bool {
+        !pane.trim().is_empty()
+    }
+
+    /// Phase of the agent from its captured pane, read with `rules`.
+    fn infer_phase(&self, pane: &str, rules: &PhaseRules) -> AgentPhase {
+        if self.is_ready(pane) {
+            rules.infer(pane)
+        } else {
+            AgentPhase::Starting
+        }
+    }
+
+    /// Keys sent with `tmux send-keys` to ask the agent to exit.
+    fn exit_keys(&self) -> Vec<String>;
+}
+
+/// Claude Code in print mode (the default).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ClaudeCode;
+
+impl AgentRuntime for ClaudeCode {
+    fn required_binaries(&self) -> Vec<String> {
+        vec!["claude".to_string()]
+    }
+
+    fn launch_command(&self, prompt: &str, model: Option<&str>) -> Vec<String> {
+        // stream-json reports tokens and cost as the agent works;
+        // `grotto stream-view` keeps them and shows the pane readable lines
+        let mut command = vec![
+            logsink::grotto_exe(),
+            "stream-view".to_string(),
+            "claude".to_string(),
+            "--dangerously-skip-permissions".to_string(),
+            "--output-format".to_string(),
+            "stream-json".to_string(),
+            "--verbose".to_string(),
+        ];
+        if let Some(model) = model {
+            command.push("--model".to_string());
+            command.push(model.to_string());
+        }
+        command.push("-p".to_string());
+        command.push(prompt.to_string());
+        command
+    }
+
+    fn exit_keys(&self) -> Vec<String> {
+        vec!["/exit".to_string(), "Enter".to_string()]
+    }
+}
+
+/// Any agent CLI, run through `sh -c` with `{prompt}` replaced by the quoted
+/// prompt (or the prompt appended when there is no placeholder) and
+/// `{model}` by the quoted model.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShellCommand {
+    pub command: String,
+    /// Output that shows the agent is up; any output counts when unset
+    pub ready: Option<String>,
+    /// Text typed to make the agent exit; Ctrl-C when unset
+    pub exit: Option<String>,
+}
+
+impl AgentRuntime for ShellCommand {
+    fn required_binaries(&self) -> Vec<String> {
+        let program = self.command.split_whitespace().next().unwrap_or("sh");
+        vec!["sh".to_string(), program.to_string()]
+    }
+
+    fn launch_command(&self, prompt: &str, model: Option<&str>) -> Vec<String> {
+        let quoted = shell_quote(prompt);
+        let command = self
+            .command
+            .replace(MODEL_PLACEHOLDER, &shell_quote(model.unwrap_or_default()));
+        let command = if command.contains(PROMPT_PLACEHOLDER) {
+            command.replace(PROMPT_PLACEHOLDER, &quoted)
+        } else {
+            format!("{} {}", command, quoted)
+        };
+        vec!["sh".to_string(), "-c".to_string(), command]
+    }
+
+    fn is_ready(&self, pane: &str) -> bool {
+        match &self.ready {
+            Some(marker) => pane.contains(marker.as_str()),
+            None => !pane.trim().is_empty(),
+        }
+    }
+
+    fn exit_keys(&self) -> Vec<String> {
+        match &self.exit {
+            Some(text) => vec![text.clone(), "Enter".to_string()],
+            None => vec!["C-c".to_string()],
+        }
+    }
+}
+
+/// Prints scripted lines and exits, for testing orchestration without a
+/// real agent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FakeRuntime {
+    pub lines: Vec<String>,
+    pub exit_code: i32,
+    /// Pause after each line
+    pub delay_ms: u64,
+}
+
+impl AgentRuntime for FakeRuntime {
+    fn required_binaries(&self) -> Vec<String> {
+        vec!["sh".to_string()]
+    }
+
+    fn launch_command(&self, _prompt: &str, _model: Option<&str>) -> Vec<String> {
+        let mut script = String::new();
+        for line in &self.lines {
+            script.push_str(&format!("printf '%s\\n' {}; ", shell_quote(line)));
+            if self.delay_ms > 0 {
+                script.push_str(&format!("sleep {}; ", self.delay_ms as f64 / 1000.0));
+            }
+        }
+        script.push_str(&format!("exit {}", self.exit_code));
+        vec!["sh".to_string(), "-c".to_string(), script]
+    }
+
+    fn exit_keys(&self) -> Vec<String> {
+        vec!["C-c".to_string()]
+    }
+}
+
+/// Which runtime a session or agent uses, as stored in `config.toml`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RuntimeConfig {
+    #[default]
+    Claude,
+    Shell {
+        command: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        ready: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        exit: Option<String>,
+    },
+    Fake {
+        #[serde(default = "default_fake_lines")]
+        lines: Vec<String>,
+        #[serde(default)]
+        exit_code: i32,
+        #[serde(default)]
+        delay_ms: u64,
+    },
+}
+
+fn default_fake_lines() -> Vec<String> {
+    vec!["Working on it...".to_string(), "All done".to_string()]
+}
+
+impl RuntimeConfig {
+    pub fn is_claude(&self) -> bool {
+        *self == RuntimeConfig::Claude
+    }
+
+    /// Short name of the runtime, which names its phase rules file.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            RuntimeConfig::Claude => "claude",
+            RuntimeConfig::Shell { .. } => "shell",
+            RuntimeConfig::Fake { .. } => "fake",
+        }
+    }
+
+    pub fn runtime(&self) -> Box<dyn AgentRuntime> {
+        match self {
+            RuntimeConfig::Claude => Box::new(ClaudeCode),
+            RuntimeConfig::Shell {
+                command,
+                ready,
+                exit,
+            } => Box::new(ShellCommand {
+                command: command.clone(),
+                ready: ready.clone(),
+                exit: exit.clone(),
+            }),
+            RuntimeConfig::Fake {
+                lines,
+                exit_code,
+                delay_ms,
+            } => Box::new(FakeRuntime {
+                lines: lines.clone(),
+                exit_code: *exit_code,
+                delay_ms: *delay_ms,
+            }),
+        }
+    }
+}
+
+impl fmt::Display for RuntimeConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuntimeConfig::Claude => write!(f, "claude"),
+            RuntimeConfig::Shell { command, .. } => write!(f, "shell: {}", command),
+            RuntimeConfig::Fake { .. } => write!(f, "fake"),
+        }
+    }
+}
+
+/// `claude`, `fake`, or a shell command (`shell:<cmd>`, or any command
+/// containing `{prompt}`).
+impl std::str::FromStr for RuntimeConfig {
+    type Err = GrottoError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        match s {
+            "claude" => Ok(RuntimeConfig::Claude),
+            "fake" => Ok(RuntimeConfig::Fake {
+                lines: default_fake_lines(),
+                exit_code: 0,
+                delay_ms: 0,
+            }),
+            _ => {
+                let command = s
+                    .strip_prefix("shell:")
+                    .map(str::trim)
+                    .or_else(|| s.contains(PROMPT_PLACEHOLDER).then_some(s));
+                match command {
+                    Some(command) if !command.is_empty() => Ok(RuntimeConfig::Shell {
+                        command: command.to_string(),
+                        ready: None,
+                        exit: None,
+                    }),
+                    _ => Err(GrottoError::Io(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        format!(
+                            "Unknown runtime '{}'. Use claude, fake, or a shell command containing {{prompt}} (e.g. \"aider --message {{prompt}}\")",
+                            s
+                        ),
+                    ))),
+                }
+            }
+        }
+    }
+}
+
+/// Single-quote `s` for `sh`.
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    #[test]
+    fn claude_runtime_launches_print_mode() {
+        let runtime = RuntimeConfig::Claude.runtime();
+        let command = runtime.launch_command("do it", None);
+        assert_eq!(command[0], logsink::grotto_exe());
+        assert_eq!(
+            command[1..],
+            [
+                "stream-view",
+                "claude",
+                "--dangerously-skip-permissions",
+                "--output-format",
+                "stream-json",
+                "--verbose",
+                "-p",
+                "do it"
+            ]
+        );
+        assert_eq!(
+            runtime.launch_command("do it", Some("haiku"))[7..],
+            ["--model", "haiku", "-p", "do it"]
+        );
+        assert_eq!(runtime.required_binaries(), vec!["claude"]);
+        assert_eq!(runtime.exit_keys(), vec!["/exit", "Enter"]);
+        assert_eq!(
+            runtime.infer_phase("", PhaseRules::built_in()),
+            AgentPhase::Starting
+        );
+    }
+
+    /// Stands in for `claude`: prints stream-json only when asked for it.
+    const FAKE_CLAUDE: &str = r#"
+case " $* " in
+  *" --output-format stream-json --verbose "*) ;;
+  *) echo "Done."; exit 0 ;;
+esac
+echo '{"type":"assistant","message":{"id":"m1","content":[{"type":"tool_use","name":"Bash","input":{"command":"cargo test"}}],"usage":{"input_tokens":100,"output_tokens":20}}}'
+echo '{"type":"result","subtype":"success","num_turns":1,"result":"Done.","total_cost_usd":0.25,"usage":{"input_tokens":1200,"output_tokens":300}}'
+"#;
+
+    #[test]
+    fn claude_launch_output_is_counted_as_usage() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let agent_dir = tmp.path().join("agents/agent-1");
+        std::fs::create_dir_all(&agent_dir).unwrap();
+
+        // What `grotto stream-view` runs: everything after its own argv
+        let command = ClaudeCode.launch_command("do it", None);
+        let output = Command::new("sh")
+            .arg("-c")
+            .arg(FAKE_CLAUDE)
+            .args(&command[2..])
+            .output()
+            .unwrap();
+        let mut pane = Vec::new();
+        logsink::render_stream(&output.stdout[..], &mut pane, Some(&agent_dir)).unwrap();
+
+        let usage = crate::usage::agent_usage(tmp.path(), "agent-1");
+        assert_eq!(usage.input_tokens, 1200);
+        assert_eq!(usage.output_tokens, 300);
+        assert!((usage.cost_usd - 0.25).abs() < 1e-9);
+        // The pane reads like Claude's terminal, not like JSON
+        assert_eq!(
+            String::from_utf8(pane).unwrap(),
+            "Bash(cargo test)\nDone.\nFinished: success after 1 turn(s)\n"
+        );
+    }
+
+    #[test]
+    fn shell_runtime_substitutes_quoted_prompt() {
+        let config: RuntimeConfig = "aider --yes --message {prompt}".parse().unwrap();
+        let runtime = config.runtime();
+        let command = runtime.launch_command("it's done", None);
+        assert_eq!(command[..2], ["sh", "-c"]);
+        assert_eq!(command[2], r"aider --yes --message 'it'\''s done'");
+        assert_eq!(runtime.required_binaries(), vec!["sh", "aider"]);
+        assert_eq!(runtime.exit_keys(), vec!["C-c"]);
+
+        let output = Command::new("sh")
+            .args([
+                "-c",
+                &command[2].replace("aider --yes --message", "printf %s"),
+            ])
+            .output()
+            .unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "it's done");
+    }
+
+    #[test]
+    fn shell_runtime_waits_for_ready_marker() {
+        let runtime = ShellCommand {
+            command: "agent".into(),
+            ready: Some("ready>".into()),
+            exit: Some("/quit".into()),
+        };
+        assert_eq!(
+            runtime.infer_phase("loading model", PhaseRules::built_in()),
+            AgentPhase::Starting
+        );
+        assert!(runtime.is_ready("loading model\nready>"));
+        assert_eq!(runtime.exit_keys(), vec!["/quit", "Enter"]);
+        assert_eq!(runtime.launch_command("x", None)[2], "agent 'x'");
+
+        let routed = ShellCommand {
+            command: "aider --model {model} --message {prompt}".into(),
+            ready: None,
+            exit: None,
+        };
+        assert_eq!(
+            routed.launch_command("x", Some("gpt-4o"))[2],
+            "aider --model 'gpt-4o' --message 'x'"
+        );
+    }
+
+    #[test]
+    fn fake_runtime_prints_script_and_exits() {
+        let runtime = RuntimeConfig::Fake {
+            lines: vec!["Thinking...".into(), "it's fine".into()],
+            exit_code: 3,
+            delay_ms: 0,
+        }
+        .runtime();
+        let command = runtime.launch_command("ignored", Some("opus"));
+        let output = Command::new(&command[0])
+            .args(&command[1..])
+            .output()
+            .unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "Thinking...\nit's fine\n"
+        );
+        assert_eq!(output.status.code(), Some(3));
+    }
+
+    #[test]
+    fn runtime_specs_parse_and_roundtrip_through_toml() {
+        assert_eq!(
+            "claude".parse::<RuntimeConfig>().unwrap(),
+            RuntimeConfig::Claude
+        );
+        assert!(matches!(
+            "shell:codex exec".parse::<RuntimeConfig>().unwrap(),
+            RuntimeConfig::Shell { command, .. } if command == "codex exec"
+        ));
+        let err = "gpt".parse::<RuntimeConfig>().unwrap_err().to_string();
+        assert!(err.contains("Unknown runtime 'gpt'"), "{err}");
+
+        #[derive(Serialize, Deserialize)]
+        struct Wrapper {
+            runtime: RuntimeConfig,
+        }
+        let parsed: Wrapper =
+            toml::from_str("[runtime]\nkind = \"fake\"\nexit_code = 1\n").unwrap();
+        assert_eq!(
+            parsed.runtime,
+            RuntimeConfig::Fake {
+                lines: default_fake_lines(),
+                exit_code: 1,
+                delay_ms: 0,
+            }
+        );
+        let written = toml::to_string(&Wrapper {
+            runtime: "shell:aider".parse().unwrap(),
+        })
+        .unwrap();
+        assert!(written.contains("kind = \"shell\""), "{written}");
+    }
+}
@@ -0,0 +1,244 @@
+use crate::logsink;
+use crate::monitor::{AgentPhase, PaneSnapshot};
+use crate::mux::Multiplexer;
+use crate::runtime::RuntimeConfig;
+use crate::{Grotto, GrottoError, Result};
+use std::time::Duration;
+
+/// Agents added and retired by one `grotto scale` / `grotto add-agent`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScaleChange {
+    pub added: Vec<String>,
+    /// Retired agents with the task each one gave back
+    pub retired: Vec<(String, Option<String>)>,
+}
+
+/// Add an agent to the live session and launch it in a new pane after the
+/// last one, with its prompt and stream log. `runtime` overrides the
+/// session's runtime for this agent; `task` is claimed for it before launch,
+/// so the task's labels take part in model routing.
+pub fn add_agent(
+    grotto: &mut Grotto,
+    mux: &dyn Multiplexer,
+    role: Option<&str>,
+    runtime: Option<RuntimeConfig>,
+    task: Option<&str>,
+) -> Result<String> {
+    if let Some(task_id) = task
+        && !grotto.tasks.iter().any(|t| t.id == task_id)
+    {
+        return Err(GrottoError::TaskNotFound(task_id.to_string()));
+    }
+    // Split the last pane so the new one lands at the end without
+    // renumbering the others
+    let last_pane = grotto
+        .agent_panes()
+        .into_iter()
+        .max_by_key(|pane| pane.pane_index)
+        .map(|pane| pane.target)
+        .unwrap_or_else(|| format!("{}:0", grotto.config.tmux_session));
+    let agent_id = grotto.add_agent(role)?;
+    if let Some(runtime) = runtime {
+        grotto
+            .config
+            .agent_runtimes
+            .insert(agent_id.clone(), runtime);
+        grotto.write_config()?;
+    }
+    if let Some(task_id) = task {
+        grotto.claim_task(task_id, &agent_id)?;
+    }
+    grotto.assign_model(&agent_id)?;
+
+    let prompt = grotto.generate_claude_prompt(&agent_id);
+    let pane = mux.split(&last_pane, &grotto.agent_launch(&agent_id, prompt))?;
+    grotto.set_pane_id(&agent_id, pane.pane_id)?;
+
+    let stream_path = grotto
+        .grotto_dir
+        .join("agents")
+        .join(&agent_id)
+        .join(logsink::RAW_LOG);
+    let _ = mux.pipe(&grotto.pane_target(&agent_id)?, &stream_path);
+    Ok(agent_id)
+}
+
+/// The `count` agents to retire when scaling down: idle agents without a
+/// task first, then busy ones, then agents holding a task; newest first
+/// within each group.
+pub fn pick_retirees(grotto: &Grotto, snapshots: &[PaneSnapshot], count: usize) -> Vec<String> {
+    let mut candidates = grotto.active_agent_ids();
+    candidates.sort_by_key(|agent_id| {
+        let agent = &grotto.agents[agent_id];
+        let phase = snapshots
+            .iter()
+            .find(|s| &s.agent_id == agent_id)
+            .map(|s| &s.phase);
+        let busy = !matches!(
+            phase,
+            Some(AgentPhase::Idle | AgentPhase::Finished | AgentPhase::Error)
+        );
+        (
+            agent.current_task.is_some(),
+            busy,
+            std::cmp::Reverse(agent.pane_index),
+        )
+    });
+    candidates.truncate(count);
+    candidates
+}
+
+/// Stop agents and take them off the team: their runtime's exit keys, then
+/// `kill-pane` after `grace`, then requeue their claims (see [`Grotto::retire_agent`]).
+pub fn retire_agents(
+    grotto: &mut Grotto,
+    mux: &dyn Multiplexer,
+    agent_ids: &[String],
+    grace: Duration,
+) -> Result<Vec<(String, Option<String>)>> {
+    let mut targets = Vec::new();
+    for agent_id in agent_ids {
+        targets.push((
+            agent_id.clone(),
+            grotto.get_agent_pane(agent_id)?,
+            grotto.pane_target(agent_id)?,
+        ));
+    }
+    for (agent_id, _, pane) in &targets {
+        let _ = mux.send_keys(pane, &grotto.agent_runtime(agent_id).exit_keys());
+    }
+    if targets.is_empty() {
+        return Ok(Vec::new());
+    }
+    std::thread::sleep(grace);
+
+    // Highest pane first so killing one doesn't renumber the rest, for
+    // agents addressed by index
+    targets.sort_by_key(|(_, pane_index, _)| std::cmp::Reverse(*pane_index));
+    let mut retired = Vec::new();
+    for (agent_id, _, pane) in targets {
+        let _ = mux.kill_pane(&pane);
+        let requeued_task = grotto.retire_agent(&agent_id)?;
+        retired.push((agent_id, requeued_task));
+    }
+    retired.reverse();
+    Ok(retired)
+}
+
+/// Grow or shrink the live team to `target` agents.
+pub fn scale(
+    grotto: &mut Grotto,
+    mux: &dyn Multiplexer,
+    target: usize,
+    snapshots: &[PaneSnapshot],
+    grace: Duration,
+) -> Result<ScaleChange> {
+    if target == 0 {
+        return Err(GrottoError::Io(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "A team needs at least one agent; use `grotto kill all` to stop the session",
+        )));
+    }
+
+    let current = grotto.active_agent_ids().len();
+    let mut change = ScaleChange::default();
+    if target > current {
+        for _ in current..target {
+            change.added.push(add_agent(grotto, mux, None, None, None)?);
+        }
+    } else if target < current {
+        let retirees = pick_retirees(grotto, snapshots, current - target);
+        change.retired = retire_agents(grotto, mux, &retirees, grace)?;
+    }
+    Ok(change)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mux::FakeMultiplexer;
+    use tempfile::TempDir;
+
+    fn snapshot(agent_id: &str, pane_index: usize, phase: AgentPhase) -> PaneSnapshot {
+        PaneSnapshot {
+            agent_id: agent_id.to_string(),
+            pane_index,
+            raw_content: String::new(),
+            phase,
+            last_activity_line: String::new(),
+            timestamp: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn retirees_are_idle_agents_first_then_newest() {
+        let tmp = TempDir::new().unwrap();
+        let mut grotto = Grotto::new(tmp.path(), 4, "t".into()).unwrap();
+        grotto.claim_task("main", "agent-4").unwrap();
+        let snapshots = vec![
+            snapshot("agent-1", 0, AgentPhase::Idle),
+            snapshot("agent-2", 1, AgentPhase::Thinking),
+            snapshot("agent-3", 2, AgentPhase::Running),
+            snapshot("agent-4", 3, AgentPhase::Idle),
+        ];
+
+        assert_eq!(pick_retirees(&grotto, &snapshots, 1), vec!["agent-1"]);
+        assert_eq!(
+            pick_retirees(&grotto, &snapshots, 3),
+            vec!["agent-1", "agent-3", "agent-2"]
+        );
+        assert_eq!(pick_retirees(&grotto, &snapshots, 9).len(), 4);
+    }
+
+    #[test]
+    fn scale_to_zero_is_rejected() {
+        let tmp = TempDir::new().unwrap();
+        let mut grotto = Grotto::new(tmp.path(), 2, "t".into()).unwrap();
+        let err = scale(&mut grotto, &FakeMultiplexer::new(), 0, &[], Duration::ZERO)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("at least one agent"), "{err}");
+        assert_eq!(grotto.active_agent_ids().len(), 2);
+    }
+
+    #[test]
+    fn scale_splits_new_panes_and_closes_retired_ones() {
+        let tmp = TempDir::new().unwrap();
+        let mut grotto = Grotto::new(tmp.path(), 2, "t".into()).unwrap();
+        grotto.claim_task("main", "agent-2").unwrap();
+        let mux = FakeMultiplexer::with_team(&mut grotto);
+        let tmux_session = grotto.config.tmux_session.clone();
+        let agent_in = |pane: &crate::mux::FakePane| {
+            pane.launches[0]
+                .env
+                .iter()
+                .find(|(key, _)| key == "GROTTO_AGENT_ID")
+                .map(|(_, value)| value.clone())
+                .unwrap()
+        };
+
+        let change = scale(&mut grotto, &mux, 3, &[], Duration::ZERO).unwrap();
+        assert_eq!(change.added, vec!["agent-3"]);
+        let panes = mux.panes(&tmux_session);
+        assert_eq!(panes.len(), 3);
+        assert_eq!(agent_in(&panes[2]), "agent-3");
+        assert_eq!(grotto.pane_target("agent-3").unwrap(), panes[2].id);
+        assert!(
+            panes[2]
+                .pipe
+                .as_ref()
+                .unwrap()
+                .ends_with("agents/agent-3/stream.log")
+        );
+
+        // The agent holding a task is kept
+        let change = scale(&mut grotto, &mux, 1, &[], Duration::ZERO).unwrap();
+        assert_eq!(change.retired.len(), 2);
+        let panes = mux.panes(&tmux_session);
+        assert_eq!(panes.len(), 1);
+        assert_eq!(agent_in(&panes[0]), "agent-2");
+        assert_eq!(grotto.active_agent_ids(), vec!["agent-2"]);
+        // Its pane moved to index 0 but keeps its ID
+        assert_eq!(grotto.pane_target("agent-2").unwrap(), panes[0].id);
+    }
+}
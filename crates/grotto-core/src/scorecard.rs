@@ -0,0 +1,512 @@
+use crate::monitor::{self, AgentPhase};
+use crate::usage::{self, Usage};
+use crate::verify::SessionOutcome;
+use crate::{Event, Grotto, Result, changes};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+
+/// Phases shown as columns in the rendered scorecard.
+const PHASE_COLUMNS: [AgentPhase; 4] = [
+    AgentPhase::Thinking,
+    AgentPhase::Editing,
+    AgentPhase::Running,
+    AgentPhase::Idle,
+];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskCounts {
+    pub opened: usize,
+    pub completed: usize,
+    pub requeued: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AgentScore {
+    pub agent_id: String,
+    /// Model the agent ran on, when routed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Seconds spent thinking, editing or running commands
+    pub active_secs: u64,
+    /// Seconds spent in each phase, keyed by phase name
+    pub phase_secs: BTreeMap<String, u64>,
+    pub errors: usize,
+    pub rate_limit_hits: usize,
+    /// Direct `grotto steer` messages received
+    pub steers: usize,
+    /// Commits attributed to the agent (git sessions only)
+    pub commits: usize,
+    #[serde(default)]
+    pub usage: Usage,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerificationSummary {
+    pub round: u32,
+    pub success: bool,
+    pub failed_command: Option<String>,
+}
+
+/// How a session went, for comparing runs over time.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Scorecard {
+    pub session_id: Option<String>,
+    pub task: String,
+    pub started_at: DateTime<Utc>,
+    /// Last recorded activity (event or phase change)
+    pub ended_at: DateTime<Utc>,
+    pub wall_clock_secs: u64,
+    pub outcome: Option<SessionOutcome>,
+    pub tasks: TaskCounts,
+    pub agents: Vec<AgentScore>,
+    pub errors: usize,
+    pub rate_limit_hits: usize,
+    /// Steer and broadcast messages sent to the team
+    pub steering: usize,
+    pub verification: Vec<VerificationSummary>,
+    /// `None` outside a git repo
+    pub files_changed: Option<usize>,
+    pub commits: Option<usize>,
+    #[serde(default)]
+    pub usage: Usage,
+    /// Spent on each task while an agent held it
+    #[serde(default)]
+    pub task_usage: BTreeMap<String, Usage>,
+    /// Spent by agents on each routed model
+    #[serde(default)]
+    pub model_usage: BTreeMap<String, Usage>,
+}
+
+impl Scorecard {
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        out.push_str(&format!(
+            "- Wall clock: {}\n",
+            format_secs(self.wall_clock_secs)
+        ));
+        if let Some(outcome) = self.outcome {
+            out.push_str(&format!("- Outcome: {}\n", outcome));
+        }
+        out.push_str(&format!(
+            "- Tasks: {} opened, {} completed, {} requeued\n",
+            self.tasks.opened, self.tasks.completed, self.tasks.requeued
+        ));
+        out.push_str(&format!("- Steering messages: {}\n", self.steering));
+        if !self.usage.is_empty() {
+            out.push_str(&format!("- Cost: {}\n", self.usage));
+        }
+        if !self.usage.is_empty() && !self.task_usage.is_empty() {
+            let tasks: Vec<String> = self
+                .task_usage
+                .iter()
+                .map(|(task, usage)| format!("{} {}", task, usage::format_cost(usage.cost_usd)))
+                .collect();
+            out.push_str(&format!("- Cost by task: {}\n", tasks.join(", ")));
+        }
+        if !self.usage.is_empty() && !self.model_usage.is_empty() {
+            let models: Vec<String> = self
+                .model_usage
+                .iter()
+                .map(|(model, usage)| format!("{} {}", model, usage::format_cost(usage.cost_usd)))
+                .collect();
+            out.push_str(&format!("- Cost by model: {}\n", models.join(", ")));
+        }
+        out.push_str(&format!(
+            "- Errors: {} (rate-limit hits: {})\n",
+            self.errors, self.rate_limit_hits
+        ));
+        if let (Some(files), Some(commits)) = (self.files_changed, self.commits) {
+            out.push_str(&format!(
+                "- Files changed: {} ({} commits)\n",
+                files, commits
+            ));
+        }
+        if !self.verification.is_empty() {
+            let rounds: Vec<String> = self
+                .verification
+                .iter()
+                .map(|v| {
+                    format!(
+                        "round {} {}",
+                        v.round,
+                        if v.success { "passed" } else { "failed" }
+                    )
+                })
+                .collect();
+            out.push_str(&format!("- Verification: {}\n", rounds.join(", ")));
+        }
+
+        out.push_str("\n| Agent | Model | Active |");
+        for phase in &PHASE_COLUMNS {
+            out.push_str(&format!(" {} |", phase));
+        }
+        out.push_str(" Errors | Rate limits | Steers | Commits | Cost |\n|---|---|---|");
+        for _ in &PHASE_COLUMNS {
+            out.push_str("---|");
+        }
+        out.push_str("---|---|---|---|---|\n");
+        for agent in &self.agents {
+            out.push_str(&format!(
+                "| {} | {} | {} |",
+                agent.agent_id,
+                agent.model.as_deref().unwrap_or("-"),
+                format_secs(agent.active_secs)
+            ));
+            for phase in &PHASE_COLUMNS {
+                let secs = agent
+                    .phase_secs
+                    .get(&phase.to_string())
+                    .copied()
+                    .unwrap_or(0);
+                out.push_str(&format!(" {} |", format_secs(secs)));
+            }
+            out.push_str(&format!(
+                " {} | {} | {} | {} | {} |\n",
+                agent.errors,
+                agent.rate_limit_hits,
+                agent.steers,
+                agent.commits,
+                usage::format_cost(agent.usage.cost_usd)
+            ));
+        }
+        out
+    }
+}
+
+/// Build the scorecard for the current session from events, recorded phase
+/// transitions and git.
+pub fn build(grotto: &Grotto) -> Result<Scorecard> {
+    let events = grotto.read_session_events()?;
+    let started_at = events.first().map(|e| e.timestamp).unwrap_or_else(Utc::now);
+
+    let transitions = monitor::session_timeline(grotto)?;
+
+    let ended_at = events
+        .iter()
+        .map(|e| e.timestamp)
+        .chain(transitions.values().flatten().map(|t| t.timestamp))
+        .max()
+        .unwrap_or(started_at);
+
+    let commits = grotto
+        .config
+        .git
+        .as_ref()
+        .and_then(|_| changes::build(grotto).ok())
+        .map(|report| report.commits);
+    let files_changed = grotto
+        .config
+        .git
+        .as_ref()
+        .and_then(|_| changes::changed_files(grotto).ok())
+        .map(|files| files.len());
+    let usage = usage::report(grotto)?;
+
+    let mut agents = Vec::new();
+    for (agent_id, agent_transitions) in &transitions {
+        let mut score = AgentScore {
+            agent_id: agent_id.clone(),
+            model: grotto.agents.get(agent_id).and_then(|a| a.model.clone()),
+            ..Default::default()
+        };
+        for (i, transition) in agent_transitions.iter().enumerate() {
+            let until = agent_transitions
+                .get(i + 1)
+                .map(|next| next.timestamp)
+                .unwrap_or(ended_at);
+            let secs = (until - transition.timestamp).num_seconds().max(0) as u64;
+            *score
+                .phase_secs
+                .entry(transition.phase.to_string())
+                .or_insert(0) += secs;
+            if matches!(
+                transition.phase,
+                AgentPhase::Thinking | AgentPhase::Editing | AgentPhase::Running
+            ) {
+                score.active_secs += secs;
+            }
+            if transition.phase == AgentPhase::RateLimited {
+                score.rate_limit_hits += 1;
+            } else if transition.phase == AgentPhase::Error {
+                // Recorded before rate limits got their own phase
+                if monitor::is_rate_limit_line(&transition.last_activity) {
+                    score.rate_limit_hits += 1;
+                } else {
+                    score.errors += 1;
+                }
+            }
+        }
+        score.errors += events
+            .iter()
+            .filter(|e| e.event_type == "startup_failed" && e.agent_id.as_deref() == Some(agent_id))
+            .count();
+        score.steers = events
+            .iter()
+            .filter(|e| e.event_type == "agent_steered" && e.agent_id.as_deref() == Some(agent_id))
+            .count();
+        score.commits = commits
+            .iter()
+            .flatten()
+            .filter(|c| c.agent_id.as_deref() == Some(agent_id))
+            .count();
+        score.usage = usage.agents.get(agent_id).copied().unwrap_or_default();
+        agents.push(score);
+    }
+
+    let model_usage = model_usage(&agents);
+    Ok(Scorecard {
+        session_id: grotto.config.session_id.clone(),
+        task: grotto.config.task.clone(),
+        started_at,
+        ended_at,
+        wall_clock_secs: (ended_at - started_at).num_seconds().max(0) as u64,
+        outcome: grotto.config.outcome,
+        tasks: task_counts(&events),
+        errors: agents.iter().map(|a| a.errors).sum(),
+        rate_limit_hits: agents.iter().map(|a| a.rate_limit_hits).sum(),
+        agents,
+        steering: events
+            .iter()
+            .filter(|e| e.event_type == "agent_steered" || e.event_type == "broadcast")
+            .count(),
+        verification: events
+            .iter()
+            .filter(|e| e.event_type == "verification_round")
+            .map(|e| VerificationSummary {
+                round: e.data["round"].as_u64().unwrap_or(0) as u32,
+                success: e.data["success"].as_bool().unwrap_or(false),
+                failed_command: e.data["failed_command"].as_str().map(|s| s.to_string()),
+            })
+            .collect(),
+        files_changed,
+        commits: commits.map(|c| c.len()),
+        model_usage,
+        usage: usage.total,
+        task_usage: usage.tasks,
+    })
+}
+
+/// Build the scorecard and write `.grotto/scorecard.json` plus the
+/// "Scorecard" section of `summary.md`.
+pub fn refresh(grotto: &Grotto) -> Result<Scorecard> {
+    let scorecard = build(grotto)?;
+    fs::write(
+        grotto.grotto_dir.join("scorecard.json"),
+        serde_json::to_string_pretty(&scorecard)?,
+    )?;
+    grotto.write_summary_section("Scorecard", &scorecard.to_markdown())?;
+    Ok(scorecard)
+}
+
+fn model_usage(agents: &[AgentScore]) -> BTreeMap<String, Usage> {
+    let mut by_model: BTreeMap<String, Usage> = BTreeMap::new();
+    for agent in agents {
+        if let Some(model) = &agent.model {
+            *by_model.entry(model.clone()).or_default() += agent.usage;
+        }
+    }
+    by_model
+}
+
+fn task_counts(events: &[Event]) -> TaskCounts {
+    let mut opened: BTreeSet<&str> = BTreeSet::new();
+    let mut completed: BTreeSet<&str> = BTreeSet::new();
+    let mut requeued = 0;
+    for event in events {
+        match event.event_type.as_str() {
+            // The main task is created with the team
+            "team_spawned" => {
+                opened.insert("main");
+            }
+            "task_completed" => {
+                if let Some(id) = event.task_id.as_deref() {
+                    opened.insert(id);
+                    completed.insert(id);
+                }
+            }
+            "task_requeued" => requeued += 1,
+            _ => {
+                if let Some(id) = event.task_id.as_deref() {
+                    opened.insert(id);
+                }
+            }
+        }
+    }
+    TaskCounts {
+        opened: opened.len(),
+        completed: completed.len(),
+        requeued,
+    }
+}
+
+fn format_secs(secs: u64) -> String {
+    format!("{}m {}s", secs / 60, secs % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitor::{PhaseTransition, record_phase_transition};
+    use chrono::Duration;
+    use tempfile::TempDir;
+
+    fn transition(at: DateTime<Utc>, phase: AgentPhase, line: &str) -> PhaseTransition {
+        PhaseTransition {
+            timestamp: at,
+            phase,
+            last_activity: line.to_string(),
+        }
+    }
+
+    #[test]
+    fn build_counts_tasks_steering_and_verification() {
+        let tmp = TempDir::new().unwrap();
+        let mut grotto = Grotto::new(tmp.path(), 2, "task".into()).unwrap();
+        grotto.config.model = Some("sonnet".into());
+        grotto.assign_model("agent-1").unwrap();
+        grotto.claim_task("main", "agent-1").unwrap();
+        std::fs::write(
+            grotto.grotto_dir.join("agents/agent-1/output.log"),
+            "Total cost: $0.30\nUsage: 20k input, 1.5k output\n",
+        )
+        .unwrap();
+        grotto.complete_task("main").unwrap();
+        grotto
+            .log_event(
+                "agent_steered",
+                Some("agent-2"),
+                None,
+                None,
+                serde_json::json!({}),
+            )
+            .unwrap();
+        grotto
+            .log_event("broadcast", None, None, None, serde_json::json!({}))
+            .unwrap();
+        grotto
+            .log_event(
+                "verification_round",
+                None,
+                None,
+                None,
+                serde_json::json!({ "round": 1, "success": false, "failed_command": "make test" }),
+            )
+            .unwrap();
+
+        let card = build(&grotto).unwrap();
+        assert_eq!(
+            card.tasks,
+            TaskCounts {
+                opened: 1,
+                completed: 1,
+                requeued: 0
+            }
+        );
+        assert_eq!(card.steering, 2);
+        assert_eq!(card.agents[1].steers, 1);
+        assert_eq!(
+            card.verification,
+            vec![VerificationSummary {
+                round: 1,
+                success: false,
+                failed_command: Some("make test".to_string())
+            }]
+        );
+        assert_eq!(card.files_changed, None);
+        assert_eq!(card.usage.input_tokens, 20_000);
+        assert!((card.agents[0].usage.cost_usd - 0.30).abs() < 1e-9);
+        assert!((card.task_usage["main"].cost_usd - 0.30).abs() < 1e-9);
+
+        let md = card.to_markdown();
+        assert!(md.contains("- Cost: $0.30 (20.0k in / 1.5k out)"), "{md}");
+        assert!(md.contains("- Cost by task: main $0.30"), "{md}");
+        assert!(md.contains("- Cost by model: sonnet $0.30"), "{md}");
+        assert!(md.contains("| agent-1 | sonnet |"), "{md}");
+        assert!(md.contains("| agent-2 | - |"), "{md}");
+        assert!(md.contains("| 0 | 0 | 0 | 0 | $0.30 |"), "{md}");
+    }
+
+    #[test]
+    fn build_sums_phase_time_from_transitions() {
+        let tmp = TempDir::new().unwrap();
+        let grotto = Grotto::new(tmp.path(), 1, "task".into()).unwrap();
+        let t0 = Utc::now();
+        for t in [
+            transition(t0, AgentPhase::Thinking, "◐ Thinking"),
+            transition(
+                t0 + Duration::seconds(30),
+                AgentPhase::Running,
+                "$ cargo test",
+            ),
+            transition(
+                t0 + Duration::seconds(90),
+                AgentPhase::Error,
+                "API error: Rate limit reached",
+            ),
+            transition(
+                t0 + Duration::seconds(100),
+                AgentPhase::Error,
+                "error: build failed",
+            ),
+            transition(t0 + Duration::seconds(110), AgentPhase::Idle, "claude>"),
+        ] {
+            record_phase_transition(&grotto.grotto_dir, "agent-1", &t).unwrap();
+        }
+
+        let card = build(&grotto).unwrap();
+        let agent = &card.agents[0];
+        assert_eq!(agent.phase_secs["thinking"], 30);
+        assert_eq!(agent.phase_secs["running"], 60);
+        assert_eq!(agent.phase_secs["error"], 20);
+        assert_eq!(agent.active_secs, 90);
+        assert_eq!(agent.rate_limit_hits, 1);
+        assert_eq!(agent.errors, 1);
+        assert_eq!(card.rate_limit_hits, 1);
+        assert!(card.wall_clock_secs >= 110);
+
+        let md = card.to_markdown();
+        assert!(md.contains("| agent-1 | - | 1m 30s | 0m 30s | 0m 0s | 1m 0s |"));
+    }
+
+    #[test]
+    fn build_ignores_previous_sessions() {
+        let tmp = TempDir::new().unwrap();
+        let old = Grotto::new(tmp.path(), 1, "old".into()).unwrap();
+        old.log_event("broadcast", None, None, None, serde_json::json!({}))
+            .unwrap();
+        record_phase_transition(
+            &old.grotto_dir,
+            "agent-1",
+            &transition(Utc::now() - Duration::hours(1), AgentPhase::Running, "$ ls"),
+        )
+        .unwrap();
+
+        let grotto = Grotto::new(tmp.path(), 1, "new".into()).unwrap();
+        let card = build(&grotto).unwrap();
+        assert_eq!(card.steering, 0);
+        assert!(card.agents[0].phase_secs.is_empty());
+    }
+
+    #[test]
+    fn refresh_writes_json_and_summary() {
+        let tmp = TempDir::new().unwrap();
+        crate::git::test_support::init_repo(tmp.path());
+        let grotto = Grotto::new(tmp.path(), 1, "task".into()).unwrap();
+        std::fs::write(tmp.path().join("new.rs"), "\n").unwrap();
+        crate::git::test_support::commit_all(tmp.path(), "Add new\n\nGrotto-Agent: agent-1");
+
+        let card = refresh(&grotto).unwrap();
+        assert_eq!(card.files_changed, Some(1));
+        assert_eq!(card.commits, Some(1));
+        assert_eq!(card.agents[0].commits, 1);
+
+        let json = std::fs::read_to_string(tmp.path().join(".grotto/scorecard.json")).unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed["files_changed"], 1);
+        let summary = std::fs::read_to_string(tmp.path().join(".grotto/summary.md")).unwrap();
+        assert!(summary.contains("## Scorecard"));
+        assert!(summary.contains("- Files changed: 1 (1 commits)"));
+    }
+}
@@ -0,0 +1,466 @@
+use crate::monitor::{AgentPhase, PaneSnapshot};
+use crate::mux::Multiplexer;
+use crate::steer::{self, SteerOptions};
+use crate::{Grotto, GrottoError, Result, budget};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+
+/// How long a working agent's screen may stay unchanged before it counts as
+/// stalled, unless `spawn --stall-after` says otherwise.
+pub const DEFAULT_STALL_AFTER_SECS: u64 = 600;
+
+/// Times the same command or error line may show up before the agent counts
+/// as looping, unless `spawn --loop-repeats` says otherwise.
+pub const DEFAULT_LOOP_REPEATS: u32 = 3;
+
+/// Recent command and error lines remembered per agent for loop detection.
+const LOOP_WINDOW: usize = 30;
+
+/// What to do about a stalled or looping agent.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StallAction {
+    /// Log and broadcast the signal only
+    #[default]
+    Notify,
+    /// Also paste a message into the agent's pane telling it to change course
+    Nudge,
+    /// Also mark the agent `stuck` so the lead picks it up
+    Escalate,
+}
+
+impl StallAction {
+    pub fn is_notify(&self) -> bool {
+        *self == StallAction::Notify
+    }
+}
+
+impl fmt::Display for StallAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StallAction::Notify => write!(f, "notify"),
+            StallAction::Nudge => write!(f, "nudge"),
+            StallAction::Escalate => write!(f, "escalate"),
+        }
+    }
+}
+
+impl std::str::FromStr for StallAction {
+    type Err = GrottoError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "notify" => Ok(StallAction::Notify),
+            "nudge" => Ok(StallAction::Nudge),
+            "escalate" => Ok(StallAction::Escalate),
+            other => Err(GrottoError::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "Unknown stall action '{}'. Valid actions: notify, nudge, escalate",
+                    other
+                ),
+            ))),
+        }
+    }
+}
+
+/// An agent that looks busy but isn't getting anywhere.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Signal {
+    /// The agent's screen hasn't changed for `idle_secs` while it was working
+    Stalled {
+        agent_id: String,
+        idle_secs: u64,
+        line: String,
+    },
+    /// The same command or error line keeps coming back
+    Looping {
+        agent_id: String,
+        line: String,
+        repeats: u32,
+    },
+}
+
+impl Signal {
+    pub fn agent_id(&self) -> &str {
+        match self {
+            Signal::Stalled { agent_id, .. } | Signal::Looping { agent_id, .. } => agent_id,
+        }
+    }
+
+    /// Event type logged for the signal.
+    pub fn event_type(&self) -> &'static str {
+        match self {
+            Signal::Stalled { .. } => "agent_stalled",
+            Signal::Looping { .. } => "agent_looping",
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Signal::Stalled {
+                agent_id,
+                idle_secs,
+                ..
+            } => format!(
+                "{} has shown no new output for {}",
+                agent_id,
+                budget::format_duration(*idle_secs)
+            ),
+            Signal::Looping {
+                agent_id,
+                line,
+                repeats,
+            } => format!("{} repeated `{}` {} times", agent_id, line, repeats),
+        }
+    }
+
+    fn nudge(&self) -> String {
+        match self {
+            Signal::Stalled { idle_secs, .. } => format!(
+                "[grotto] Your screen hasn't changed in {}. If you are stuck, run `grotto report --blocked-on` with what you need, or try a different approach.",
+                budget::format_duration(*idle_secs)
+            ),
+            Signal::Looping { line, repeats, .. } => format!(
+                "[grotto] `{}` has come up {} times. Stop repeating it: read the error, try a different approach, or report what blocks you with `grotto report --blocked-on`.",
+                line, repeats
+            ),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Watched {
+    screen: Vec<String>,
+    unchanged_since: Option<DateTime<Utc>>,
+    stalled: bool,
+    recent: VecDeque<String>,
+    looping: HashSet<String>,
+}
+
+/// Tracks each agent's screen across monitor ticks to spot stalls and loops.
+#[derive(Default)]
+pub struct StallDetector {
+    agents: HashMap<String, Watched>,
+}
+
+impl StallDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Compare the latest snapshots with earlier ones. Each stall and each
+    /// looping line is signalled once, until the agent moves on.
+    pub fn observe(
+        &mut self,
+        snapshots: &[PaneSnapshot],
+        stall_after_secs: u64,
+        loop_repeats: u32,
+        now: DateTime<Utc>,
+    ) -> Vec<Signal> {
+        let mut signals = Vec::new();
+        for snap in snapshots {
+            let screen = screen_lines(&snap.raw_content);
+            let watched = self.agents.entry(snap.agent_id.clone()).or_default();
+
+            if watched.unchanged_since.is_none() || screen != watched.screen {
+                for line in new_lines(&watched.screen, &screen) {
+                    if is_command_or_error(line) {
+                        watched.recent.push_back(line.trim().to_string());
+                        if watched.recent.len() > LOOP_WINDOW {
+                            watched.recent.pop_front();
+                        }
+                    }
+                }
+                watched.screen = screen;
+                watched.unchanged_since = Some(now);
+                watched.stalled = false;
+            } else if working(&snap.phase) && !watched.stalled {
+                let idle_secs = watched
+                    .unchanged_since
+                    .map_or(0, |since| (now - since).num_seconds().max(0) as u64);
+                if idle_secs >= stall_after_secs {
+                    watched.stalled = true;
+                    signals.push(Signal::Stalled {
+                        agent_id: snap.agent_id.clone(),
+                        idle_secs,
+                        line: snap.last_activity_line.clone(),
+                    });
+                }
+            }
+
+            let mut counts: HashMap<&str, u32> = HashMap::new();
+            for line in &watched.recent {
+                *counts.entry(line.as_str()).or_default() += 1;
+            }
+            watched
+                .looping
+                .retain(|line| counts.get(line.as_str()).copied().unwrap_or(0) >= loop_repeats);
+            let mut repeated: Vec<(&str, u32)> = counts
+                .into_iter()
+                .filter(|(line, n)| *n >= loop_repeats && !watched.looping.contains(*line))
+                .collect();
+            repeated.sort();
+            for (line, repeats) in repeated {
+                watched.looping.insert(line.to_string());
+                signals.push(Signal::Looping {
+                    agent_id: snap.agent_id.clone(),
+                    line: line.to_string(),
+                    repeats,
+                });
+            }
+        }
+        signals
+    }
+}
+
+/// Phases in which an unchanged screen means the agent is stuck rather than
+/// waiting for work.
+fn working(phase: &AgentPhase) -> bool {
+    matches!(
+        phase,
+        AgentPhase::Thinking | AgentPhase::Editing | AgentPhase::Running | AgentPhase::Error
+    )
+}
+
+fn screen_lines(content: &str) -> Vec<String> {
+    let mut lines: Vec<String> = content.lines().map(|l| l.trim_end().to_string()).collect();
+    while lines.last().is_some_and(|l| l.is_empty()) {
+        lines.pop();
+    }
+    lines
+}
+
+/// Lines of `current` that weren't on `previous`: what follows the part of
+/// `previous` still on screen after scrolling, or, when the screen was
+/// redrawn, lines that now appear more often than before.
+fn new_lines<'a>(previous: &[String], current: &'a [String]) -> Vec<&'a String> {
+    for start in 0..previous.len() {
+        let kept = &previous[start..];
+        if current.len() >= kept.len() && current[..kept.len()] == *kept {
+            return current[kept.len()..].iter().collect();
+        }
+    }
+    let mut before: HashMap<&str, usize> = HashMap::new();
+    for line in previous {
+        *before.entry(line.as_str()).or_default() += 1;
+    }
+    current
+        .iter()
+        .filter(|line| match before.get_mut(line.as_str()) {
+            Some(n) if *n > 0 => {
+                *n -= 1;
+                false
+            }
+            _ => true,
+        })
+        .collect()
+}
+
+/// A shell command the agent ran or an error it hit.
+fn is_command_or_error(line: &str) -> bool {
+    let line = line.trim();
+    let lower = line.to_lowercase();
+    line.starts_with("$ ")
+        || line.starts_with("Bash(")
+        || line.starts_with("● Bash(")
+        || lower.starts_with("error")
+        || lower.contains("error:")
+        || lower.contains("failed")
+        || lower.contains("panicked")
+}
+
+/// Log the signal and carry out the session's stall action: nudge the
+/// agent, or mark it `stuck` for the lead.
+pub fn apply(
+    grotto: &mut Grotto,
+    mux: &dyn Multiplexer,
+    signal: &Signal,
+    action: StallAction,
+) -> Result<()> {
+    let agent_id = signal.agent_id().to_string();
+    let mut delivery = None;
+    match action {
+        StallAction::Notify => {}
+        StallAction::Nudge => {
+            let options = SteerOptions {
+                busy_polls: 0,
+                ..SteerOptions::default()
+            };
+            delivery =
+                Some(steer::deliver(grotto, mux, &agent_id, &signal.nudge(), options).delivery);
+        }
+        StallAction::Escalate => {
+            if let Some(agent) = grotto.agents.get_mut(&agent_id) {
+                agent.state = "stuck".to_string();
+                agent.progress = signal.describe();
+                agent.last_update = Utc::now();
+            }
+            grotto.write_agent_status(&agent_id)?;
+        }
+    }
+
+    let mut data = match signal {
+        Signal::Stalled {
+            idle_secs, line, ..
+        } => serde_json::json!({ "idle_secs": idle_secs, "line": line }),
+        Signal::Looping { line, repeats, .. } => {
+            serde_json::json!({ "line": line, "repeats": repeats })
+        }
+    };
+    data["action"] = serde_json::json!(action);
+    if let Some(delivery) = delivery {
+        data["delivery"] = serde_json::json!(delivery);
+    }
+    let task_id = grotto
+        .agents
+        .get(&agent_id)
+        .and_then(|a| a.current_task.clone());
+    grotto.log_event(
+        signal.event_type(),
+        Some(&agent_id),
+        task_id.as_deref(),
+        Some(&signal.describe()),
+        data,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mux::FakeMultiplexer;
+    use chrono::Duration;
+    use tempfile::TempDir;
+
+    fn snapshot(agent_id: &str, phase: AgentPhase, screen: &str) -> PaneSnapshot {
+        PaneSnapshot {
+            agent_id: agent_id.to_string(),
+            pane_index: 0,
+            raw_content: screen.to_string(),
+            phase,
+            last_activity_line: screen.lines().last().unwrap_or_default().to_string(),
+            timestamp: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn stall_is_signalled_once_after_the_threshold() {
+        let mut detector = StallDetector::new();
+        let t0 = Utc::now();
+        let running = [snapshot("agent-1", AgentPhase::Running, "$ cargo build")];
+
+        assert!(detector.observe(&running, 600, 3, t0).is_empty());
+        let early = t0 + Duration::seconds(599);
+        assert!(detector.observe(&running, 600, 3, early).is_empty());
+        let late = t0 + Duration::seconds(700);
+        assert_eq!(
+            detector.observe(&running, 600, 3, late),
+            vec![Signal::Stalled {
+                agent_id: "agent-1".into(),
+                idle_secs: 700,
+                line: "$ cargo build".into(),
+            }]
+        );
+        assert!(detector.observe(&running, 600, 3, late).is_empty());
+
+        // New output starts the clock again
+        let moved = [snapshot(
+            "agent-1",
+            AgentPhase::Running,
+            "$ cargo build\nCompiling grotto",
+        )];
+        assert!(detector.observe(&moved, 600, 3, late).is_empty());
+        let later = late + Duration::seconds(601);
+        assert_eq!(detector.observe(&moved, 600, 3, later).len(), 1);
+    }
+
+    #[test]
+    fn idle_agents_are_not_stalled() {
+        let mut detector = StallDetector::new();
+        let t0 = Utc::now();
+        let idle = [snapshot("agent-1", AgentPhase::Idle, "claude>")];
+        detector.observe(&idle, 60, 3, t0);
+        assert!(
+            detector
+                .observe(&idle, 60, 3, t0 + Duration::hours(1))
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn repeated_failing_command_is_looping_even_as_the_screen_scrolls() {
+        let mut detector = StallDetector::new();
+        let t0 = Utc::now();
+        let mut screen = vec!["working on tests".to_string()];
+        let mut signals = Vec::new();
+        for round in 0..3 {
+            screen.push("$ cargo test".into());
+            screen.push(format!("test run {round}"));
+            screen.push("error: test failed, to rerun pass `--lib`".into());
+            // Keep only the last few lines, like a small pane
+            let visible = screen[screen.len().saturating_sub(5)..].join("\n");
+            let snap = [snapshot("agent-1", AgentPhase::Running, &visible)];
+            signals.extend(detector.observe(&snap, 600, 3, t0));
+        }
+        assert_eq!(
+            signals,
+            vec![
+                Signal::Looping {
+                    agent_id: "agent-1".into(),
+                    line: "$ cargo test".into(),
+                    repeats: 3,
+                },
+                Signal::Looping {
+                    agent_id: "agent-1".into(),
+                    line: "error: test failed, to rerun pass `--lib`".into(),
+                    repeats: 3,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn apply_nudges_or_escalates() {
+        let tmp = TempDir::new().unwrap();
+        let mut grotto = Grotto::new(tmp.path(), 2, "t".into()).unwrap();
+        let mux = FakeMultiplexer::with_team(&mut grotto);
+        let target = grotto.pane_target("agent-1").unwrap();
+        mux.set_screen(&target, "$ cargo test");
+        let signal = Signal::Looping {
+            agent_id: "agent-1".into(),
+            line: "$ cargo test".into(),
+            repeats: 3,
+        };
+
+        apply(&mut grotto, &mux, &signal, StallAction::Nudge).unwrap();
+        let pasted = mux.pane(&target).unwrap().pasted;
+        assert_eq!(pasted.len(), 1);
+        assert!(pasted[0].contains("`$ cargo test` has come up 3 times"));
+        let event = grotto.read_events().unwrap().pop().unwrap();
+        assert_eq!(event.event_type, "agent_looping");
+        assert_eq!(event.data["action"], "nudge");
+        assert_eq!(event.data["delivery"], "delivered");
+
+        let stalled = Signal::Stalled {
+            agent_id: "agent-2".into(),
+            idle_secs: 1200,
+            line: "Running…".into(),
+        };
+        apply(&mut grotto, &mux, &stalled, StallAction::Escalate).unwrap();
+        assert_eq!(grotto.agents["agent-2"].state, "stuck");
+        assert_eq!(
+            grotto.agents["agent-2"].progress,
+            "agent-2 has shown no new output for 20m"
+        );
+    }
+
+    #[test]
+    fn stall_action_parses_and_displays() {
+        for action in ["notify", "nudge", "escalate"] {
+            assert_eq!(action.parse::<StallAction>().unwrap().to_string(), action);
+        }
+        assert!("panic".parse::<StallAction>().is_err());
+    }
+}
@@ -0,0 +1,485 @@
+use crate::monitor::AgentPhase;
+use crate::mux::Multiplexer;
+use crate::{Backend, Grotto, GrottoError, Result, headless};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Messages waiting for a busy agent, one JSON line each, in its agent dir.
+pub const QUEUE_FILE: &str = "steer_queue.jsonl";
+
+/// Characters from the end of a message looked for in the pane to confirm
+/// it arrived; long pastes wrap and scroll, the tail stays visible.
+const CONFIRM_CHARS: usize = 24;
+
+/// What happened to a steering message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Delivery {
+    /// Pasted, seen in the pane and submitted
+    Delivered,
+    /// The agent was thinking; the message waits in its queue
+    Queued,
+    /// The pane is dead or the message never showed up in it
+    Failed,
+}
+
+impl fmt::Display for Delivery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Delivery::Delivered => write!(f, "delivered"),
+            Delivery::Queued => write!(f, "queued"),
+            Delivery::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+/// A delivery and, unless it was delivered, why not.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    pub delivery: Delivery,
+    pub reason: Option<String>,
+}
+
+impl Outcome {
+    fn delivered() -> Self {
+        Self {
+            delivery: Delivery::Delivered,
+            reason: None,
+        }
+    }
+
+    fn not(delivery: Delivery, reason: impl Into<String>) -> Self {
+        Self {
+            delivery,
+            reason: Some(reason.into()),
+        }
+    }
+}
+
+/// How patiently messages are delivered.
+#[derive(Debug, Clone, Copy)]
+pub struct SteerOptions {
+    /// Wait between pane captures
+    pub poll: Duration,
+    /// Captures after pasting before giving up on seeing the message
+    pub confirm_polls: u32,
+    /// Captures waiting for a thinking agent before queueing the message
+    pub busy_polls: u32,
+}
+
+impl Default for SteerOptions {
+    fn default() -> Self {
+        Self {
+            poll: Duration::from_millis(250),
+            confirm_polls: 8,
+            busy_polls: 8,
+        }
+    }
+}
+
+/// A message waiting in an agent's queue.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueuedMessage {
+    pub timestamp: DateTime<Utc>,
+    pub message: String,
+}
+
+/// Where an agent's keystrokes go.
+enum Terminal<'a> {
+    Pane {
+        mux: &'a dyn Multiplexer,
+        target: String,
+    },
+    Headless {
+        grotto: &'a Grotto,
+        agent_id: String,
+    },
+}
+
+impl<'a> Terminal<'a> {
+    fn of(grotto: &'a Grotto, mux: &'a dyn Multiplexer, agent_id: &str) -> Result<Self> {
+        match grotto.config.backend {
+            Backend::Tmux => Ok(Terminal::Pane {
+                mux,
+                target: grotto.pane_target(agent_id)?,
+            }),
+            Backend::Headless => {
+                grotto.get_agent_pane(agent_id)?;
+                Ok(Terminal::Headless {
+                    grotto,
+                    agent_id: agent_id.to_string(),
+                })
+            }
+        }
+    }
+
+    fn check_alive(&self) -> Result<()> {
+        match self {
+            Terminal::Pane { mux, target } => mux.check_alive(target),
+            Terminal::Headless { .. } => match self.capture() {
+                Some(_) => Ok(()),
+                None => Err(GrottoError::DeadPane(format!(
+                    "{} is not running",
+                    self.name()
+                ))),
+            },
+        }
+    }
+
+    fn capture(&self) -> Option<String> {
+        match self {
+            Terminal::Pane { mux, target } => mux.capture(target, 0),
+            Terminal::Headless { grotto, agent_id } => {
+                headless::capture(&grotto.grotto_dir, agent_id)
+            }
+        }
+    }
+
+    fn paste(&self, text: &str) -> Result<()> {
+        match self {
+            Terminal::Pane { mux, target } => mux.paste(target, text),
+            Terminal::Headless { grotto, agent_id } => {
+                headless::paste(&grotto.grotto_dir, agent_id, text)
+            }
+        }
+    }
+
+    fn enter(&self) -> Result<()> {
+        let enter = ["Enter".to_string()];
+        match self {
+            Terminal::Pane { mux, target } => mux.send_keys(target, &enter),
+            Terminal::Headless { grotto, agent_id } => {
+                headless::send_keys(&grotto.grotto_dir, agent_id, &enter)
+            }
+        }
+    }
+
+    fn name(&self) -> &str {
+        match self {
+            Terminal::Pane { target, .. } => target,
+            Terminal::Headless { agent_id, .. } => agent_id,
+        }
+    }
+}
+
+/// Paste `message` into an agent's terminal and press Enter once it shows
+/// up. A thinking agent gets `busy_polls` captures to finish before the
+/// message is left for the caller to queue; nothing is typed into a dead
+/// pane.
+pub fn deliver(
+    grotto: &Grotto,
+    mux: &dyn Multiplexer,
+    agent_id: &str,
+    message: &str,
+    options: SteerOptions,
+) -> Outcome {
+    let terminal = match Terminal::of(grotto, mux, agent_id) {
+        Ok(terminal) => terminal,
+        Err(e) => return Outcome::not(Delivery::Failed, e.to_string()),
+    };
+    if let Err(e) = terminal.check_alive() {
+        return Outcome::not(Delivery::Failed, e.to_string());
+    }
+
+    let mut before = terminal.capture().unwrap_or_default();
+    let mut polls = 0;
+    while grotto.infer_agent_phase(agent_id, &before) == AgentPhase::Thinking {
+        if polls >= options.busy_polls {
+            return Outcome::not(Delivery::Queued, "agent is thinking");
+        }
+        std::thread::sleep(options.poll);
+        before = terminal.capture().unwrap_or_default();
+        polls += 1;
+    }
+
+    if let Err(e) = terminal.paste(message) {
+        return Outcome::not(Delivery::Failed, e.to_string());
+    }
+    for poll in 0..=options.confirm_polls {
+        if poll > 0 {
+            std::thread::sleep(options.poll);
+        }
+        let after = terminal.capture().unwrap_or_default();
+        if appeared(&before, &after, message) {
+            return match terminal.enter() {
+                Ok(()) => Outcome::delivered(),
+                Err(e) => Outcome::not(Delivery::Failed, e.to_string()),
+            };
+        }
+    }
+    Outcome::not(Delivery::Failed, "message did not appear in the pane")
+}
+
+/// Whether `message` was pasted between two captures: the end of its last
+/// line shows up once more than before, or the program collapsed it into a
+/// `[Pasted text ...]` placeholder.
+fn appeared(before: &str, after: &str, message: &str) -> bool {
+    let squash = |s: &str| s.chars().filter(|c| !c.is_whitespace()).collect::<String>();
+    let (before, after) = (squash(before), squash(after));
+    let last_line = message
+        .lines()
+        .rev()
+        .map(squash)
+        .find(|l| !l.is_empty())
+        .unwrap_or_default();
+    let skip = last_line.chars().count().saturating_sub(CONFIRM_CHARS);
+    let tail: String = last_line.chars().skip(skip).collect();
+    if tail.is_empty() {
+        return true;
+    }
+    let placeholder = "[Pastedtext";
+    after.matches(tail.as_str()).count() > before.matches(tail.as_str()).count()
+        || after.matches(placeholder).count() > before.matches(placeholder).count()
+}
+
+/// Deliver `message` to one agent, queueing it if the agent is busy, and
+/// log `agent_steered` with the outcome.
+pub fn steer(
+    grotto: &Grotto,
+    mux: &dyn Multiplexer,
+    agent_id: &str,
+    message: &str,
+    options: SteerOptions,
+) -> Result<Outcome> {
+    let outcome = deliver(grotto, mux, agent_id, message, options);
+    if outcome.delivery == Delivery::Queued {
+        enqueue(grotto, agent_id, message)?;
+    }
+    grotto.log_event(
+        "agent_steered",
+        Some(agent_id),
+        None,
+        Some(message),
+        serde_json::json!({
+            "message": message,
+            "delivery": outcome.delivery,
+            "reason": outcome.reason,
+        }),
+    )?;
+    Ok(outcome)
+}
+
+/// Deliver `message` to every active agent, queueing it for busy ones, and
+/// log one `broadcast` event with each agent's outcome.
+pub fn broadcast(
+    grotto: &Grotto,
+    mux: &dyn Multiplexer,
+    message: &str,
+    options: SteerOptions,
+) -> Result<Vec<(String, Outcome)>> {
+    let mut outcomes = Vec::new();
+    for agent_id in grotto.active_agent_ids() {
+        let outcome = deliver(grotto, mux, &agent_id, message, options);
+        if outcome.delivery == Delivery::Queued {
+            enqueue(grotto, &agent_id, message)?;
+        }
+        outcomes.push((agent_id, outcome));
+    }
+    let deliveries: BTreeMap<&str, Delivery> = outcomes
+        .iter()
+        .map(|(agent_id, outcome)| (agent_id.as_str(), outcome.delivery))
+        .collect();
+    grotto.log_event(
+        "broadcast",
+        None,
+        None,
+        Some(message),
+        serde_json::json!({
+            "message": message,
+            "agent_count": outcomes.len(),
+            "deliveries": deliveries,
+        }),
+    )?;
+    Ok(outcomes)
+}
+
+fn queue_path(grotto: &Grotto, agent_id: &str) -> PathBuf {
+    grotto
+        .grotto_dir
+        .join("agents")
+        .join(agent_id)
+        .join(QUEUE_FILE)
+}
+
+fn enqueue(grotto: &Grotto, agent_id: &str, message: &str) -> Result<()> {
+    let path = queue_path(grotto, agent_id);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let entry = QueuedMessage {
+        timestamp: Utc::now(),
+        message: message.to_string(),
+    };
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all((serde_json::to_string(&entry)? + "\n").as_bytes())?;
+    Ok(())
+}
+
+/// Messages waiting for an agent, oldest first.
+pub fn queued(grotto: &Grotto, agent_id: &str) -> Vec<QueuedMessage> {
+    fs::read_to_string(queue_path(grotto, agent_id))
+        .map(|content| {
+            content
+                .lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn write_queue(grotto: &Grotto, agent_id: &str, messages: &[QueuedMessage]) -> Result<()> {
+    let path = queue_path(grotto, agent_id);
+    if messages.is_empty() {
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+        return Ok(());
+    }
+    let mut content = String::new();
+    for message in messages {
+        content.push_str(&serde_json::to_string(message)?);
+        content.push('\n');
+    }
+    fs::write(path, content)?;
+    Ok(())
+}
+
+/// Try once to deliver every queued message, in order, to agents that have
+/// stopped thinking. A message still blocked stays queued along with the
+/// ones after it; delivered and failed ones leave the queue and are logged
+/// as `queued_message_delivered`. Returns what was attempted.
+pub fn flush_queued(
+    grotto: &Grotto,
+    mux: &dyn Multiplexer,
+    options: SteerOptions,
+) -> Result<Vec<(String, Outcome)>> {
+    let options = SteerOptions {
+        busy_polls: 0,
+        ..options
+    };
+    let mut attempted = Vec::new();
+    for agent_id in grotto.active_agent_ids() {
+        let mut pending = queued(grotto, &agent_id);
+        if pending.is_empty() {
+            continue;
+        }
+        let mut sent = 0;
+        for entry in &pending {
+            let outcome = deliver(grotto, mux, &agent_id, &entry.message, options);
+            if outcome.delivery == Delivery::Queued {
+                break;
+            }
+            grotto.log_event(
+                "queued_message_delivered",
+                Some(&agent_id),
+                None,
+                Some(&entry.message),
+                serde_json::json!({
+                    "message": entry.message,
+                    "queued_at": entry.timestamp,
+                    "delivery": outcome.delivery,
+                    "reason": outcome.reason,
+                }),
+            )?;
+            attempted.push((agent_id.clone(), outcome));
+            sent += 1;
+        }
+        pending.drain(..sent);
+        write_queue(grotto, &agent_id, &pending)?;
+    }
+    Ok(attempted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mux::FakeMultiplexer;
+    use tempfile::TempDir;
+
+    fn quick() -> SteerOptions {
+        SteerOptions {
+            poll: Duration::ZERO,
+            confirm_polls: 2,
+            busy_polls: 2,
+        }
+    }
+
+    fn team() -> (TempDir, Grotto, FakeMultiplexer) {
+        let tmp = TempDir::new().unwrap();
+        let mut grotto = Grotto::new(tmp.path(), 2, "t".into()).unwrap();
+        let mux = FakeMultiplexer::with_team(&mut grotto);
+        (tmp, grotto, mux)
+    }
+
+    #[test]
+    fn multi_line_message_is_pasted_whole_then_submitted() {
+        let (_tmp, grotto, mux) = team();
+        let target = grotto.pane_target("agent-1").unwrap();
+        mux.set_screen(&target, "claude> ");
+
+        let message = "Stop.\n- rerun the tests\nthen Escape the loop";
+        let outcome = steer(&grotto, &mux, "agent-1", message, quick()).unwrap();
+
+        assert_eq!(outcome, Outcome::delivered());
+        let pane = mux.pane(&target).unwrap();
+        assert_eq!(pane.pasted, vec![message.to_string()]);
+        assert_eq!(pane.sent, vec![vec!["Enter".to_string()]]);
+        let event = grotto.read_events().unwrap().pop().unwrap();
+        assert_eq!(event.event_type, "agent_steered");
+        assert_eq!(event.data["delivery"], "delivered");
+    }
+
+    #[test]
+    fn thinking_agent_gets_message_queued_then_flushed() {
+        let (_tmp, grotto, mux) = team();
+        let target = grotto.pane_target("agent-2").unwrap();
+        mux.set_screen(&target, "◐ Thinking… (esc to interrupt)");
+
+        let outcome = steer(&grotto, &mux, "agent-2", "use the cache", quick()).unwrap();
+        assert_eq!(outcome.delivery, Delivery::Queued);
+        assert!(mux.pane(&target).unwrap().pasted.is_empty());
+        assert_eq!(queued(&grotto, "agent-2").len(), 1);
+
+        // Still thinking: the message stays queued
+        assert!(flush_queued(&grotto, &mux, quick()).unwrap().is_empty());
+        assert_eq!(queued(&grotto, "agent-2").len(), 1);
+
+        mux.set_screen(&target, "claude> ");
+        let flushed = flush_queued(&grotto, &mux, quick()).unwrap();
+        assert_eq!(flushed, vec![("agent-2".to_string(), Outcome::delivered())]);
+        assert!(queued(&grotto, "agent-2").is_empty());
+        assert_eq!(mux.pane(&target).unwrap().pasted, vec!["use the cache"]);
+        let event = grotto.read_events().unwrap().pop().unwrap();
+        assert_eq!(event.event_type, "queued_message_delivered");
+    }
+
+    #[test]
+    fn dead_pane_fails_without_typing() {
+        let (_tmp, grotto, mux) = team();
+        let target = grotto.pane_target("agent-1").unwrap();
+        mux.exit(&target, 1);
+
+        let outcome = deliver(&grotto, &mux, "agent-1", "hi", quick());
+        assert_eq!(outcome.delivery, Delivery::Failed);
+        assert!(outcome.reason.unwrap().contains("Pane is dead"));
+        assert!(
+            mux.pane(&target)
+                .map(|p| p.pasted.is_empty())
+                .unwrap_or(true)
+        );
+    }
+
+    #[test]
+    fn paste_counts_as_seen_by_its_tail_or_placeholder() {
+        assert!(appeared("> ", "> fix it\n  now", "fix it now"));
+        assert!(!appeared("> fix it now", "> fix it now", "fix it now"));
+        assert!(appeared("> ", "> [Pasted text #1 +40 lines]", "a\nb"));
+        assert!(appeared("", "", "\n\n"));
+    }
+}
@@ -0,0 +1,440 @@
+use crate::logsink;
+use crate::mux::{Multiplexer, PaneStatus};
+use crate::{Grotto, GrottoError, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Restarts allowed per agent unless `spawn --max-restarts` says otherwise.
+pub const DEFAULT_MAX_RESTARTS: u32 = 3;
+
+/// Agent states the supervisor leaves alone.
+const SETTLED_STATES: &[&str] = &["done", "failed", "timed_out", "retired"];
+
+/// When a dead agent is relaunched.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RestartPolicy {
+    /// An exited agent stays gone (the original behavior)
+    #[default]
+    Never,
+    /// Relaunch agents that exit with a non-zero status
+    OnFailure,
+    /// Relaunch agents whenever they exit
+    Always,
+}
+
+impl RestartPolicy {
+    pub fn is_never(&self) -> bool {
+        *self == RestartPolicy::Never
+    }
+}
+
+impl fmt::Display for RestartPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RestartPolicy::Never => write!(f, "never"),
+            RestartPolicy::OnFailure => write!(f, "on-failure"),
+            RestartPolicy::Always => write!(f, "always"),
+        }
+    }
+}
+
+impl std::str::FromStr for RestartPolicy {
+    type Err = GrottoError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "never" => Ok(RestartPolicy::Never),
+            "on-failure" => Ok(RestartPolicy::OnFailure),
+            "always" => Ok(RestartPolicy::Always),
+            other => Err(GrottoError::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "Unknown restart policy '{}'. Valid policies: never, on-failure, always",
+                    other
+                ),
+            ))),
+        }
+    }
+}
+
+/// What to do about an agent whose process exited.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Decision {
+    /// Relaunch it; `attempt` counts this session's restarts of the agent
+    Restart {
+        agent_id: String,
+        exit_status: Option<i32>,
+        attempt: u32,
+    },
+    /// Leave it stopped; `gave_up` when the policy wanted a restart but the
+    /// agent used up `max_restarts`
+    Stop {
+        agent_id: String,
+        exit_status: Option<i32>,
+        gave_up: bool,
+    },
+}
+
+/// Decide what to do about each dead agent pane under the session's policy.
+pub fn plan(grotto: &Grotto, panes: &[PaneStatus]) -> Result<Vec<Decision>> {
+    let events = grotto.read_session_events()?;
+    let mut decisions = Vec::new();
+    for agent_id in grotto.sorted_agent_ids() {
+        let agent = &grotto.agents[&agent_id];
+        if SETTLED_STATES.contains(&agent.state.as_str()) {
+            continue;
+        }
+        let Some(pane) = panes.iter().find(|p| p.belongs_to(agent) && p.dead) else {
+            continue;
+        };
+
+        let wants_restart = match grotto.config.restart {
+            RestartPolicy::Never => false,
+            RestartPolicy::OnFailure => pane.exit_status != Some(0),
+            RestartPolicy::Always => true,
+        };
+        let restarts = events
+            .iter()
+            .filter(|e| {
+                e.event_type == "agent_restarted" && e.agent_id.as_deref() == Some(&agent_id)
+            })
+            .count() as u32;
+
+        decisions.push(if wants_restart && restarts < grotto.config.max_restarts {
+            Decision::Restart {
+                agent_id,
+                exit_status: pane.exit_status,
+                attempt: restarts + 1,
+            }
+        } else {
+            Decision::Stop {
+                agent_id,
+                exit_status: pane.exit_status,
+                gave_up: wants_restart,
+            }
+        });
+    }
+    Ok(decisions)
+}
+
+/// Relaunch an agent in its own pane with a prompt that carries its claimed
+/// task, on the model routed for that task, and reattach its stream log.
+pub fn respawn_agent(
+    grotto: &mut Grotto,
+    mux: &dyn Multiplexer,
+    agent_id: &str,
+    reason: &str,
+) -> Result<()> {
+    grotto.assign_model(agent_id)?;
+    let pane = grotto.pane_target(agent_id)?;
+    mux.respawn(&pane, &grotto.agent_resume_launch(agent_id, reason))?;
+    // Replaces any pipe left over from the previous process
+    let stream_path = grotto
+        .grotto_dir
+        .join("agents")
+        .join(agent_id)
+        .join(logsink::RAW_LOG);
+    let _ = mux.pipe(&pane, &stream_path);
+    Ok(())
+}
+
+/// Carry out a decision: relaunch the agent in its pane and log
+/// `agent_restarted`, or mark it done or failed (requeueing a failed agent's
+/// task) and log `agent_exited`.
+pub fn apply(grotto: &mut Grotto, mux: &dyn Multiplexer, decision: &Decision) -> Result<()> {
+    apply_with(grotto, decision, |grotto, agent_id, reason| {
+        respawn_agent(grotto, mux, agent_id, reason)
+    })
+}
+
+/// [`apply`] with the relaunch done by `relaunch(grotto, agent_id, reason)`,
+/// for agents that don't live in tmux panes.
+pub fn apply_with(
+    grotto: &mut Grotto,
+    decision: &Decision,
+    mut relaunch: impl FnMut(&mut Grotto, &str, &str) -> Result<()>,
+) -> Result<()> {
+    match decision {
+        Decision::Restart {
+            agent_id,
+            exit_status,
+            attempt,
+        } => {
+            let reason = match exit_status {
+                Some(0) => "your process exited".to_string(),
+                Some(code) => format!("your process exited with status {}", code),
+                None => "your process died".to_string(),
+            };
+            relaunch(grotto, agent_id, &reason)?;
+            let current_task = grotto.agents[agent_id].current_task.clone();
+            if let Some(agent) = grotto.agents.get_mut(agent_id) {
+                agent.state = "working".to_string();
+                agent.progress =
+                    format!("Restarted ({} of {})", attempt, grotto.config.max_restarts);
+                agent.last_update = Utc::now();
+            }
+            grotto.write_agent_status(agent_id)?;
+            grotto.log_event(
+                "agent_restarted",
+                Some(agent_id),
+                current_task.as_deref(),
+                Some(&format!(
+                    "{} restarted after exit {} (restart {} of {})",
+                    agent_id,
+                    describe_status(*exit_status),
+                    attempt,
+                    grotto.config.max_restarts
+                )),
+                serde_json::json!({
+                    "exit_status": exit_status,
+                    "attempt": attempt,
+                    "max_restarts": grotto.config.max_restarts,
+                    "policy": grotto.config.restart,
+                }),
+            )?;
+        }
+        Decision::Stop {
+            agent_id,
+            exit_status,
+            gave_up,
+        } => {
+            let succeeded = *exit_status == Some(0);
+            let requeued_task = if succeeded {
+                None
+            } else {
+                grotto.agents[agent_id].current_task.clone()
+            };
+            if let Some(task_id) = &requeued_task {
+                let reason = format!(
+                    "{} exited with status {}",
+                    agent_id,
+                    describe_status(*exit_status)
+                );
+                match grotto.requeue_task(task_id, &reason) {
+                    Ok(()) | Err(GrottoError::TaskNotFound(_)) => {}
+                    Err(e) => return Err(e),
+                }
+            }
+
+            let message = if *gave_up {
+                format!(
+                    "{} exited with status {}; not restarted after {} restarts",
+                    agent_id,
+                    describe_status(*exit_status),
+                    grotto.config.max_restarts
+                )
+            } else {
+                format!(
+                    "{} exited with status {}",
+                    agent_id,
+                    describe_status(*exit_status)
+                )
+            };
+            if let Some(agent) = grotto.agents.get_mut(agent_id) {
+                agent.state = if succeeded { "done" } else { "failed" }.to_string();
+                agent.current_task = None;
+                agent.progress = message.clone();
+                agent.last_update = Utc::now();
+            }
+            grotto.write_agent_status(agent_id)?;
+            grotto.log_event(
+                "agent_exited",
+                Some(agent_id),
+                requeued_task.as_deref(),
+                Some(&message),
+                serde_json::json!({
+                    "exit_status": exit_status,
+                    "gave_up": gave_up,
+                    "requeued_task": requeued_task,
+                }),
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// An exit status for messages; `unknown` when tmux did not report one.
+pub fn describe_status(exit_status: Option<i32>) -> String {
+    exit_status
+        .map(|code| code.to_string())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// One supervision pass over the session: restart or settle dead agents.
+/// Once every pane is dead and none was restarted the session is killed, so
+/// `grotto wait` and the daemon see the run end as they would without
+/// `remain-on-exit`.
+pub fn tick(grotto: &mut Grotto, mux: &dyn Multiplexer) -> Result<Vec<Decision>> {
+    let Some(panes) = mux.list_panes(&grotto.config.tmux_session) else {
+        return Ok(Vec::new());
+    };
+    let decisions = plan(grotto, &panes)?;
+    for decision in &decisions {
+        apply(grotto, mux, decision)?;
+    }
+
+    let restarted = decisions
+        .iter()
+        .any(|d| matches!(d, Decision::Restart { .. }));
+    if !restarted && !panes.is_empty() && panes.iter().all(|p| p.dead) {
+        let _ = mux.kill_session(&grotto.config.tmux_session);
+    }
+    Ok(decisions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mux::FakeMultiplexer;
+    use tempfile::TempDir;
+
+    fn dead(pane_index: usize, exit_status: i32) -> PaneStatus {
+        PaneStatus {
+            pane_id: None,
+            pane_index,
+            dead: true,
+            exit_status: Some(exit_status),
+        }
+    }
+
+    fn session(agents: usize, policy: RestartPolicy) -> (TempDir, Grotto) {
+        let tmp = TempDir::new().unwrap();
+        let mut grotto = Grotto::new(tmp.path(), agents, "t".into()).unwrap();
+        grotto.config.restart = policy;
+        grotto.config.max_restarts = 2;
+        (tmp, grotto)
+    }
+
+    #[test]
+    fn restart_policy_parses_and_displays() {
+        for policy in ["never", "on-failure", "always"] {
+            assert_eq!(policy.parse::<RestartPolicy>().unwrap().to_string(), policy);
+        }
+        let err = "sometimes".parse::<RestartPolicy>().unwrap_err();
+        assert!(err.to_string().contains("Unknown restart policy"));
+    }
+
+    #[test]
+    fn plan_follows_policy() {
+        let panes = [dead(0, 0), dead(1, 1)];
+
+        let (_tmp, grotto) = session(3, RestartPolicy::OnFailure);
+        let decisions = plan(&grotto, &panes).unwrap();
+        assert_eq!(
+            decisions,
+            vec![
+                Decision::Stop {
+                    agent_id: "agent-1".to_string(),
+                    exit_status: Some(0),
+                    gave_up: false
+                },
+                Decision::Restart {
+                    agent_id: "agent-2".to_string(),
+                    exit_status: Some(1),
+                    attempt: 1
+                },
+            ]
+        );
+
+        let (_tmp, grotto) = session(2, RestartPolicy::Always);
+        let decisions = plan(&grotto, &panes).unwrap();
+        assert!(
+            decisions
+                .iter()
+                .all(|d| matches!(d, Decision::Restart { .. }))
+        );
+
+        let (_tmp, grotto) = session(2, RestartPolicy::Never);
+        let decisions = plan(&grotto, &panes).unwrap();
+        assert!(
+            decisions
+                .iter()
+                .all(|d| matches!(d, Decision::Stop { gave_up: false, .. }))
+        );
+    }
+
+    #[test]
+    fn plan_gives_up_after_max_restarts() {
+        let (_tmp, grotto) = session(1, RestartPolicy::Always);
+        for _ in 0..2 {
+            grotto
+                .log_event(
+                    "agent_restarted",
+                    Some("agent-1"),
+                    None,
+                    None,
+                    serde_json::json!({}),
+                )
+                .unwrap();
+        }
+        assert_eq!(
+            plan(&grotto, &[dead(0, 1)]).unwrap(),
+            vec![Decision::Stop {
+                agent_id: "agent-1".to_string(),
+                exit_status: Some(1),
+                gave_up: true
+            }]
+        );
+    }
+
+    #[test]
+    fn stop_requeues_a_failed_agents_task_and_settles_it() {
+        let (tmp, mut grotto) = session(1, RestartPolicy::OnFailure);
+        grotto.claim_task("main", "agent-1").unwrap();
+        let decision = Decision::Stop {
+            agent_id: "agent-1".to_string(),
+            exit_status: Some(2),
+            gave_up: true,
+        };
+        apply(&mut grotto, &FakeMultiplexer::new(), &decision).unwrap();
+
+        let loaded = Grotto::load(tmp.path()).unwrap();
+        assert_eq!(loaded.agents["agent-1"].state, "failed");
+        assert!(matches!(loaded.tasks[0].status, crate::TaskStatus::Open));
+        let events = loaded.read_session_events().unwrap();
+        let exited = events
+            .iter()
+            .find(|e| e.event_type == "agent_exited")
+            .unwrap();
+        assert_eq!(exited.data["requeued_task"], "main");
+
+        // Settled agents are not considered again
+        assert!(plan(&loaded, &[dead(0, 2)]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn tick_restarts_dead_panes_then_ends_the_session() {
+        let (_tmp, mut grotto) = session(2, RestartPolicy::OnFailure);
+        grotto.claim_task("main", "agent-1").unwrap();
+        let mux = FakeMultiplexer::with_team(&mut grotto);
+        let tmux_session = grotto.config.tmux_session.clone();
+        mux.keep_exited_panes(&tmux_session).unwrap();
+        let agent_1 = grotto.pane_target("agent-1").unwrap();
+        let agent_2 = grotto.pane_target("agent-2").unwrap();
+
+        mux.exit(&agent_1, 1);
+        let decisions = tick(&mut grotto, &mux).unwrap();
+        assert!(matches!(
+            &decisions[..],
+            [Decision::Restart { attempt: 1, .. }]
+        ));
+        let pane = mux.pane(&agent_1).unwrap();
+        assert_eq!(pane.launches.len(), 2);
+        assert!(
+            pane.launches[1]
+                .command
+                .join(" ")
+                .contains("You had claimed task main")
+        );
+        assert!(pane.pipe.unwrap().ends_with("agents/agent-1/stream.log"));
+
+        mux.exit(&agent_1, 0);
+        mux.exit(&agent_2, 0);
+        let decisions = tick(&mut grotto, &mux).unwrap();
+        assert_eq!(decisions.len(), 2);
+        assert_eq!(grotto.agents["agent-2"].state, "done");
+        assert!(!mux.has_session(&tmux_session));
+    }
+}
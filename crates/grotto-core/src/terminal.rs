@@ -0,0 +1,301 @@
+use crate::{Result, logsink};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+/// Most output sent for one agent per poll; the rest follows on the next.
+pub const MAX_CHUNK_BYTES: u64 = 64 * 1024;
+
+/// Scrollback sent when a client doesn't say how much it wants.
+pub const DEFAULT_SCROLLBACK_BYTES: u64 = 64 * 1024;
+
+/// Raw terminal output of an agent (ANSI escapes included), from its
+/// `stream.log`.
+pub fn stream_path(grotto_dir: &Path, agent_id: &str) -> PathBuf {
+    grotto_dir
+        .join("agents")
+        .join(agent_id)
+        .join(logsink::RAW_LOG)
+}
+
+/// A piece of an agent's `stream.log`: the bytes from `start` to `end`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Chunk {
+    pub data: String,
+    pub start: u64,
+    pub end: u64,
+    /// The log was truncated or replaced (a new session) since the last
+    /// read, so earlier output no longer applies
+    #[serde(default)]
+    pub reset: bool,
+}
+
+/// Output appended to `path` after `offset`, up to [`MAX_CHUNK_BYTES`].
+/// Stops before a UTF-8 sequence that hasn't been fully written yet. `None`
+/// when there is nothing new.
+pub fn read_from(path: &Path, offset: u64) -> Result<Option<Chunk>> {
+    let Ok(mut file) = fs::File::open(path) else {
+        return Ok(None);
+    };
+    let len = file.metadata()?.len();
+    let (start, reset) = if len < offset {
+        (0, true)
+    } else {
+        (offset, false)
+    };
+    if len == start {
+        return Ok(reset.then(|| Chunk {
+            data: String::new(),
+            start,
+            end: start,
+            reset,
+        }));
+    }
+
+    let mut bytes = Vec::new();
+    file.seek(SeekFrom::Start(start))?;
+    file.take((len - start).min(MAX_CHUNK_BYTES))
+        .read_to_end(&mut bytes)?;
+    let complete = complete_len(&bytes);
+    if complete == 0 && !reset {
+        return Ok(None);
+    }
+    Ok(Some(Chunk {
+        data: String::from_utf8_lossy(&bytes[..complete]).into_owned(),
+        start,
+        end: start + complete as u64,
+        reset,
+    }))
+}
+
+/// Up to `bytes` of output before `end` (the end of the log when `None`),
+/// starting on a line boundary when there is one in range.
+pub fn scrollback(path: &Path, end: Option<u64>, bytes: u64) -> Result<Chunk> {
+    let Ok(mut file) = fs::File::open(path) else {
+        return Ok(Chunk {
+            data: String::new(),
+            start: 0,
+            end: 0,
+            reset: false,
+        });
+    };
+    let len = file.metadata()?.len();
+    let end = end.unwrap_or(len).min(len);
+    let mut start = end.saturating_sub(bytes);
+
+    let mut buf = Vec::new();
+    file.seek(SeekFrom::Start(start))?;
+    file.take(end - start).read_to_end(&mut buf)?;
+    let mut skip = 0;
+    if start > 0 {
+        skip = match buf.iter().position(|&b| b == b'\n') {
+            Some(newline) => newline + 1,
+            // No line break in range: just avoid starting mid-character
+            None => buf.iter().take_while(|&&b| b & 0xC0 == 0x80).count(),
+        };
+        start += skip as u64;
+    }
+    Ok(Chunk {
+        data: String::from_utf8_lossy(&buf[skip..]).into_owned(),
+        start,
+        end,
+        reset: false,
+    })
+}
+
+/// Length of `bytes` without a trailing UTF-8 sequence that is cut short.
+fn complete_len(bytes: &[u8]) -> usize {
+    let n = bytes.len();
+    for back in 1..=n.min(4) {
+        let b = bytes[n - back];
+        if b & 0xC0 == 0x80 {
+            continue;
+        }
+        let needed = match b {
+            0xF0.. => 4,
+            0xE0.. => 3,
+            0xC0.. => 2,
+            _ => 1,
+        };
+        return if needed > back { n - back } else { n };
+    }
+    n
+}
+
+/// Follows the stream logs of the agents one viewer subscribed to.
+#[derive(Debug)]
+pub struct TerminalFollower {
+    grotto_dir: PathBuf,
+    follows: BTreeMap<String, Follow>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Follow {
+    /// Where streaming started; scrollback ends here
+    since: u64,
+    /// Where the next poll reads from
+    offset: u64,
+    /// The log being read, to notice when it is rotated
+    file: Option<u64>,
+}
+
+impl TerminalFollower {
+    pub fn new(grotto_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            grotto_dir: grotto_dir.into(),
+            follows: BTreeMap::new(),
+        }
+    }
+
+    /// Start following an agent from the current end of its log. Earlier
+    /// output is fetched with [`TerminalFollower::scrollback`].
+    pub fn subscribe(&mut self, agent_id: &str) {
+        let path = stream_path(&self.grotto_dir, agent_id);
+        let len = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        self.follows.entry(agent_id.to_string()).or_insert(Follow {
+            since: len,
+            offset: len,
+            file: logsink::file_id(&path),
+        });
+    }
+
+    pub fn unsubscribe(&mut self, agent_id: &str) {
+        self.follows.remove(agent_id);
+    }
+
+    /// Output from before the agent's first streamed chunk, so a viewer can
+    /// prepend it without gaps or repeats.
+    pub fn scrollback(&self, agent_id: &str, bytes: u64) -> Result<Chunk> {
+        scrollback(
+            &stream_path(&self.grotto_dir, agent_id),
+            self.follows.get(agent_id).map(|f| f.since),
+            bytes,
+        )
+    }
+
+    /// New output for each subscribed agent.
+    pub fn poll(&mut self) -> Vec<(String, Chunk)> {
+        let mut chunks = Vec::new();
+        for (agent_id, follow) in &mut self.follows {
+            let path = stream_path(&self.grotto_dir, agent_id);
+            let file = logsink::file_id(&path);
+            if follow.file.is_some()
+                && file != follow.file
+                && logsink::file_id(&logsink::rotated(&path)) == follow.file
+            {
+                // Rotated: the new log carries on, nothing to reset
+                follow.since = 0;
+                follow.offset = 0;
+            }
+            follow.file = file;
+            if let Ok(Some(chunk)) = read_from(&path, follow.offset) {
+                if chunk.reset {
+                    follow.since = 0;
+                }
+                follow.offset = chunk.end;
+                chunks.push((agent_id.clone(), chunk));
+            }
+        }
+        chunks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn append(path: &Path, bytes: &[u8]) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .unwrap()
+            .write_all(bytes)
+            .unwrap();
+    }
+
+    #[test]
+    fn follower_streams_new_output_and_waits_for_whole_characters() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = stream_path(tmp.path(), "agent-1");
+        append(&path, b"old output\n");
+
+        let mut follower = TerminalFollower::new(tmp.path());
+        follower.subscribe("agent-1");
+        assert!(follower.poll().is_empty());
+
+        // "◐" is three bytes; the last one hasn't been written yet
+        append(&path, b"\x1b[1m\xe2\x97");
+        let chunks = follower.poll();
+        assert_eq!(chunks[0].1.data, "\x1b[1m");
+        append(&path, b"\x90 Thinking\n");
+        let chunks = follower.poll();
+        assert_eq!(chunks[0].1.data, "◐ Thinking\n");
+        assert_eq!(chunks[0].1.end, fs::metadata(&path).unwrap().len());
+
+        follower.unsubscribe("agent-1");
+        append(&path, b"more\n");
+        assert!(follower.poll().is_empty());
+    }
+
+    #[test]
+    fn scrollback_ends_where_streaming_starts() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = stream_path(tmp.path(), "agent-1");
+        append(&path, b"line one\nline two\nline three\n");
+
+        let mut follower = TerminalFollower::new(tmp.path());
+        follower.subscribe("agent-1");
+        append(&path, b"live\n");
+
+        // Starts after the first line break in range
+        let back = follower.scrollback("agent-1", 16).unwrap();
+        assert_eq!(back.data, "line three\n");
+        let all = follower.scrollback("agent-1", 1024).unwrap();
+        assert_eq!(all.data, "line one\nline two\nline three\n");
+        assert_eq!(all.start, 0);
+        let live = follower.poll();
+        assert_eq!(live[0].1.data, "live\n");
+        assert_eq!(live[0].1.start, all.end);
+
+        // Still ends where streaming started
+        assert_eq!(follower.scrollback("agent-1", 1024).unwrap(), all);
+    }
+
+    #[test]
+    fn truncated_log_resets_the_stream() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = stream_path(tmp.path(), "agent-1");
+        append(&path, b"previous session\n");
+
+        let mut follower = TerminalFollower::new(tmp.path());
+        follower.subscribe("agent-1");
+        fs::write(&path, b"new\n").unwrap();
+
+        let chunks = follower.poll();
+        assert!(chunks[0].1.reset);
+        assert_eq!(chunks[0].1.data, "new\n");
+        assert_eq!(chunks[0].1.start, 0);
+    }
+
+    #[test]
+    fn rotated_log_carries_on_without_a_reset() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = stream_path(tmp.path(), "agent-1");
+        append(&path, b"before rotation\n");
+
+        let mut follower = TerminalFollower::new(tmp.path());
+        follower.subscribe("agent-1");
+        fs::rename(&path, logsink::rotated(&path)).unwrap();
+        append(&path, b"after\n");
+
+        let chunks = follower.poll();
+        assert!(!chunks[0].1.reset);
+        assert_eq!(chunks[0].1.data, "after\n");
+        assert_eq!(follower.scrollback("agent-1", 1024).unwrap().data, "");
+    }
+}
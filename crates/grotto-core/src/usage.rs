@@ -0,0 +1,457 @@
+use crate::{Event, Grotto, Result, logsink, monitor};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs;
+use std::ops::AddAssign;
+use std::path::Path;
+use std::sync::LazyLock;
+
+/// `Total cost: $0.42` from Claude's `/cost` summary.
+static COST_LINE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)total cost:\s*\$\s*([0-9][0-9,]*(?:\.[0-9]+)?)").unwrap());
+
+/// One token count in a usage line, e.g. `12.3k input` or `2k cache write`.
+static TOKEN_COUNT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)([0-9][0-9,]*(?:\.[0-9]+)?)\s*([km]?)\s+(input|output|cache read|cache write)")
+        .unwrap()
+});
+
+/// Tokens and dollars spent by an agent, a task or a whole session.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Usage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    #[serde(default)]
+    pub cache_read_tokens: u64,
+    #[serde(default)]
+    pub cache_write_tokens: u64,
+    pub cost_usd: f64,
+}
+
+impl Usage {
+    pub fn total_tokens(&self) -> u64 {
+        self.input_tokens + self.output_tokens + self.cache_read_tokens + self.cache_write_tokens
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.total_tokens() == 0 && self.cost_usd == 0.0
+    }
+
+    /// What was spent since `earlier`, a snapshot of the same counters.
+    pub fn since(&self, earlier: &Usage) -> Usage {
+        Usage {
+            input_tokens: self.input_tokens.saturating_sub(earlier.input_tokens),
+            output_tokens: self.output_tokens.saturating_sub(earlier.output_tokens),
+            cache_read_tokens: self
+                .cache_read_tokens
+                .saturating_sub(earlier.cache_read_tokens),
+            cache_write_tokens: self
+                .cache_write_tokens
+                .saturating_sub(earlier.cache_write_tokens),
+            cost_usd: (self.cost_usd - earlier.cost_usd).max(0.0),
+        }
+    }
+}
+
+impl AddAssign for Usage {
+    fn add_assign(&mut self, other: Usage) {
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cache_read_tokens += other.cache_read_tokens;
+        self.cache_write_tokens += other.cache_write_tokens;
+        self.cost_usd += other.cost_usd;
+    }
+}
+
+impl fmt::Display for Usage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} ({} in / {} out",
+            format_cost(self.cost_usd),
+            format_tokens(self.input_tokens),
+            format_tokens(self.output_tokens)
+        )?;
+        let cached = self.cache_read_tokens + self.cache_write_tokens;
+        if cached > 0 {
+            write!(f, " / {} cached", format_tokens(cached))?;
+        }
+        write!(f, ")")
+    }
+}
+
+/// `$0.42`
+pub fn format_cost(usd: f64) -> String {
+    format!("${:.2}", usd)
+}
+
+/// `950`, `12.3k`, `1.2M`
+pub fn format_tokens(tokens: u64) -> String {
+    match tokens {
+        0..1_000 => tokens.to_string(),
+        1_000..1_000_000 => format!("{:.1}k", tokens as f64 / 1_000.0),
+        _ => format!("{:.1}M", tokens as f64 / 1_000_000.0),
+    }
+}
+
+/// Extract usage from an agent's terminal output.
+///
+/// Structured `stream-json` output wins: `result` lines carry each run's
+/// totals and `assistant` lines count a run still in progress. Without it,
+/// the last `/cost` summary (`Total cost: $…` plus the `… input, … output`
+/// lines) is used, since those totals are cumulative.
+pub fn parse_usage(output: &str) -> Usage {
+    let mut parser = UsageParser::default();
+    for line in output.lines() {
+        parser.push(line);
+    }
+    parser.usage()
+}
+
+/// [`parse_usage`] one line at a time, for output that is still coming in.
+#[derive(Debug, Default)]
+pub struct UsageParser {
+    structured: Usage,
+    saw_structured: bool,
+    /// Per message ID: partial messages repeat the same usage
+    in_flight: HashMap<String, Usage>,
+    text: Usage,
+    text_tokens: Option<Usage>,
+    in_usage_block: bool,
+}
+
+impl UsageParser {
+    pub fn push(&mut self, raw: &str) {
+        let line = monitor::strip_ansi(raw);
+        let line = line.trim();
+
+        if line.starts_with('{')
+            && let Ok(value) = serde_json::from_str::<serde_json::Value>(line)
+        {
+            match value["type"].as_str() {
+                Some("result") => {
+                    self.saw_structured = true;
+                    let mut run = tokens_from_json(&value["usage"]);
+                    run.cost_usd = value["total_cost_usd"]
+                        .as_f64()
+                        .or_else(|| value["cost_usd"].as_f64())
+                        .unwrap_or(0.0);
+                    self.structured += run;
+                    self.in_flight.clear();
+                }
+                Some("assistant") => {
+                    self.saw_structured = true;
+                    let message = &value["message"];
+                    let id = message["id"].as_str().unwrap_or_default().to_string();
+                    self.in_flight
+                        .insert(id, tokens_from_json(&message["usage"]));
+                }
+                _ => {}
+            }
+            return;
+        }
+
+        if let Some(caps) = COST_LINE.captures(line) {
+            self.text.cost_usd = parse_number(&caps[1]);
+        }
+        if line.to_lowercase().starts_with("usage by model") {
+            self.text_tokens = Some(Usage::default());
+            self.in_usage_block = true;
+            return;
+        }
+        // Needs at least input and output, so prose like "3 input files" is skipped
+        let counts: Vec<_> = TOKEN_COUNT.captures_iter(line).collect();
+        if counts.len() < 2 {
+            self.in_usage_block = false;
+            return;
+        }
+        // One line per model inside a block; a lone line is a new total
+        if !self.in_usage_block {
+            self.text_tokens = Some(Usage::default());
+            self.in_usage_block = true;
+        }
+        let tokens = self.text_tokens.get_or_insert_with(Usage::default);
+        for caps in counts {
+            let scale = match caps[2].to_lowercase().as_str() {
+                "k" => 1_000.0,
+                "m" => 1_000_000.0,
+                _ => 1.0,
+            };
+            let n = (parse_number(&caps[1]) * scale).round() as u64;
+            match caps[3].to_lowercase().as_str() {
+                "input" => tokens.input_tokens += n,
+                "output" => tokens.output_tokens += n,
+                "cache read" => tokens.cache_read_tokens += n,
+                _ => tokens.cache_write_tokens += n,
+            }
+        }
+    }
+
+    /// Usage in the lines pushed so far.
+    pub fn usage(&self) -> Usage {
+        if self.saw_structured {
+            let mut usage = self.structured;
+            for in_flight in self.in_flight.values() {
+                usage += *in_flight;
+            }
+            return usage;
+        }
+        let mut usage = self.text;
+        if let Some(tokens) = self.text_tokens {
+            usage.input_tokens = tokens.input_tokens;
+            usage.output_tokens = tokens.output_tokens;
+            usage.cache_read_tokens = tokens.cache_read_tokens;
+            usage.cache_write_tokens = tokens.cache_write_tokens;
+        }
+        usage
+    }
+}
+
+fn tokens_from_json(usage: &serde_json::Value) -> Usage {
+    let count = |key: &str| usage[key].as_u64().unwrap_or(0);
+    Usage {
+        input_tokens: count("input_tokens"),
+        output_tokens: count("output_tokens"),
+        cache_read_tokens: count("cache_read_input_tokens"),
+        cache_write_tokens: count("cache_creation_input_tokens"),
+        cost_usd: 0.0,
+    }
+}
+
+fn parse_number(s: &str) -> f64 {
+    s.replace(',', "").parse().unwrap_or(0.0)
+}
+
+/// Running totals the log sink keeps beside an agent's logs, so what was
+/// spent isn't lost when the logs rotate.
+pub const USAGE_FILE: &str = "usage.json";
+
+/// Running totals `grotto stream-view` keeps from the `stream-json` a Claude
+/// Code agent printed before it was rendered for its pane.
+pub const STREAM_USAGE_FILE: &str = "stream-usage.json";
+
+/// The totals saved as `file` in `agent_dir`, if any.
+pub fn load(agent_dir: &Path, file: &str) -> Option<Usage> {
+    let json = fs::read_to_string(agent_dir.join(file)).ok()?;
+    serde_json::from_str(&json).ok()
+}
+
+/// Replace the totals saved as `file` in `agent_dir` atomically; readers
+/// poll the file.
+pub fn save(agent_dir: &Path, file: &str, usage: &Usage) -> Result<()> {
+    let path = agent_dir.join(file);
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, serde_json::to_string_pretty(usage)?)?;
+    fs::rename(tmp, path)?;
+    Ok(())
+}
+
+/// Usage so far for one agent: the totals reported by its `stream-json`,
+/// else those kept by its log sink, else parsed from its cleaned log.
+pub fn agent_usage(grotto_dir: &Path, agent_id: &str) -> Usage {
+    let agent_dir = grotto_dir.join("agents").join(agent_id);
+    load(&agent_dir, STREAM_USAGE_FILE)
+        .or_else(|| load(&agent_dir, USAGE_FILE))
+        .unwrap_or_else(|| parse_usage(&logsink::read_text(grotto_dir, agent_id)))
+}
+
+/// Usage of every agent in the session, added up.
+pub fn total(grotto: &Grotto) -> Usage {
+    let mut sum = Usage::default();
+    for agent_id in grotto.agents.keys() {
+        sum += agent_usage(&grotto.grotto_dir, agent_id);
+    }
+    sum
+}
+
+/// Usage per agent, per task and for the whole session.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UsageReport {
+    pub total: Usage,
+    pub agents: BTreeMap<String, Usage>,
+    /// Spent by whichever agent held each task, from claim to completion
+    pub tasks: BTreeMap<String, Usage>,
+}
+
+/// Build the usage report for the current session.
+///
+/// Task claims, completions and requeues record the agent's usage at that
+/// moment, so each task is charged what its agent spent while holding it.
+pub fn report(grotto: &Grotto) -> Result<UsageReport> {
+    let agents: BTreeMap<String, Usage> = grotto
+        .sorted_agent_ids()
+        .into_iter()
+        .map(|id| {
+            let usage = agent_usage(&grotto.grotto_dir, &id);
+            (id, usage)
+        })
+        .collect();
+
+    let mut tasks: BTreeMap<String, Usage> = BTreeMap::new();
+    let mut held: HashMap<String, (String, Usage)> = HashMap::new();
+    let mut charge = |task_id: String, start: Usage, end: Usage| {
+        *tasks.entry(task_id).or_default() += end.since(&start);
+    };
+    for event in grotto.read_session_events()? {
+        let Some(agent_id) = event.agent_id.clone() else {
+            continue;
+        };
+        match event.event_type.as_str() {
+            "task_claimed" => {
+                let at = snapshot(&event);
+                if let Some((task_id, start)) = held.remove(&agent_id) {
+                    charge(task_id, start, at);
+                }
+                if let Some(task_id) = event.task_id.clone() {
+                    held.insert(agent_id, (task_id, at));
+                }
+            }
+            "task_completed" | "task_requeued" => {
+                if held.get(&agent_id).map(|(task, _)| task) == event.task_id.as_ref()
+                    && let Some((task_id, start)) = held.remove(&agent_id)
+                {
+                    charge(task_id, start, snapshot(&event));
+                }
+            }
+            _ => {}
+        }
+    }
+    for (agent_id, (task_id, start)) in held {
+        let now = agents.get(&agent_id).copied().unwrap_or_default();
+        charge(task_id, start, now);
+    }
+
+    let mut total = Usage::default();
+    for usage in agents.values() {
+        total += *usage;
+    }
+    Ok(UsageReport {
+        total,
+        agents,
+        tasks,
+    })
+}
+
+fn snapshot(event: &Event) -> Usage {
+    serde_json::from_value(event.data["usage"].clone()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    const COST_SUMMARY: &str = "\
+\x1b[1m> /cost\x1b[0m
+  Total cost:            $0.0512
+  Total duration (API):  41.2s
+  Total duration (wall): 2m 3.4s
+  Total code changes:    12 lines added, 3 lines removed
+  Usage by model:
+      claude-3-5-haiku:  1.2k input, 85 output, 0 cache read, 0 cache write
+         claude-sonnet:  14.1k input, 2,310 output, 120.5k cache read, 8k cache write
+";
+
+    const STREAM_JSON: &str = r#"{"type":"system","subtype":"init","session_id":"s1"}
+{"type":"assistant","message":{"id":"msg_1","usage":{"input_tokens":100,"output_tokens":20,"cache_read_input_tokens":500}}}
+{"type":"assistant","message":{"id":"msg_1","usage":{"input_tokens":100,"output_tokens":40,"cache_read_input_tokens":500}}}
+{"type":"result","subtype":"success","total_cost_usd":0.031,"usage":{"input_tokens":300,"output_tokens":90,"cache_read_input_tokens":1500,"cache_creation_input_tokens":200}}
+{"type":"assistant","message":{"id":"msg_9","usage":{"input_tokens":50,"output_tokens":5}}}
+"#;
+
+    #[test]
+    fn parses_cost_summary_text() {
+        let usage = parse_usage(&format!("Working on it...\n{}\nDone.\n", COST_SUMMARY));
+        assert_eq!(usage.input_tokens, 15_300);
+        assert_eq!(usage.output_tokens, 2_395);
+        assert_eq!(usage.cache_read_tokens, 120_500);
+        assert_eq!(usage.cache_write_tokens, 8_000);
+        assert!((usage.cost_usd - 0.0512).abs() < 1e-9);
+    }
+
+    #[test]
+    fn later_cost_summary_replaces_earlier_one() {
+        let log = "Total cost: $0.10\nUsage: 1k input, 200 output\n\
+                   ...more work...\n\
+                   Total cost: $0.25\nUsage: 3.5k input, 600 output\n";
+        let usage = parse_usage(log);
+        assert_eq!(usage.input_tokens, 3_500);
+        assert_eq!(usage.output_tokens, 600);
+        assert!((usage.cost_usd - 0.25).abs() < 1e-9);
+    }
+
+    #[test]
+    fn structured_results_are_summed_and_preferred() {
+        let log = format!("{}{}", STREAM_JSON, COST_SUMMARY);
+        let usage = parse_usage(&log);
+        // One finished run plus the message of the run in progress
+        assert_eq!(usage.input_tokens, 350);
+        assert_eq!(usage.output_tokens, 95);
+        assert_eq!(usage.cache_read_tokens, 1_500);
+        assert_eq!(usage.cache_write_tokens, 200);
+        assert!((usage.cost_usd - 0.031).abs() < 1e-9);
+
+        // Without a result, repeated partial messages count once
+        let in_progress: String = STREAM_JSON
+            .lines()
+            .take(3)
+            .map(|l| format!("{l}\n"))
+            .collect();
+        let usage = parse_usage(&in_progress);
+        assert_eq!(usage.input_tokens, 100);
+        assert_eq!(usage.output_tokens, 40);
+    }
+
+    #[test]
+    fn output_without_usage_is_empty() {
+        assert!(parse_usage("I changed 3 files.\nAll tests pass.\n").is_empty());
+        assert!(parse_usage("").is_empty());
+    }
+
+    #[test]
+    fn formats_tokens_and_usage() {
+        assert_eq!(format_tokens(950), "950");
+        assert_eq!(format_tokens(12_345), "12.3k");
+        assert_eq!(format_tokens(1_200_000), "1.2M");
+        let usage = Usage {
+            input_tokens: 12_300,
+            output_tokens: 4_500,
+            cost_usd: 0.4249,
+            ..Default::default()
+        };
+        assert_eq!(usage.to_string(), "$0.42 (12.3k in / 4.5k out)");
+    }
+
+    #[test]
+    fn report_charges_tasks_while_held() {
+        let tmp = TempDir::new().unwrap();
+        let mut grotto = Grotto::new(tmp.path(), 2, "t".into()).unwrap();
+        let log = |agent: &str, cost: &str| {
+            fs::write(
+                tmp.path()
+                    .join(".grotto/agents")
+                    .join(agent)
+                    .join("output.log"),
+                format!("Total cost: ${}\nUsage: 1k input, 100 output\n", cost),
+            )
+            .unwrap();
+        };
+
+        log("agent-1", "0.10");
+        grotto.claim_task("main", "agent-1").unwrap();
+        log("agent-1", "0.35");
+        grotto.complete_task("main").unwrap();
+        log("agent-1", "0.50");
+        log("agent-2", "0.20");
+
+        let report = report(&grotto).unwrap();
+        assert!((report.tasks["main"].cost_usd - 0.25).abs() < 1e-9);
+        assert!((report.agents["agent-1"].cost_usd - 0.50).abs() < 1e-9);
+        assert!((report.total.cost_usd - 0.70).abs() < 1e-9);
+        assert_eq!(report.total.input_tokens, 2_000);
+        assert!((total(&grotto).cost_usd - 0.70).abs() < 1e-9);
+    }
+}
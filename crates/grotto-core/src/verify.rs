@@ -0,0 +1,251 @@
+use crate::checks::{self, CheckResult};
+use crate::monitor::{AgentPhase, PaneSnapshot};
+use crate::{Grotto, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Default number of verification rounds for `--until`.
+pub const DEFAULT_MAX_ROUNDS: u32 = 3;
+
+/// Final result of a session with success criteria.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionOutcome {
+    Succeeded,
+    Failed,
+}
+
+impl fmt::Display for SessionOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SessionOutcome::Succeeded => write!(f, "succeeded"),
+            SessionOutcome::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+/// One run of the session's success criteria.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoundResult {
+    pub round: u32,
+    pub max_rounds: u32,
+    pub checks: Vec<CheckResult>,
+}
+
+impl RoundResult {
+    pub fn success(&self) -> bool {
+        self.checks.iter().all(|c| c.success)
+    }
+
+    pub fn failed_check(&self) -> Option<&CheckResult> {
+        self.checks.iter().find(|c| !c.success)
+    }
+
+    /// Message broadcast to agents after a failed round, with the failing
+    /// check's captured output line for line. Steering pastes it, so its
+    /// newlines don't submit it early.
+    pub fn feedback_message(&self) -> String {
+        let Some(failed) = self.failed_check() else {
+            return format!(
+                "[grotto] Success criteria passed (round {}/{}).",
+                self.round, self.max_rounds
+            );
+        };
+        format!(
+            "[grotto] Success criteria failed (round {}/{}): `{}` exited with {}. {}\n\nFix the failure, commit, and go idle when done.",
+            self.round,
+            self.max_rounds,
+            failed.command,
+            describe_exit(failed),
+            describe_output(failed)
+        )
+    }
+
+    /// Why agents that exited are relaunched after a failed round, for
+    /// [`Grotto::agent_resume_launch`]: the failing check and its output.
+    pub fn relaunch_reason(&self) -> String {
+        let Some(failed) = self.failed_check() else {
+            return format!("verification round {}/{}", self.round, self.max_rounds);
+        };
+        format!(
+            "verification round {}/{} failed: `{}` exited with {}. {}\n\nFix the failure and commit",
+            self.round,
+            self.max_rounds,
+            failed.command,
+            describe_exit(failed),
+            describe_output(failed)
+        )
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut out = format!(
+            "- Round {}/{}: {}\n",
+            self.round,
+            self.max_rounds,
+            if self.success() { "passed" } else { "failed" }
+        );
+        for check in &self.checks {
+            out.push_str(&format!(
+                "  - {} `{}` ({} ms)\n",
+                if check.success { "✅" } else { "❌" },
+                check.command,
+                check.duration_ms
+            ));
+        }
+        out
+    }
+}
+
+/// A failed check's captured output (already cut to its tail by
+/// [`checks::run_check`]), as a sentence and block.
+fn describe_output(failed: &CheckResult) -> String {
+    let output = failed.output.trim_end();
+    if output.trim().is_empty() {
+        "It printed nothing.".to_string()
+    } else {
+        format!("Its output:\n\n{}", output)
+    }
+}
+
+fn describe_exit(failed: &CheckResult) -> String {
+    failed
+        .exit_code
+        .map(|c| c.to_string())
+        .unwrap_or_else(|| "no exit code".to_string())
+}
+
+/// Whether every agent is idle or finished, i.e. it's time to verify.
+pub fn agents_settled(snapshots: &[PaneSnapshot]) -> bool {
+    !snapshots.is_empty()
+        && snapshots
+            .iter()
+            .all(|s| matches!(s.phase, AgentPhase::Idle | AgentPhase::Finished))
+}
+
+/// Run the session's success criteria in the project directory and log the round.
+pub fn run_round(grotto: &Grotto, round: u32) -> Result<RoundResult> {
+    let result = RoundResult {
+        round,
+        max_rounds: grotto.config.max_rounds,
+        checks: checks::run_checks(&grotto.config.project_dir, &grotto.config.until),
+    };
+
+    let failed = result.failed_check();
+    grotto.log_event(
+        "verification_round",
+        None,
+        None,
+        Some(&format!(
+            "Round {}/{} {}",
+            round,
+            result.max_rounds,
+            if failed.is_none() { "passed" } else { "failed" }
+        )),
+        serde_json::json!({
+            "round": round,
+            "max_rounds": result.max_rounds,
+            "success": failed.is_none(),
+            "failed_command": failed.map(|c| c.command.clone()),
+            "exit_code": failed.and_then(|c| c.exit_code),
+        }),
+    )?;
+
+    Ok(result)
+}
+
+/// Record the session outcome in config.toml and the event log.
+pub fn finish(grotto: &mut Grotto, outcome: SessionOutcome, rounds: u32) -> Result<()> {
+    grotto.config.outcome = Some(outcome);
+    grotto.write_config()?;
+    grotto.log_event(
+        &format!("session_{}", outcome),
+        None,
+        None,
+        Some(&format!("Session {} after {} round(s)", outcome, rounds)),
+        serde_json::json!({ "outcome": outcome, "rounds": rounds }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn snapshot(phase: AgentPhase) -> PaneSnapshot {
+        PaneSnapshot {
+            agent_id: "agent-1".to_string(),
+            pane_index: 0,
+            raw_content: String::new(),
+            phase,
+            last_activity_line: String::new(),
+            timestamp: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn agents_settled_requires_all_idle_or_finished() {
+        assert!(!agents_settled(&[]));
+        assert!(agents_settled(&[
+            snapshot(AgentPhase::Idle),
+            snapshot(AgentPhase::Finished)
+        ]));
+        assert!(!agents_settled(&[
+            snapshot(AgentPhase::Idle),
+            snapshot(AgentPhase::Running)
+        ]));
+    }
+
+    #[test]
+    fn run_round_logs_result() {
+        let tmp = TempDir::new().unwrap();
+        let mut grotto = Grotto::new(tmp.path(), 1, "t".into()).unwrap();
+        grotto.config.until = vec!["echo first; echo broken >&2; exit 2".to_string()];
+
+        let result = run_round(&grotto, 1).unwrap();
+        assert!(!result.success());
+        assert_eq!(result.failed_check().unwrap().exit_code, Some(2));
+
+        let events = std::fs::read_to_string(tmp.path().join(".grotto/events.jsonl")).unwrap();
+        assert!(events.contains("\"event_type\":\"verification_round\""));
+        assert!(events.contains("\"success\":false"));
+    }
+
+    #[test]
+    fn feedback_message_keeps_output_lines() {
+        let tmp = TempDir::new().unwrap();
+        let mut grotto = Grotto::new(tmp.path(), 1, "t".into()).unwrap();
+        grotto.config.until = vec!["printf 'a\\n  b\\n'; exit 1".to_string()];
+
+        let message = run_round(&grotto, 2).unwrap().feedback_message();
+        assert!(message.contains("round 2/3"));
+        assert!(message.contains("exited with 1"));
+        assert!(message.contains("Its output:\n\na\n  b\n\nFix the failure"));
+    }
+
+    #[test]
+    fn relaunch_reason_carries_the_failing_output() {
+        let tmp = TempDir::new().unwrap();
+        let mut grotto = Grotto::new(tmp.path(), 1, "t".into()).unwrap();
+        grotto.config.until = vec!["printf 'a\\nb\\n'; exit 1".to_string()];
+
+        let reason = run_round(&grotto, 1).unwrap().relaunch_reason();
+        assert!(reason.starts_with("verification round 1/3 failed"));
+        assert!(reason.contains("exited with 1"));
+        assert!(reason.contains("\n\na\nb\n"));
+
+        let prompt = grotto.generate_resume_prompt("agent-1", &reason);
+        assert!(prompt.contains("Fix the failure and commit. You had no task claimed"));
+    }
+
+    #[test]
+    fn finish_records_outcome() {
+        let tmp = TempDir::new().unwrap();
+        let mut grotto = Grotto::new(tmp.path(), 1, "t".into()).unwrap();
+        finish(&mut grotto, SessionOutcome::Succeeded, 2).unwrap();
+
+        let loaded = Grotto::load(tmp.path()).unwrap();
+        assert_eq!(loaded.config.outcome, Some(SessionOutcome::Succeeded));
+        let events = std::fs::read_to_string(tmp.path().join(".grotto/events.jsonl")).unwrap();
+        assert!(events.contains("\"event_type\":\"session_succeeded\""));
+    }
+}
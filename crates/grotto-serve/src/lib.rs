@@ -1,22 +1,32 @@
 use axum::{
     Json, Router,
     extract::{
-        Path, State, WebSocketUpgrade,
+        Path, Query, State, WebSocketUpgrade,
         ws::{Message, WebSocket},
     },
     response::{IntoResponse, Response},
     routing::{delete, get, post},
 };
 use futures::{Sink, SinkExt, StreamExt};
+use grotto_core::approvals::{self, ApprovalStatus};
+use grotto_core::backoff;
+use grotto_core::budget;
 use grotto_core::daemon::{self, SessionEntry, SessionRegistry};
 use grotto_core::monitor::{self, AgentPhase};
-use grotto_core::{AgentState, Event, Grotto};
+use grotto_core::mux::Tmux;
+use grotto_core::policy;
+use grotto_core::stall;
+use grotto_core::steer;
+use grotto_core::supervise;
+use grotto_core::terminal;
+use grotto_core::usage;
+use grotto_core::{AgentPane, AgentState, Event, Grotto};
 use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::sync::{RwLock, broadcast};
+use tokio::sync::{RwLock, broadcast, mpsc};
 
 #[derive(rust_embed::Embed)]
 // Use crate-local assets so packaged installs always include the UI bundle.
@@ -120,8 +130,7 @@ impl WsEvent {
     }
 }
 
-fn detect_session_liveness(session_id: &str, agent_count: usize) -> (bool, String) {
-    let snapshots = monitor::capture_all_agents(session_id, agent_count);
+fn detect_session_liveness(snapshots: &[monitor::PaneSnapshot]) -> (bool, String) {
     if snapshots.is_empty() {
         return (false, "completed".to_string());
     }
@@ -172,7 +181,9 @@ impl DaemonState {
 
         // Add sessions present in registry but missing in memory
         for (id, entry) in registry.sessions {
-            if sessions.contains_key(&id) {
+            if let Some(session) = sessions.get_mut(&id) {
+                // `grotto scale` updates the agent count of a live session
+                session.entry = entry;
                 continue;
             }
             let dir = PathBuf::from(&entry.dir);
@@ -182,8 +193,8 @@ impl DaemonState {
             }
 
             let (tx, _rx) = broadcast::channel::<String>(256);
-            let abort_handle = spawn_session_watcher(grotto_dir, tx.clone());
-            let monitor_abort = spawn_tmux_monitor(entry.id.clone(), entry.agent_count, tx.clone());
+            let abort_handle = spawn_session_watcher(grotto_dir.clone(), tx.clone());
+            let monitor_abort = spawn_tmux_monitor(entry.id.clone(), grotto_dir, tx.clone());
             sessions.insert(
                 id,
                 LiveSession {
@@ -216,8 +227,8 @@ impl DaemonState {
                 continue; // stale session, skip
             }
             let (tx, _rx) = broadcast::channel::<String>(256);
-            let abort_handle = spawn_session_watcher(grotto_dir, tx.clone());
-            let monitor_abort = spawn_tmux_monitor(entry.id.clone(), entry.agent_count, tx.clone());
+            let abort_handle = spawn_session_watcher(grotto_dir.clone(), tx.clone());
+            let monitor_abort = spawn_tmux_monitor(entry.id.clone(), grotto_dir, tx.clone());
             let mut sessions = state.sessions.write().await;
             sessions.insert(
                 entry.id.clone(),
@@ -240,20 +251,29 @@ impl DaemonState {
                 let tasks = parse_task_board(&grotto_dir.join("tasks.md"));
 
                 // Enrich agents with live tmux phase data
+                let snapshots = if g.config.session_id.is_some() {
+                    monitor::capture_team(&g, &Tmux)
+                } else {
+                    Vec::new()
+                };
                 let mut agents = g.agents;
                 let mut session_active = false;
                 let mut session_status = "completed".to_string();
 
-                if let Some(session_id) = &g.config.session_id {
-                    let snapshots = monitor::capture_all_agents(session_id, g.config.agent_count);
+                if g.config.session_id.is_some() {
                     for snap in &snapshots {
                         if let Some(agent) = agents.get_mut(&snap.agent_id) {
                             agent.phase = Some(snap.phase.to_string());
                         }
                     }
+                    // Agents without a live pane keep their last recorded phase
+                    for (id, agent) in agents.iter_mut().filter(|(_, a)| a.phase.is_none()) {
+                        agent.phase = monitor::read_phase_transitions(grotto_dir, id)
+                            .pop()
+                            .map(|t| t.phase.to_string());
+                    }
 
-                    let (active, status) =
-                        detect_session_liveness(session_id, g.config.agent_count);
+                    let (active, status) = detect_session_liveness(&snapshots);
                     session_active = active;
                     session_status = status;
                 }
@@ -377,10 +397,10 @@ pub async fn run_server(
 
     // Start tmux monitor for real-time phase tracking
     let project_dir = grotto_dir.parent().unwrap_or(std::path::Path::new("."));
-    if let Ok(g) = Grotto::load(project_dir) {
-        if let Some(session_id) = &g.config.session_id {
-            let _monitor = spawn_tmux_monitor(session_id.clone(), g.config.agent_count, tx.clone());
-        }
+    if let Ok(g) = Grotto::load(project_dir)
+        && let Some(session_id) = &g.config.session_id
+    {
+        let _monitor = spawn_tmux_monitor(session_id.clone(), grotto_dir.clone(), tx.clone());
     }
 
     let mut app = Router::new()
@@ -419,25 +439,161 @@ async fn ws_handler(ws: WebSocketUpgrade, State(state): State<AppState>) -> impl
     ws.on_upgrade(move |socket| handle_ws(socket, state.tx.clone(), state.grotto_dir.clone()))
 }
 
-async fn forward_broadcast_to_ws<S>(mut sender: S, mut rx: broadcast::Receiver<String>)
-where
+/// Send session broadcasts, and messages meant for this client only
+/// (`direct`), to a WebSocket until either side closes.
+async fn forward_broadcast_to_ws<S>(
+    mut sender: S,
+    mut rx: broadcast::Receiver<String>,
+    mut direct: mpsc::UnboundedReceiver<String>,
+) where
     S: Sink<Message> + Unpin,
 {
     loop {
-        match rx.recv().await {
-            Ok(msg) => {
-                if sender.send(Message::Text(msg.into())).await.is_err() {
-                    break;
+        let msg = tokio::select! {
+            received = rx.recv() => match received {
+                Ok(msg) => msg,
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    eprintln!("ws receiver lagged; skipped {skipped} messages");
+                    continue;
                 }
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            Some(msg) = direct.recv() => msg,
+        };
+        if sender.send(Message::Text(msg.into())).await.is_err() {
+            break;
+        }
+    }
+}
+
+/// Requests a client can send on a session WebSocket.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type")]
+pub enum ClientMessage {
+    /// Stream the agent's terminal output (`terminal:output`) from now on
+    #[serde(rename = "terminal:subscribe")]
+    Subscribe { agent_id: String },
+    #[serde(rename = "terminal:unsubscribe")]
+    Unsubscribe { agent_id: String },
+    /// Output from before the subscription (`terminal:scrollback`)
+    #[serde(rename = "terminal:scrollback")]
+    Scrollback {
+        agent_id: String,
+        #[serde(default)]
+        bytes: Option<u64>,
+    },
+}
+
+/// How often subscribed agents' stream logs are checked for new output.
+const TERMINAL_POLL_MS: u64 = 250;
+
+/// Serve one client's terminal subscriptions, reading its requests from
+/// `requests` and sending output to `out` until the client goes away.
+async fn stream_terminals(
+    grotto_dir: PathBuf,
+    mut requests: mpsc::UnboundedReceiver<ClientMessage>,
+    out: mpsc::UnboundedSender<String>,
+) {
+    let mut follower = terminal::TerminalFollower::new(&grotto_dir);
+    let mut interval = tokio::time::interval(std::time::Duration::from_millis(TERMINAL_POLL_MS));
+    let send = |event: WsEvent| {
+        serde_json::to_string(&event)
+            .map(|json| out.send(json).is_ok())
+            .unwrap_or(true)
+    };
+
+    loop {
+        let events = tokio::select! {
+            request = requests.recv() => {
+                let Some(request) = request else { break };
+                vec![handle_terminal_request(&grotto_dir, &mut follower, request)]
             }
-            Err(broadcast::error::RecvError::Lagged(skipped)) => {
-                eprintln!("ws receiver lagged; skipped {skipped} messages");
+            _ = interval.tick() => follower
+                .poll()
+                .into_iter()
+                .map(|(agent_id, chunk)| terminal_event("terminal:output", agent_id, chunk))
+                .collect(),
+        };
+        if !events.into_iter().all(send) {
+            break;
+        }
+    }
+}
+
+fn handle_terminal_request(
+    grotto_dir: &std::path::Path,
+    follower: &mut terminal::TerminalFollower,
+    request: ClientMessage,
+) -> WsEvent {
+    let agent_id = match &request {
+        ClientMessage::Subscribe { agent_id }
+        | ClientMessage::Unsubscribe { agent_id }
+        | ClientMessage::Scrollback { agent_id, .. } => agent_id.clone(),
+    };
+    // Only agents on the team: the ID becomes part of a path
+    let project_dir = grotto_dir.parent().unwrap_or(std::path::Path::new("."));
+    let known = Grotto::load(project_dir)
+        .map(|g| g.agents.contains_key(&agent_id))
+        .unwrap_or(false);
+    if !known {
+        return terminal_error(agent_id.clone(), format!("Agent not found: {}", agent_id));
+    }
+
+    match request {
+        ClientMessage::Subscribe { .. } => {
+            follower.subscribe(&agent_id);
+            WsEvent::message_event(
+                "terminal:subscribed",
+                chrono::Utc::now().to_rfc3339(),
+                Some(agent_id),
+                None,
+                None,
+                None,
+            )
+        }
+        ClientMessage::Unsubscribe { .. } => {
+            follower.unsubscribe(&agent_id);
+            WsEvent::message_event(
+                "terminal:unsubscribed",
+                chrono::Utc::now().to_rfc3339(),
+                Some(agent_id),
+                None,
+                None,
+                None,
+            )
+        }
+        ClientMessage::Scrollback { bytes, .. } => {
+            let bytes = bytes.unwrap_or(terminal::DEFAULT_SCROLLBACK_BYTES);
+            match follower.scrollback(&agent_id, bytes) {
+                Ok(chunk) => terminal_event("terminal:scrollback", agent_id, chunk),
+                Err(e) => terminal_error(agent_id, e.to_string()),
             }
-            Err(broadcast::error::RecvError::Closed) => break,
         }
     }
 }
 
+fn terminal_event(kind: &str, agent_id: String, chunk: terminal::Chunk) -> WsEvent {
+    WsEvent::message_event(
+        kind,
+        chrono::Utc::now().to_rfc3339(),
+        Some(agent_id),
+        None,
+        None,
+        Some(serde_json::json!(chunk)),
+    )
+}
+
+fn terminal_error(agent_id: String, message: String) -> WsEvent {
+    WsEvent::message_event(
+        "terminal:error",
+        chrono::Utc::now().to_rfc3339(),
+        Some(agent_id),
+        None,
+        Some(message),
+        None,
+    )
+}
+
 async fn handle_ws(socket: WebSocket, tx: broadcast::Sender<String>, grotto_dir: PathBuf) {
     let (mut sender, mut receiver) = socket.split();
 
@@ -448,18 +604,31 @@ async fn handle_ws(socket: WebSocket, tx: broadcast::Sender<String>, grotto_dir:
     }
 
     let rx = tx.subscribe();
+    let (direct_tx, direct_rx) = mpsc::unbounded_channel();
+    let (request_tx, request_rx) = mpsc::unbounded_channel();
 
     let mut send_task = tokio::spawn(async move {
-        forward_broadcast_to_ws(sender, rx).await;
+        forward_broadcast_to_ws(sender, rx, direct_rx).await;
     });
 
-    let mut recv_task =
-        tokio::spawn(async move { while let Some(Ok(_msg)) = receiver.next().await {} });
+    let terminal_task = tokio::spawn(stream_terminals(grotto_dir, request_rx, direct_tx));
+
+    let mut recv_task = tokio::spawn(async move {
+        while let Some(Ok(msg)) = receiver.next().await {
+            let Message::Text(text) = msg else { continue };
+            if let Ok(request) = serde_json::from_str::<ClientMessage>(&text)
+                && request_tx.send(request).is_err()
+            {
+                break;
+            }
+        }
+    });
 
     tokio::select! {
         _ = &mut send_task => { recv_task.abort(); }
         _ = &mut recv_task => { send_task.abort(); }
     }
+    terminal_task.abort();
 }
 
 // ---------------------------------------------------------------------------
@@ -482,6 +651,8 @@ pub struct SessionResponse {
     pub task: Option<String>,
     pub status: String,
     pub last_updated: Option<String>,
+    /// Tokens and cost parsed from the agents' stream logs
+    pub usage: Option<usage::Usage>,
 }
 
 /// Run the multi-session daemon server
@@ -524,6 +695,34 @@ pub async fn run_daemon(port: u16, web_dir: Option<PathBuf>) -> std::io::Result<
                 move |path| api_session_events(s, path)
             }),
         )
+        .route(
+            "/api/sessions/{id}/scorecard",
+            get({
+                let s = daemon_state.clone();
+                move |path| api_session_scorecard(s, path)
+            }),
+        )
+        .route(
+            "/api/sessions/{id}/timeline",
+            get({
+                let s = daemon_state.clone();
+                move |path, query| api_session_timeline(s, path, query)
+            }),
+        )
+        .route(
+            "/api/sessions/{id}/approvals",
+            get({
+                let s = daemon_state.clone();
+                move |path| api_session_approvals(s, path)
+            }),
+        )
+        .route(
+            "/api/sessions/{id}/approvals/{approval_id}",
+            post({
+                let s = daemon_state.clone();
+                move |path, body| api_decide_approval(s, path, body)
+            }),
+        )
         .route(
             "/ws/{id}",
             get({
@@ -558,10 +757,7 @@ fn read_last_event_timestamp(session_dir: &str) -> Option<String> {
         .join(".grotto")
         .join("events.jsonl");
     let content = std::fs::read_to_string(&events_path).ok()?;
-    let last_line = content
-        .lines()
-        .filter(|line| !line.trim().is_empty())
-        .next_back()?;
+    let last_line = content.lines().rfind(|line| !line.trim().is_empty())?;
     let value: serde_json::Value = serde_json::from_str(last_line).ok()?;
     value
         .get("timestamp")
@@ -569,11 +765,17 @@ fn read_last_event_timestamp(session_dir: &str) -> Option<String> {
         .map(|s| s.to_string())
 }
 
+fn read_session_usage(session_dir: &str) -> Option<usage::Usage> {
+    let grotto = Grotto::load(session_dir).ok()?;
+    Some(usage::total(&grotto))
+}
+
 fn infer_session_status(entry: &SessionEntry) -> String {
-    if entry.agent_count == 0 {
-        return "completed".to_string();
-    }
-    let (_active, status) = detect_session_liveness(&entry.id, entry.agent_count);
+    let snapshots = match Grotto::load(&entry.dir) {
+        Ok(g) => monitor::capture_team(&g, &Tmux),
+        Err(_) => Vec::new(),
+    };
+    let (_active, status) = detect_session_liveness(&snapshots);
     status
 }
 
@@ -590,6 +792,7 @@ async fn api_list_sessions(state: Arc<DaemonState>) -> impl IntoResponse {
             task: Some(session.entry.task.clone()),
             status: infer_session_status(&session.entry),
             last_updated: read_last_event_timestamp(&session.entry.dir),
+            usage: read_session_usage(&session.entry.dir),
         });
     }
 
@@ -617,7 +820,7 @@ async fn api_register_session(
     }
 
     let (tx, _rx) = broadcast::channel::<String>(256);
-    let abort_handle = spawn_session_watcher(grotto_dir, tx.clone());
+    let abort_handle = spawn_session_watcher(grotto_dir.clone(), tx.clone());
 
     // Read task info from the grotto state
     let (agent_count, task) = match Grotto::load(&dir) {
@@ -625,7 +828,7 @@ async fn api_register_session(
         Err(_) => (0, String::new()),
     };
 
-    let monitor_abort = spawn_tmux_monitor(body.id.clone(), agent_count, tx.clone());
+    let monitor_abort = spawn_tmux_monitor(body.id.clone(), grotto_dir, tx.clone());
 
     let entry = SessionEntry {
         id: body.id.clone(),
@@ -698,6 +901,157 @@ async fn api_session_events(state: Arc<DaemonState>, Path(id): Path<String>) ->
     }
 }
 
+async fn api_session_scorecard(
+    state: Arc<DaemonState>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    state.sync_from_registry().await;
+    let sessions = state.sessions.read().await;
+    let Some(session) = sessions.get(&id) else {
+        return (
+            axum::http::StatusCode::NOT_FOUND,
+            Json(serde_json::json!({"error": format!("Session '{}' not found", id)})),
+        );
+    };
+    let dir = PathBuf::from(&session.entry.dir);
+    drop(sessions);
+
+    // Building the scorecard shells out to git. Only built, not written:
+    // scorecard.json and summary.md belong to `grotto scorecard` and `wait`
+    let built = tokio::task::spawn_blocking(move || {
+        Grotto::load(&dir).and_then(|g| grotto_core::scorecard::build(&g))
+    })
+    .await;
+    match built {
+        Ok(Ok(scorecard)) => (
+            axum::http::StatusCode::OK,
+            Json(serde_json::json!(scorecard)),
+        ),
+        Ok(Err(e)) => (
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({"error": e.to_string()})),
+        ),
+        Err(e) => (
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({"error": e.to_string()})),
+        ),
+    }
+}
+
+async fn session_dir(state: &DaemonState, id: &str) -> Option<PathBuf> {
+    state.sync_from_registry().await;
+    let sessions = state.sessions.read().await;
+    sessions.get(id).map(|s| PathBuf::from(&s.entry.dir))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TimelineQuery {
+    /// Only this agent's transitions
+    pub agent: Option<String>,
+}
+
+/// Each agent's recorded phase transitions this session, keyed by agent ID.
+async fn api_session_timeline(
+    state: Arc<DaemonState>,
+    Path(id): Path<String>,
+    Query(query): Query<TimelineQuery>,
+) -> impl IntoResponse {
+    let Some(dir) = session_dir(&state, &id).await else {
+        return (
+            axum::http::StatusCode::NOT_FOUND,
+            Json(serde_json::json!({"error": format!("Session '{}' not found", id)})),
+        );
+    };
+    let timeline = Grotto::load(&dir).and_then(|g| monitor::session_timeline(&g));
+    match timeline {
+        Ok(mut timeline) => {
+            if let Some(agent) = query.agent {
+                timeline.retain(|id, _| *id == agent);
+            }
+            (
+                axum::http::StatusCode::OK,
+                Json(serde_json::json!(timeline)),
+            )
+        }
+        Err(e) => (
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({"error": e.to_string()})),
+        ),
+    }
+}
+
+async fn api_session_approvals(
+    state: Arc<DaemonState>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    let Some(dir) = session_dir(&state, &id).await else {
+        return (
+            axum::http::StatusCode::NOT_FOUND,
+            Json(serde_json::json!({"error": format!("Session '{}' not found", id)})),
+        );
+    };
+    match approvals::list(&dir.join(".grotto")) {
+        Ok(list) => (axum::http::StatusCode::OK, Json(serde_json::json!(list))),
+        Err(e) => (
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({"error": e.to_string()})),
+        ),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ApprovalDecision {
+    /// "approve" or "deny"
+    pub decision: String,
+    pub reason: Option<String>,
+}
+
+async fn api_decide_approval(
+    state: Arc<DaemonState>,
+    Path((id, approval_id)): Path<(String, String)>,
+    Json(body): Json<ApprovalDecision>,
+) -> impl IntoResponse {
+    let status = match body.decision.as_str() {
+        "approve" => ApprovalStatus::Approved,
+        "deny" => ApprovalStatus::Denied,
+        other => {
+            return (
+                axum::http::StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({
+                    "error": format!("Unknown decision '{}' (expected approve or deny)", other)
+                })),
+            );
+        }
+    };
+    let Some(dir) = session_dir(&state, &id).await else {
+        return (
+            axum::http::StatusCode::NOT_FOUND,
+            Json(serde_json::json!({"error": format!("Session '{}' not found", id)})),
+        );
+    };
+
+    let decided = Grotto::load(&dir)
+        .and_then(|g| approvals::decide(&g, &approval_id, status, body.reason.as_deref(), "web"));
+    match decided {
+        Ok(approval) => (
+            axum::http::StatusCode::OK,
+            Json(serde_json::json!(approval)),
+        ),
+        Err(e @ grotto_core::GrottoError::ApprovalNotFound(_)) => (
+            axum::http::StatusCode::NOT_FOUND,
+            Json(serde_json::json!({"error": e.to_string()})),
+        ),
+        Err(grotto_core::GrottoError::Io(e)) if e.kind() == std::io::ErrorKind::InvalidInput => (
+            axum::http::StatusCode::CONFLICT,
+            Json(serde_json::json!({"error": e.to_string()})),
+        ),
+        Err(e) => (
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({"error": e.to_string()})),
+        ),
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Per-session WebSocket handler
 // ---------------------------------------------------------------------------
@@ -824,40 +1178,106 @@ fn mime_from_ext(path: &std::path::Path) -> &'static str {
 // Tmux pane monitor (spawned as a tokio task)
 // ---------------------------------------------------------------------------
 
-/// Spawn a tmux monitor that polls pane output every 750ms and broadcasts
-/// `agent:phase` events when an agent's phase changes.
+/// Spawn a tmux monitor that polls pane output every 750ms, broadcasts
+/// `agent:phase` events when an agent's phase changes, and records each
+/// change in the agent's `phases.jsonl`.
 fn spawn_tmux_monitor(
     session_id: String,
-    agent_count: usize,
+    grotto_dir: PathBuf,
     tx: broadcast::Sender<String>,
 ) -> tokio::task::AbortHandle {
     let handle = tokio::spawn(async move {
-        run_tmux_monitor(session_id, agent_count, tx).await;
+        run_tmux_monitor(session_id, grotto_dir, tx).await;
     });
     handle.abort_handle()
 }
 
-async fn run_tmux_monitor(session_id: String, agent_count: usize, tx: broadcast::Sender<String>) {
-    use std::collections::HashMap;
-
-    let mut prev_phases: HashMap<String, AgentPhase> = HashMap::new();
+async fn run_tmux_monitor(session_id: String, grotto_dir: PathBuf, tx: broadcast::Sender<String>) {
+    let mut phases = monitor::PhaseRecorder::new();
     let mut interval = tokio::time::interval(std::time::Duration::from_millis(750));
+    let mut policy_watcher = policy::PolicyWatcher::new(&grotto_dir);
+    let mut backoff = backoff::Backoff::new();
+    let mut stalls = stall::StallDetector::new();
+    let mut panes: Vec<AgentPane> = Vec::new();
 
     // Track consecutive capture failures to detect session death
     let mut consecutive_failures: usize = 0;
+    let mut ticks: u64 = 0;
 
     loop {
         interval.tick().await;
+        ticks += 1;
+
+        // Re-read the team each tick: `grotto scale` adds and retires agents.
+        // The policy scan reads agent logs, so it runs off the runtime too
+        let captured = tokio::task::spawn_blocking({
+            let grotto_dir = grotto_dir.clone();
+            let panes = panes.clone();
+            let tx = tx.clone();
+            move || {
+                let project_dir = grotto_dir.parent().unwrap_or(std::path::Path::new("."));
+                let (team, snapshots) = match Grotto::load(project_dir) {
+                    Ok(g) => (g.agent_panes(), monitor::capture_team(&g, &Tmux)),
+                    Err(_) => {
+                        let snapshots = monitor::capture_all_agents(&Tmux, &panes);
+                        (panes, snapshots)
+                    }
+                };
+                let agent_ids: Vec<String> =
+                    team.iter().map(|pane| pane.agent_id.clone()).collect();
+                check_policy(
+                    &mut policy_watcher,
+                    &grotto_dir,
+                    &agent_ids,
+                    &snapshots,
+                    &tx,
+                );
+                (team, snapshots, policy_watcher)
+            }
+        })
+        .await;
+        let snapshots = match captured {
+            Ok((team, snapshots, watcher)) => {
+                panes = team;
+                policy_watcher = watcher;
+                snapshots
+            }
+            Err(_) => {
+                policy_watcher = policy::PolicyWatcher::new(&grotto_dir);
+                continue;
+            }
+        };
+
+        if snapshots.is_empty() {
+            continue;
+        }
 
-        let snapshots = tokio::task::spawn_blocking({
-            let session_id = session_id.clone();
-            move || monitor::capture_all_agents(&session_id, agent_count)
+        recover_rate_limits(&mut backoff, &grotto_dir, &snapshots).await;
+        // Stall checks load the session and nudges wait for the pasted text,
+        // so the whole pass runs off the runtime
+        stalls = tokio::task::spawn_blocking({
+            let grotto_dir = grotto_dir.clone();
+            let snapshots = snapshots.clone();
+            let tx = tx.clone();
+            move || {
+                detect_stalls(&mut stalls, &grotto_dir, &snapshots, &tx);
+                stalls
+            }
         })
         .await
         .unwrap_or_default();
 
-        if snapshots.is_empty() {
-            continue;
+        if ticks.is_multiple_of(BUDGET_CHECK_TICKS) {
+            let grotto_dir = grotto_dir.clone();
+            let _ = tokio::task::spawn_blocking(move || enforce_budgets(&grotto_dir)).await;
+        }
+        if ticks.is_multiple_of(SUPERVISE_TICKS) {
+            let grotto_dir = grotto_dir.clone();
+            let _ = tokio::task::spawn_blocking(move || supervise_agents(&grotto_dir)).await;
+        }
+        if ticks.is_multiple_of(STEER_QUEUE_TICKS) {
+            let grotto_dir = grotto_dir.clone();
+            let _ = tokio::task::spawn_blocking(move || flush_steer_queues(&grotto_dir)).await;
         }
 
         // Check if all panes failed to capture (session likely dead)
@@ -894,32 +1314,238 @@ async fn run_tmux_monitor(session_id: String, agent_count: usize, tx: broadcast:
             consecutive_failures = 0;
         }
 
-        for snap in &snapshots {
-            let changed = prev_phases
-                .get(&snap.agent_id)
-                .map(|prev| *prev != snap.phase)
-                .unwrap_or(true);
+        for snap in phases.record(&grotto_dir, &snapshots) {
+            let ws_event = WsEvent::message_event(
+                "agent:phase",
+                snap.timestamp.to_rfc3339(),
+                Some(snap.agent_id.clone()),
+                None,
+                Some(format!("Agent {} phase: {}", snap.agent_id, snap.phase)),
+                Some(serde_json::json!({
+                    "phase": snap.phase.to_string(),
+                    "last_activity": snap.last_activity_line,
+                })),
+            );
+
+            if let Ok(json) = serde_json::to_string(&ws_event) {
+                let _ = tx.send(json);
+            }
+        }
+    }
+}
+
+/// Monitor ticks between budget checks (~6s).
+const BUDGET_CHECK_TICKS: u64 = 8;
 
-            if changed {
-                prev_phases.insert(snap.agent_id.clone(), snap.phase.clone());
+/// Stop agents past their `--max-duration`, `--agent-timeout` or `--max-cost`
+/// budget even when nobody is running `grotto wait`. Resulting events and status changes
+/// reach clients through the file watcher.
+fn enforce_budgets(grotto_dir: &std::path::Path) {
+    let project_dir = grotto_dir.parent().unwrap_or(std::path::Path::new("."));
+    let Ok(mut grotto) = Grotto::load(project_dir) else {
+        return;
+    };
+    // `grotto wait` enforces budgets too; skip this pass while it holds the lock
+    let Ok(Some(_lock)) = grotto.try_lock("budget") else {
+        return;
+    };
+    let alerts = match budget::check(&grotto, chrono::Utc::now()) {
+        Ok(alerts) if !alerts.is_empty() => alerts,
+        _ => return,
+    };
+    if let Err(e) = budget::enforce(
+        &mut grotto,
+        &Tmux,
+        &alerts,
+        std::time::Duration::from_secs(2),
+    ) {
+        eprintln!("Failed to enforce budget: {}", e);
+    }
+}
 
-                let ws_event = WsEvent::message_event(
-                    "agent:phase",
-                    snap.timestamp.to_rfc3339(),
-                    Some(snap.agent_id.clone()),
-                    None,
-                    Some(format!("Agent {} phase: {}", snap.agent_id, snap.phase)),
-                    Some(serde_json::json!({
-                        "phase": snap.phase.to_string(),
-                        "last_activity": snap.last_activity_line,
-                    })),
-                );
+/// Monitor ticks between supervisor passes (~3s).
+const SUPERVISE_TICKS: u64 = 4;
 
-                if let Ok(json) = serde_json::to_string(&ws_event) {
-                    let _ = tx.send(json);
-                }
+/// Restart or settle agents whose process exited, for sessions spawned with
+/// a restart policy. Events and status changes reach clients through the
+/// file watcher.
+fn supervise_agents(grotto_dir: &std::path::Path) {
+    let project_dir = grotto_dir.parent().unwrap_or(std::path::Path::new("."));
+    let Ok(mut grotto) = Grotto::load(project_dir) else {
+        return;
+    };
+    if grotto.config.restart.is_never() {
+        return;
+    }
+    // `grotto wait` supervises too; skip this pass while it holds the lock
+    let Ok(Some(_lock)) = grotto.try_lock("supervise") else {
+        return;
+    };
+    if let Err(e) = supervise::tick(&mut grotto, &Tmux) {
+        eprintln!("Failed to supervise agents: {}", e);
+    }
+}
+
+/// Monitor ticks between attempts to deliver queued steering messages (~1.5s).
+const STEER_QUEUE_TICKS: u64 = 2;
+
+/// Deliver steering messages queued while their agents were thinking, so
+/// they arrive even when nobody is running `grotto wait`.
+fn flush_steer_queues(grotto_dir: &std::path::Path) {
+    let project_dir = grotto_dir.parent().unwrap_or(std::path::Path::new("."));
+    let Ok(grotto) = Grotto::load(project_dir) else {
+        return;
+    };
+    // `grotto wait` delivers them too; skip this pass while it holds the lock
+    let Ok(Some(_lock)) = grotto.try_lock("steer") else {
+        return;
+    };
+    if let Err(e) = steer::flush_queued(&grotto, &Tmux, steer::SteerOptions::default()) {
+        eprintln!("Failed to deliver queued messages: {}", e);
+    }
+}
+
+/// Back off rate-limited agents, then nudge or restart them. Each step is
+/// logged, so clients see it through the file watcher.
+async fn recover_rate_limits(
+    backoff: &mut backoff::Backoff,
+    grotto_dir: &std::path::Path,
+    snapshots: &[monitor::PaneSnapshot],
+) {
+    let steps = backoff.observe(snapshots, chrono::Utc::now());
+    if steps.is_empty() {
+        return;
+    }
+
+    // Nudges and restarts drive tmux, so keep them off the runtime
+    let grotto_dir = grotto_dir.to_path_buf();
+    let _ = tokio::task::spawn_blocking(move || {
+        let project_dir = grotto_dir.parent().unwrap_or(std::path::Path::new("."));
+        let Ok(mut grotto) = Grotto::load(project_dir) else {
+            return;
+        };
+        for step in &steps {
+            if let Err(e) = backoff::apply(&mut grotto, &Tmux, step) {
+                eprintln!("Failed to recover rate-limited agent: {}", e);
             }
         }
+    })
+    .await;
+}
+
+/// Flag agents whose screen stopped changing or that keep repeating the same
+/// command or error, broadcasting `agent:stalled` / `agent:looping` and
+/// carrying out the session's `on_stall` action.
+fn detect_stalls(
+    detector: &mut stall::StallDetector,
+    grotto_dir: &std::path::Path,
+    snapshots: &[monitor::PaneSnapshot],
+    tx: &broadcast::Sender<String>,
+) {
+    let project_dir = grotto_dir.parent().unwrap_or(std::path::Path::new("."));
+    let Ok(mut grotto) = Grotto::load(project_dir) else {
+        return;
+    };
+    // Another monitor of this session may be watching too; whoever holds
+    // the lock goes
+    let Ok(Some(_lock)) = grotto.try_lock("stall") else {
+        return;
+    };
+    let signals = detector.observe(
+        snapshots,
+        grotto.config.stall_after_secs,
+        grotto.config.loop_repeats,
+        chrono::Utc::now(),
+    );
+    if signals.is_empty() {
+        return;
+    }
+
+    for signal in &signals {
+        let (kind, data) = match signal {
+            stall::Signal::Stalled {
+                idle_secs, line, ..
+            } => (
+                "agent:stalled",
+                serde_json::json!({ "idle_secs": idle_secs, "line": line }),
+            ),
+            stall::Signal::Looping { line, repeats, .. } => (
+                "agent:looping",
+                serde_json::json!({ "line": line, "repeats": repeats }),
+            ),
+        };
+        let ws_event = WsEvent::message_event(
+            kind,
+            chrono::Utc::now().to_rfc3339(),
+            Some(signal.agent_id().to_string()),
+            None,
+            Some(signal.describe()),
+            Some(data),
+        );
+        if let Ok(json) = serde_json::to_string(&ws_event) {
+            let _ = tx.send(json);
+        }
+    }
+
+    let action = grotto.config.on_stall;
+    for signal in &signals {
+        if let Err(e) = stall::apply(&mut grotto, &Tmux, signal, action) {
+            eprintln!("Failed to handle {}: {}", signal.event_type(), e);
+        }
+    }
+}
+
+/// Evaluate new agent output against `.grotto/policy.toml`, pausing offenders
+/// and broadcasting a `policy:violation` event for each violation.
+fn check_policy(
+    watcher: &mut policy::PolicyWatcher,
+    grotto_dir: &std::path::Path,
+    agent_ids: &[String],
+    snapshots: &[monitor::PaneSnapshot],
+    tx: &broadcast::Sender<String>,
+) {
+    let violations = match watcher.scan(agent_ids, snapshots) {
+        Ok(violations) => violations,
+        Err(e) => {
+            eprintln!("Policy check failed: {}", e);
+            return;
+        }
+    };
+    if violations.is_empty() {
+        return;
+    }
+
+    let project_dir = grotto_dir.parent().unwrap_or(std::path::Path::new("."));
+    let Ok(mut grotto) = Grotto::load(project_dir) else {
+        return;
+    };
+    let action = watcher.pause_action();
+    for violation in violations {
+        if let Err(e) = policy::enforce(&mut grotto, &Tmux, &violation, action) {
+            eprintln!("Failed to enforce policy for {}: {}", violation.agent_id, e);
+        }
+
+        let ws_event = WsEvent::message_event(
+            "policy:violation",
+            chrono::Utc::now().to_rfc3339(),
+            Some(violation.agent_id.clone()),
+            None,
+            Some(format!(
+                "Policy rule '{}' violated by {}: {}",
+                violation.rule_match.rule, violation.agent_id, violation.line
+            )),
+            Some(serde_json::json!({
+                "rule": violation.rule_match.rule,
+                "kind": violation.rule_match.kind,
+                "matched": violation.rule_match.matched,
+                "line": violation.line,
+                "source": violation.source,
+                "action": action,
+            })),
+        );
+        if let Ok(json) = serde_json::to_string(&ws_event) {
+            let _ = tx.send(json);
+        }
     }
 }
 
@@ -1016,6 +1642,27 @@ async fn run_file_watcher(
                                     let _ = tx.send(json);
                                 }
                             }
+                            _ if is_approval_file(path) => {
+                                if let Ok(content) = tokio::fs::read_to_string(path).await
+                                    && let Ok(approval) =
+                                        serde_json::from_str::<approvals::Approval>(&content)
+                                {
+                                    let ws_event = WsEvent::message_event(
+                                        "approval:updated",
+                                        chrono::Utc::now().to_rfc3339(),
+                                        Some(approval.agent_id.clone()),
+                                        None,
+                                        Some(format!(
+                                            "{} {}: {}",
+                                            approval.id, approval.status, approval.description
+                                        )),
+                                        Some(serde_json::to_value(&approval).unwrap_or_default()),
+                                    );
+                                    if let Ok(json) = serde_json::to_string(&ws_event) {
+                                        let _ = tx.send(json);
+                                    }
+                                }
+                            }
                             _ => {}
                         }
                     }
@@ -1028,6 +1675,14 @@ async fn run_file_watcher(
     Ok(())
 }
 
+fn is_approval_file(path: &std::path::Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "json")
+        && path
+            .parent()
+            .and_then(|p| p.file_name())
+            .is_some_and(|name| name == approvals::APPROVALS_DIR)
+}
+
 fn count_lines(path: &std::path::Path) -> usize {
     std::fs::read_to_string(path)
         .map(|c| c.lines().count())
@@ -1278,9 +1933,10 @@ mod tests {
     async fn test_forward_broadcast_survives_lagged() {
         let (tx, rx) = broadcast::channel::<String>(2);
         let (sink_tx, mut sink_rx) = mpsc::channel::<Message>(1);
+        let (_direct_tx, direct_rx) = tokio::sync::mpsc::unbounded_channel();
 
         let forward = tokio::spawn(async move {
-            forward_broadcast_to_ws(sink_tx, rx).await;
+            forward_broadcast_to_ws(sink_tx, rx, direct_rx).await;
         });
 
         tx.send("first".to_string()).unwrap();
@@ -1304,12 +1960,10 @@ mod tests {
 
         tokio::time::timeout(Duration::from_secs(1), async {
             loop {
-                if let Some(msg) = sink_rx.next().await {
-                    if let Message::Text(text) = msg {
-                        if text == "after" {
-                            break;
-                        }
-                    }
+                if let Some(Message::Text(text)) = sink_rx.next().await
+                    && text == "after"
+                {
+                    break;
                 }
             }
         })
@@ -1,5 +1,6 @@
-use futures::StreamExt;
+use futures::{SinkExt, StreamExt};
 use grotto_core::Grotto;
+use grotto_core::monitor::{AgentPhase, PhaseTransition, record_phase_transition};
 use grotto_serve::WsEvent;
 use std::time::Duration;
 use tempfile::TempDir;
@@ -57,10 +58,10 @@ async fn wait_for_event_type(ws: &mut WsStream, event_type: &str, timeout: Durat
         loop {
             match ws.next().await {
                 Some(Ok(msg)) => {
-                    if let Ok(event) = parse_ws_text_message(msg) {
-                        if event.event_type == event_type {
-                            return event;
-                        }
+                    if let Ok(event) = parse_ws_text_message(msg)
+                        && event.event_type == event_type
+                    {
+                        return event;
                     }
                 }
                 Some(Err(err)) => panic!("WebSocket error while waiting for {event_type}: {err}"),
@@ -90,14 +91,10 @@ async fn http_get(port: u16, path: &str) -> String {
     http_request(port, &req).await
 }
 
-async fn register_session(port: u16, id: &str, dir: &std::path::Path) -> String {
-    let body = serde_json::json!({
-        "id": id,
-        "dir": dir.display().to_string()
-    });
-    let body_str = serde_json::to_string(&body).unwrap();
+async fn http_post_json(port: u16, path: &str, body: &serde_json::Value) -> String {
+    let body_str = serde_json::to_string(body).unwrap();
     let req = format!(
-        "POST /api/sessions HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+        "POST {path} HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
         body_str.len(),
         body_str
     );
@@ -105,6 +102,14 @@ async fn register_session(port: u16, id: &str, dir: &std::path::Path) -> String
     http_request(port, &req).await
 }
 
+async fn register_session(port: u16, id: &str, dir: &std::path::Path) -> String {
+    let body = serde_json::json!({
+        "id": id,
+        "dir": dir.display().to_string()
+    });
+    http_post_json(port, "/api/sessions", &body).await
+}
+
 #[tokio::test]
 async fn test_health_endpoint() {
     let tmp = TempDir::new().unwrap();
@@ -156,11 +161,17 @@ async fn test_ws_receives_agent_status_change() {
     let agent_status = grotto_core::AgentState {
         id: "agent-1".to_string(),
         pane_index: 0,
+        pane_id: None,
         state: "working".to_string(),
         current_task: Some("main".to_string()),
         progress: "Building the API".to_string(),
         last_update: chrono::Utc::now(),
         phase: None,
+        worktree: None,
+        branch: None,
+        role: None,
+        model: None,
+        report: None,
     };
     let status_json = serde_json::to_string_pretty(&agent_status).unwrap();
     let status_path = dir.join(".grotto/agents/agent-1/status.json");
@@ -261,6 +272,11 @@ async fn test_daemon_register_and_list_sessions() {
     let tmp = TempDir::new().unwrap();
     let dir = tmp.path().to_path_buf();
     let _grotto = Grotto::new(&dir, 2, "test daemon task".into()).unwrap();
+    std::fs::write(
+        dir.join(".grotto/agents/agent-1/output.log"),
+        "Total cost: $0.42\nUsage: 12k input, 3k output\n",
+    )
+    .unwrap();
 
     // Register the session via POST
     let register_response = register_session(port, "test-coral-reef", &dir).await;
@@ -281,6 +297,12 @@ async fn test_daemon_register_and_list_sessions() {
     assert!(response.contains("test daemon task"), "Got: {}", response);
     assert!(response.contains("\"status\""), "Got: {}", response);
     assert!(response.contains("\"last_updated\""), "Got: {}", response);
+    assert!(response.contains("\"cost_usd\":0.42"), "Got: {}", response);
+    assert!(
+        response.contains("\"input_tokens\":12000"),
+        "Got: {}",
+        response
+    );
 }
 
 #[tokio::test]
@@ -312,6 +334,131 @@ async fn test_daemon_session_events_endpoint_returns_history() {
     assert!(response.contains("historical event"), "Got: {}", response);
 }
 
+#[tokio::test]
+async fn test_daemon_session_scorecard_endpoint() {
+    let port = start_daemon_server().await;
+
+    let tmp = TempDir::new().unwrap();
+    let dir = tmp.path().to_path_buf();
+    let grotto = Grotto::new(&dir, 2, "scorecard endpoint test".into()).unwrap();
+    grotto
+        .log_event(
+            "agent_steered",
+            Some("agent-1"),
+            None,
+            Some("focus on tests"),
+            serde_json::json!({}),
+        )
+        .unwrap();
+
+    let _ = register_session(port, "scorecard-session", &dir).await;
+
+    let response = http_get(port, "/api/sessions/scorecard-session/scorecard").await;
+    assert!(response.contains("200 OK"), "Got: {}", response);
+    assert!(response.contains("\"steering\":1"), "Got: {}", response);
+    assert!(
+        response.contains("\"agent_id\":\"agent-2\""),
+        "Got: {}",
+        response
+    );
+    // Reading the scorecard doesn't write it
+    assert!(!dir.join(".grotto/scorecard.json").exists());
+
+    let missing = http_get(port, "/api/sessions/nope/scorecard").await;
+    assert!(missing.contains("404"), "Got: {}", missing);
+}
+
+#[tokio::test]
+async fn test_daemon_session_timeline_endpoint() {
+    let port = start_daemon_server().await;
+
+    let tmp = TempDir::new().unwrap();
+    let dir = tmp.path().to_path_buf();
+    let grotto = Grotto::new(&dir, 2, "timeline endpoint test".into()).unwrap();
+    for (phase, line) in [
+        (AgentPhase::Thinking, "◐ Thinking"),
+        (AgentPhase::Idle, "❯"),
+    ] {
+        let transition = PhaseTransition {
+            timestamp: chrono::Utc::now(),
+            phase,
+            last_activity: line.to_string(),
+        };
+        record_phase_transition(&grotto.grotto_dir, "agent-1", &transition).unwrap();
+    }
+
+    let _ = register_session(port, "timeline-session", &dir).await;
+
+    let response = http_get(
+        port,
+        "/api/sessions/timeline-session/timeline?agent=agent-1",
+    )
+    .await;
+    assert!(response.contains("200 OK"), "Got: {}", response);
+    let body = response.split("\r\n\r\n").nth(1).unwrap_or_default();
+    let timeline: serde_json::Value = serde_json::from_str(body).unwrap();
+    let phases: Vec<&str> = timeline["agent-1"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| t["phase"].as_str().unwrap())
+        .collect();
+    assert_eq!(phases[..2], ["thinking", "idle"]);
+    assert!(timeline.get("agent-2").is_none(), "Got: {}", body);
+
+    let missing = http_get(port, "/api/sessions/nope/timeline").await;
+    assert!(missing.contains("404"), "Got: {}", missing);
+}
+
+#[tokio::test]
+async fn test_daemon_approval_endpoints_and_ws_broadcast() {
+    let port = start_daemon_server().await;
+
+    let tmp = TempDir::new().unwrap();
+    let dir = tmp.path().to_path_buf();
+    let grotto = Grotto::new(&dir, 2, "approval test".into()).unwrap();
+    let approval =
+        grotto_core::approvals::request(&grotto, "agent-2", "run the DB migration").unwrap();
+
+    let _ = register_session(port, "approval-session", &dir).await;
+
+    let listed = http_get(port, "/api/sessions/approval-session/approvals").await;
+    assert!(listed.contains("200 OK"), "Got: {}", listed);
+    assert!(listed.contains("run the DB migration"), "Got: {}", listed);
+
+    let url = format!("ws://127.0.0.1:{}/ws/approval-session", port);
+    let (mut ws, _) = connect_async(&url).await.expect("WS connect failed");
+    consume_initial_snapshot(&mut ws).await;
+
+    let path = format!("/api/sessions/approval-session/approvals/{}", approval.id);
+    let bad = http_post_json(port, &path, &serde_json::json!({"decision": "maybe"})).await;
+    assert!(bad.contains("400"), "Got: {}", bad);
+
+    let denied = http_post_json(
+        port,
+        &path,
+        &serde_json::json!({"decision": "deny", "reason": "not during business hours"}),
+    )
+    .await;
+    assert!(denied.contains("200 OK"), "Got: {}", denied);
+    assert!(denied.contains("\"status\":\"denied\""), "Got: {}", denied);
+
+    let event = wait_for_event_type(&mut ws, "approval:updated", DEFAULT_TIMEOUT).await;
+    assert_eq!(event.agent_id.as_deref(), Some("agent-2"));
+    assert_eq!(event.data.unwrap()["status"], "denied");
+
+    let again = http_post_json(port, &path, &serde_json::json!({"decision": "approve"})).await;
+    assert!(again.contains("409"), "Got: {}", again);
+
+    let missing = http_post_json(
+        port,
+        "/api/sessions/approval-session/approvals/approval-9",
+        &serde_json::json!({"decision": "approve"}),
+    )
+    .await;
+    assert!(missing.contains("404"), "Got: {}", missing);
+}
+
 #[tokio::test]
 async fn test_daemon_per_session_ws() {
     let port = start_daemon_server().await;
@@ -348,6 +495,63 @@ async fn test_daemon_per_session_ws() {
     assert_eq!(event.message, Some("daemon event".to_string()));
 }
 
+#[tokio::test]
+async fn test_daemon_ws_streams_subscribed_terminals() {
+    let port = start_daemon_server().await;
+
+    let tmp = TempDir::new().unwrap();
+    let dir = tmp.path().to_path_buf();
+    let grotto = Grotto::new(&dir, 2, "terminal stream test".into()).unwrap();
+    let stream_log = grotto.grotto_dir.join("agents/agent-1/stream.log");
+    std::fs::write(&stream_log, "earlier line\n").unwrap();
+
+    let _ = register_session(port, "terminal-session", &dir).await;
+    let url = format!("ws://127.0.0.1:{}/ws/terminal-session", port);
+    let (mut ws, _) = connect_async(&url).await.expect("WS connect failed");
+    let _ = consume_initial_snapshot(&mut ws).await;
+
+    let request = |body: serde_json::Value| Message::Text(body.to_string().into());
+    ws.send(request(serde_json::json!({
+        "type": "terminal:subscribe",
+        "agent_id": "agent-1",
+    })))
+    .await
+    .unwrap();
+    let _ = wait_for_event_type(&mut ws, "terminal:subscribed", DEFAULT_TIMEOUT).await;
+
+    // Output is streamed as written, escape sequences and all
+    let mut file = std::fs::OpenOptions::new()
+        .append(true)
+        .open(&stream_log)
+        .unwrap();
+    std::io::Write::write_all(&mut file, b"\x1b[32mcargo test\x1b[0m\n").unwrap();
+    let output = wait_for_event_type(&mut ws, "terminal:output", DEFAULT_TIMEOUT).await;
+    assert_eq!(output.agent_id.as_deref(), Some("agent-1"));
+    let data = output.data.unwrap();
+    assert_eq!(data["data"], "\x1b[32mcargo test\x1b[0m\n");
+
+    // Scrollback ends where the live stream started
+    ws.send(request(serde_json::json!({
+        "type": "terminal:scrollback",
+        "agent_id": "agent-1",
+    })))
+    .await
+    .unwrap();
+    let scrollback = wait_for_event_type(&mut ws, "terminal:scrollback", DEFAULT_TIMEOUT).await;
+    let scrollback = scrollback.data.unwrap();
+    assert_eq!(scrollback["data"], "earlier line\n");
+    assert_eq!(scrollback["end"], data["start"]);
+
+    ws.send(request(serde_json::json!({
+        "type": "terminal:subscribe",
+        "agent_id": "../../etc",
+    })))
+    .await
+    .unwrap();
+    let error = wait_for_event_type(&mut ws, "terminal:error", DEFAULT_TIMEOUT).await;
+    assert_eq!(error.message.as_deref(), Some("Agent not found: ../../etc"));
+}
+
 #[tokio::test]
 async fn test_daemon_unregister_session() {
     let port = start_daemon_server().await;
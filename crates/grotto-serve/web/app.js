@@ -8,12 +8,16 @@
   const SESSION_ID = location.pathname.replace(/^\//, '').replace(/\/$/, '');
   const WS_URL = `${location.protocol === 'https:' ? 'wss:' : 'ws:'}//${location.host}/ws/${SESSION_ID}`;
   const EVENTS_API_URL = `/api/sessions/${encodeURIComponent(SESSION_ID)}/events`;
+  const APPROVALS_API_URL = `/api/sessions/${encodeURIComponent(SESSION_ID)}/approvals`;
+  const TIMELINE_API_URL = `/api/sessions/${encodeURIComponent(SESSION_ID)}/timeline`;
   const SAND_Y_RATIO = 0.75;
   const CRAB_SCALE = 0.35; // scale down the DALL-E sprites
 
   // --- State ---
   let agents = {};
   let tasks = [];
+  let approvals = {};
+  let timelines = {};
   let config = {};
   let crabSprites = {};
   let ws = null;
@@ -123,6 +127,10 @@
   function createCrab(agentId, colorIndex) {
     const wrapper = new PIXI.Container();
     wrapper.label = agentId;
+    // Tap a crab to watch its terminal
+    wrapper.eventMode = 'static';
+    wrapper.cursor = 'pointer';
+    wrapper.on('pointertap', () => openTerminal(agentId));
 
     // Crab sprite
     const tex = textures.idle || PIXI.Texture.WHITE;
@@ -259,13 +267,20 @@
       if (statusLabel && agents[id]) {
         const a = agents[id];
         const display = a.phase || a.state || '';
-        statusLabel.text = display;
+        // Progress the agent reported with `grotto report`
+        const report = a.report || null;
+        const percent = report && report.percent != null ? ` ${report.percent}%` : '';
+        const blocked = report && report.blocked_on;
+        statusLabel.text = blocked ? `${display}${percent} · blocked` : `${display}${percent}`;
         statusLabel.style.fill =
+          blocked ? 0xffb040 :
           display === 'thinking' ? 0x60a0ff :
           display === 'editing' ? 0xe0c050 :
           display === 'running' ? 0xff9050 :
           display === 'working' ? 0xe0c050 :
           display === 'error' ? 0xff4040 :
+          display === 'rate_limited' ? 0xffb040 :
+          display === 'stuck' ? 0xff4040 :
           display === 'finished' ? 0x50c878 :
           display === 'idle' ? 0x8899aa :
           display === 'spawning' || display === 'starting' ? 0x50c878 :
@@ -331,7 +346,7 @@
       const isFinished = phase === 'finished'
         || (phase === 'idle' && anim.state === 'working')
         || (newState === 'idle' && anim.state === 'working');
-      const isError = phase === 'error';
+      const isError = phase === 'error' || phase === 'rate_limited';
 
       if (isActive && anim.state !== 'working' && anim.state !== 'spawning') {
         anim.state = 'working';
@@ -384,15 +399,259 @@
     }).join('');
   }
 
+  // --- Approvals ---
+  async function loadApprovals() {
+    if (!SESSION_ID) return;
+    try {
+      const res = await fetch(APPROVALS_API_URL);
+      if (!res.ok) return;
+      const list = await res.json();
+      if (!Array.isArray(list)) return;
+      approvals = {};
+      for (const approval of list) approvals[approval.id] = approval;
+      renderApprovals();
+    } catch {
+      // no-op: the approvals panel is best effort when the daemon API is unavailable
+    }
+  }
+
+  function renderApprovals() {
+    const panel = document.getElementById('approvals');
+    if (!panel) return;
+    const pending = Object.values(approvals).filter(a => a.status === 'pending');
+    if (pending.length === 0) {
+      panel.innerHTML = '<div style="color: var(--text-dim); font-size: 12px;">No pending approvals</div>';
+      return;
+    }
+
+    panel.innerHTML = pending.map(a => `<div class="task-card approval-card" data-id="${esc(a.id)}">
+        <span class="task-id">${esc(a.id)}</span>
+        <span class="task-status pending">pending</span>
+        <div class="task-desc">${esc(a.description)}</div>
+        <div class="task-agent">${esc(a.agent_id)}</div>
+        <div class="approval-actions">
+          <button class="approve" data-decision="approve">Approve</button>
+          <button class="deny" data-decision="deny">Deny</button>
+        </div>
+      </div>`).join('');
+  }
+
+  async function decideApproval(id, decision) {
+    const body = { decision };
+    if (decision === 'deny') {
+      const reason = window.prompt(`Reason for denying ${id}?`);
+      if (reason === null) { renderApprovals(); return; }
+      if (reason.trim()) body.reason = reason.trim();
+    }
+
+    try {
+      const res = await fetch(`${APPROVALS_API_URL}/${encodeURIComponent(id)}`, {
+        method: 'POST',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify(body),
+      });
+      if (res.ok) {
+        const approval = await res.json();
+        approvals[approval.id] = approval;
+        renderApprovals();
+      } else {
+        // Already answered elsewhere (409) or gone; resync from the daemon
+        loadApprovals();
+      }
+    } catch {
+      renderApprovals();
+    }
+  }
+
+  document.getElementById('approvals')?.addEventListener('click', (e) => {
+    const button = e.target.closest('button[data-decision]');
+    if (!button) return;
+    const card = button.closest('.approval-card');
+    for (const b of card.querySelectorAll('button')) b.disabled = true;
+    decideApproval(card.dataset.id, button.dataset.decision);
+  });
+
+  // --- Terminal View ---
+  // Streams one agent's stream.log over the WebSocket: scrollback first,
+  // then live output (held back until the scrollback has been written).
+  const SCROLLBACK_BYTES = 128 * 1024;
+  let terminalAgent = null;
+  let terminalReady = false;
+  let terminalPending = [];
+  let term = null;
+
+  function sendWs(message) {
+    if (ws && ws.readyState === WebSocket.OPEN) ws.send(JSON.stringify(message));
+  }
+
+  function terminalWrite(data) {
+    if (term) {
+      term.write(data);
+      return;
+    }
+    // xterm.js unavailable: show plain text
+    const pre = document.querySelector('#terminal-view pre');
+    if (!pre) return;
+    pre.textContent += data.replace(/\x1b\[[0-9;?]*[ -/]*[@-~]|\x1b\][^\x07]*\x07/g, '');
+    pre.parentElement.scrollTop = pre.parentElement.scrollHeight;
+  }
+
+  function terminalClear() {
+    terminalReady = false;
+    terminalPending = [];
+    const view = document.getElementById('terminal-view');
+    if (typeof Terminal === 'undefined') {
+      view.innerHTML = '<pre></pre>';
+      return;
+    }
+    if (!term) {
+      term = new Terminal({
+        convertEol: true,
+        disableStdin: true,
+        fontSize: 12,
+        scrollback: 5000,
+        theme: { background: '#0a0e1a' },
+      });
+      term.open(view);
+    }
+    term.reset();
+  }
+
+  function subscribeTerminal() {
+    if (!terminalAgent) return;
+    terminalClear();
+    document.getElementById('terminal-status').textContent = 'connecting…';
+    sendWs({ type: 'terminal:subscribe', agent_id: terminalAgent });
+    sendWs({ type: 'terminal:scrollback', agent_id: terminalAgent, bytes: SCROLLBACK_BYTES });
+  }
+
+  function openTerminal(agentId) {
+    if (terminalAgent && terminalAgent !== agentId) {
+      sendWs({ type: 'terminal:unsubscribe', agent_id: terminalAgent });
+    }
+    terminalAgent = agentId;
+    document.getElementById('terminal-panel').classList.remove('hidden');
+    renderTerminalTabs();
+    subscribeTerminal();
+  }
+
+  function closeTerminal() {
+    if (terminalAgent) sendWs({ type: 'terminal:unsubscribe', agent_id: terminalAgent });
+    terminalAgent = null;
+    document.getElementById('terminal-panel').classList.add('hidden');
+  }
+
+  function renderTerminalTabs() {
+    const tabs = document.getElementById('terminal-tabs');
+    if (!tabs) return;
+    tabs.innerHTML = Object.keys(agents).sort().map(id =>
+      `<button data-agent="${esc(id)}" class="${id === terminalAgent ? 'active' : ''}">${esc(id)}</button>`
+    ).join('');
+  }
+
+  function handleTerminalEvent(kind, event) {
+    if (event.agent_id !== terminalAgent) return;
+    const data = event.data || {};
+    const status = document.getElementById('terminal-status');
+    if (kind === 'terminal:scrollback') {
+      terminalWrite(data.data || '');
+      for (const chunk of terminalPending) terminalWrite(chunk);
+      terminalPending = [];
+      terminalReady = true;
+      status.textContent = 'live';
+    } else if (kind === 'terminal:output') {
+      if (data.reset) {
+        // The log was replaced (new session): start over
+        if (term) term.reset();
+        else terminalClear();
+      }
+      if (terminalReady) terminalWrite(data.data || '');
+      else terminalPending.push(data.data || '');
+    } else if (kind === 'terminal:error') {
+      status.textContent = event.message || 'terminal unavailable';
+    }
+  }
+
+  document.getElementById('terminal-tabs')?.addEventListener('click', (e) => {
+    const button = e.target.closest('button[data-agent]');
+    if (button) openTerminal(button.dataset.agent);
+  });
+  document.getElementById('terminal-close')?.addEventListener('click', closeTerminal);
+
+  // --- Phase Timeline ---
+  const PHASE_COLORS = {
+    starting: '#50c878',
+    thinking: '#60a0ff',
+    editing: '#e0c050',
+    running: '#ff9050',
+    idle: '#8899aa',
+    finished: '#50c878',
+    rate_limited: '#ffb040',
+    error: '#ff4040',
+  };
+
+  // Rebuild each crab's phase history from the agents' phases.jsonl
+  async function loadTimeline() {
+    if (!SESSION_ID) return;
+    try {
+      const res = await fetch(TIMELINE_API_URL);
+      if (!res.ok) return;
+      const loaded = await res.json();
+      if (!loaded || typeof loaded !== 'object') return;
+      timelines = loaded;
+      for (const [id, transitions] of Object.entries(timelines)) {
+        const last = transitions[transitions.length - 1];
+        if (last && agents[id] && !agents[id].phase) agents[id].phase = last.phase;
+      }
+      syncCrabs();
+      renderTimeline();
+    } catch {
+      // no-op: the timeline is best effort when the daemon API is unavailable
+    }
+  }
+
+  function renderTimeline() {
+    const panel = document.getElementById('timeline');
+    if (!panel) return;
+    const rows = Object.entries(timelines).filter(([, t]) => t.length > 0);
+    if (rows.length === 0) {
+      panel.innerHTML = '<div style="color: var(--text-dim); font-size: 12px;">No phase changes yet</div>';
+      return;
+    }
+
+    const now = Date.now();
+    const start = Math.min(...rows.map(([, t]) => Date.parse(t[0].timestamp)));
+    const span = Math.max(1, now - start);
+    panel.innerHTML = rows.map(([id, transitions]) => {
+      const segments = transitions.map((t, i) => {
+        const from = Date.parse(t.timestamp);
+        const to = i + 1 < transitions.length ? Date.parse(transitions[i + 1].timestamp) : now;
+        const width = (100 * Math.max(0, to - from)) / span;
+        const since = new Date(from).toLocaleTimeString();
+        const title = `${t.phase} since ${since}${t.last_activity ? ' — ' + t.last_activity : ''}`;
+        const color = PHASE_COLORS[t.phase] || PHASE_COLORS.idle;
+        return `<span style="width: ${width}%; background: ${color}" title="${esc(title)}"></span>`;
+      }).join('');
+      const offset = (100 * (Date.parse(transitions[0].timestamp) - start)) / span;
+      return `<div class="timeline-row">
+        <div class="timeline-agent">${esc(id)}</div>
+        <div class="timeline-bar"><span style="width: ${offset}%"></span>${segments}</div>
+      </div>`;
+    }).join('');
+  }
+
   // --- Event Log ---
   function getEventKind(event) {
     return event?.type || event?.event_type || '';
   }
 
+  const ALERT_EVENTS = new Set(['policy:violation', 'policy_violation']);
+
   function addLogEntry(event) {
     const entries = document.getElementById('log-entries');
     const div = document.createElement('div');
     div.className = 'log-entry';
+    if (ALERT_EVENTS.has(getEventKind(event))) div.classList.add('log-alert');
 
     const time = event.timestamp ? new Date(event.timestamp).toLocaleTimeString() : '';
     const agentPart = event.agent_id
@@ -446,6 +705,7 @@
   function completeFromHistory() {
     // Keep fallback behavior centralized so onclose/onerror stay in sync.
     loadEventHistory();
+    loadTimeline();
     setConnectionStatus('disconnected');
     scheduleReconnect();
   }
@@ -454,6 +714,10 @@
     wsEverConnected = true;
     setConnectionStatus('connected');
     clearReconnectTimer();
+    loadApprovals();
+    loadTimeline();
+    // Subscriptions belong to the old socket
+    subscribeTerminal();
   }
 
   function handleSocketClose() {
@@ -558,11 +822,12 @@
         }
         syncCrabs();
         renderTaskBoard();
+        renderTerminalTabs();
         break;
 
       case 'agent:status':
         if (event.agent_id && event.data) {
-          agents[event.agent_id] = { ...agents[event.agent_id], ...event.data };
+          agents[event.agent_id] = { ...agents[event.agent_id], report: null, ...event.data };
           syncCrabs();
         }
         addLogEntry(event);
@@ -574,6 +839,14 @@
           agents[event.agent_id].phase = event.data.phase;
           agents[event.agent_id].last_activity = event.data.last_activity;
           syncCrabs();
+          if (!options.fromHistory) {
+            (timelines[event.agent_id] = timelines[event.agent_id] || []).push({
+              timestamp: event.timestamp,
+              phase: event.data.phase,
+              last_activity: event.data.last_activity,
+            });
+            renderTimeline();
+          }
         }
         addLogEntry(event);
         break;
@@ -603,6 +876,23 @@
         addLogEntry(event);
         break;
 
+      case 'approval:updated':
+        if (event.data && event.data.id) {
+          approvals[event.data.id] = event.data;
+          renderApprovals();
+        }
+        addLogEntry(event);
+        break;
+
+      case 'policy:violation':
+      case 'policy_violation':
+        if (event.agent_id && agents[event.agent_id]) {
+          agents[event.agent_id].state = 'paused';
+          syncCrabs();
+        }
+        addLogEntry(event);
+        break;
+
       case 'team:spawned':
       case 'team_spawned':
       case 'agent:summary':
@@ -617,6 +907,16 @@
         addLogEntry(event);
         break;
 
+      case 'terminal:output':
+      case 'terminal:scrollback':
+      case 'terminal:error':
+        handleTerminalEvent(eventKind, event);
+        break;
+
+      case 'terminal:subscribed':
+      case 'terminal:unsubscribed':
+        break;
+
       case 'event:raw':
         if (event.data) {
           const rawType = getEventKind(event.data);
@@ -638,7 +938,7 @@
 
     // Apply some state on historical events too
     if (options.fromHistory && eventKind === 'agent:status' && event.agent_id && event.data) {
-      agents[event.agent_id] = { ...agents[event.agent_id], ...event.data };
+      agents[event.agent_id] = { ...agents[event.agent_id], report: null, ...event.data };
       syncCrabs();
     }
   }
@@ -663,5 +963,7 @@
   }
 
   connectWS();
+  // Keep the current phase's segment growing between phase changes
+  setInterval(renderTimeline, 5000);
 
 })();